@@ -4,159 +4,845 @@ use serde_yaml::Mapping as YamlMapping;
 use serde_yaml::Value as YamlValue;
 use std::path::Path;
 use serde::{Deserialize, Serialize};
+use prefixmap::{IriRef, PrefixMap};
+use shex_ast::iri_exclusion::IriExclusion;
+use shex_ast::{Annotation, IriRefOrWildcard, LangOrWildcard, NodeConstraint, NodeKind as ShexNodeKind, NumericFacet, ObjectValue, ShapeDecl, ShapeExpr, StringFacet, TripleExpr, TripleExprLabel, ValueSetValue, XsFacet};
+use std::cell::RefCell;
+
+use crate::naming::{cased, NameCase};
 
 /// Internal, slightly richer shape metadata used by the converters
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
 pub struct ShapeInfo {
     pub id: String,
     pub name: String,
     pub properties: Vec<PropertyInfo>,
+    /// names of the shapes this one extends (ShEx EXTENDS, LinkML is_a/mixins);
+    /// `properties` holds only the shape's own properties
+    #[serde(default)]
+    pub extends: Vec<String>,
+    /// shape-level constraints that don't reduce to a list of properties
+    #[serde(default)]
+    pub constraints: Vec<ShapeConstraint>,
+    /// ShEx CLOSED: no properties beyond its own and those it inherits
+    #[serde(default)]
+    pub closed: bool,
+    /// the schema's START shape, which documents are validated against by default
+    #[serde(default)]
+    pub start: bool,
+    /// lifted out of an inline shape expression: a part of the shape that holds it rather
+    /// than a shape documents are written against
+    #[serde(default)]
+    pub inline: bool,
+    /// ShEx ABSTRACT: data only conforms to it through a shape that extends it
+    #[serde(default)]
+    pub is_abstract: bool,
+    /// ShEx EXTERNAL: defined outside this schema, so an opaque reference to `id`.
+    /// JSON Schema refers to it by that IRI and LinkML marks it with an `external` annotation.
+    #[serde(default)]
+    pub external: bool,
+    /// human-readable name (rdfs:label annotation)
+    #[serde(default)]
+    pub title: Option<String>,
+    /// human-readable description (rdfs:comment annotation)
+    #[serde(default)]
+    pub description: Option<String>,
+    /// ShEx semantic actions on the shape, which other formats carry as annotations
+    #[serde(default)]
+    pub sem_acts: Vec<SemAct>,
+    /// LinkML `identifier` slot: its value is the focus node's IRI, so it is not a property
+    #[serde(default)]
+    pub identifier: Option<String>,
+}
+
+/// A ShEx shape expression with no per-property equivalent in the shape model
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum ShapeConstraint {
+    /// exactly one of the alternatives (each a group of properties) is satisfied (ShEx OneOf)
+    OneOf(Vec<Vec<PropertyInfo>>),
+    /// the node must not conform to the named shape (ShEx NOT)
+    Not(String),
+    /// the node conforms to at least one of the named shapes (ShEx OR)
+    AnyOf(Vec<String>),
+    /// a node meeting the preconditions must meet the postconditions, and one that doesn't,
+    /// the elseconditions (LinkML rules, JSON Schema if/then/else and dependentRequired);
+    /// each condition is a property that must be present (min >= 1) and, if it lists
+    /// `values`, take one of them
+    Rule { preconditions: Vec<PropertyInfo>, postconditions: Vec<PropertyInfo>, elseconditions: Vec<PropertyInfo> },
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
 pub struct PropertyInfo {
     pub name: String,
     pub predicate: String,
     pub range: String, // datatype or a class name
     pub min: Option<u64>,
+    /// None when unbounded (ShEx `*` and `+`, which rudof writes as -1)
     pub max: Option<u64>,
+    /// permissible values when the property is restricted to an enumeration
+    #[serde(default)]
+    pub values: Vec<String>,
+    /// the kind of RDF term values must be (ShEx nodeKind, SHACL sh:nodeKind)
+    #[serde(default)]
+    pub node_kind: Option<NodeKind>,
+    /// XSD facets on the property's literal values
+    #[serde(default)]
+    pub facets: Facets,
+    /// language tags a language-tagged string may carry: `en` is exactly `en`,
+    /// `en~` is `en` or a subtag of it, and `~` alone is any tag (rdf:langString)
+    #[serde(default)]
+    pub languages: Vec<String>,
+    /// IRI stems values may fall under, alongside any listed in `values`
+    #[serde(default)]
+    pub iri_stems: Vec<IriStem>,
+    /// human-readable name (rdfs:label annotation)
+    #[serde(default)]
+    pub title: Option<String>,
+    /// human-readable description (rdfs:comment annotation)
+    #[serde(default)]
+    pub description: Option<String>,
+    /// ShEx semantic actions on the triple constraint
+    #[serde(default)]
+    pub sem_acts: Vec<SemAct>,
+    /// ranges combined by a LinkML `any_of`, `exactly_one_of` or `all_of`; `range` is the first
+    #[serde(default)]
+    pub range_expr: Option<RangeExpr>,
+    /// how values of a shape range are nested or referred to; `None` is LinkML's
+    /// default, inlined unless the range has an identifier
+    #[serde(default)]
+    pub inlining: Option<Inlining>,
 }
 
-/// Convert a rudof AST (shex_ast::Schema) into our ShapeInfo vector
-pub fn shapes_from_rudof_ast(schema: &shex_ast::Schema) -> anyhow::Result<Vec<ShapeInfo>> {
-    // Serialize the AST to JSON Value and use heuristics similar to the original
-    let ast_json = serde_json::to_value(schema).context("serialize AST")?;
-    Ok(extract_shapes_from_ast(&ast_json))
+/// How a property's values of a shape range are written (LinkML `inlined` and `inlined_as_list`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Inlining {
+    /// referred to by the range's identifier (`inlined: false`)
+    Reference,
+    /// nested in the referring object, several keyed by their identifiers (`inlined: true`)
+    Inlined,
+    /// nested in the referring object, several as a list (`inlined_as_list: true`)
+    InlinedAsList,
 }
 
-fn extract_shapes_from_ast(ast: &JsonValue) -> Vec<ShapeInfo> {
-    use serde_json::Map as JsonMap;
+/// A boolean combination of a property's ranges, each a datatype or a class name
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum RangeExpr {
+    /// values are in at least one of the ranges (ShEx OR, JSON Schema anyOf)
+    AnyOf(Vec<String>),
+    /// values are in exactly one of the ranges (JSON Schema oneOf)
+    ExactlyOneOf(Vec<String>),
+    /// values are in every one of the ranges (ShEx AND, JSON Schema allOf)
+    AllOf(Vec<String>),
+}
 
-    let mut shapes = Vec::new();
+impl RangeExpr {
+    /// The ranges combined
+    pub fn ranges(&self) -> &[String] {
+        match self {
+            RangeExpr::AnyOf(ranges) | RangeExpr::ExactlyOneOf(ranges) | RangeExpr::AllOf(ranges) => ranges,
+        }
+    }
 
-    fn walk_for_shapes(v: &JsonValue, out: &mut Vec<ShapeInfo>) {
-        if let Some(obj) = v.as_object() {
-            // find objects that look like shapeDecls or shapes
-            if obj.contains_key("shapeExprs") || obj.contains_key("shapes") || obj.contains_key("shapeDecls") {
-                // attempt to extract map-like entries
-                for (_k, v2) in obj.iter() {
-                    if let Some(m) = v2.as_object() {
-                        // If children look like shapes (have expression / tripleConstraints)
-                        for (label, possible_shape) in m.iter() {
-                            let props = extract_props_from_shape(possible_shape);
-                            if !props.is_empty() {
-                                let name = label.clone();
-                                out.push(ShapeInfo { id: label.clone(), name: name.clone(), properties: props });
-                            }
-                        }
-                        return;
-                    }
-                }
+    /// The ranges combined, for renaming
+    pub fn ranges_mut(&mut self) -> &mut Vec<String> {
+        match self {
+            RangeExpr::AnyOf(ranges) | RangeExpr::ExactlyOneOf(ranges) | RangeExpr::AllOf(ranges) => ranges,
+        }
+    }
+
+    /// The LinkML slot expression key
+    pub fn linkml_key(&self) -> &'static str {
+        match self {
+            RangeExpr::AnyOf(_) => "any_of",
+            RangeExpr::ExactlyOneOf(_) => "exactly_one_of",
+            RangeExpr::AllOf(_) => "all_of",
+        }
+    }
+}
+
+/// A ShEx semantic action (`%ex:js{ ... %}`): code for the extension named by
+/// `name`, run by validators that support it. Nothing else can run it, so it's
+/// only carried along to be written back to ShEx.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SemAct {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub code: Option<String>,
+}
+
+/// An IRI stem from a value set (ShEx `ex:~`): IRIs starting with `stem`, less
+/// the exclusions, where an exclusion ending in `~` is itself a stem.
+/// An empty stem is ShEx's `.` wildcard, i.e. any IRI.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct IriStem {
+    pub stem: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub exclusions: Vec<String>,
+}
+
+/// XSD string and numeric facets (ShEx MINLENGTH, PATTERN, MININCLUSIVE, ...).
+/// Bounds keep their lexical number so integers stay integers in the output.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Facets {
+    pub min_length: Option<u64>,
+    pub max_length: Option<u64>,
+    pub pattern: Option<String>,
+    pub min_inclusive: Option<serde_json::Number>,
+    pub min_exclusive: Option<serde_json::Number>,
+    pub max_inclusive: Option<serde_json::Number>,
+    pub max_exclusive: Option<serde_json::Number>,
+    pub total_digits: Option<u64>,
+    pub fraction_digits: Option<u64>,
+}
+
+/// RDF term kinds a property's values can be restricted to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum NodeKind {
+    Iri,
+    BNode,
+    /// an IRI or a blank node
+    NonLiteral,
+    Literal,
+}
+
+/// Namespace declarations of the source schema (ShEx PREFIX/BASE, LinkML `prefixes:`),
+/// in declaration order. Writers use them to abbreviate IRIs as CURIEs.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Prefixes {
+    pub base: Option<String>,
+    pub prefixes: Vec<(String, String)>,
+}
+
+impl Prefixes {
+    /// The CURIE for an IRI under the longest matching namespace, if any
+    pub fn compact(&self, iri: &str) -> Option<String> {
+        self.prefixes.iter()
+            .filter_map(|(prefix, ns)| Some((prefix, iri.strip_prefix(ns.as_str())?)))
+            .filter(|(_, local)| is_curie_local(local))
+            .min_by_key(|(_, local)| local.len())
+            .map(|(prefix, local)| format!("{}:{}", prefix, local))
+    }
+
+    /// The IRI for a CURIE under a declared namespace, if any
+    pub fn expand(&self, curie: &str) -> Option<String> {
+        let (prefix, local) = curie.split_once(':')?;
+        self.prefixes.iter().find(|(p, _)| p == prefix).map(|(_, ns)| format!("{}{}", ns, local))
+    }
+
+    /// Like `iri_term`, but IRIs under a declared namespace become CURIEs
+    fn term(&self, name: &str) -> String {
+        self.compact(name).unwrap_or_else(|| iri_term(name))
+    }
+
+    /// A shape label: relative to the base when there is one, else like `iri_term`
+    fn label(&self, name: &str) -> String {
+        match &self.base {
+            Some(_) if !name.contains(':') => format!("<{}>", name),
+            _ => iri_term(name),
+        }
+    }
+
+    /// Like `value_term`, but IRIs under a declared namespace become CURIEs
+    fn value_term(&self, v: &str) -> String {
+        if v.starts_with("http://") || v.starts_with("https://") {
+            self.term(v)
+        } else {
+            string_literal(v)
+        }
+    }
+
+    /// Like `shex_iri_stem`, but IRIs under a declared namespace become CURIEs
+    fn shex_iri_stem(&self, s: &IriStem) -> String {
+        let mut out = if s.stem.is_empty() { ".".to_string() } else { format!("{}~", self.term(&s.stem)) };
+        for e in s.exclusions.iter() {
+            match e.strip_suffix('~') {
+                Some(stem) => out.push_str(&format!(" - {}~", self.term(stem))),
+                None => out.push_str(&format!(" - {}", self.term(e))),
+            }
+        }
+        out
+    }
+
+    /// ShExC semantic actions, e.g. ` %ex:js{ print(o) %}`, escaping `%` and `\` in the code
+    fn shex_sem_acts(&self, sem_acts: &[SemAct]) -> String {
+        sem_acts.iter().map(|a| match &a.code {
+            Some(code) => format!(" %{}{{{}%}}", self.term(&a.name), code.replace('\\', "\\\\").replace('%', "\\%")),
+            None => format!(" %{}%", self.term(&a.name)),
+        }).collect()
+    }
+
+    /// A class term: a shape label, compacted where a declared namespace covers it
+    fn class_term(&self, name: &str) -> String {
+        self.compact(name).unwrap_or_else(|| self.label(name))
+    }
+
+    fn declares(&self, prefix: &str) -> bool {
+        self.prefixes.iter().any(|(p, _)| p == prefix)
+    }
+
+    /// Add `other`'s declarations for prefixes not declared here, and its base if there is none
+    pub fn merge(&mut self, other: Prefixes) {
+        if self.base.is_none() {
+            self.base = other.base;
+        }
+        for (prefix, ns) in other.prefixes {
+            if !self.declares(&prefix) {
+                self.prefixes.push((prefix, ns));
             }
+        }
+    }
 
-            // otherwise recursively search
-            for (_k, v2) in obj.iter() { walk_for_shapes(v2, out); }
-        } else if let Some(arr) = v.as_array() {
-            for e in arr { walk_for_shapes(e, out); }
+    /// Turtle `@base` and `@prefix` lines for these declarations, then for any of
+    /// `defaults` whose prefix isn't declared
+    fn turtle_header(&self, defaults: &[(&str, &str)]) -> String {
+        let mut out = String::new();
+        if let Some(base) = &self.base {
+            out.push_str(&format!("@base <{}> .\n", base));
         }
+        for (prefix, ns) in self.with_defaults(defaults) {
+            out.push_str(&format!("@prefix {}: <{}> .\n", prefix, ns));
+        }
+        out
+    }
+
+    /// SPARQL `BASE` and `PREFIX` lines, like `turtle_header`
+    fn sparql_header(&self, defaults: &[(&str, &str)]) -> String {
+        let mut out = String::new();
+        if let Some(base) = &self.base {
+            out.push_str(&format!("BASE <{}>\n", base));
+        }
+        for (prefix, ns) in self.with_defaults(defaults) {
+            out.push_str(&format!("PREFIX {}: <{}>\n", prefix, ns));
+        }
+        out
     }
 
-    walk_for_shapes(ast, &mut shapes);
-    shapes
+    /// These declarations, then those of `defaults` whose prefix isn't declared
+    fn with_defaults<'a>(&'a self, defaults: &'a [(&'a str, &'a str)]) -> impl Iterator<Item = (&'a str, &'a str)> {
+        self.prefixes.iter().map(|(prefix, ns)| (prefix.as_str(), ns.as_str()))
+            .chain(defaults.iter().copied().filter(move |(prefix, _)| !self.declares(prefix)))
+    }
 }
 
-fn extract_props_from_shape(shape_val: &JsonValue) -> Vec<PropertyInfo> {
-    use serde_json::Map as JsonMap;
-    let mut props = Vec::new();
+/// Read a prefix map file, see `parse_prefix_file`.
+pub fn read_prefix_file(path: &Path) -> anyhow::Result<Prefixes> {
+    let text = std::fs::read_to_string(path)?;
+    parse_prefix_file(&text)
+}
 
-    if let Some(obj) = shape_val.as_object() {
-        // Common locations: expression.tripleConstraints OR tripleConstraints direct
-        if let Some(expr) = obj.get("expression").or_else(|| obj.get("shapeExpr")) {
-            if let Some(tcs) = expr.get("tripleConstraints").or_else(|| expr.get("triple_constraints")) {
-                if let Some(arr) = tcs.as_array() {
-                    for tc in arr.iter() {
-                        if let Some(tcobj) = tc.as_object() {
-                            props.push(build_prop_from_tc(tcobj));
-                        }
-                    }
+/// Parse a prefix map: Turtle (or SPARQL) `@prefix` and `@base` declarations, a
+/// JSON-LD context, or a rudof prefix map, i.e. a YAML or JSON mapping of prefix
+/// to namespace. Context terms whose definitions aren't plain namespaces are skipped.
+pub fn parse_prefix_file(text: &str) -> anyhow::Result<Prefixes> {
+    let first = text.lines().map(str::trim).find(|l| !l.is_empty() && !l.starts_with('#')).unwrap_or("");
+    let lower = first.to_ascii_lowercase();
+    if first.starts_with('@') || lower.starts_with("prefix") || lower.starts_with("base") {
+        let mut parser = oxttl::TurtleParser::new().for_slice(text);
+        for triple in parser.by_ref() {
+            triple.context("parsing Turtle prefixes")?;
+        }
+        let mut prefixes: Vec<(String, String)> = parser.prefixes().map(|(p, ns)| (p.to_string(), ns.to_string())).collect();
+        // the parser keeps them unordered
+        prefixes.sort();
+        return Ok(Prefixes { base: parser.base_iri().map(str::to_string), prefixes });
+    }
+
+    let doc: YamlValue = serde_yaml::from_str(text).context("parsing prefix map")?;
+    let map = match doc.get("@context").unwrap_or(&doc) {
+        YamlValue::Mapping(m) => m.clone(),
+        _ => anyhow::bail!("a prefix map must be a mapping of prefix to namespace"),
+    };
+    let mut prefixes = Prefixes::default();
+    for (k, v) in map.iter() {
+        let Some(key) = k.as_str() else { continue };
+        // a JSON-LD term may be a string or an object with an `@id`
+        let Some(ns) = v.as_str().or_else(|| v.get("@id").and_then(|id| id.as_str())) else { continue };
+        match key {
+            "@base" => prefixes.base = Some(ns.to_string()),
+            _ if key.starts_with('@') || key.contains(':') => {}
+            // terms for properties and classes aren't namespaces, unless flagged `@prefix`
+            _ if !(ns.ends_with('/') || ns.ends_with('#')) && v.get("@prefix").and_then(|p| p.as_bool()) != Some(true) => {}
+            _ => prefixes.prefixes.push((key.to_string(), ns.to_string())),
+        }
+    }
+    Ok(prefixes)
+}
+
+/// Whether a local name can follow `prefix:` unescaped (a conservative PN_LOCAL)
+pub(crate) fn is_curie_local(local: &str) -> bool {
+    !local.ends_with('.') && local.chars().all(|c| c.is_alphanumeric() || "_-.".contains(c))
+        && !local.starts_with(['-', '.'])
+}
+
+/// The PREFIX and BASE declarations of a rudof AST
+pub fn prefixes_from_rudof_ast(schema: &shex_ast::Schema) -> Prefixes {
+    Prefixes {
+        base: schema.base().map(|b| b.as_str().to_string()),
+        prefixes: schema.prefixmap().unwrap_or_default().iter().map(|(p, ns)| (p.clone(), ns.as_str().to_string())).collect(),
+    }
+}
+
+/// Convert a rudof AST (shex_ast::Schema) into our ShapeInfo vector
+pub fn shapes_from_rudof_ast(schema: &shex_ast::Schema) -> anyhow::Result<Vec<ShapeInfo>> {
+    let decls = schema.shapes().unwrap_or_default();
+    let walker = AstWalker { prefixmap: schema.prefixmap().unwrap_or_default(), decls: &decls, including: RefCell::new(Vec::new()), minted: RefCell::new(Vec::new()) };
+
+    let mut shapes = Vec::new();
+    for decl in decls.iter() {
+        // datatype and value set declarations are inlined wherever they are referenced
+        if matches!(decl.shape_expr, ShapeExpr::NodeConstraint(_)) {
+            continue;
+        }
+        let id = decl.id.to_string();
+        let mut shape = ShapeInfo { id: id.clone(), name: local_name(&id), is_abstract: decl.is_abstract, external: matches!(decl.shape_expr, ShapeExpr::External), ..Default::default() };
+        let mut nested = Vec::new();
+        walker.shape_expr(&decl.shape_expr, &mut shape, &mut nested);
+        shapes.push(shape);
+        shapes.append(&mut nested);
+    }
+    match schema.start() {
+        Some(ShapeExpr::Ref(label)) => {
+            let name = local_name(&label.to_string());
+            shapes.iter_mut().filter(|s| s.name == name).for_each(|s| s.start = true);
+        }
+        // an inline start shape gets a name of its own
+        Some(se @ ShapeExpr::Shape(_)) => {
+            let mut nested = Vec::new();
+            walker.nested_shape("Start".to_string(), &se, &mut nested);
+            nested[0].start = true;
+            shapes.append(&mut nested);
+        }
+        _ => {}
+    }
+    Ok(shapes)
+}
+
+/// Typed traversal of a ShEx schema's shape declarations
+struct AstWalker<'a> {
+    prefixmap: PrefixMap,
+    decls: &'a [ShapeDecl],
+    /// labels of the triple expressions being walked, so a circular `&label` inclusion stops
+    including: RefCell<Vec<String>>,
+    /// names given to inline shapes so far
+    minted: RefCell<Vec<String>>,
+}
+
+impl AstWalker<'_> {
+    fn iri(&self, iri: &IriRef) -> String {
+        match self.prefixmap.resolve_iriref(iri) {
+            Ok(resolved) => resolved.as_str().to_string(),
+            Err(_) => iri.to_string(),
+        }
+    }
+
+    /// The rdfs:label and rdfs:comment literals among some annotations
+    fn docs(&self, annotations: &Option<Vec<Annotation>>) -> (Option<String>, Option<String>) {
+        let find = |local: &str| annotations.iter().flatten()
+            .find(|a| self.iri(&a.predicate()) == format!("{}{}", RDFS, local))
+            .and_then(|a| match a.object() {
+                ObjectValue::Literal(l) => Some(l.lexical_form()),
+                _ => None,
+            });
+        (find("label"), find("comment"))
+    }
+
+    /// Semantic actions, their extension names resolved to IRIs
+    fn sem_acts(&self, sem_acts: &Option<Vec<shex_ast::SemAct>>) -> Vec<SemAct> {
+        sem_acts.iter().flatten().map(|a| SemAct { name: self.iri(&a.name()), code: a.code() }).collect()
+    }
+
+    /// Fold a shape expression into `shape`; inline shapes found along the way go to `nested`
+    fn shape_expr(&self, se: &ShapeExpr, shape: &mut ShapeInfo, nested: &mut Vec<ShapeInfo>) {
+        match se {
+            ShapeExpr::Shape(s) => {
+                shape.closed |= s.closed.unwrap_or(false);
+                let (title, description) = self.docs(&s.annotations);
+                shape.title = shape.title.take().or(title);
+                shape.description = shape.description.take().or(description);
+                shape.sem_acts.extend(self.sem_acts(&s.sem_acts));
+                shape.extends.extend(s.extends.iter().flatten().map(|l| local_name(&l.to_string())));
+                if let Some(expression) = &s.expression {
+                    let props = self.triple_expr(&expression.te, shape, nested);
+                    shape.properties.extend(props);
                 }
             }
-
-            // arrays in `items` or `expressions` sometimes used
-            if props.is_empty() {
-                if let Some(items) = expr.get("items").or_else(|| expr.get("expressions")) {
-                    if let Some(arr) = items.as_array() {
-                        for it in arr.iter() {
-                            if let Some(itobj) = it.as_object() {
-                                if itobj.contains_key("predicate") {
-                                    props.push(build_prop_from_tc(itobj));
-                                }
-                            }
-                        }
-                    }
+            ShapeExpr::ShapeAnd { shape_exprs } => {
+                for member in shape_exprs {
+                    self.shape_expr(&member.se, shape, nested);
+                }
+            }
+            ShapeExpr::ShapeOr { shape_exprs } => {
+                // inline branches become shapes of their own; node constraints have no shape to name
+                let names: Vec<String> = shape_exprs.iter().enumerate().filter_map(|(i, branch)| match &branch.se {
+                    ShapeExpr::Ref(l) => Some(local_name(&l.to_string())),
+                    ShapeExpr::NodeConstraint(_) | ShapeExpr::External => None,
+                    other => Some(self.nested_shape(format!("{}Option{}", shape.name, i + 1), other, nested)),
+                }).collect();
+                if !names.is_empty() {
+                    shape.constraints.push(ShapeConstraint::AnyOf(names));
                 }
             }
+            ShapeExpr::ShapeNot { shape_expr } => {
+                let label = match &shape_expr.se {
+                    ShapeExpr::Ref(l) => local_name(&l.to_string()),
+                    other => self.nested_shape(format!("{}Not", shape.name), other, nested),
+                };
+                shape.constraints.push(ShapeConstraint::Not(label));
+            }
+            ShapeExpr::Ref(l) => shape.extends.push(local_name(&l.to_string())),
+            ShapeExpr::NodeConstraint(_) | ShapeExpr::External => {}
         }
+    }
 
-        // fallback: direct tripleConstraints
-        if props.is_empty() {
-            if let Some(tcs) = obj.get("tripleConstraints").or_else(|| obj.get("triple_constraints")) {
-                if let Some(arr) = tcs.as_array() {
-                    for tc in arr.iter() {
-                        if let Some(tcobj) = tc.as_object() {
-                            props.push(build_prop_from_tc(tcobj));
-                        }
-                    }
+    /// Flatten a triple expression into properties, recording OneOf groups on `shape`
+    fn triple_expr(&self, te: &TripleExpr, shape: &mut ShapeInfo, nested: &mut Vec<ShapeInfo>) -> Vec<PropertyInfo> {
+        let id = match te {
+            TripleExpr::EachOf { id, .. } | TripleExpr::OneOf { id, .. } | TripleExpr::TripleConstraint { id, .. } => id.as_ref(),
+            TripleExpr::TripleExprRef(_) => None,
+        };
+        let Some(label) = id.map(|id| self.te_label(id)) else {
+            return self.triple_expr_body(te, shape, nested);
+        };
+        self.including.borrow_mut().push(label);
+        let props = self.triple_expr_body(te, shape, nested);
+        self.including.borrow_mut().pop();
+        props
+    }
+
+    fn triple_expr_body(&self, te: &TripleExpr, shape: &mut ShapeInfo, nested: &mut Vec<ShapeInfo>) -> Vec<PropertyInfo> {
+        match te {
+            TripleExpr::EachOf { expressions, min, max, .. } => {
+                let props = expressions.iter().flat_map(|e| self.triple_expr(&e.te, shape, nested)).collect();
+                group_cardinality(props, *min, *max)
+            }
+            TripleExpr::OneOf { expressions, min, max, .. } => {
+                let alternatives: Vec<Vec<PropertyInfo>> = expressions.iter().map(|e| self.triple_expr(&e.te, shape, nested)).collect();
+                shape.constraints.push(ShapeConstraint::OneOf(alternatives.clone()));
+                // the choice itself is a constraint; each property on its own is optional
+                let props = alternatives.into_iter().flatten().map(|p| PropertyInfo { min: Some(0), ..p }).collect();
+                group_cardinality(props, *min, *max)
+            }
+            TripleExpr::TripleConstraint { negated, inverse, predicate, value_expr, min, max, sem_acts, annotations, .. } => {
+                if negated.unwrap_or(false) || inverse.unwrap_or(false) {
+                    return Vec::new();
+                }
+                let predicate = self.iri(predicate);
+                let mut prop = PropertyInfo {
+                    name: local_name(&predicate),
+                    predicate,
+                    range: "string".to_string(),
+                    min: Some(min.unwrap_or(1).max(0) as u64),
+                    max: match max.unwrap_or(1) { -1 => None, n => Some(n.max(0) as u64) },
+                    sem_acts: self.sem_acts(sem_acts),
+                    ..Default::default()
+                };
+                (prop.title, prop.description) = self.docs(annotations);
+                if let Some(ve) = value_expr {
+                    self.value_expr(ve, &mut prop, &shape.name, nested);
                 }
+                vec![prop]
+            }
+            TripleExpr::TripleExprRef(label) => {
+                let label = self.te_label(label);
+                let Some(included) = self.decls.iter().find_map(|d| self.labelled_in_shape(&d.shape_expr, &label)) else {
+                    return Vec::new();
+                };
+                // ShEx forbids circular inclusions; don't recurse forever on one
+                if self.including.borrow().contains(&label) {
+                    return Vec::new();
+                }
+                self.triple_expr(included, shape, nested)
             }
         }
     }
 
-    props
-}
+    fn te_label(&self, label: &TripleExprLabel) -> String {
+        match label {
+            TripleExprLabel::IriRef { value } => self.iri(value),
+            TripleExprLabel::BNode { value } => value.to_string(),
+        }
+    }
+
+    /// The triple expression labelled `label` within a shape expression, for `&label`
+    fn labelled_in_shape<'s>(&self, se: &'s ShapeExpr, label: &str) -> Option<&'s TripleExpr> {
+        match se {
+            ShapeExpr::Shape(s) => s.expression.as_ref().and_then(|e| self.labelled(&e.te, label)),
+            ShapeExpr::ShapeAnd { shape_exprs } | ShapeExpr::ShapeOr { shape_exprs } => {
+                shape_exprs.iter().find_map(|member| self.labelled_in_shape(&member.se, label))
+            }
+            ShapeExpr::ShapeNot { shape_expr } => self.labelled_in_shape(&shape_expr.se, label),
+            ShapeExpr::NodeConstraint(_) | ShapeExpr::Ref(_) | ShapeExpr::External => None,
+        }
+    }
+
+    fn labelled<'s>(&self, te: &'s TripleExpr, label: &str) -> Option<&'s TripleExpr> {
+        let (id, inner): (&Option<TripleExprLabel>, Vec<&'s TripleExpr>) = match te {
+            TripleExpr::EachOf { id, expressions, .. } | TripleExpr::OneOf { id, expressions, .. } => {
+                (id, expressions.iter().map(|e| &e.te).collect())
+            }
+            TripleExpr::TripleConstraint { id, value_expr, .. } => {
+                let found = value_expr.as_deref().and_then(|ve| self.labelled_in_shape(ve, label));
+                if found.is_some() { return found; }
+                (id, Vec::new())
+            }
+            TripleExpr::TripleExprRef(_) => return None,
+        };
+        if id.as_ref().is_some_and(|id| self.te_label(id) == label) {
+            return Some(te);
+        }
+        inner.into_iter().find_map(|e| self.labelled(e, label))
+    }
+
+    fn value_expr(&self, ve: &ShapeExpr, prop: &mut PropertyInfo, owner: &str, nested: &mut Vec<ShapeInfo>) {
+        match ve {
+            ShapeExpr::NodeConstraint(nc) => self.node_constraint(nc, prop),
+            ShapeExpr::Ref(label) => match self.decls.iter().find(|d| &d.id == label).map(|d| &d.shape_expr) {
+                Some(ShapeExpr::NodeConstraint(nc)) => self.node_constraint(nc, prop),
+                _ => prop.range = local_name(&label.to_string()),
+            },
+            ShapeExpr::External => {}
+            other => prop.range = self.nested_shape(format!("{}{}", owner, rust_type_name(&prop.name)), other, nested),
+        }
+    }
 
-fn build_prop_from_tc(tcobj: &serde_json::Map<String, JsonValue>) -> PropertyInfo {
-    let predicate = tcobj.get("predicate").and_then(|v| v.as_str()).unwrap_or("<unknown>").to_string();
-    // property name: if a CURIE/IRI, take last segment after / or # or :
-    let name = predicate.split(|c| c == '/' || c == '#' || c == ':').last().unwrap_or(&predicate).to_string();
+    fn node_constraint(&self, nc: &NodeConstraint, prop: &mut PropertyInfo) {
+        if let Some(dt) = nc.datatype() {
+            let dt = self.iri(&dt);
+            if dt == RDF_LANG_STRING {
+                prop.languages = vec!["~".to_string()];
+            }
+            prop.range = range_for_datatype(&dt);
+        } else if let Some(kind) = nc.node_kind() {
+            let (range, kind) = match kind {
+                ShexNodeKind::Iri => ("uriorcurie", NodeKind::Iri),
+                ShexNodeKind::BNode => ("nodeidentifier", NodeKind::BNode),
+                ShexNodeKind::NonLiteral => ("nodeidentifier", NodeKind::NonLiteral),
+                ShexNodeKind::Literal => ("string", NodeKind::Literal),
+            };
+            prop.range = range.to_string();
+            prop.node_kind = Some(kind);
+        }
+        for facet in nc.xs_facet().unwrap_or_default() {
+            let f = &mut prop.facets;
+            // numeric bounds that don't read back as a JSON number are dropped
+            let number = |n: &dyn std::fmt::Display| serde_json::from_str::<serde_json::Number>(&n.to_string()).ok();
+            match facet {
+                XsFacet::StringFacet(StringFacet::Length(n)) => (f.min_length, f.max_length) = (Some(n as u64), Some(n as u64)),
+                XsFacet::StringFacet(StringFacet::MinLength(n)) => f.min_length = Some(n as u64),
+                XsFacet::StringFacet(StringFacet::MaxLength(n)) => f.max_length = Some(n as u64),
+                XsFacet::StringFacet(StringFacet::Pattern(p)) => f.pattern = Some(p.regex().to_string()),
+                XsFacet::NumericFacet(NumericFacet::MinInclusive(n)) => f.min_inclusive = number(&n),
+                XsFacet::NumericFacet(NumericFacet::MinExclusive(n)) => f.min_exclusive = number(&n),
+                XsFacet::NumericFacet(NumericFacet::MaxInclusive(n)) => f.max_inclusive = number(&n),
+                XsFacet::NumericFacet(NumericFacet::MaxExclusive(n)) => f.max_exclusive = number(&n),
+                XsFacet::NumericFacet(NumericFacet::TotalDigits(n)) => f.total_digits = Some(n as u64),
+                XsFacet::NumericFacet(NumericFacet::FractionDigits(n)) => f.fraction_digits = Some(n as u64),
+            }
+        }
+        let values = nc.values().unwrap_or_default();
+        prop.values = values.iter().filter_map(|v| match v {
+            ValueSetValue::ObjectValue(ObjectValue::IriRef(i)) => Some(self.iri(i)),
+            ValueSetValue::ObjectValue(ObjectValue::Literal(l)) => Some(l.lexical_form()),
+            _ => None,
+        }).collect();
+        // `[@en @fr~]` restricts language tags rather than enumerating values;
+        // exclusions from a stem range (`@fr~ - @fr-BE`) aren't kept
+        prop.languages.extend(values.iter().filter_map(|v| match v {
+            ValueSetValue::Language { language_tag } => Some(language_tag.to_string()),
+            ValueSetValue::LanguageStem { stem } | ValueSetValue::LanguageStemRange { stem, .. } => Some(match stem {
+                LangOrWildcard::Lang(l) => format!("{}~", l),
+                LangOrWildcard::Wildcard => "~".to_string(),
+            }),
+            _ => None,
+        }));
+        prop.iri_stems = values.iter().filter_map(|v| match v {
+            ValueSetValue::IriStem { stem } => Some(IriStem { stem: self.iri(stem), exclusions: Vec::new() }),
+            ValueSetValue::IriStemRange { stem, exclusions } => Some(IriStem {
+                stem: match stem {
+                    IriRefOrWildcard::IriRef(i) => self.iri(i),
+                    IriRefOrWildcard::Wildcard => String::new(),
+                },
+                exclusions: exclusions.iter().flatten().map(|e| match e {
+                    IriExclusion::Iri(i) => self.iri(i),
+                    IriExclusion::IriStem(i) => format!("{}~", self.iri(i)),
+                }).collect(),
+            }),
+            _ => None,
+        }).collect();
+        if !prop.iri_stems.is_empty() {
+            prop.range = "uriorcurie".to_string();
+        }
+    }
 
-    let range = infer_range_from_tc(tcobj);
-    let min = tcobj.get("min").and_then(|v| v.as_u64());
-    let max = tcobj.get("max").and_then(|v| v.as_u64());
+    /// Lift an inline shape expression into a shape of its own, returning its name
+    fn nested_shape(&self, name: String, se: &ShapeExpr, nested: &mut Vec<ShapeInfo>) -> String {
+        let name = self.mint(name);
+        let mut inner = ShapeInfo { id: name.clone(), name: name.clone(), inline: true, ..Default::default() };
+        let mut inner_nested = Vec::new();
+        self.shape_expr(se, &mut inner, &mut inner_nested);
+        nested.push(inner);
+        nested.append(&mut inner_nested);
+        name
+    }
 
-    PropertyInfo { name, predicate, range, min, max }
+    /// A name for an inline shape, numbered if a declared or earlier inline shape has it
+    fn mint(&self, name: String) -> String {
+        let taken = |n: &str| self.minted.borrow().iter().any(|m| m == n)
+            || self.decls.iter().any(|d| local_name(&d.id.to_string()) == n);
+        let name = if taken(&name) {
+            (2..).map(|i| format!("{}{}", name, i)).find(|n| !taken(n)).unwrap_or(name)
+        } else {
+            name
+        };
+        self.minted.borrow_mut().push(name.clone());
+        name
+    }
 }
 
-fn infer_range_from_tc(tcobj: &serde_json::Map<String, JsonValue>) -> String {
-    if let Some(dt) = tcobj.get("datatype").and_then(|v| v.as_str()) {
-        match dt {
-            "http://www.w3.org/2001/XMLSchema#integer" => "integer".to_string(),
-            "http://www.w3.org/2001/XMLSchema#decimal" => "number".to_string(),
-            "http://www.w3.org/2001/XMLSchema#boolean" => "boolean".to_string(),
-            s if s.starts_with("http://www.w3.org/2001/XMLSchema#") => "string".to_string(),
-            other => other.to_string(),
+/// Apply a group's cardinality to its members: an optional group makes them
+/// optional and a repeated group lets them repeat
+fn group_cardinality(props: Vec<PropertyInfo>, min: Option<i32>, max: Option<i32>) -> Vec<PropertyInfo> {
+    let (min, max) = (min.unwrap_or(1), max.unwrap_or(1));
+    props.into_iter().map(|mut p| {
+        if min == 0 {
+            p.min = Some(0);
         }
-    } else if let Some(nk) = tcobj.get("nodeKind").and_then(|v| v.as_str()) {
-        match nk {
-            "iri" => "string".to_string(),
-            "literal" => "string".to_string(),
-            _ => "string".to_string(),
+        if max != 1 {
+            p.max = None;
         }
-    } else if let Some(vc) = tcobj.get("valueClass") {
-        // valueClass may be an object or a string referring to another shape
-        if vc.is_string() {
-            vc.as_str().unwrap().to_string()
-        } else if let Some(o) = vc.as_object() {
-            // might have a reference like { "type": "ShapeRef", "reference": "<label>" }
-            if let Some(reference) = o.get("reference").and_then(|v| v.as_str()) {
-                reference.to_string()
-            } else { "string".to_string() }
-        } else { "string".to_string() }
-    } else { "string".to_string() }
+        p
+    }).collect()
+}
+
+/// Slot/property name for a predicate: if a CURIE/IRI, take last segment after / or # or :
+pub fn local_name(iri: &str) -> String {
+    iri.split(['/', '#', ':']).next_back().unwrap_or(iri).to_string()
+}
+
+/// xsd datatypes: (local name, the range we read it as, JSON Schema type, JSON Schema format).
+/// The first entry for a range is the datatype written back for it; xsd
+/// datatypes not listed here are read as strings.
+const XSD_TYPES: &[(&str, &str, &str, Option<&str>)] = &[
+    ("string", "string", "string", None),
+    ("integer", "integer", "integer", None),
+    ("decimal", "number", "number", None),
+    ("boolean", "boolean", "boolean", None),
+    ("float", "float", "number", None),
+    ("double", "double", "number", None),
+    ("date", "date", "string", Some("date")),
+    ("dateTime", "datetime", "string", Some("date-time")),
+    ("time", "time", "string", Some("time")),
+    ("anyURI", "uri", "string", Some("uri")),
+    ("dateTimeStamp", "datetime", "string", Some("date-time")),
+    ("long", "integer", "integer", None),
+    ("int", "integer", "integer", None),
+    ("short", "integer", "integer", None),
+    ("byte", "integer", "integer", None),
+    ("nonNegativeInteger", "integer", "integer", None),
+    ("positiveInteger", "integer", "integer", None),
+    ("nonPositiveInteger", "integer", "integer", None),
+    ("negativeInteger", "integer", "integer", None),
+    ("unsignedLong", "integer", "integer", None),
+    ("unsignedInt", "integer", "integer", None),
+    ("unsignedShort", "integer", "integer", None),
+    ("unsignedByte", "integer", "integer", None),
+];
+
+/// How generated code types the values of a primitive range
+struct CodeTypes {
+    range: &'static str,
+    graphql: &'static str,
+    protobuf: &'static str,
+    typescript: &'static str,
+    zod: &'static str,
+    pydantic: &'static str,
+    rust: &'static str,
+    kotlin: &'static str,
+    bigquery: &'static str,
+}
+
+/// Code types per primitive range. Targets without dates, times or URIs of their own
+/// take them as strings; ranges not listed, like `string`, are typed as the first entry.
+const CODE_TYPES: &[CodeTypes] = &[
+    CodeTypes { range: "string", graphql: "String", protobuf: "string", typescript: "string", zod: "z.string()", pydantic: "str", rust: "String", kotlin: "String", bigquery: "STRING" },
+    CodeTypes { range: "integer", graphql: "Int", protobuf: "int64", typescript: "number", zod: "z.number().int()", pydantic: "int", rust: "i64", kotlin: "Long", bigquery: "INTEGER" },
+    CodeTypes { range: "number", graphql: "Float", protobuf: "double", typescript: "number", zod: "z.number()", pydantic: "float", rust: "f64", kotlin: "Double", bigquery: "NUMERIC" },
+    CodeTypes { range: "float", graphql: "Float", protobuf: "float", typescript: "number", zod: "z.number()", pydantic: "float", rust: "f32", kotlin: "Float", bigquery: "FLOAT64" },
+    CodeTypes { range: "double", graphql: "Float", protobuf: "double", typescript: "number", zod: "z.number()", pydantic: "float", rust: "f64", kotlin: "Double", bigquery: "FLOAT64" },
+    CodeTypes { range: "boolean", graphql: "Boolean", protobuf: "bool", typescript: "boolean", zod: "z.boolean()", pydantic: "bool", rust: "bool", kotlin: "Boolean", bigquery: "BOOLEAN" },
+    CodeTypes { range: "date", graphql: "String", protobuf: "string", typescript: "string", zod: "z.string().date()", pydantic: "date", rust: "String", kotlin: "String", bigquery: "DATE" },
+    // xsd:dateTime needn't have a time zone
+    CodeTypes { range: "datetime", graphql: "String", protobuf: "string", typescript: "string", zod: "z.string().datetime({ offset: true, local: true })", pydantic: "datetime", rust: "String", kotlin: "String", bigquery: "TIMESTAMP" },
+    CodeTypes { range: "time", graphql: "String", protobuf: "string", typescript: "string", zod: "z.string().time()", pydantic: "time", rust: "String", kotlin: "String", bigquery: "TIME" },
+    CodeTypes { range: "uri", graphql: "String", protobuf: "string", typescript: "string", zod: "z.string().url()", pydantic: "str", rust: "Iri", kotlin: "String", bigquery: "STRING" },
+    CodeTypes { range: "uriorcurie", graphql: "String", protobuf: "string", typescript: "string", zod: "z.string()", pydantic: "str", rust: "Iri", kotlin: "String", bigquery: "STRING" },
+];
+
+/// The code types of a range's values
+fn code_types(range: &str) -> &'static CodeTypes {
+    CODE_TYPES.iter().find(|t| t.range == range).unwrap_or(&CODE_TYPES[0])
+}
+
+/// xsd datatypes with no primitive range of their own, kept as IRIs for the JSON Schema `format` they have
+const XSD_FORMATS: &[(&str, &str)] = &[
+    ("http://www.w3.org/2001/XMLSchema#duration", "duration"),
+];
+
+pub(crate) const XSD: &str = "http://www.w3.org/2001/XMLSchema#";
+
+const RDFS: &str = "http://www.w3.org/2000/01/rdf-schema#";
+
+pub const RDF_LANG_STRING: &str = "http://www.w3.org/1999/02/22-rdf-syntax-ns#langString";
+
+/// Map a datatype IRI onto one of our primitive ranges; non-xsd datatypes are kept as IRIs
+pub fn range_for_datatype(dt: &str) -> String {
+    if dt == RDF_LANG_STRING {
+        // the language tag lives in `PropertyInfo::languages`
+        return "string".to_string();
+    }
+    match dt.strip_prefix(XSD) {
+        Some(local) => match XSD_TYPES.iter().find(|t| t.0 == local) {
+            Some(t) => t.1.to_string(),
+            None if XSD_FORMATS.iter().any(|f| f.0 == dt) => dt.to_string(),
+            None => "string".to_string(),
+        },
+        None => dt.to_string(),
+    }
+}
+
+/// Range for a JSON Schema `type` and `format`, e.g. `string` + `date` -> `date`
+pub fn range_for_json_type(json_type: &str, format: Option<&str>) -> Option<&'static str> {
+    if json_type == "string" && format == Some("iri") {
+        return Some("uriorcurie");
+    }
+    if let Some(f) = XSD_FORMATS.iter().find(|f| json_type == "string" && Some(f.1) == format) {
+        return Some(f.0);
+    }
+    XSD_TYPES.iter().find(|t| t.2 == json_type && t.3 == format).map(|t| t.1)
 }
 
 /// Build a LinkML YAML document from shapes
 pub fn build_linkml_doc(input: &Path, shapes: &[ShapeInfo]) -> anyhow::Result<String> {
+    build_linkml_doc_with_prefixes(input, shapes, &Prefixes::default())
+}
+
+/// Build a LinkML YAML document that also declares the source schema's prefixes
+pub fn build_linkml_doc_with_prefixes(input: &Path, shapes: &[ShapeInfo], prefixes: &Prefixes) -> anyhow::Result<String> {
+    serde_yaml::to_string(&linkml_value(input, shapes, prefixes)).context("serialize LinkML YAML")
+}
+
+/// Build the same LinkML document as `build_linkml_doc`, serialized as JSON
+pub fn build_linkml_json(input: &Path, shapes: &[ShapeInfo]) -> anyhow::Result<String> {
+    build_linkml_json_with_prefixes(input, shapes, &Prefixes::default())
+}
+
+/// Build the same LinkML document as `build_linkml_doc_with_prefixes`, serialized as JSON
+pub fn build_linkml_json_with_prefixes(input: &Path, shapes: &[ShapeInfo], prefixes: &Prefixes) -> anyhow::Result<String> {
+    serde_json::to_string_pretty(&linkml_value(input, shapes, prefixes)).context("serialize LinkML JSON")
+}
+
+fn linkml_value(input: &Path, shapes: &[ShapeInfo], declared: &Prefixes) -> YamlValue {
     // Build YAML mapping using serde_yaml::Value
     let mut root = YamlMapping::new();
 
@@ -166,75 +852,2506 @@ pub fn build_linkml_doc(input: &Path, shapes: &[ShapeInfo]) -> anyhow::Result<St
     // prefixes: allow conversion back to CURIEs later
     let mut prefixes = YamlMapping::new();
     prefixes.insert(YamlValue::String("ex".to_string()), YamlValue::String("http://example.org/".to_string()));
+    for (prefix, ns) in declared.prefixes.iter() {
+        prefixes.insert(YamlValue::String(prefix.clone()), YamlValue::String(ns.clone()));
+    }
+    // LinkML has no BASE; a `base` prefix made the default stands in for it
+    if let Some(base) = &declared.base {
+        prefixes.insert(YamlValue::String("base".to_string()), YamlValue::String(base.clone()));
+    }
     root.insert(YamlValue::String("prefixes".to_string()), YamlValue::Mapping(prefixes));
+    if declared.base.is_some() {
+        root.insert(YamlValue::String("default_prefix".to_string()), YamlValue::String("base".to_string()));
+    }
+    // class and slot URIs are abbreviated with the prefixes just declared
+    let mut curies = Prefixes { base: None, prefixes: vec![("ex".to_string(), "http://example.org/".to_string())] };
+    curies.prefixes.extend(declared.prefixes.iter().cloned());
+    curies.prefixes.extend(declared.base.iter().map(|b| ("base".to_string(), b.clone())));
+    // names without a namespace (e.g. from JSON Schema) keep LinkML's default URIs
+    let uri = |iri: &str| (iri.contains(':')).then(|| YamlValue::String(curies.compact(iri).unwrap_or_else(|| iri.to_string())));
 
     // classes and slots
     let mut classes_map = YamlMapping::new();
     let mut slots_map = YamlMapping::new();
+    let mut enums_map = YamlMapping::new();
+    let mut types_map = YamlMapping::new();
+    // (class, slot, definition) for each use of a slot
+    let mut slot_uses: Vec<(String, String, YamlMapping)> = Vec::new();
 
     for s in shapes.iter() {
         let class_name = s.name.clone();
         let mut class_map = YamlMapping::new();
-        // slot refs
-        let slot_refs: Vec<YamlValue> = s.properties.iter().map(|p| YamlValue::String(p.name.clone())).collect();
+        linkml_docs(&mut class_map, &s.title, &s.description);
+        if let Some(class_uri) = uri(&s.id) {
+            class_map.insert(YamlValue::String("class_uri".to_string()), class_uri);
+        }
+        if s.start {
+            class_map.insert(YamlValue::String("tree_root".to_string()), YamlValue::Bool(true));
+        }
+        if s.is_abstract {
+            class_map.insert(YamlValue::String("abstract".to_string()), YamlValue::Bool(true));
+        }
+        // slot refs, one per name however many triple constraints share it
+        let properties = merged_properties(&s.properties);
+        let slot_refs: Vec<YamlValue> = s.identifier.iter().chain(properties.iter().map(|p| &p.name)).map(|n| YamlValue::String(n.clone())).collect();
         class_map.insert(YamlValue::String("slots".to_string()), YamlValue::Sequence(slot_refs));
-        classes_map.insert(YamlValue::String(class_name.clone()), YamlValue::Mapping(class_map));
-
-        for p in s.properties.iter() {
+        // the identifier slot holds the instance's own IRI
+        if let Some(identifier) = &s.identifier {
             let mut slot_entry = YamlMapping::new();
-            // range may be a data type or another class name
-            let range = if p.range.contains(':') || p.range.starts_with("http") { // IRI/fq
-                // preserve as IRI string in the slot mapping
-                YamlValue::String(p.range.clone())
+            slot_entry.insert(YamlValue::String("identifier".to_string()), YamlValue::Bool(true));
+            slot_entry.insert(YamlValue::String("range".to_string()), YamlValue::String("uriorcurie".to_string()));
+            slot_uses.push((class_name.clone(), identifier.clone(), slot_entry));
+        }
+        // LinkML has single inheritance; further parents become mixins
+        if let Some((parent, mixins)) = s.extends.split_first() {
+            class_map.insert(YamlValue::String("is_a".to_string()), YamlValue::String(parent.clone()));
+            if !mixins.is_empty() {
+                let mixins = mixins.iter().map(|m| YamlValue::String(m.clone())).collect();
+                class_map.insert(YamlValue::String("mixins".to_string()), YamlValue::Sequence(mixins));
+            }
+        }
+        // LinkML has no closed or external classes, or semantic actions, so they're kept as annotations
+        let mut annotations = YamlMapping::new();
+        if s.closed {
+            annotations.insert(YamlValue::String("closed".to_string()), YamlValue::Bool(true));
+        }
+        if s.external {
+            annotations.insert(YamlValue::String("external".to_string()), YamlValue::Bool(true));
+        }
+        if !s.sem_acts.is_empty() {
+            annotations.insert(YamlValue::String("semantic_actions".to_string()), serde_yaml::to_value(&s.sem_acts).unwrap_or(YamlValue::Null));
+        }
+        if !annotations.is_empty() {
+            class_map.insert(YamlValue::String("annotations".to_string()), YamlValue::Mapping(annotations));
+        }
+        // LinkML allows one `exactly_one_of` and one `any_of` per class; further groups go under `all_of`
+        let mut all_of = Vec::new();
+        let mut none_of = Vec::new();
+        let mut rules = Vec::new();
+        for c in s.constraints.iter() {
+            let (key, value) = match c {
+                ShapeConstraint::OneOf(alternatives) => ("exactly_one_of", linkml_exactly_one_of(alternatives)),
+                ShapeConstraint::AnyOf(names) => ("any_of", YamlValue::Sequence(names.iter().map(|n| linkml_is_a(n)).collect())),
+                ShapeConstraint::Not(name) => {
+                    none_of.push(linkml_is_a(name));
+                    continue;
+                }
+                ShapeConstraint::Rule { preconditions, postconditions, elseconditions } => {
+                    rules.push(linkml_rule(preconditions, postconditions, elseconditions));
+                    continue;
+                }
+            };
+            let key = YamlValue::String(key.to_string());
+            if class_map.contains_key(&key) {
+                let mut m = YamlMapping::new();
+                m.insert(key, value);
+                all_of.push(YamlValue::Mapping(m));
+            } else {
+                class_map.insert(key, value);
+            }
+        }
+        if !none_of.is_empty() {
+            class_map.insert(YamlValue::String("none_of".to_string()), YamlValue::Sequence(none_of));
+        }
+        if !all_of.is_empty() {
+            class_map.insert(YamlValue::String("all_of".to_string()), YamlValue::Sequence(all_of));
+        }
+        if !rules.is_empty() {
+            class_map.insert(YamlValue::String("rules".to_string()), YamlValue::Sequence(rules));
+        }
+        for p in properties.iter() {
+            let mut slot_entry = YamlMapping::new();
+            linkml_docs(&mut slot_entry, &p.title, &p.description);
+            if let Some(slot_uri) = uri(&p.predicate) {
+                slot_entry.insert(YamlValue::String("slot_uri".to_string()), slot_uri);
+            }
+            // range may be a data type or another class name; a datatype with no
+            // built-in counterpart ranges over a custom type with that `uri`
+            let range = if p.range.contains(':') && !shapes.iter().any(|other| other.name == p.range) {
+                YamlValue::String(linkml_type(&mut types_map, &p.range, &curies))
             } else {
                 YamlValue::String(p.range.clone())
             };
+            // enumerated properties range over a generated enum
+            // a single literal is an exact value rather than an enumeration
+            let exact = single_literal(p);
+            if let Some(v) = exact {
+                slot_entry.insert(YamlValue::String("equals_string".to_string()), YamlValue::String(v.to_string()));
+            }
+            let range = if p.values.is_empty() || exact.is_some() {
+                range
+            } else {
+                let mut pvs = YamlMapping::new();
+                for v in p.values.iter() {
+                    pvs.insert(YamlValue::String(v.clone()), YamlValue::Mapping(YamlMapping::new()));
+                }
+                let mut enum_entry = YamlMapping::new();
+                enum_entry.insert(YamlValue::String("permissible_values".to_string()), YamlValue::Mapping(pvs));
+                let enum_entry = YamlValue::Mapping(enum_entry);
+                // another class's value set for the same slot gets an enum of its own
+                let mut enum_name = enum_name_for(p);
+                if enums_map.get(enum_name.as_str()).is_some_and(|e| e != &enum_entry) {
+                    enum_name = format!("{}{}", class_name, enum_name);
+                }
+                enums_map.insert(YamlValue::String(enum_name.clone()), enum_entry);
+                YamlValue::String(enum_name)
+            };
             slot_entry.insert(YamlValue::String("range".to_string()), range);
-            if let Some(min) = p.min { slot_entry.insert(YamlValue::String("min_count".to_string()), YamlValue::Number(min.into())); }
-            if let Some(max) = p.max { slot_entry.insert(YamlValue::String("max_count".to_string()), YamlValue::Number(max.into())); }
-            slots_map.insert(YamlValue::String(p.name.clone()), YamlValue::Mapping(slot_entry));
+            // a repeated predicate whose constraints have different ranges takes any of them
+            let mut ranges: Vec<&str> = Vec::new();
+            for q in s.properties.iter().filter(|q| q.name == p.name && q.values.is_empty()) {
+                if !ranges.contains(&q.range.as_str()) { ranges.push(&q.range); }
+            }
+            let mut key = "any_of";
+            if let Some(expr) = &p.range_expr {
+                key = expr.linkml_key();
+                ranges = expr.ranges().iter().map(String::as_str).collect();
+            }
+            if ranges.len() > 1 || p.range_expr.is_some() {
+                slot_entry.remove(YamlValue::String("range".to_string()));
+                let expressions = ranges.iter().map(|r| {
+                    let mut m = YamlMapping::new();
+                    m.insert(YamlValue::String("range".to_string()), YamlValue::String(r.to_string()));
+                    YamlValue::Mapping(m)
+                }).collect();
+                slot_entry.insert(YamlValue::String(key.to_string()), YamlValue::Sequence(expressions));
+            }
+            // `required` and `multivalued` cover ?, *, + and the default; other counts are explicit cardinalities
+            let min = p.min.unwrap_or(0);
+            if min >= 1 { slot_entry.insert(YamlValue::String("required".to_string()), YamlValue::Bool(true)); }
+            if min > 1 { slot_entry.insert(YamlValue::String("minimum_cardinality".to_string()), YamlValue::Number(min.into())); }
+            if p.max != Some(1) { slot_entry.insert(YamlValue::String("multivalued".to_string()), YamlValue::Bool(true)); }
+            if let Some(max) = p.max.filter(|max| *max != 1) { slot_entry.insert(YamlValue::String("maximum_cardinality".to_string()), YamlValue::Number(max.into())); }
+            match p.inlining {
+                Some(Inlining::Reference) => { slot_entry.insert(YamlValue::String("inlined".to_string()), YamlValue::Bool(false)); }
+                Some(Inlining::Inlined) => { slot_entry.insert(YamlValue::String("inlined".to_string()), YamlValue::Bool(true)); }
+                Some(Inlining::InlinedAsList) => { slot_entry.insert(YamlValue::String("inlined_as_list".to_string()), YamlValue::Bool(true)); }
+                None => {}
+            }
+            // LinkML has no length, exclusive bound or digit facets
+            let number = |n: &serde_json::Number| serde_yaml::to_value(n).unwrap_or(YamlValue::Null);
+            if let Some(n) = &p.facets.min_inclusive { slot_entry.insert(YamlValue::String("minimum_value".to_string()), number(n)); }
+            if let Some(n) = &p.facets.max_inclusive { slot_entry.insert(YamlValue::String("maximum_value".to_string()), number(n)); }
+            if let Some(pattern) = &p.facets.pattern { slot_entry.insert(YamlValue::String("pattern".to_string()), YamlValue::String(pattern.clone())); }
+            // nor language tags, IRI stems or semantic actions, so they're kept as annotations:
+            // languages in ShEx's `en fr~` notation, the others as structured values
+            let mut annotations = YamlMapping::new();
+            if !p.languages.is_empty() {
+                annotations.insert(YamlValue::String("languages".to_string()), YamlValue::String(p.languages.join(" ")));
+            }
+            if !p.iri_stems.is_empty() {
+                annotations.insert(YamlValue::String("iri_stems".to_string()), serde_yaml::to_value(&p.iri_stems).unwrap_or(YamlValue::Null));
+            }
+            if !p.sem_acts.is_empty() {
+                annotations.insert(YamlValue::String("semantic_actions".to_string()), serde_yaml::to_value(&p.sem_acts).unwrap_or(YamlValue::Null));
+            }
+            if !annotations.is_empty() {
+                slot_entry.insert(YamlValue::String("annotations".to_string()), YamlValue::Mapping(annotations));
+            }
+            slot_uses.push((class_name.clone(), p.name.clone(), slot_entry));
+        }
+        classes_map.insert(YamlValue::String(class_name.clone()), YamlValue::Mapping(class_map));
+    }
+
+    // a slot's definition holds what every class using it agrees on; a class
+    // whose constraints differ gets the rest as `slot_usage` for that slot
+    let mut slot_names: Vec<&str> = Vec::new();
+    for (_, name, _) in slot_uses.iter() {
+        if !slot_names.contains(&name.as_str()) { slot_names.push(name); }
+    }
+    for name in slot_names {
+        let uses: Vec<&(String, String, YamlMapping)> = slot_uses.iter().filter(|u| u.1 == name).collect();
+        let global: YamlMapping = uses[0].2.iter()
+            .filter(|(k, v)| uses.iter().all(|u| u.2.get(*k) == Some(*v)))
+            .map(|(k, v)| (k.clone(), v.clone()))
+            .collect();
+        for (class_name, _, entry) in uses.iter().filter(|u| u.2 != global) {
+            let usage: YamlMapping = entry.iter().filter(|(k, _)| !global.contains_key(*k)).map(|(k, v)| (k.clone(), v.clone())).collect();
+            if let Some(YamlValue::Mapping(class_map)) = classes_map.get_mut(class_name.as_str()) {
+                if let YamlValue::Mapping(slot_usage) = class_map.entry(YamlValue::String("slot_usage".to_string())).or_insert(YamlValue::Mapping(YamlMapping::new())) {
+                    slot_usage.insert(YamlValue::String(name.to_string()), YamlValue::Mapping(usage));
+                }
+            }
         }
+        slots_map.insert(YamlValue::String(name.to_string()), YamlValue::Mapping(global));
     }
 
     root.insert(YamlValue::String("classes".to_string()), YamlValue::Mapping(classes_map));
     root.insert(YamlValue::String("slots".to_string()), YamlValue::Mapping(slots_map));
+    if !enums_map.is_empty() {
+        root.insert(YamlValue::String("enums".to_string()), YamlValue::Mapping(enums_map));
+    }
+    if !types_map.is_empty() {
+        root.insert(YamlValue::String("types".to_string()), YamlValue::Mapping(types_map));
+    }
 
-    let doc = YamlValue::Mapping(root);
-    Ok(serde_yaml::to_string(&doc).context("serialize LinkML YAML")?)
+    YamlValue::Mapping(root)
 }
 
-/// Build a basic JSON Schema (draft-07) with definitions per shape
-pub fn build_json_schema(_input: &Path, shapes: &[ShapeInfo]) -> serde_json::Value {
-    use serde_json::{json, Map as JsonMap, Value as JsonValue};
+/// The name of a custom type, a `typeof: string` with the datatype as its `uri`,
+/// added to `types` unless it's there already; named after the datatype's local
+/// name, qualified by its prefix if another datatype has that name
+fn linkml_type(types: &mut YamlMapping, datatype: &str, curies: &Prefixes) -> String {
+    let uri = curies.compact(datatype).unwrap_or_else(|| datatype.to_string());
+    let mut entry = YamlMapping::new();
+    entry.insert(YamlValue::String("typeof".to_string()), YamlValue::String("string".to_string()));
+    entry.insert(YamlValue::String("uri".to_string()), YamlValue::String(uri.clone()));
+    let entry = YamlValue::Mapping(entry);
+    let mut name = local_name(datatype);
+    if types.get(name.as_str()).is_some_and(|e| e != &entry) {
+        name = uri.replace(':', "_");
+    }
+    types.insert(YamlValue::String(name.clone()), entry);
+    name
+}
 
-    let mut defs = JsonMap::new();
+/// `exactly_one_of` branches for a OneOf group: each alternative's slots as
+/// `slot_conditions`, marked `required` where the alternative needs them
+fn linkml_exactly_one_of(alternatives: &[Vec<PropertyInfo>]) -> YamlValue {
+    YamlValue::Sequence(alternatives.iter().map(|alt| {
+        let mut conditions = YamlMapping::new();
+        for p in alt.iter() {
+            let mut condition = YamlMapping::new();
+            if p.min.unwrap_or(0) > 0 {
+                condition.insert(YamlValue::String("required".to_string()), YamlValue::Bool(true));
+            }
+            conditions.insert(YamlValue::String(p.name.clone()), YamlValue::Mapping(condition));
+        }
+        let mut branch = YamlMapping::new();
+        branch.insert(YamlValue::String("slot_conditions".to_string()), YamlValue::Mapping(conditions));
+        YamlValue::Mapping(branch)
+    }).collect())
+}
 
-    for s in shapes.iter() {
-        let mut props = JsonMap::new();
-        let mut required: Vec<JsonValue> = Vec::new();
-        for p in s.properties.iter() {
-            let jt = match p.range.as_str() {
-                "integer" => json!({ "type": "integer" }),
-                "number" => json!({ "type": "number" }),
-                "boolean" => json!({ "type": "boolean" }),
-                _ => json!({ "type": "string" }),
-            };
-            props.insert(p.name.clone(), jt);
+/// A LinkML rule, each of its conditions' slots under `slot_conditions`, marked `required`
+/// where the condition needs the slot, and with the value it must equal, or any of them
+fn linkml_rule(preconditions: &[PropertyInfo], postconditions: &[PropertyInfo], elseconditions: &[PropertyInfo]) -> YamlValue {
+    let equals = |v: &String| {
+        let mut m = YamlMapping::new();
+        m.insert(YamlValue::String("equals_string".to_string()), YamlValue::String(v.clone()));
+        m
+    };
+    let conditions = |props: &[PropertyInfo]| {
+        let mut conditions = YamlMapping::new();
+        for p in props.iter() {
+            let mut condition = YamlMapping::new();
             if p.min.unwrap_or(0) > 0 {
-                required.push(JsonValue::String(p.name.clone()));
+                condition.insert(YamlValue::String("required".to_string()), YamlValue::Bool(true));
             }
+            match p.values.as_slice() {
+                [] => {}
+                [value] => condition.extend(equals(value)),
+                values => {
+                    let any_of = values.iter().map(|v| YamlValue::Mapping(equals(v))).collect();
+                    condition.insert(YamlValue::String("any_of".to_string()), YamlValue::Sequence(any_of));
+                }
+            }
+            conditions.insert(YamlValue::String(p.name.clone()), YamlValue::Mapping(condition));
+        }
+        let mut expression = YamlMapping::new();
+        expression.insert(YamlValue::String("slot_conditions".to_string()), YamlValue::Mapping(conditions));
+        YamlValue::Mapping(expression)
+    };
+    let mut rule = YamlMapping::new();
+    for (key, props) in [("preconditions", preconditions), ("postconditions", postconditions), ("elseconditions", elseconditions)] {
+        if !props.is_empty() {
+            rule.insert(YamlValue::String(key.to_string()), conditions(props));
         }
-        let mut obj = JsonMap::new();
-        obj.insert("type".to_string(), JsonValue::String("object".to_string()));
-        obj.insert("properties".to_string(), JsonValue::Object(props));
-        if !required.is_empty() { obj.insert("required".to_string(), JsonValue::Array(required)); }
-        defs.insert(s.name.clone(), JsonValue::Object(obj));
     }
+    YamlValue::Mapping(rule)
+}
 
-    let mut root = JsonMap::new();
-    root.insert("$schema".to_string(), JsonValue::String("http://json-schema.org/draft-07/schema#".to_string()));
-    root.insert("$id".to_string(), JsonValue::String("http://example.org/generated-schema".to_string()));
-    root.insert("definitions".to_string(), JsonValue::Object(defs));
+/// LinkML `title` and `description` of a class or slot
+fn linkml_docs(entry: &mut YamlMapping, title: &Option<String>, description: &Option<String>) {
+    for (key, text) in [("title", title), ("description", description)] {
+        if let Some(text) = text {
+            entry.insert(YamlValue::String(key.to_string()), YamlValue::String(text.clone()));
+        }
+    }
+}
 
-    JsonValue::Object(root)
+/// An anonymous class expression standing for a named class, e.g. in `any_of`
+fn linkml_is_a(name: &str) -> YamlValue {
+    let mut m = YamlMapping::new();
+    m.insert(YamlValue::String("is_a".to_string()), YamlValue::String(name.to_string()));
+    YamlValue::Mapping(m)
+}
+
+/// A shape's properties with those sharing a name (several triple constraints on one
+/// predicate) merged into one: the counts add up, value sets, language tags and IRI
+/// stems are pooled when every constraint has one, and an unshared range becomes `string`
+pub fn merged_properties(props: &[PropertyInfo]) -> Vec<PropertyInfo> {
+    let mut merged: Vec<PropertyInfo> = Vec::new();
+    for p in props.iter() {
+        if merged.iter().any(|m| m.name == p.name) {
+            continue;
+        }
+        let group: Vec<&PropertyInfo> = props.iter().filter(|q| q.name == p.name).collect();
+        if group.len() == 1 {
+            merged.push(p.clone());
+            continue;
+        }
+        let all = |f: &dyn Fn(&PropertyInfo) -> bool| group.iter().all(|q| f(q));
+        let enumerated = all(&|q| !q.values.is_empty() || !q.iri_stems.is_empty());
+        let tagged = all(&|q| !q.languages.is_empty());
+        let mut values: Vec<String> = Vec::new();
+        let mut iri_stems: Vec<IriStem> = Vec::new();
+        let mut languages: Vec<String> = Vec::new();
+        for q in group.iter() {
+            if enumerated {
+                values.extend(q.values.iter().filter(|v| !values.contains(v)).cloned().collect::<Vec<_>>());
+                iri_stems.extend(q.iri_stems.iter().filter(|s| !iri_stems.contains(s)).cloned().collect::<Vec<_>>());
+            }
+            if tagged {
+                languages.extend(q.languages.iter().filter(|l| !languages.contains(l)).cloned().collect::<Vec<_>>());
+            }
+        }
+        merged.push(PropertyInfo {
+            range: if group.iter().all(|q| q.range == p.range) { p.range.clone() } else { "string".to_string() },
+            min: Some(group.iter().map(|q| q.min.unwrap_or(0)).sum()),
+            max: group.iter().map(|q| q.max).sum(),
+            values,
+            node_kind: if group.iter().all(|q| q.node_kind == p.node_kind) { p.node_kind } else { None },
+            facets: if group.iter().all(|q| q.facets == p.facets) { p.facets.clone() } else { Facets::default() },
+            languages,
+            iri_stems,
+            title: group.iter().find_map(|q| q.title.clone()),
+            description: group.iter().find_map(|q| q.description.clone()),
+            sem_acts: group.iter().flat_map(|q| q.sem_acts.iter().cloned()).collect(),
+            range_expr: None,
+            inlining: None,
+            ..p.clone()
+        });
+    }
+    merged
+}
+
+/// The value of a value set holding just one literal (not an IRI or stem)
+pub fn single_literal(p: &PropertyInfo) -> Option<&str> {
+    match p.values.as_slice() {
+        [v] if p.iri_stems.is_empty() && !v.starts_with("http://") && !v.starts_with("https://") => Some(v),
+        _ => None,
+    }
+}
+
+/// LinkML enum name for an enumerated property, e.g. `color` -> `ColorEnum`
+pub fn enum_name_for(p: &PropertyInfo) -> String {
+    let mut chars = p.name.chars();
+    match chars.next() {
+        Some(c) => format!("{}{}Enum", c.to_uppercase(), chars.as_str()),
+        None => "Enum".to_string(),
+    }
+}
+
+/// The enum a code generator types `p` with in class `class_name`, and whether it
+/// still has to be declared: a value set already declared reuses its enum, and
+/// another class's value set for the same property gets one named after the class
+fn declared_enum(declared: &mut Vec<(String, Vec<String>)>, class_name: &str, p: &PropertyInfo) -> (String, bool) {
+    let enum_name = enum_name_for(p);
+    let candidates = std::iter::once(enum_name.clone())
+        .chain(std::iter::once(format!("{}{}", class_name, enum_name)))
+        .chain((2..).map(|i| format!("{}{}{}", class_name, enum_name, i)));
+    for name in candidates {
+        match declared.iter().find(|(declared_name, _)| *declared_name == name) {
+            Some((_, values)) if *values == p.values => return (name, false),
+            Some(_) => continue,
+            None => {
+                declared.push((name.clone(), p.values.clone()));
+                return (name, true);
+            }
+        }
+    }
+    (enum_name, false)
+}
+
+/// Shapes named by `roots` (matched by id, name or local name) and every
+/// shape they reach through references, in their original order
+pub fn select_shapes(shapes: &[ShapeInfo], roots: &[String]) -> anyhow::Result<Vec<ShapeInfo>> {
+    let mut keep: Vec<&str> = Vec::new();
+    let mut todo: Vec<&str> = Vec::new();
+    for root in roots.iter() {
+        let shape = find_shape(shapes, root).with_context(|| format!("no shape matches `{}`", root))?;
+        todo.push(&shape.name);
+    }
+    while let Some(name) = todo.pop() {
+        if keep.contains(&name) { continue; }
+        keep.push(name);
+        if let Some(shape) = shapes.iter().find(|s| s.name == name) {
+            for other in shape_references(shape) {
+                if shapes.iter().any(|s| &s.name == other) { todo.push(other); }
+            }
+        }
+    }
+    Ok(shapes.iter().filter(|s| keep.contains(&s.name.as_str())).cloned().collect())
+}
+
+/// The shapes one of several schemas converted together writes: those it defines,
+/// named in `own`, then those of the others that they refer to, as external shapes
+/// whose ids `locate` gives, e.g. a `$ref` into the output of the schema defining them
+pub fn schema_part(shapes: &[ShapeInfo], own: &[String], locate: impl Fn(&ShapeInfo) -> String) -> Vec<ShapeInfo> {
+    let mut part: Vec<ShapeInfo> = shapes.iter().filter(|s| own.contains(&s.name)).cloned().collect();
+    let mut referenced: Vec<&str> = Vec::new();
+    for s in part.iter() {
+        for name in shape_references(s) {
+            if !own.contains(name) && !referenced.contains(&name.as_str()) { referenced.push(name); }
+        }
+    }
+    let elsewhere: Vec<ShapeInfo> = shapes.iter().filter(|s| referenced.contains(&s.name.as_str()))
+        .map(|s| ShapeInfo {
+            id: locate(s),
+            properties: Vec::new(),
+            extends: Vec::new(),
+            constraints: Vec::new(),
+            external: true,
+            ..s.clone()
+        })
+        .collect();
+    part.extend(elsewhere);
+    part
+}
+
+/// The names a shape refers to, which may be shapes: its properties' ranges, those of its
+/// constraints' properties, the shapes it extends and those its constraints name
+fn shape_references(s: &ShapeInfo) -> Vec<&String> {
+    let ranges = s.properties.iter()
+        .chain(s.constraints.iter().flat_map(|c| match c {
+            ShapeConstraint::OneOf(alternatives) => alternatives.iter().flatten().collect::<Vec<_>>(),
+            ShapeConstraint::Rule { preconditions, postconditions, elseconditions } => preconditions.iter().chain(postconditions).chain(elseconditions).collect(),
+            ShapeConstraint::AnyOf(_) | ShapeConstraint::Not(_) => Vec::new(),
+        }))
+        .flat_map(|p| std::iter::once(&p.range).chain(p.range_expr.iter().flat_map(|e| e.ranges())));
+    let names = s.constraints.iter().flat_map(|c| match c {
+        ShapeConstraint::AnyOf(names) => names.iter().collect::<Vec<_>>(),
+        ShapeConstraint::Not(name) => vec![name],
+        ShapeConstraint::OneOf(_) | ShapeConstraint::Rule { .. } => Vec::new(),
+    });
+    ranges.chain(s.extends.iter()).chain(names).collect()
+}
+
+/// Put shapes, their properties and the prefix declarations in name order, so every
+/// output is the same however the source schema happened to order them
+pub fn sort_schema(shapes: &mut [ShapeInfo], prefixes: &mut Prefixes) {
+    shapes.sort_by(|a, b| a.name.cmp(&b.name));
+    for s in shapes.iter_mut() {
+        s.properties.sort_by(|a, b| a.name.cmp(&b.name));
+    }
+    prefixes.prefixes.sort();
+}
+
+/// Look a shape up by id, name or the local name of a label
+pub fn find_shape<'a>(shapes: &'a [ShapeInfo], label: &str) -> Option<&'a ShapeInfo> {
+    shapes.iter().find(|s| s.id == label || s.name == label)
+        .or_else(|| shapes.iter().find(|s| s.name == local_name(label)))
+}
+
+/// Parse a namespace rewrite, `old-namespace=new-namespace`
+pub fn parse_rewrite(arg: &str) -> anyhow::Result<(String, String)> {
+    match arg.split_once('=') {
+        Some((old, new)) if !old.is_empty() && !new.is_empty() => Ok((old.to_string(), new.to_string())),
+        _ => anyhow::bail!("expected `old-namespace=new-namespace`, got `{}`", arg),
+    }
+}
+
+/// Move IRIs from old namespaces to new ones: shape ids, predicates, IRI ranges,
+/// values and stems, and the base and prefix declarations. Each IRI is rewritten
+/// once, under the longest old namespace it starts with.
+pub fn rewrite_namespaces(shapes: &mut [ShapeInfo], prefixes: &mut Prefixes, rewrites: &[(String, String)]) {
+    let rewrite = |iri: &mut String| {
+        let matched = rewrites.iter()
+            .filter(|(old, _)| iri.starts_with(old.as_str()))
+            .max_by_key(|(old, _)| old.len());
+        if let Some((old, new)) = matched {
+            *iri = format!("{}{}", new, &iri[old.len()..]);
+        }
+    };
+    for s in shapes.iter_mut() {
+        rewrite(&mut s.id);
+        let alternatives = s.constraints.iter_mut().flat_map(|c| match c {
+            ShapeConstraint::OneOf(alternatives) => alternatives.iter_mut().flatten().collect::<Vec<_>>(),
+            ShapeConstraint::Rule { preconditions, postconditions, elseconditions } => preconditions.iter_mut().chain(postconditions).chain(elseconditions).collect(),
+            ShapeConstraint::Not(_) | ShapeConstraint::AnyOf(_) => Vec::new(),
+        });
+        for p in s.properties.iter_mut().chain(alternatives) {
+            rewrite(&mut p.predicate);
+            if p.range.starts_with("http://") || p.range.starts_with("https://") { rewrite(&mut p.range); }
+            for v in p.values.iter_mut().filter(|v| v.starts_with("http://") || v.starts_with("https://")) {
+                rewrite(v);
+            }
+            for stem in p.iri_stems.iter_mut() {
+                rewrite(&mut stem.stem);
+                stem.exclusions.iter_mut().for_each(rewrite);
+            }
+        }
+    }
+    prefixes.base.iter_mut().for_each(rewrite);
+    prefixes.prefixes.iter_mut().for_each(|(_, ns)| rewrite(ns));
+}
+
+/// The JSON Schema draft a schema is written for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum JsonSchemaDraft {
+    /// draft-07, keeping shapes in `definitions`
+    #[default]
+    Draft07,
+    /// 2020-12, keeping shapes in `$defs`
+    Draft2020_12,
+}
+
+impl JsonSchemaDraft {
+    /// The `$schema` IRI of the draft's meta-schema
+    pub fn meta_schema(&self) -> &'static str {
+        match self {
+            JsonSchemaDraft::Draft07 => "http://json-schema.org/draft-07/schema#",
+            JsonSchemaDraft::Draft2020_12 => "https://json-schema.org/draft/2020-12/schema",
+        }
+    }
+
+    /// The keyword definitions are kept under, e.g. `$defs` for `#/$defs/Person`
+    pub fn definitions_keyword(&self) -> &'static str {
+        match self {
+            JsonSchemaDraft::Draft07 => "definitions",
+            JsonSchemaDraft::Draft2020_12 => "$defs",
+        }
+    }
+
+    /// The keyword listing the properties a property requires: draft-07 has only the
+    /// array form of `dependencies`, which 2020-12 split out as `dependentRequired`
+    pub fn dependent_required_keyword(&self) -> &'static str {
+        match self {
+            JsonSchemaDraft::Draft07 => "dependencies",
+            JsonSchemaDraft::Draft2020_12 => "dependentRequired",
+        }
+    }
+
+    /// The keyword and value naming a definition so `<$id>#name` reaches it from other
+    /// documents: a 2020-12 `$anchor`, or a draft-07 plain-name fragment `$id`.
+    /// `None` when `name` isn't a valid anchor.
+    pub fn anchor(&self, name: &str) -> Option<(&'static str, String)> {
+        let mut chars = name.chars();
+        let valid = chars.next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+            && chars.all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '.' | '_'));
+        if !valid {
+            return None;
+        }
+        Some(match self {
+            JsonSchemaDraft::Draft07 => ("$id", format!("#{}", name)),
+            JsonSchemaDraft::Draft2020_12 => ("$anchor", name.to_string()),
+        })
+    }
+}
+
+/// What a JSON Schema validates a document against at its top level
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum JsonSchemaRoot {
+    /// a `$ref` to the root shape (`anyOf` for several); the START shape if none are named
+    #[default]
+    Start,
+    /// nothing: the schema is only a bag of definitions for other schemas to `$ref`
+    Definitions,
+    /// `oneOf` over the concrete top-level shapes that no other of them extends, so
+    /// a document matches one branch rather than also matching its shape's parents
+    OneOf,
+}
+
+/// Whether the objects of a JSON Schema accept properties their shapes don't declare
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AdditionalProperties {
+    /// only CLOSED shapes reject them
+    #[default]
+    FromShex,
+    /// every object accepts them
+    Allow,
+    /// every object rejects them
+    Deny,
+}
+
+/// The `$id` of schemas that don't name one
+pub const DEFAULT_JSON_SCHEMA_ID: &str = "http://example.org/generated-schema";
+
+/// How shapes are written as JSON Schema
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct JsonSchemaOptions {
+    /// shapes documents are validated against; the START shape if none are named
+    pub roots: Vec<String>,
+    /// write language-tagged strings as JSON-LD value objects whose `@language`
+    /// must match the tags the property allows, rather than as plain strings
+    pub language_tags: bool,
+    pub draft: JsonSchemaDraft,
+    /// the schema's `$id`, against which its definitions' anchors resolve; `DEFAULT_JSON_SCHEMA_ID` if `None`
+    pub id: Option<String>,
+    pub root: JsonSchemaRoot,
+    /// carry the shapes' class, predicate and datatype IRIs in `x-rdf-*` extension keywords
+    pub rdf_keywords: bool,
+    /// title every definition and property, by its name when it has no label, and give
+    /// value sets `examples`, for editors to offer in completions
+    pub annotate: bool,
+    /// leave out the `format` of dates, times, durations and URIs, for validators that assert
+    /// it: an xsd:dateTime needn't have the time zone a JSON Schema `date-time` must
+    pub plain_strings: bool,
+    pub additional_properties: AdditionalProperties,
+}
+
+/// Build a basic JSON Schema (draft-07) with definitions per shape
+pub fn build_json_schema(input: &Path, shapes: &[ShapeInfo]) -> serde_json::Value {
+    build_json_schema_with(input, shapes, &JsonSchemaOptions::default())
+}
+
+/// Build the draft-07 JSON Schema of `build_json_schema_with` for `roots`, with or without `language_tags`
+pub fn build_json_schema_with_roots(input: &Path, shapes: &[ShapeInfo], roots: &[String], language_tags: bool) -> serde_json::Value {
+    build_json_schema_with(input, shapes, &JsonSchemaOptions { roots: roots.to_vec(), language_tags, ..Default::default() })
+}
+
+/// Build a JSON Schema with a definition per shape, validating documents as `options.root` says:
+/// by default against the root shapes, with a top-level `$ref` for one root, `anyOf` for several.
+/// Each definition carries an anchor (see `JsonSchemaDraft::anchor`) so it can be referenced as `<$id>#Name`.
+/// 2020-12 closes a shape that extends others with `unevaluatedProperties`, which,
+/// unlike draft-07's `additionalProperties`, sees the properties its `allOf` admits.
+pub fn build_json_schema_with(_input: &Path, shapes: &[ShapeInfo], options: &JsonSchemaOptions) -> serde_json::Value {
+    use serde_json::{json, Map as JsonMap, Value as JsonValue};
+
+    let language_tags = options.language_tags;
+    let draft = options.draft;
+    let def_ref = |name: &str| json!({ "$ref": format!("#/{}/{}", draft.definitions_keyword(), json_pointer_token(name)) });
+    let mut defs = JsonMap::new();
+
+    // the schema of one value of a property in one range
+    let range_schema = |p: &PropertyInfo| -> JsonValue {
+        let mut jt = match (json_schema_type_for(&p.range), p.range.as_str()) {
+            // value sets are enumerated strings whatever their range
+            _ if !p.iri_stems.is_empty() => {
+                let mut iris = json!({ "type": "string", "format": "iri", "pattern": iri_stem_pattern(&p.values, &p.iri_stems) });
+                if let Some(excluded) = iri_exclusion_pattern(&p.iri_stems) {
+                    iris["not"] = json!({ "pattern": excluded });
+                }
+                iris
+            }
+            _ if single_literal(p).is_some() => json!({ "type": "string", "const": p.values[0] }),
+            _ if !p.values.is_empty() => json!({ "type": "string", "enum": p.values }),
+            (Some(mut primitive), _) => {
+                if options.plain_strings {
+                    primitive.as_object_mut().map(|o| o.remove("format"));
+                }
+                primitive
+            }
+            // references to other shapes point at their definitions
+            // unless they're referred to by identifier, i.e. by IRI
+            (None, _) if inlining_of(p, shapes) == Some(Inlining::Reference) => json!({ "type": "string", "format": "iri" }),
+            (None, r) if shapes.iter().any(|other| other.name == r) => def_ref(r),
+            // node identifiers are strings in Turtle/N-Triples syntax, e.g. `_:b0`
+            _ if p.node_kind == Some(NodeKind::BNode) => json!({ "type": "string", "pattern": "^_:" }),
+            _ if p.node_kind == Some(NodeKind::NonLiteral) => json!({ "type": "string", "pattern": NON_LITERAL_PATTERN }),
+            _ if p.node_kind == Some(NodeKind::Iri) => json!({ "type": "string", "format": "iri" }),
+            (None, "uriorcurie") => json!({ "type": "string", "format": "iri" }),
+            (None, "nodeidentifier") => json!({ "type": "string", "pattern": NON_LITERAL_PATTERN }),
+            _ => json!({ "type": "string" }),
+        };
+        if let Some(obj) = jt.as_object_mut().filter(|o| !o.contains_key("$ref")) {
+            json_schema_facets(&p.facets, obj);
+            if options.annotate && p.values.len() > 1 {
+                obj.insert("examples".to_string(), json!(p.values));
+            }
+            let shape = shapes.iter().any(|other| other.name == p.range);
+            if let Some(datatype) = rdf_datatype(p).filter(|_| options.rdf_keywords && !shape) {
+                obj.insert(RDF_DATATYPE_KEYWORD.to_string(), datatype.into());
+            }
+        }
+        if language_tags && !p.languages.is_empty() {
+            jt = json!({
+                "type": "object",
+                "properties": {
+                    "@value": jt,
+                    "@language": { "type": "string", "pattern": language_tag_pattern(&p.languages) },
+                },
+                "required": ["@value", "@language"],
+            });
+        }
+        jt
+    };
+    // the schema of one value of a property, in any, exactly one or all of its ranges
+    let value_schema = |p: &PropertyInfo| -> JsonValue {
+        let Some(expr) = &p.range_expr else { return range_schema(p) };
+        let members: Vec<JsonValue> = expr.ranges().iter()
+            .map(|r| range_schema(&PropertyInfo { range: r.clone(), range_expr: None, ..p.clone() }))
+            .collect();
+        let keyword = match expr {
+            RangeExpr::AnyOf(_) => "anyOf",
+            RangeExpr::ExactlyOneOf(_) => "oneOf",
+            RangeExpr::AllOf(_) => "allOf",
+        };
+        json!({ keyword: members })
+    };
+
+    for s in shapes.iter() {
+        // an external shape is defined elsewhere, so its definition only points there
+        if s.external {
+            defs.insert(s.name.clone(), json!({ "$ref": s.id }));
+            continue;
+        }
+        let mut props = JsonMap::new();
+        let mut required: Vec<JsonValue> = Vec::new();
+        // the identifier is the instance's IRI, which every instance has
+        if let Some(identifier) = &s.identifier {
+            props.insert(identifier.clone(), json!({ "type": "string", "format": "iri", IDENTIFIER_KEYWORD: true }));
+            required.push(JsonValue::String(identifier.clone()));
+        }
+        for p in merged_properties(&s.properties).iter() {
+            // each triple constraint on a repeated predicate admits values of its own
+            let repeated: Vec<&PropertyInfo> = s.properties.iter().filter(|q| q.name == p.name).collect();
+            let mut members: Vec<JsonValue> = Vec::new();
+            for q in repeated.iter() {
+                let member = value_schema(q);
+                if !members.contains(&member) { members.push(member); }
+            }
+            let mut jt = if members.len() > 1 { json!({ "anyOf": members }) } else { value_schema(p) };
+            // a property that may repeat is an array of its values, or of inlined
+            // values with identifiers, an object keyed by them
+            let keyed = p.range_expr.is_none() && inlining_of(p, shapes) == Some(Inlining::Inlined)
+                && shapes.iter().any(|other| other.name == p.range && other.identifier.is_some());
+            if p.max != Some(1) && keyed {
+                let mut object = json!({ "type": "object", "additionalProperties": jt });
+                if let Some(min) = p.min.filter(|m| *m > 0) { object["minProperties"] = min.into(); }
+                if let Some(max) = p.max { object["maxProperties"] = max.into(); }
+                jt = object;
+            } else if p.max != Some(1) {
+                let mut array = json!({ "type": "array", "items": jt });
+                if let Some(min) = p.min.filter(|m| *m > 0) { array["minItems"] = min.into(); }
+                if let Some(max) = p.max { array["maxItems"] = max.into(); }
+                // and each required constraint must be met by one of them
+                let contains: Vec<JsonValue> = repeated.iter().filter(|q| q.min.unwrap_or(0) > 0 && members.len() > 1)
+                    .map(|q| json!({ "contains": value_schema(q) })).collect();
+                if !contains.is_empty() { array["allOf"] = JsonValue::Array(contains); }
+                jt = array;
+            }
+            if let Some(obj) = jt.as_object_mut() {
+                if options.rdf_keywords && p.predicate != p.name {
+                    obj.insert(RDF_PREDICATE_KEYWORD.to_string(), p.predicate.clone().into());
+                }
+                let title = p.title.clone().or_else(|| options.annotate.then(|| p.name.clone()));
+                json_schema_docs(&title, &p.description, obj);
+                json_schema_sem_acts(&p.sem_acts, obj);
+            }
+            props.insert(p.name.clone(), jt);
+            if p.min.unwrap_or(0) > 0 {
+                required.push(JsonValue::String(p.name.clone()));
+            }
+        }
+        let mut obj = JsonMap::new();
+        if let Some((key, anchor)) = draft.anchor(&s.name) {
+            obj.insert(key.to_string(), JsonValue::String(anchor));
+        }
+        if options.rdf_keywords && s.id != s.name {
+            obj.insert(RDF_CLASS_KEYWORD.to_string(), s.id.clone().into());
+        }
+        let title = s.title.clone().or_else(|| options.annotate.then(|| s.name.clone()));
+        json_schema_docs(&title, &s.description, &mut obj);
+        json_schema_sem_acts(&s.sem_acts, &mut obj);
+        let mut all_of: Vec<JsonValue> = s.extends.iter().map(|e| def_ref(e)).collect();
+        // a OneOf group is satisfied by exactly one alternative's required properties;
+        // a second group of the same kind goes under allOf
+        let mut nots = Vec::new();
+        let mut dependent_required = JsonMap::new();
+        for c in s.constraints.iter() {
+            let (key, value) = match c {
+                ShapeConstraint::OneOf(alternatives) => ("oneOf", json_schema_alternatives(alternatives)),
+                ShapeConstraint::AnyOf(names) => ("anyOf", names.iter().map(|n| def_ref(n)).collect()),
+                ShapeConstraint::Not(name) => {
+                    nots.push(def_ref(name));
+                    continue;
+                }
+                // one property requiring others is dependentRequired (draft-07 dependencies), any other rule if/then/else
+                ShapeConstraint::Rule { preconditions, postconditions, elseconditions } => {
+                    let presence = |p: &PropertyInfo| p.min.unwrap_or(0) > 0 && p.values.is_empty();
+                    match preconditions.as_slice() {
+                        [p] if presence(p) && postconditions.iter().all(presence) && elseconditions.is_empty() => {
+                            let names = postconditions.iter().map(|q| JsonValue::String(q.name.clone()));
+                            if let JsonValue::Array(required) = dependent_required.entry(p.name.clone()).or_insert_with(|| json!([])) {
+                                required.extend(names);
+                            }
+                        }
+                        _ => {
+                            let mut rule = JsonMap::new();
+                            rule.insert("if".to_string(), json_schema_condition(preconditions));
+                            if !postconditions.is_empty() { rule.insert("then".to_string(), json_schema_condition(postconditions)); }
+                            if !elseconditions.is_empty() { rule.insert("else".to_string(), json_schema_condition(elseconditions)); }
+                            if obj.contains_key("if") {
+                                all_of.push(JsonValue::Object(rule));
+                            } else {
+                                obj.extend(rule);
+                            }
+                        }
+                    }
+                    continue;
+                }
+            };
+            if obj.contains_key(key) {
+                all_of.push(json!({ key: value }));
+            } else {
+                obj.insert(key.to_string(), value);
+            }
+        }
+        if !dependent_required.is_empty() {
+            obj.insert(draft.dependent_required_keyword().to_string(), JsonValue::Object(dependent_required));
+        }
+        match nots.len() {
+            0 => {}
+            1 => { obj.insert("not".to_string(), nots.remove(0)); }
+            _ => { obj.insert("not".to_string(), json!({ "anyOf": nots })); }
+        }
+        if !all_of.is_empty() {
+            obj.insert("allOf".to_string(), JsonValue::Array(all_of));
+        }
+        obj.insert("type".to_string(), JsonValue::String("object".to_string()));
+        let closed = match options.additional_properties {
+            AdditionalProperties::FromShex => s.closed,
+            AdditionalProperties::Allow => false,
+            AdditionalProperties::Deny => true,
+        };
+        let unevaluated = closed && draft == JsonSchemaDraft::Draft2020_12 && !s.extends.is_empty();
+        if closed && !unevaluated {
+            // draft-07 additionalProperties can't see into allOf, so admit inherited properties by name
+            for name in inherited_property_names(shapes, s) {
+                props.entry(name).or_insert(JsonValue::Bool(true));
+            }
+        }
+        obj.insert("properties".to_string(), JsonValue::Object(props));
+        if !required.is_empty() { obj.insert("required".to_string(), JsonValue::Array(required)); }
+        if unevaluated {
+            obj.insert("unevaluatedProperties".to_string(), JsonValue::Bool(false));
+        } else if closed {
+            obj.insert("additionalProperties".to_string(), JsonValue::Bool(false));
+        }
+        defs.insert(s.name.clone(), JsonValue::Object(obj));
+    }
+
+    let mut root = JsonMap::new();
+    root.insert("$schema".to_string(), JsonValue::String(draft.meta_schema().to_string()));
+    root.insert("$id".to_string(), JsonValue::String(options.id.as_deref().unwrap_or(DEFAULT_JSON_SCHEMA_ID).to_string()));
+    root.insert(draft.definitions_keyword().to_string(), JsonValue::Object(defs));
+    // no document is an instance of an abstract shape itself; its children still reach it through allOf
+    let concrete = |name: &String| !shapes.iter().any(|s| &s.name == name && s.is_abstract);
+    match options.root {
+        JsonSchemaRoot::Definitions => {}
+        JsonSchemaRoot::Start => {
+            let start: Vec<String> = shapes.iter().filter(|s| s.start).map(|s| s.name.clone()).collect();
+            let roots = if options.roots.is_empty() { &start } else { &options.roots };
+            let refs: Vec<JsonValue> = roots.iter().filter(|r| concrete(r)).map(|r| def_ref(r)).collect();
+            match refs.len() {
+                0 => {}
+                1 => { root.insert("$ref".to_string(), refs[0]["$ref"].clone()); }
+                _ => { root.insert("anyOf".to_string(), JsonValue::Array(refs)); }
+            }
+        }
+        JsonSchemaRoot::OneOf => {
+            let top_level = |s: &&ShapeInfo| concrete(&s.name) && (!s.inline || s.start);
+            let refs: Vec<JsonValue> = shapes.iter().filter(top_level)
+                .filter(|s| !shapes.iter().filter(top_level).any(|child| child.extends.contains(&s.name)))
+                .map(|s| def_ref(&s.name))
+                .collect();
+            root.insert("oneOf".to_string(), JsonValue::Array(refs));
+        }
+    }
+
+    JsonValue::Object(root)
+}
+
+/// Build a JSON Schema per shape, `<Name>.schema.<extension>`, whose `$ref`s to the other
+/// shapes name their files, plus an index, `index.schema.<extension>`, defining every shape
+/// by a `$ref` to its file and validating documents as `options.root` says. Each file is validated
+/// against its own shape, and its `$id` is `options.id` with the file name as its last segment.
+pub fn build_json_schema_bundle(input: &Path, shapes: &[ShapeInfo], options: &JsonSchemaOptions, extension: &str) -> Vec<(String, serde_json::Value)> {
+    let stems = file_stems(shapes);
+    let file_name = |s: &ShapeInfo| format!("{}.schema.{}", stems[shapes.iter().position(|t| t.name == s.name).unwrap_or(0)], extension);
+    // shapes that are already external stay where they are
+    let locate = |s: &ShapeInfo| if s.external { s.id.clone() } else { file_name(s) };
+    let id = options.id.as_deref().unwrap_or(DEFAULT_JSON_SCHEMA_ID);
+    let mut files: Vec<(String, serde_json::Value)> = shapes.iter().filter(|s| !s.external).map(|s| {
+        let part = schema_part(shapes, std::slice::from_ref(&s.name), locate);
+        let options = JsonSchemaOptions { roots: vec![s.name.clone()], root: JsonSchemaRoot::Start, id: Some(sibling_iri(id, &file_name(s))), ..options.clone() };
+        (file_name(s), build_json_schema_with(input, &part, &options))
+    }).collect();
+    let index: Vec<ShapeInfo> = shapes.iter().map(|s| ShapeInfo {
+        id: locate(s),
+        // external definitions ignore `extends`, but the root still leaves out the shapes others extend
+        properties: Vec::new(),
+        constraints: Vec::new(),
+        external: true,
+        ..s.clone()
+    }).collect();
+    let index_name = format!("index.schema.{}", extension);
+    let options = JsonSchemaOptions { id: Some(sibling_iri(id, &index_name)), ..options.clone() };
+    files.push((index_name, build_json_schema_with(input, &index, &options)));
+    files
+}
+
+/// A definition's name as a JSON Pointer reference token, for names that are IRIs
+pub fn json_pointer_token(name: &str) -> String {
+    name.replace('~', "~0").replace('/', "~1")
+}
+
+/// A file name stem per shape: its name, which may be a CURIE or an IRI, with anything but
+/// ASCII letters, digits, `-` and `_` replaced by `_`, numbered where stems would clash
+/// with each other or with the index
+fn file_stems(shapes: &[ShapeInfo]) -> Vec<String> {
+    let mut stems: Vec<String> = Vec::new();
+    for s in shapes {
+        let stem: String = s.name.chars().map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' }).collect();
+        let taken = |n: &String| stems.contains(n) || n == "index";
+        let stem = if taken(&stem) { (2..).map(|i| format!("{}_{}", stem, i)).find(|n| !taken(n)).unwrap_or(stem) } else { stem };
+        stems.push(stem);
+    }
+    stems
+}
+
+/// `name` in the place of the last path segment of `iri`, where a relative reference to `name` from `iri` resolves
+pub fn sibling_iri(iri: &str, name: &str) -> String {
+    format!("{}{}", &iri[..iri.rfind('/').map_or(0, |i| i + 1)], name)
+}
+
+/// Names of the properties `shape` inherits through `extends`
+fn inherited_property_names(shapes: &[ShapeInfo], shape: &ShapeInfo) -> Vec<String> {
+    let mut names = Vec::new();
+    let mut seen = vec![shape.name.as_str()];
+    let mut pending: Vec<&str> = shape.extends.iter().map(String::as_str).collect();
+    while let Some(parent) = pending.pop() {
+        if seen.contains(&parent) {
+            continue;
+        }
+        seen.push(parent);
+        if let Some(p) = shapes.iter().find(|s| s.name == parent) {
+            names.extend(p.properties.iter().map(|prop| prop.name.clone()));
+            pending.extend(p.extends.iter().map(String::as_str));
+        }
+    }
+    names
+}
+
+/// Build a ShEx compact schema from shapes.
+/// Local names are minted in the `ex:` namespace used by `build_linkml_doc`;
+/// a missing `max` is unbounded and a missing `min` is 0. Parent shapes are
+/// written as `EXTENDS` qualifiers, closed shapes as `CLOSED` and OneOf
+/// groups as `( ... | ... )`; OR and NOT constraints are ANDed with the shape.
+pub fn build_shex(shapes: &[ShapeInfo]) -> String {
+    build_shex_with_prefixes(shapes, &Prefixes::default())
+}
+
+/// How a property's values of a shape range are written: as it says, else inlined
+/// unless the range has an identifier. `None` when its range isn't a shape.
+pub(crate) fn inlining_of(p: &PropertyInfo, shapes: &[ShapeInfo]) -> Option<Inlining> {
+    let range = shapes.iter().find(|s| s.name == p.range)?;
+    Some(p.inlining.unwrap_or(if range.identifier.is_some() { Inlining::Reference } else { Inlining::Inlined }))
+}
+
+/// Build a ShEx compact schema that keeps the source schema's BASE and PREFIX
+/// declarations and writes predicates and value IRIs as CURIEs where it can
+pub fn build_shex_with_prefixes(shapes: &[ShapeInfo], prefixes: &Prefixes) -> String {
+    build_shex_with_imports(shapes, prefixes, &[])
+}
+
+/// Build a ShEx compact schema that IMPORTs the schemas at `imports`, e.g. those
+/// defining the shapes it refers to but doesn't declare
+pub fn build_shex_with_imports(shapes: &[ShapeInfo], prefixes: &Prefixes, imports: &[String]) -> String {
+    let mut out = String::new();
+    if let Some(base) = &prefixes.base {
+        out.push_str(&format!("BASE <{}>\n", base));
+    }
+    for (prefix, ns) in prefixes.prefixes.iter() {
+        out.push_str(&format!("PREFIX {}: <{}>\n", prefix, ns));
+    }
+    for (prefix, ns) in [("ex", "http://example.org/"), ("xsd", XSD), ("rdfs", RDFS)] {
+        if !prefixes.declares(prefix) {
+            out.push_str(&format!("PREFIX {}: <{}>\n", prefix, ns));
+        }
+    }
+    for import in imports.iter() {
+        out.push_str(&format!("IMPORT <{}>\n", import));
+    }
+    if let Some(start) = shapes.iter().find(|s| s.start) {
+        out.push_str(&format!("\nstart = @{}\n", prefixes.label(&start.name)));
+    }
+
+    for s in shapes.iter() {
+        let extends: String = s.extends.iter().map(|e| format!(" EXTENDS @{}", prefixes.label(e))).collect();
+        let closed = if s.closed { " CLOSED" } else { "" };
+        // OR and NOT are conjoined with the shape definition, e.g. `( @ex:A OR @ex:B ) AND {`
+        let conjuncts: String = s.constraints.iter().filter_map(|c| match c {
+            ShapeConstraint::AnyOf(names) => {
+                let refs: Vec<String> = names.iter().map(|n| format!("@{}", prefixes.label(n))).collect();
+                Some(format!(" ( {} ) AND", refs.join(" OR ")))
+            }
+            ShapeConstraint::Not(name) => Some(format!(" NOT @{} AND", prefixes.label(name))),
+            // a rule is ( NOT pre OR post ), and ( pre OR else ) when it has elseconditions
+            ShapeConstraint::Rule { preconditions, postconditions, elseconditions } => {
+                let (pre, post) = (shex_condition_shape(preconditions, prefixes), shex_condition_shape(postconditions, prefixes));
+                let mut conjunct = format!(" ( NOT {} OR {} ) AND", pre, post);
+                if !elseconditions.is_empty() {
+                    conjunct.push_str(&format!(" ( {} OR {} ) AND", pre, shex_condition_shape(elseconditions, prefixes)));
+                }
+                Some(conjunct)
+            }
+            ShapeConstraint::OneOf(_) => None,
+        }).collect();
+        if s.external {
+            out.push_str(&format!("\n{} EXTERNAL\n", prefixes.label(&s.name)));
+            continue;
+        }
+        let is_abstract = if s.is_abstract { "ABSTRACT " } else { "" };
+        // a shape with an identifier slot describes IRI-named nodes
+        let node_kind = if s.identifier.is_some() { " IRI" } else { "" };
+        out.push_str(&format!("\n{}{}{}{}{}{} {{\n", is_abstract, prefixes.label(&s.name), conjuncts, node_kind, extends, closed));
+        let tcs: Vec<String> = shex_shape_body(s, shapes, prefixes, &[&s.name]).iter().map(|tc| format!("  {}", tc)).collect();
+        out.push_str(&tcs.join(" ;\n"));
+        if !tcs.is_empty() { out.push('\n'); }
+        out.push_str(&format!("}}{}{}\n", shex_annotations(&s.title, &s.description), prefixes.shex_sem_acts(&s.sem_acts)));
+    }
+
+    out
+}
+
+/// The triple constraints of a shape's body, OneOf groups last
+fn shex_shape_body(s: &ShapeInfo, shapes: &[ShapeInfo], prefixes: &Prefixes, nesting: &[&str]) -> Vec<String> {
+    let tc = |p: &PropertyInfo| shex_triple_constraint(p, shapes, prefixes, nesting);
+    // properties in a OneOf group are written inside the group rather than on their own
+    let alternatives: Vec<&Vec<Vec<PropertyInfo>>> = s.constraints.iter().filter_map(|c| match c {
+        ShapeConstraint::OneOf(alternatives) => Some(alternatives),
+        ShapeConstraint::Not(_) | ShapeConstraint::AnyOf(_) | ShapeConstraint::Rule { .. } => None,
+    }).collect();
+    let grouped = |p: &PropertyInfo| alternatives.iter().flat_map(|alts| alts.iter().flatten()).any(|q| q.name == p.name);
+    let mut tcs: Vec<String> = s.properties.iter().filter(|p| !grouped(p)).map(tc).collect();
+    for alts in alternatives.iter() {
+        let branches: Vec<String> = alts.iter().map(|alt| alt.iter().map(tc).collect::<Vec<_>>().join(" ; ")).collect();
+        tcs.push(format!("( {} )", branches.join(" | ")));
+    }
+    tcs
+}
+
+/// The shape a rule's conditions make: each property present, `+`, or not, `*`,
+/// with values among those it lists. The shape is open, so only they are constrained.
+fn shex_condition_shape(conditions: &[PropertyInfo], prefixes: &Prefixes) -> String {
+    let tcs: Vec<String> = conditions.iter().map(|p| {
+        let value_expr = if p.values.is_empty() {
+            ".".to_string()
+        } else {
+            format!("[ {} ]", p.values.iter().map(|v| prefixes.value_term(v)).collect::<Vec<_>>().join(" "))
+        };
+        format!("{} {} {}", prefixes.term(&p.predicate), value_expr, if p.min.unwrap_or(0) > 0 { "+" } else { "*" })
+    }).collect();
+    if tcs.is_empty() { "{ }".to_string() } else { format!("{{ {} }}", tcs.join(" ; ")) }
+}
+
+/// A ShExC triple constraint. Values of a shape range the property says are inlined
+/// are that shape nested in place, unless it's one of the shapes being nested in.
+fn shex_triple_constraint(p: &PropertyInfo, shapes: &[ShapeInfo], prefixes: &Prefixes, nesting: &[&str]) -> String {
+    let value_expr = if !p.languages.is_empty() {
+        let tags: Vec<String> = p.languages.iter().map(|l| format!("@{}", l)).collect();
+        format!("[ {} ]", tags.join(" "))
+    } else if !p.values.is_empty() || !p.iri_stems.is_empty() {
+        let vs: Vec<String> = p.values.iter().map(|v| prefixes.value_term(v)).chain(p.iri_stems.iter().map(|s| prefixes.shex_iri_stem(s))).collect();
+        format!("[ {} ]", vs.join(" "))
+    } else if let Some(expr) = &p.range_expr {
+        let atoms: Vec<String> = expr.ranges().iter().map(|r| shex_range(r, None, shapes, prefixes)).collect();
+        format!("( {} )", shex_junction(expr, &atoms))
+    } else if let Some(range) = shapes.iter().find(|r| r.name == p.range).filter(|r| nestable(p, r, shapes, nesting)) {
+        let nesting: Vec<&str> = nesting.iter().copied().chain(std::iter::once(range.name.as_str())).collect();
+        let node_kind = if range.identifier.is_some() { "IRI " } else { "" };
+        let extends: String = range.extends.iter().map(|e| format!("EXTENDS @{} ", prefixes.label(e))).collect();
+        let closed = if range.closed { "CLOSED " } else { "" };
+        let body = shex_shape_body(range, shapes, prefixes, &nesting);
+        if body.is_empty() {
+            format!("{}{}{}{{ }}", node_kind, extends, closed)
+        } else {
+            format!("{}{}{}{{ {} }}", node_kind, extends, closed, body.join(" ; "))
+        }
+    } else {
+        shex_range(&p.range, p.node_kind, shapes, prefixes)
+    };
+    // facets follow a node constraint, not a parenthesized expression
+    let facets = if p.range_expr.is_some() { String::new() } else { shex_facets(&p.facets) };
+    format!("{} {}{}{}{}{}", prefixes.term(&p.predicate), value_expr, facets, shex_cardinality(p.min, p.max),
+        shex_annotations(&p.title, &p.description), prefixes.shex_sem_acts(&p.sem_acts))
+}
+
+/// Whether a property's values of shape `range` are written as the shape nested in place:
+/// only when the property says they're inlined, as ShEx otherwise refers to shapes by label,
+/// and only for a shape whose whole definition fits inside a triple constraint
+fn nestable(p: &PropertyInfo, range: &ShapeInfo, shapes: &[ShapeInfo], nesting: &[&str]) -> bool {
+    p.inlining.is_some()
+        && matches!(inlining_of(p, shapes), Some(Inlining::Inlined | Inlining::InlinedAsList))
+        && !range.external && !range.is_abstract && range.sem_acts.is_empty()
+        && range.constraints.iter().all(|c| matches!(c, ShapeConstraint::OneOf(_)))
+        && !nesting.contains(&range.name.as_str())
+}
+
+/// A ShExC value expression for one range: a shape reference, node kind, datatype or `.`
+fn shex_range(range: &str, node_kind: Option<NodeKind>, shapes: &[ShapeInfo], prefixes: &Prefixes) -> String {
+    if shapes.iter().any(|other| other.name == range) {
+        format!("@{}", prefixes.label(range))
+    } else if let Some(kind) = node_kind.or_else(|| node_kind_for_range(range)) {
+        match kind {
+            NodeKind::Iri => "IRI",
+            NodeKind::BNode => "BNODE",
+            NodeKind::NonLiteral => "NONLITERAL",
+            NodeKind::Literal => "LITERAL",
+        }.to_string()
+    } else if let Some(dt) = xsd_datatype_for(range) {
+        dt.to_string()
+    } else if let Some(local) = range.strip_prefix(XSD) {
+        format!("xsd:{}", local)
+    } else if range.starts_with("http") {
+        prefixes.term(range)
+    } else {
+        ".".to_string()
+    }
+}
+
+/// Build a SHACL shapes graph (Turtle) from shapes.
+/// Uses the same `ex:` namespace and min/max conventions as `build_shex`.
+pub fn build_shacl(shapes: &[ShapeInfo]) -> String {
+    build_shacl_with_prefixes(shapes, &Prefixes::default())
+}
+
+/// Build a SHACL shapes graph that keeps the source schema's base and prefixes
+/// and writes paths and value IRIs as CURIEs where it can
+pub fn build_shacl_with_prefixes(shapes: &[ShapeInfo], prefixes: &Prefixes) -> String {
+    let defaults = [("ex", "http://example.org/"), ("sh", "http://www.w3.org/ns/shacl#"), ("xsd", XSD)];
+    let mut out = prefixes.turtle_header(&defaults);
+    let mut sparql = false;
+
+    // external shapes are defined elsewhere; sh:node still refers to them
+    for s in shapes.iter().filter(|s| !s.external) {
+        out.push_str(&format!("\n{} a sh:NodeShape", prefixes.label(&s.name)));
+        // a shape with an identifier slot describes IRI-named nodes
+        if s.identifier.is_some() {
+            out.push_str(" ;\n  sh:nodeKind sh:IRI");
+        }
+        // what a property's values must be, leaving out its path and counts
+        let value_constraints = |p: &PropertyInfo| -> Vec<String> {
+            let mut constraints = Vec::new();
+            if !p.languages.is_empty() {
+                constraints.push(format!("sh:datatype <{}>", RDF_LANG_STRING));
+                // sh:languageIn matches subtags too, so `en` and `en~` both become "en"
+                if !p.languages.iter().any(|l| l == "~") {
+                    let tags: Vec<String> = p.languages.iter().map(|l| value_term(l.trim_end_matches('~'))).collect();
+                    constraints.push(format!("sh:languageIn ( {} )", tags.join(" ")));
+                }
+            } else if !p.iri_stems.is_empty() {
+                // SHACL has no stems, but sh:pattern matches an IRI's string form
+                constraints.push("sh:nodeKind sh:IRI".to_string());
+                constraints.push(format!("sh:pattern {}", value_term(&iri_stem_pattern(&p.values, &p.iri_stems))));
+                if let Some(excluded) = iri_exclusion_pattern(&p.iri_stems) {
+                    constraints.push(format!("sh:not [ sh:pattern {} ]", value_term(&excluded)));
+                }
+            } else if !p.values.is_empty() {
+                let vs: Vec<String> = p.values.iter().map(|v| prefixes.value_term(v)).collect();
+                constraints.push(format!("sh:in ( {} )", vs.join(" ")));
+            } else if shapes.iter().any(|other| other.name == p.range) {
+                constraints.push(format!("sh:node {}", prefixes.label(&p.range)));
+            } else if let Some(kind) = p.node_kind.or_else(|| node_kind_for_range(&p.range)) {
+                constraints.push(format!("sh:nodeKind {}", match kind {
+                    NodeKind::Iri => "sh:IRI",
+                    NodeKind::BNode => "sh:BlankNode",
+                    NodeKind::NonLiteral => "sh:BlankNodeOrIRI",
+                    NodeKind::Literal => "sh:Literal",
+                }));
+            } else if let Some(dt) = xsd_datatype_for(&p.range) {
+                constraints.push(format!("sh:datatype {}", dt));
+            } else if p.range.starts_with("http") {
+                constraints.push(format!("sh:datatype {}", prefixes.term(&p.range)));
+            }
+            let f = &p.facets;
+            let facets = [
+                ("minLength", f.min_length.map(|n| n.to_string())),
+                ("maxLength", f.max_length.map(|n| n.to_string())),
+                ("pattern", f.pattern.as_deref().map(value_term)),
+                ("minInclusive", f.min_inclusive.as_ref().map(|n| n.to_string())),
+                ("minExclusive", f.min_exclusive.as_ref().map(|n| n.to_string())),
+                ("maxInclusive", f.max_inclusive.as_ref().map(|n| n.to_string())),
+                ("maxExclusive", f.max_exclusive.as_ref().map(|n| n.to_string())),
+            ];
+            constraints.extend(facets.into_iter().filter_map(|(name, v)| Some(format!("sh:{} {}", name, v?))));
+            constraints
+        };
+        let property = |constraints: Vec<String>| format!(" ;\n  sh:property [\n    {}\n  ]", constraints.join(" ;\n    "));
+        let counts = |constraints: &mut Vec<String>, min: u64, max: Option<u64>, (min_key, max_key): (&str, &str)| {
+            if min > 0 { constraints.push(format!("sh:{} {}", min_key, min)); }
+            if let Some(max) = max { constraints.push(format!("sh:{} {}", max_key, max)); }
+        };
+        let mut written: Vec<&str> = Vec::new();
+        for p in s.properties.iter() {
+            if written.contains(&p.name.as_str()) {
+                continue;
+            }
+            written.push(&p.name);
+            let path = format!("sh:path {}", prefixes.term(&p.predicate));
+            let group: Vec<&PropertyInfo> = s.properties.iter().filter(|q| q.name == p.name).collect();
+            if group.len() == 1 {
+                let mut constraints = vec![path];
+                constraints.extend(value_constraints(p));
+                counts(&mut constraints, p.min.unwrap_or(0), p.max, ("minCount", "maxCount"));
+                out.push_str(&property(constraints));
+                continue;
+            }
+            // several triple constraints on one predicate: each value is one of theirs, and each
+            // counts the values it admits with a qualified value shape, as ShEx partitions them
+            let value_shapes: Vec<String> = group.iter().map(|q| format!("[ {} ]", value_constraints(q).join(" ; "))).collect();
+            let mut constraints = vec![path.clone(), format!("sh:or ( {} )", value_shapes.join(" "))];
+            counts(&mut constraints, group.iter().map(|q| q.min.unwrap_or(0)).sum(), group.iter().map(|q| q.max).sum(), ("minCount", "maxCount"));
+            out.push_str(&property(constraints));
+            for (q, value_shape) in group.iter().zip(value_shapes) {
+                let mut constraints = vec![path.clone(), format!("sh:qualifiedValueShape {}", value_shape)];
+                counts(&mut constraints, q.min.unwrap_or(0), q.max, ("qualifiedMinCount", "qualifiedMaxCount"));
+                constraints.push("sh:qualifiedValueShapesDisjoint true".to_string());
+                out.push_str(&property(constraints));
+            }
+        }
+        for c in s.constraints.iter() {
+            if let ShapeConstraint::AnyOf(names) = c {
+                let refs: Vec<String> = names.iter().map(|n| prefixes.label(n)).collect();
+                out.push_str(&format!(" ;\n  sh:or ( {} )", refs.join(" ")));
+                continue;
+            }
+            // SHACL core has no counterpart for the rest, so enforce them with SHACL-SPARQL
+            let (message, select) = shacl_sparql_constraint(c, shapes);
+            out.push_str(&format!(
+                " ;\n  sh:sparql [\n    a sh:SPARQLConstraint ;\n    sh:message {} ;\n    sh:prefixes ex: ;\n    sh:select \"\"\"\nSELECT $this\nWHERE {{\n{}}}\n\"\"\"\n  ]",
+                value_term(&message), select));
+            sparql = true;
+        }
+        out.push_str(" .\n");
+    }
+
+    // the queries' `sh:prefixes`: the graph's own prefixes, declared the SHACL way
+    if sparql {
+        let declarations: Vec<String> = prefixes.with_defaults(&defaults)
+            .map(|(prefix, ns)| format!("[ sh:prefix \"{}\" ; sh:namespace \"{}\"^^xsd:anyURI ]", prefix, ns))
+            .collect();
+        out.push_str(&format!("\nex: sh:declare\n  {} .\n", declarations.join(" ,\n  ")));
+    }
+
+    out
+}
+
+/// Message and SELECT body of a SHACL-SPARQL constraint for a ShapeConstraint.
+/// An alternative, or a negated shape, counts as satisfied when the node has
+/// all of its required (min >= 1) properties.
+fn shacl_sparql_constraint(c: &ShapeConstraint, shapes: &[ShapeInfo]) -> (String, String) {
+    let required = |props: &[PropertyInfo]| -> String {
+        let patterns: Vec<String> = props.iter()
+            .filter(|p| p.min.unwrap_or(0) >= 1)
+            .map(|p| format!("$this {} []", iri_term(&p.predicate)))
+            .collect();
+        if patterns.is_empty() { "true".to_string() } else { format!("EXISTS {{ {} }}", patterns.join(" . ")) }
+    };
+    match c {
+        // as in JSON Schema, an alternative with no required properties is matched by any of
+        // its properties being there, and one with no properties at all by no other matching
+        ShapeConstraint::OneOf(alternatives) => {
+            let present = |alt: &[PropertyInfo]| -> Option<String> {
+                if alt.iter().any(|p| p.min.unwrap_or(0) >= 1) {
+                    return Some(required(alt));
+                }
+                let mut predicates: Vec<&str> = Vec::new();
+                for p in alt.iter() {
+                    if !predicates.contains(&p.predicate.as_str()) { predicates.push(&p.predicate); }
+                }
+                let patterns: Vec<String> = predicates.iter().map(|p| format!("EXISTS {{ $this {} [] }}", iri_term(p))).collect();
+                if patterns.is_empty() { None } else { Some(patterns.join(" || ")) }
+            };
+            let matched: Vec<Option<String>> = alternatives.iter().map(|alt| present(alt)).collect();
+            let others: Vec<String> = matched.iter().flatten().cloned().collect();
+            let counts: Vec<String> = matched.into_iter()
+                .map(|m| m.unwrap_or_else(|| if others.is_empty() { "true".to_string() } else { format!("!({})", others.join(" || ")) }))
+                .map(|m| format!("IF({}, 1, 0)", m))
+                .collect();
+            let names: Vec<String> = alternatives.iter()
+                .map(|alt| alt.iter().map(|p| p.name.as_str()).collect::<Vec<_>>().join(" + "))
+                .collect();
+            (
+                format!("exactly one of ({}) is required", names.join(" | ")),
+                format!("  BIND (({}) AS ?matches)\n  FILTER (?matches != 1)\n", counts.join(" + ")),
+            )
+        }
+        ShapeConstraint::Not(shape) => {
+            let props = find_shape(shapes, shape).map(|s| s.properties.as_slice()).unwrap_or(&[]);
+            (
+                format!("must not conform to {}", shape),
+                format!("  FILTER ({})\n", required(props)),
+            )
+        }
+        ShapeConstraint::AnyOf(names) => {
+            let branches: Vec<String> = names.iter()
+                .map(|n| required(find_shape(shapes, n).map(|s| s.properties.as_slice()).unwrap_or(&[])))
+                .collect();
+            (
+                format!("must conform to one of {}", names.join(", ")),
+                format!("  FILTER (!({}))\n", branches.join(" || ")),
+            )
+        }
+        // only whether the conditions' properties are present is checked, not their values
+        ShapeConstraint::Rule { preconditions, postconditions, elseconditions } => {
+            let names = |props: &[PropertyInfo]| props.iter().map(|p| p.name.as_str()).collect::<Vec<_>>().join(", ");
+            let mut message = format!("with {}, {} must be given", names(preconditions), names(postconditions));
+            let mut violated = format!("({} && !{})", required(preconditions), required(postconditions));
+            if !elseconditions.is_empty() {
+                message.push_str(&format!(", and without, {}", names(elseconditions)));
+                violated.push_str(&format!(" || (!{} && !{})", required(preconditions), required(elseconditions)));
+            }
+            (message, format!("  FILTER ({})\n", violated))
+        }
+    }
+}
+
+/// Build a lightweight OWL ontology (Turtle) from shapes: an owl:Class per shape,
+/// an owl:ObjectProperty or owl:DatatypeProperty per predicate, and cardinality
+/// restrictions on the classes.
+pub fn build_owl(shapes: &[ShapeInfo]) -> String {
+    build_owl_with_prefixes(shapes, &Prefixes::default())
+}
+
+/// Build an OWL ontology that declares the given prefixes and writes predicates as CURIEs where it can
+pub fn build_owl_with_prefixes(shapes: &[ShapeInfo], prefixes: &Prefixes) -> String {
+    let mut out = prefixes.turtle_header(&[
+        ("ex", "http://example.org/"),
+        ("owl", "http://www.w3.org/2002/07/owl#"),
+        ("rdfs", "http://www.w3.org/2000/01/rdf-schema#"),
+        ("xsd", XSD),
+    ]);
+
+    for s in shapes.iter() {
+        out.push_str(&format!("\n{} a owl:Class", prefixes.class_term(&s.name)));
+        for p in s.properties.iter() {
+            let min = p.min.unwrap_or(0);
+            if min > 0 {
+                out.push_str(&format!(" ;\n  rdfs:subClassOf [ a owl:Restriction ; owl:onProperty {} ; owl:minCardinality \"{}\"^^xsd:nonNegativeInteger ]", prefixes.term(&p.predicate), min));
+            }
+            if let Some(max) = p.max {
+                out.push_str(&format!(" ;\n  rdfs:subClassOf [ a owl:Restriction ; owl:onProperty {} ; owl:maxCardinality \"{}\"^^xsd:nonNegativeInteger ]", prefixes.term(&p.predicate), max));
+            }
+        }
+        out.push_str(" .\n");
+    }
+
+    for decl in predicate_declarations(shapes, prefixes) {
+        let kind = if decl.is_object { "owl:ObjectProperty" } else { "owl:DatatypeProperty" };
+        out.push_str(&format!("\n{} a {}", prefixes.term(decl.predicate), kind));
+        if let Some(domain) = &decl.domain { out.push_str(&format!(" ;\n  rdfs:domain {}", domain)); }
+        if let Some(range) = &decl.range { out.push_str(&format!(" ;\n  rdfs:range {}", range)); }
+        out.push_str(" .\n");
+    }
+
+    out
+}
+
+/// Build a minimal RDFS vocabulary (Turtle) from shapes: an rdfs:Class per
+/// shape and an rdf:Property per predicate, each with an rdfs:label, and the
+/// same domain/range rules as `build_owl`.
+pub fn build_rdfs(shapes: &[ShapeInfo]) -> String {
+    build_rdfs_with_prefixes(shapes, &Prefixes::default())
+}
+
+/// Build an RDFS vocabulary that declares the given prefixes and writes predicates as CURIEs where it can
+pub fn build_rdfs_with_prefixes(shapes: &[ShapeInfo], prefixes: &Prefixes) -> String {
+    let mut out = prefixes.turtle_header(&[
+        ("ex", "http://example.org/"),
+        ("rdf", "http://www.w3.org/1999/02/22-rdf-syntax-ns#"),
+        ("rdfs", "http://www.w3.org/2000/01/rdf-schema#"),
+        ("xsd", XSD),
+    ]);
+
+    for s in shapes.iter() {
+        out.push_str(&format!("\n{} a rdfs:Class ;\n  rdfs:label {} .\n", prefixes.class_term(&s.name), value_term(&s.name)));
+    }
+
+    for decl in predicate_declarations(shapes, prefixes) {
+        out.push_str(&format!("\n{} a rdf:Property ;\n  rdfs:label {}", prefixes.term(decl.predicate), value_term(decl.name)));
+        if let Some(domain) = &decl.domain { out.push_str(&format!(" ;\n  rdfs:domain {}", domain)); }
+        if let Some(range) = &decl.range { out.push_str(&format!(" ;\n  rdfs:range {}", range)); }
+        out.push_str(" .\n");
+    }
+
+    out
+}
+
+/// Build a SKOS vocabulary (Turtle) from the value sets in shapes: a
+/// skos:ConceptScheme per enum and a skos:Concept per permissible value.
+/// IRI values are kept as the concept IRI; other values are minted under the
+/// scheme and become the concept's skos:prefLabel.
+pub fn build_skos(shapes: &[ShapeInfo]) -> String {
+    build_skos_with_prefixes(shapes, &Prefixes::default())
+}
+
+/// Build a SKOS vocabulary that declares the given prefixes and writes IRIs as CURIEs where it can
+pub fn build_skos_with_prefixes(shapes: &[ShapeInfo], prefixes: &Prefixes) -> String {
+    let mut out = prefixes.turtle_header(&[("ex", "http://example.org/"), ("skos", "http://www.w3.org/2004/02/skos/core#")]);
+
+    let mut seen: Vec<String> = Vec::new();
+    for p in shapes.iter().flat_map(|s| s.properties.iter()).filter(|p| !p.values.is_empty()) {
+        let scheme = enum_name_for(p);
+        if seen.contains(&scheme) { continue; }
+        seen.push(scheme.clone());
+
+        let scheme_term = prefixes.class_term(&scheme);
+        out.push_str(&format!("\n{} a skos:ConceptScheme ;\n  skos:prefLabel {} .\n", scheme_term, value_term(&scheme)));
+        for v in p.values.iter() {
+            let (concept, label) = if v.starts_with("http://") || v.starts_with("https://") {
+                (prefixes.term(v), local_name(v))
+            } else {
+                let encoded: String = url::form_urlencoded::byte_serialize(v.as_bytes()).collect();
+                (format!("<http://example.org/{}/{}>", scheme, encoded), v.clone())
+            };
+            out.push_str(&format!("{} a skos:Concept ;\n  skos:inScheme {} ;\n  skos:topConceptOf {} ;\n  skos:prefLabel {} .\n",
+                concept, scheme_term, scheme_term, value_term(&label)));
+        }
+    }
+
+    out
+}
+
+/// What the shapes say about one predicate, for OWL/RDFS property declarations
+struct PredicateDecl<'a> {
+    predicate: &'a str,
+    /// property name from the first use
+    name: &'a str,
+    /// whether the values are shapes (object property) or literals
+    is_object: bool,
+    domain: Option<String>,
+    range: Option<String>,
+}
+
+/// One declaration per predicate; domain/range only when every use agrees
+fn predicate_declarations<'a>(shapes: &'a [ShapeInfo], prefixes: &Prefixes) -> Vec<PredicateDecl<'a>> {
+    let mut predicates: Vec<&str> = Vec::new();
+    for s in shapes.iter() {
+        for p in s.properties.iter() {
+            if !predicates.contains(&p.predicate.as_str()) { predicates.push(&p.predicate); }
+        }
+    }
+    predicates.into_iter().map(|pred| {
+        let uses: Vec<(&ShapeInfo, &PropertyInfo)> = shapes.iter()
+            .flat_map(|s| s.properties.iter().filter(|p| p.predicate == pred).map(move |p| (s, p)))
+            .collect();
+        let (_, first) = uses[0];
+        let is_object = shapes.iter().any(|other| other.name == first.range);
+        let domain = if uses.iter().all(|(s, _)| s.name == uses[0].0.name) { Some(prefixes.class_term(&uses[0].0.name)) } else { None };
+        let range = if !uses.iter().all(|(_, p)| p.range == first.range) {
+            None
+        } else if is_object {
+            Some(prefixes.class_term(&first.range))
+        } else if let Some(dt) = xsd_datatype_for(&first.range) {
+            Some(dt.to_string())
+        } else if first.range.starts_with("http") {
+            Some(prefixes.term(&first.range))
+        } else {
+            None
+        };
+        PredicateDecl { predicate: pred, name: &first.name, is_object, domain, range }
+    }).collect()
+}
+
+/// Build a GraphQL SDL schema from shapes: a `type` per shape, scalars for
+/// primitive ranges, `!` for min >= 1, lists for max > 1 and an `enum` per
+/// distinct value set of an enumerated property whose values are valid GraphQL
+/// names (`true`, `false` and `null` get a trailing `_`, as GraphQL reserves them).
+pub fn build_graphql(shapes: &[ShapeInfo]) -> String {
+    let mut out = String::new();
+    let mut enums = String::new();
+    let mut declared = Vec::new();
+
+    for s in shapes.iter() {
+        out.push_str(&format!("type {} {{\n", identifier(&s.name)));
+        for p in merged_properties(&s.properties).iter() {
+            let base = if !p.values.is_empty() && p.values.iter().all(|v| identifier(v) == *v) {
+                let (enum_name, new) = declared_enum(&mut declared, &identifier(&s.name), p);
+                if new {
+                    enums.push_str(&format!("\nenum {} {{\n", enum_name));
+                    for v in p.values.iter() {
+                        let reserved = ["true", "false", "null"].contains(&v.as_str());
+                        enums.push_str(&format!("  {}{}\n", v, if reserved { "_" } else { "" }));
+                    }
+                    enums.push_str("}\n");
+                }
+                enum_name
+            } else if shapes.iter().any(|other| other.name == p.range) {
+                identifier(&p.range)
+            } else {
+                code_types(&p.range).graphql.to_string()
+            };
+            let required = p.min.unwrap_or(0) > 0;
+            let field_type = match p.max {
+                Some(1) => base,
+                _ => format!("[{}!]", base),
+            };
+            out.push_str(&format!("  {}: {}{}\n", identifier(&p.name), field_type, if required { "!" } else { "" }));
+        }
+        out.push_str("}\n\n");
+    }
+
+    out.push_str(enums.trim_start());
+    out.trim_end().to_string() + "\n"
+}
+
+/// Build a proto3 file from shapes: a `message` per shape with fields numbered
+/// in property order, `repeated` for max > 1, `optional` for min = 0 and
+/// message-typed fields for shape references. Each distinct value set of an
+/// enumerated property is one top-level `enum`.
+pub fn build_protobuf(shapes: &[ShapeInfo]) -> String {
+    let mut out = String::new();
+    out.push_str("syntax = \"proto3\";\n");
+    let mut enums = String::new();
+    let mut declared = Vec::new();
+
+    for s in shapes.iter() {
+        out.push_str(&format!("\nmessage {} {{\n", identifier(&s.name)));
+        for (i, p) in merged_properties(&s.properties).iter().enumerate() {
+            let scalar = if !p.values.is_empty() && p.values.iter().all(|v| identifier(v) == *v) {
+                let (enum_name, new) = declared_enum(&mut declared, &identifier(&s.name), p);
+                if new {
+                    let prefix = proto_enum_prefix(&enum_name);
+                    enums.push_str(&format!("\nenum {} {{\n  {}_UNSPECIFIED = 0;\n", enum_name, prefix));
+                    for (j, v) in p.values.iter().enumerate() {
+                        enums.push_str(&format!("  {}_{} = {};\n", prefix, v.to_uppercase(), j + 1));
+                    }
+                    enums.push_str("}\n");
+                }
+                enum_name
+            } else if shapes.iter().any(|other| other.name == p.range) {
+                identifier(&p.range)
+            } else {
+                code_types(&p.range).protobuf.to_string()
+            };
+            let label = match (p.min.unwrap_or(0), p.max) {
+                (_, None) => "repeated ",
+                (_, Some(n)) if n > 1 => "repeated ",
+                (0, _) => "optional ",
+                _ => "",
+            };
+            out.push_str(&format!("  {}{} {} = {};\n", label, scalar, identifier(&p.name), i + 1));
+        }
+        out.push_str("}\n");
+    }
+
+    out.push_str(&enums);
+    out
+}
+
+/// proto3 enum values share a scope, so prefix them with the enum name: `StatusEnum` -> `STATUS_ENUM`
+fn proto_enum_prefix(enum_name: &str) -> String {
+    let mut out = String::new();
+    for (i, c) in enum_name.chars().enumerate() {
+        if c.is_uppercase() && i > 0 { out.push('_'); }
+        out.push(c.to_ascii_uppercase());
+    }
+    out
+}
+
+/// Build TypeScript declarations (`.d.ts`) from shapes: an interface per shape,
+/// `?` for min = 0, arrays for max > 1, string literal unions for value sets.
+pub fn build_typescript(shapes: &[ShapeInfo]) -> String {
+    let mut out = String::new();
+
+    for s in shapes.iter() {
+        if !out.is_empty() { out.push('\n'); }
+        out.push_str(&format!("export interface {} {{\n", identifier(&s.name)));
+        for p in merged_properties(&s.properties).iter() {
+            let base = if !p.values.is_empty() {
+                let vs: Vec<String> = p.values.iter().map(|v| serde_json::to_string(v).unwrap_or_default()).collect();
+                vs.join(" | ")
+            } else if shapes.iter().any(|other| other.name == p.range) {
+                identifier(&p.range)
+            } else {
+                code_types(&p.range).typescript.to_string()
+            };
+            let ts_type = match p.max {
+                Some(1) => base,
+                _ if p.values.len() > 1 => format!("({})[]", base),
+                _ => format!("{}[]", base),
+            };
+            let key = if identifier(&p.name) == p.name { p.name.clone() } else { serde_json::to_string(&p.name).unwrap_or_default() };
+            let optional = if p.min.unwrap_or(0) == 0 { "?" } else { "" };
+            out.push_str(&format!("  {}{}: {};\n", key, optional, ts_type));
+        }
+        out.push_str("}\n");
+    }
+
+    out
+}
+
+/// Build Zod schemas (TypeScript) from shapes: the `build_typescript`
+/// interfaces, then a `<Shape>Schema` per shape typed against its interface
+/// so that shape references can go through `z.lazy` in any order.
+pub fn build_zod(shapes: &[ShapeInfo]) -> String {
+    let mut out = String::from("import { z } from \"zod\";\n\n");
+    out.push_str(&build_typescript(shapes));
+
+    for s in shapes.iter() {
+        let type_name = identifier(&s.name);
+        out.push_str(&format!("\nexport const {}Schema: z.ZodType<{}> = z.object({{\n", type_name, type_name));
+        for p in merged_properties(&s.properties).iter() {
+            let base = if !p.values.is_empty() {
+                let vs: Vec<String> = p.values.iter().map(|v| serde_json::to_string(v).unwrap_or_default()).collect();
+                format!("z.enum([{}])", vs.join(", "))
+            } else if shapes.iter().any(|other| other.name == p.range) {
+                format!("z.lazy(() => {}Schema)", identifier(&p.range))
+            } else {
+                code_types(&p.range).zod.to_string()
+            };
+            let min = p.min.unwrap_or(0);
+            let mut zod = match p.max {
+                Some(1) => base,
+                max => {
+                    let mut arr = format!("z.array({})", base);
+                    if min > 0 { arr.push_str(&format!(".min({})", min)); }
+                    if let Some(m) = max { arr.push_str(&format!(".max({})", m)); }
+                    arr
+                }
+            };
+            if min == 0 { zod.push_str(".optional()"); }
+            let key = if identifier(&p.name) == p.name { p.name.clone() } else { serde_json::to_string(&p.name).unwrap_or_default() };
+            out.push_str(&format!("  {}: {},\n", key, zod));
+        }
+        out.push_str("});\n");
+    }
+
+    out
+}
+
+/// Build Pydantic v2 models (Python) from shapes: a `BaseModel` per shape with
+/// `Optional` for min = 0, `List` for max > 1, `Field` length constraints
+/// for explicit list cardinalities and `Field` constraints from the facets
+/// (on each item of a list, through `Annotated`).
+pub fn build_pydantic(shapes: &[ShapeInfo]) -> String {
+    let mut out = String::new();
+    out.push_str("from __future__ import annotations\n\n");
+    // dates and times are typed as the datetime module's classes
+    let temporal: Vec<&str> = ["date", "datetime", "time"].into_iter()
+        .filter(|t| shapes.iter().flat_map(|s| s.properties.iter()).any(|p| p.values.is_empty() && code_types(&p.range).pydantic == *t))
+        .collect();
+    if !temporal.is_empty() {
+        out.push_str(&format!("from datetime import {}\n", temporal.join(", ")));
+    }
+    out.push_str("from typing import Annotated, List, Literal, Optional\n\n");
+    out.push_str("from pydantic import BaseModel, Field\n");
+
+    for s in shapes.iter() {
+        out.push_str(&format!("\n\nclass {}(BaseModel):\n", identifier(&s.name)));
+        if s.properties.is_empty() {
+            out.push_str("    pass\n");
+        }
+        for p in merged_properties(&s.properties).iter() {
+            let base = if !p.values.is_empty() {
+                let vs: Vec<String> = p.values.iter().map(|v| serde_json::to_string(v).unwrap_or_default()).collect();
+                format!("Literal[{}]", vs.join(", "))
+            } else if shapes.iter().any(|other| other.name == p.range) {
+                identifier(&p.range)
+            } else {
+                code_types(&p.range).pydantic.to_string()
+            };
+
+            let min = p.min.unwrap_or(0);
+            let mut field_args: Vec<String> = Vec::new();
+            let facet_args = pydantic_facets(&p.facets);
+            let (py_type, default) = match p.max {
+                Some(1) if min == 0 => {
+                    field_args.extend(facet_args);
+                    (format!("Optional[{}]", base), Some("None".to_string()))
+                }
+                Some(1) => {
+                    field_args.extend(facet_args);
+                    (base, None)
+                }
+                max => {
+                    if min > 0 { field_args.push(format!("min_length={}", min)); }
+                    if let Some(max) = max { field_args.push(format!("max_length={}", max)); }
+                    let default = if min == 0 { Some("default_factory=list".to_string()) } else { None };
+                    let item = if facet_args.is_empty() { base } else { format!("Annotated[{}, Field({})]", base, facet_args.join(", ")) };
+                    (format!("List[{}]", item), default)
+                }
+            };
+
+            let mut field_name = identifier(&p.name);
+            if PYTHON_KEYWORDS.contains(&field_name.as_str()) { field_name.push('_'); }
+            if field_name != p.name {
+                field_args.push(format!("alias={}", serde_json::to_string(&p.name).unwrap_or_default()));
+            }
+
+            let assignment = match (default, field_args.is_empty()) {
+                (Some(d), true) if d == "None" => " = None".to_string(),
+                (None, true) => String::new(),
+                (Some(d), false) if d == "None" => format!(" = Field(default=None, {})", field_args.join(", ")),
+                (Some(d), _) => format!(" = Field({})", std::iter::once(d).chain(field_args).collect::<Vec<_>>().join(", ")),
+                (None, false) => format!(" = Field({})", field_args.join(", ")),
+            };
+            out.push_str(&format!("    {}: {}{}\n", field_name, py_type, assignment));
+        }
+    }
+
+    out
+}
+
+/// Pydantic `Field` constraints for XSD facets: `pattern`, string lengths and numeric bounds
+fn pydantic_facets(facets: &Facets) -> Vec<String> {
+    let mut args = Vec::new();
+    if let Some(pattern) = &facets.pattern {
+        args.push(format!("pattern={}", serde_json::to_string(pattern).unwrap_or_default()));
+    }
+    if let Some(n) = facets.min_length { args.push(format!("min_length={}", n)); }
+    if let Some(n) = facets.max_length { args.push(format!("max_length={}", n)); }
+    let bounds = [("ge", &facets.min_inclusive), ("gt", &facets.min_exclusive), ("le", &facets.max_inclusive), ("lt", &facets.max_exclusive)];
+    for (key, bound) in bounds {
+        if let Some(n) = bound { args.push(format!("{}={}", key, n)); }
+    }
+    if let Some(n) = facets.total_digits { args.push(format!("max_digits={}", n)); }
+    if let Some(n) = facets.fraction_digits { args.push(format!("decimal_places={}", n)); }
+    args
+}
+
+pub(crate) const PYTHON_KEYWORDS: &[&str] = &[
+    "False", "None", "True", "and", "as", "assert", "async", "await", "break", "class", "continue",
+    "def", "del", "elif", "else", "except", "finally", "for", "from", "global", "if", "import", "in",
+    "is", "lambda", "nonlocal", "not", "or", "pass", "raise", "return", "try", "while", "with", "yield",
+];
+
+/// Build Rust structs with serde derives from shapes: `Option` for min = 0,
+/// `Vec` for max > 1, boxed references to other shapes, an `Iri` newtype
+/// for IRI-valued properties and snake_case fields renamed to their properties.
+pub fn build_rust(shapes: &[ShapeInfo]) -> String {
+    let mut out = String::new();
+    out.push_str("use serde::{Deserialize, Serialize};\n");
+    let mut enums = String::new();
+    let mut declared = Vec::new();
+    let mut uses_iri = false;
+
+    for s in shapes.iter() {
+        out.push_str("\n#[derive(Debug, Clone, Serialize, Deserialize)]\n");
+        out.push_str(&format!("pub struct {} {{\n", identifier(&s.name)));
+        for p in merged_properties(&s.properties).iter() {
+            let is_ref = shapes.iter().any(|other| other.name == p.range);
+            let base = if !p.values.is_empty() && p.values.iter().all(|v| identifier(v) == *v) {
+                let (enum_name, new) = declared_enum(&mut declared, &identifier(&s.name), p);
+                if new {
+                    enums.push_str("\n#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]\n");
+                    enums.push_str(&format!("pub enum {} {{\n", enum_name));
+                    for v in p.values.iter() {
+                        let variant = rust_type_name(v);
+                        if variant != *v { enums.push_str(&format!("    #[serde(rename = \"{}\")]\n", v)); }
+                        enums.push_str(&format!("    {},\n", variant));
+                    }
+                    enums.push_str("}\n");
+                }
+                enum_name
+            } else if is_ref {
+                identifier(&p.range)
+            } else if p.node_kind == Some(NodeKind::Iri) {
+                uses_iri = true;
+                "Iri".to_string()
+            } else {
+                let rust = code_types(&p.range).rust;
+                if rust == "Iri" { uses_iri = true; }
+                rust.to_string()
+            };
+
+            let min = p.min.unwrap_or(0);
+            let mut attrs: Vec<String> = Vec::new();
+            let rust_type = match p.max {
+                Some(1) => {
+                    // single-valued shape references are boxed so recursive shapes have a size
+                    let base = if is_ref { format!("Box<{}>", base) } else { base };
+                    if min == 0 {
+                        attrs.push("default, skip_serializing_if = \"Option::is_none\"".to_string());
+                        format!("Option<{}>", base)
+                    } else {
+                        base
+                    }
+                }
+                _ => {
+                    if min == 0 { attrs.push("default".to_string()); }
+                    format!("Vec<{}>", base)
+                }
+            };
+
+            let mut field_name = identifier(&cased(&p.name, NameCase::Snake));
+            if RUST_KEYWORDS.contains(&field_name.as_str()) { field_name.push('_'); }
+            if field_name != p.name { attrs.insert(0, format!("rename = \"{}\"", p.name)); }
+            if !attrs.is_empty() { out.push_str(&format!("    #[serde({})]\n", attrs.join(", "))); }
+            out.push_str(&format!("    pub {}: {},\n", field_name, rust_type));
+        }
+        out.push_str("}\n");
+    }
+
+    if uses_iri {
+        out.push_str("\n/// An IRI-valued property\n");
+        out.push_str("#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]\n");
+        out.push_str("#[serde(transparent)]\n");
+        out.push_str("pub struct Iri(pub String);\n");
+    }
+    out.push_str(&enums);
+    out
+}
+
+/// Build Kotlin data classes (kotlinx.serialization) from shapes: nullable
+/// types defaulting to `null` for min = 0, `List` for max > 1 and an
+/// `enum class` per distinct value set of an enumerated property.
+pub fn build_kotlin(shapes: &[ShapeInfo]) -> String {
+    let mut out = String::new();
+    out.push_str("import kotlinx.serialization.SerialName\n");
+    out.push_str("import kotlinx.serialization.Serializable\n");
+    let mut enums = String::new();
+    let mut declared = Vec::new();
+
+    for s in shapes.iter() {
+        out.push_str(&format!("\n@Serializable\ndata class {}(\n", identifier(&s.name)));
+        for p in merged_properties(&s.properties).iter() {
+            let base = if !p.values.is_empty() {
+                let (enum_name, new) = declared_enum(&mut declared, &identifier(&s.name), p);
+                if new {
+                    enums.push_str(&format!("\n@Serializable\nenum class {} {{\n", enum_name));
+                    for v in p.values.iter() {
+                        enums.push_str(&format!("    @SerialName({}) {},\n", serde_json::to_string(v).unwrap_or_default(), identifier(v).to_uppercase()));
+                    }
+                    enums.push_str("}\n");
+                }
+                enum_name
+            } else if shapes.iter().any(|other| other.name == p.range) {
+                identifier(&p.range)
+            } else {
+                code_types(&p.range).kotlin.to_string()
+            };
+
+            let kotlin_type = match (p.min.unwrap_or(0), p.max) {
+                (0, Some(1)) => format!("{}? = null", base),
+                (_, Some(1)) => base,
+                (0, _) => format!("List<{}> = emptyList()", base),
+                _ => format!("List<{}>", base),
+            };
+
+            let mut field_name = identifier(&p.name);
+            if KOTLIN_KEYWORDS.contains(&field_name.as_str()) { field_name = format!("`{}`", field_name); }
+            let serial_name = if identifier(&p.name) != p.name { format!("@SerialName({}) ", serde_json::to_string(&p.name).unwrap_or_default()) } else { String::new() };
+            out.push_str(&format!("    {}val {}: {},\n", serial_name, field_name, kotlin_type));
+        }
+        out.push_str(")\n");
+    }
+
+    out.push_str(&enums);
+    out
+}
+
+pub(crate) const KOTLIN_KEYWORDS: &[&str] = &[
+    "as", "break", "class", "continue", "do", "else", "false", "for", "fun", "if", "in", "interface", "is",
+    "null", "object", "package", "return", "super", "this", "throw", "true", "try", "typealias", "typeof",
+    "val", "var", "when", "while",
+];
+
+pub(crate) const RUST_KEYWORDS: &[&str] = &[
+    "as", "async", "await", "box", "break", "const", "continue", "crate", "dyn", "else", "enum", "extern",
+    "false", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub", "ref",
+    "return", "self", "static", "struct", "super", "trait", "true", "type", "unsafe", "use", "where", "while",
+];
+
+/// UpperCamelCase a value for use as a Rust type or variant name: `in-progress` -> `InProgress`
+fn rust_type_name(name: &str) -> String {
+    identifier(name)
+        .split('_')
+        .filter(|w| !w.is_empty())
+        .map(|w| {
+            let mut chars = w.chars();
+            match chars.next() {
+                Some(c) => c.to_uppercase().chain(chars).collect::<String>(),
+                None => String::new(),
+            }
+        })
+        .collect()
+}
+
+/// Build an XML Schema from shapes: a complexType (and global element) per
+/// shape with an element per property carrying minOccurs/maxOccurs.
+pub fn build_xsd(shapes: &[ShapeInfo]) -> String {
+    let mut out = String::new();
+    out.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    out.push_str("<xs:schema xmlns:xs=\"http://www.w3.org/2001/XMLSchema\" elementFormDefault=\"qualified\">\n");
+
+    for s in shapes.iter() {
+        let type_name = identifier(&s.name);
+        out.push_str(&format!("  <xs:element name=\"{}\" type=\"{}\"/>\n", type_name, type_name));
+        out.push_str(&format!("  <xs:complexType name=\"{}\">\n    <xs:sequence>\n", type_name));
+        for p in merged_properties(&s.properties).iter() {
+            let min = p.min.unwrap_or(0);
+            let mut attrs = format!("name=\"{}\"", identifier(&p.name));
+            let type_attr = if !p.values.is_empty() {
+                None
+            } else if shapes.iter().any(|other| other.name == p.range) {
+                Some(identifier(&p.range))
+            } else {
+                Some(xsd_datatype_for(&p.range).unwrap_or_else(|| "xs:string".to_string()).replace("xsd:", "xs:"))
+            };
+            if let Some(t) = &type_attr { attrs.push_str(&format!(" type=\"{}\"", t)); }
+            if min != 1 { attrs.push_str(&format!(" minOccurs=\"{}\"", min)); }
+            match p.max {
+                Some(1) => {}
+                Some(n) => attrs.push_str(&format!(" maxOccurs=\"{}\"", n)),
+                None => attrs.push_str(" maxOccurs=\"unbounded\""),
+            }
+            if type_attr.is_some() {
+                out.push_str(&format!("      <xs:element {}/>\n", attrs));
+            } else {
+                out.push_str(&format!("      <xs:element {}>\n", attrs));
+                out.push_str("        <xs:simpleType>\n          <xs:restriction base=\"xs:string\">\n");
+                for v in p.values.iter() {
+                    out.push_str(&format!("            <xs:enumeration value=\"{}\"/>\n", xml_escape(v)));
+                }
+                out.push_str("          </xs:restriction>\n        </xs:simpleType>\n      </xs:element>\n");
+            }
+        }
+        out.push_str("    </xs:sequence>\n  </xs:complexType>\n");
+    }
+
+    out.push_str("</xs:schema>\n");
+    out
+}
+
+/// Build BigQuery table schemas (JSON) from shapes: a field list per shape, keyed
+/// by shape name, with REQUIRED/NULLABLE/REPEATED modes from the cardinality.
+/// Shape references are nested as RECORDs; a reference back into a record
+/// already being expanded is written as a STRING holding the node's IRI.
+pub fn build_bigquery(shapes: &[ShapeInfo]) -> serde_json::Value {
+    let mut tables = serde_json::Map::new();
+    for s in shapes.iter() {
+        tables.insert(identifier(&s.name), JsonValue::Array(bigquery_fields(s, shapes, &mut vec![s.name.as_str()])));
+    }
+    JsonValue::Object(tables)
+}
+
+fn bigquery_fields<'a>(shape: &'a ShapeInfo, shapes: &'a [ShapeInfo], expanding: &mut Vec<&'a str>) -> Vec<JsonValue> {
+    use serde_json::json;
+
+    merged_properties(&shape.properties).iter().map(|p| {
+        let mode = match p.max {
+            Some(1) if p.min.unwrap_or(0) >= 1 => "REQUIRED",
+            Some(1) => "NULLABLE",
+            _ => "REPEATED",
+        };
+        let mut field = json!({ "name": identifier(&p.name), "mode": mode });
+        match shapes.iter().find(|other| other.name == p.range) {
+            Some(target) if !expanding.contains(&target.name.as_str()) => {
+                expanding.push(target.name.as_str());
+                field["type"] = json!("RECORD");
+                field["fields"] = JsonValue::Array(bigquery_fields(target, shapes, expanding));
+                expanding.pop();
+            }
+            _ => {
+                field["type"] = json!(code_types(&p.range).bigquery);
+            }
+        }
+        field
+    }).collect()
+}
+
+/// Build a SPARQL query per shape that retrieves its instances with every
+/// declared property: required properties (min >= 1) as plain triple patterns,
+/// the rest in OPTIONALs. With `construct` the queries are CONSTRUCTs
+/// returning those triples, otherwise SELECTs binding a variable per property.
+/// Returns `(shape name, query)` pairs.
+pub fn build_sparql(shapes: &[ShapeInfo], construct: bool) -> Vec<(String, String)> {
+    build_sparql_with_prefixes(shapes, construct, &Prefixes::default())
+}
+
+/// Build SPARQL queries that declare the given base and prefixes and write predicates as CURIEs where they can
+pub fn build_sparql_with_prefixes(shapes: &[ShapeInfo], construct: bool, prefixes: &Prefixes) -> Vec<(String, String)> {
+    shapes.iter().map(|s| {
+        let vars: Vec<String> = s.properties.iter().map(|p| {
+            let v = identifier(&p.name);
+            if v == "this" { "this_".to_string() } else { v }
+        }).collect();
+
+        let mut pattern = String::new();
+        if !s.properties.iter().any(|p| p.min.unwrap_or(0) >= 1) {
+            // nothing is mandatory, so a focus node needs at least one of the properties
+            let alternatives: Vec<String> = s.properties.iter().map(|p| format!("{{ ?this {} [] }}", prefixes.term(&p.predicate))).collect();
+            if alternatives.is_empty() {
+                pattern.push_str("  ?this ?p ?o .\n");
+            } else {
+                pattern.push_str(&format!("  {}\n", alternatives.join(" UNION ")));
+            }
+        }
+        for (p, v) in s.properties.iter().zip(vars.iter()) {
+            if p.min.unwrap_or(0) >= 1 {
+                pattern.push_str(&format!("  ?this {} ?{} .\n", prefixes.term(&p.predicate), v));
+            } else {
+                pattern.push_str(&format!("  OPTIONAL {{ ?this {} ?{} }}\n", prefixes.term(&p.predicate), v));
+            }
+        }
+
+        let mut query = prefixes.sparql_header(&[("ex", "http://example.org/")]) + "\n";
+        if construct {
+            query.push_str("CONSTRUCT {\n");
+            for (p, v) in s.properties.iter().zip(vars.iter()) {
+                query.push_str(&format!("  ?this {} ?{} .\n", prefixes.term(&p.predicate), v));
+            }
+            query.push_str("}\n");
+        } else {
+            let mut select = vec!["?this".to_string()];
+            select.extend(vars.iter().map(|v| format!("?{}", v)));
+            query.push_str(&format!("SELECT {}\n", select.join(" ")));
+        }
+        query.push_str(&format!("WHERE {{\n{}}}\n", pattern));
+        (s.name.clone(), query)
+    }).collect()
+}
+
+/// Build a JSON-LD frame per shape: a context mapping property names to
+/// predicates, `@type` matching the shape's class IRI, and a nested frame
+/// embedding each referenced shape (once per branch, so cycles terminate).
+/// Multivalued properties use a `@set` container so they frame as arrays.
+/// Returns `(shape name, frame)` pairs.
+pub fn build_jsonld_frames(shapes: &[ShapeInfo]) -> Vec<(String, JsonValue)> {
+    build_jsonld_frames_with_prefixes(shapes, &Prefixes::default())
+}
+
+/// Build JSON-LD frames whose contexts declare the given base and prefixes, and whose
+/// `@type`s are CURIEs where they can be
+pub fn build_jsonld_frames_with_prefixes(shapes: &[ShapeInfo], prefixes: &Prefixes) -> Vec<(String, JsonValue)> {
+    shapes.iter().map(|s| {
+        let mut context = serde_json::Map::new();
+        if let Some(base) = &prefixes.base {
+            context.insert("@base".to_string(), JsonValue::String(base.clone()));
+        }
+        for (prefix, ns) in prefixes.with_defaults(&[("ex", "http://example.org/")]) {
+            context.insert(prefix.to_string(), JsonValue::String(ns.to_string()));
+        }
+        let mut frame = jsonld_frame(s, shapes, prefixes, &mut vec![s.name.as_str()], &mut context);
+        frame.insert("@context".to_string(), JsonValue::Object(context));
+        (s.name.clone(), JsonValue::Object(frame))
+    }).collect()
+}
+
+fn jsonld_frame<'a>(
+    shape: &'a ShapeInfo,
+    shapes: &'a [ShapeInfo],
+    prefixes: &Prefixes,
+    expanding: &mut Vec<&'a str>,
+    context: &mut serde_json::Map<String, JsonValue>,
+) -> serde_json::Map<String, JsonValue> {
+    use serde_json::json;
+
+    let mut frame = serde_json::Map::new();
+    // a CURIE, an IRI, or a name relative to `@base`
+    let class = match (prefixes.compact(&shape.name), &prefixes.base) {
+        (Some(curie), _) => curie,
+        (None, _) if shape.name.contains(':') => shape.name.clone(),
+        (None, Some(_)) => shape.name.clone(),
+        (None, None) => format!("ex:{}", shape.name),
+    };
+    frame.insert("@type".to_string(), json!(class));
+    for p in shape.properties.iter() {
+        let target = shapes.iter().find(|other| other.name == p.range);
+        if !context.contains_key(&p.name) {
+            let mut term = serde_json::Map::new();
+            term.insert("@id".to_string(), json!(p.predicate));
+            if target.is_some() { term.insert("@type".to_string(), json!("@id")); }
+            if p.max != Some(1) { term.insert("@container".to_string(), json!("@set")); }
+            context.insert(p.name.clone(), JsonValue::Object(term));
+        }
+        if let Some(target) = target {
+            if !expanding.contains(&target.name.as_str()) {
+                expanding.push(target.name.as_str());
+                frame.insert(p.name.clone(), JsonValue::Object(jsonld_frame(target, shapes, prefixes, expanding, context)));
+                expanding.pop();
+            }
+        }
+    }
+    frame
+}
+
+/// Build a Mermaid classDiagram from shapes: a class per shape listing its
+/// literal-valued properties, and an association labelled with the property
+/// name and cardinality for each shape reference.
+pub fn build_mermaid(shapes: &[ShapeInfo]) -> String {
+    let mut out = String::from("classDiagram\n");
+    let mut links = Vec::new();
+
+    for s in shapes.iter() {
+        let class_name = identifier(&s.name);
+        out.push_str(&format!("  class {} {{\n", class_name));
+        for p in s.properties.iter() {
+            if shapes.iter().any(|other| other.name == p.range) {
+                links.push(format!("  {} --> \"{}\" {} : {}\n", class_name, uml_cardinality(p.min, p.max), identifier(&p.range), p.name));
+            } else {
+                let range = if p.values.is_empty() { p.range.clone() } else { enum_name_for(p) };
+                out.push_str(&format!("    +{} {} [{}]\n", identifier(&range), p.name, uml_cardinality(p.min, p.max)));
+            }
+        }
+        out.push_str("  }\n");
+    }
+
+    for l in links { out.push_str(&l); }
+    out
+}
+
+/// Build a PlantUML class diagram from shapes, laid out like `build_mermaid`.
+/// With `cluster`, classes are wrapped in a `package` per namespace.
+pub fn build_plantuml(shapes: &[ShapeInfo], cluster: bool) -> String {
+    let mut out = String::from("@startuml\n");
+    let mut links = Vec::new();
+
+    for (ns, members) in group_by_namespace(shapes, cluster) {
+        let indent = if ns.is_empty() { "" } else { "  " };
+        if !ns.is_empty() { out.push_str(&format!("package \"{}\" {{\n", ns)); }
+        for s in members {
+            let class_name = identifier(&s.name);
+            out.push_str(&format!("{}class {} {{\n", indent, class_name));
+            for p in s.properties.iter() {
+                if shapes.iter().any(|other| other.name == p.range) {
+                    links.push(format!("{} --> \"{}\" {} : {}\n", class_name, uml_cardinality(p.min, p.max), identifier(&p.range), p.name));
+                } else {
+                    let range = if p.values.is_empty() { p.range.clone() } else { enum_name_for(p) };
+                    out.push_str(&format!("{}  +{} : {} [{}]\n", indent, p.name, range, uml_cardinality(p.min, p.max)));
+                }
+            }
+            out.push_str(&format!("{}}}\n", indent));
+        }
+        if !ns.is_empty() { out.push_str("}\n"); }
+    }
+
+    for l in links { out.push_str(&l); }
+    out.push_str("@enduml\n");
+    out
+}
+
+/// Build a Graphviz DOT digraph from shapes: a record node per shape listing
+/// its literal properties and a labelled edge per shape reference. With
+/// `cluster`, nodes are grouped into a `subgraph cluster_N` per namespace.
+pub fn build_dot(shapes: &[ShapeInfo], cluster: bool) -> String {
+    let mut out = String::from("digraph schema {\n  node [shape=record];\n");
+    let mut edges = Vec::new();
+
+    for (i, (ns, members)) in group_by_namespace(shapes, cluster).into_iter().enumerate() {
+        let indent = if ns.is_empty() { "  " } else { "    " };
+        if !ns.is_empty() {
+            out.push_str(&format!("  subgraph cluster_{} {{\n    label=\"{}\";\n", i, dot_escape(&ns)));
+        }
+        for s in members {
+            let node = identifier(&s.name);
+            let mut fields = String::new();
+            for p in s.properties.iter() {
+                if shapes.iter().any(|other| other.name == p.range) {
+                    edges.push(format!("  {} -> {} [label=\"{} {}\"];\n", node, identifier(&p.range), dot_escape(&p.name), uml_cardinality(p.min, p.max)));
+                } else {
+                    let range = if p.values.is_empty() { p.range.clone() } else { enum_name_for(p) };
+                    fields.push_str(&format!("{} : {} [{}]\\l", dot_escape(&p.name), dot_escape(&range), uml_cardinality(p.min, p.max)));
+                }
+            }
+            out.push_str(&format!("{}{} [label=\"{{{}|{}}}\"];\n", indent, node, dot_escape(&s.name), fields));
+        }
+        if !ns.is_empty() { out.push_str("  }\n"); }
+    }
+
+    for e in edges { out.push_str(&e); }
+    out.push_str("}\n");
+    out
+}
+
+/// Shapes grouped by the namespace of their id, in first-seen order.
+/// Without `cluster` everything lands in a single group with an empty namespace.
+fn group_by_namespace(shapes: &[ShapeInfo], cluster: bool) -> Vec<(String, Vec<&ShapeInfo>)> {
+    let mut groups: Vec<(String, Vec<&ShapeInfo>)> = Vec::new();
+    for s in shapes.iter() {
+        let ns = if cluster { s.id[..s.id.len() - local_name(&s.id).len()].to_string() } else { String::new() };
+        match groups.iter_mut().find(|(n, _)| *n == ns) {
+            Some((_, members)) => members.push(s),
+            None => groups.push((ns, vec![s])),
+        }
+    }
+    groups
+}
+
+/// Escape text for a DOT record label
+fn dot_escape(s: &str) -> String {
+    let mut out = String::new();
+    for c in s.chars() {
+        if "\"{}|<>\\".contains(c) { out.push('\\'); }
+        out.push(c);
+    }
+    out
+}
+
+/// UML multiplicity for a min/max pair: `1`, `0..1`, `0..*`, `2..5`, ...
+fn uml_cardinality(min: Option<u64>, max: Option<u64>) -> String {
+    let min = min.unwrap_or(0);
+    match max {
+        Some(n) if n == min => n.to_string(),
+        Some(n) => format!("{}..{}", min, n),
+        None => format!("{}..*", min),
+    }
+}
+
+fn xml_escape(s: &str) -> String {
+    s.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+/// Replace characters not allowed in GraphQL/protobuf/... identifiers (`[_A-Za-z][_0-9A-Za-z]*`)
+pub(crate) fn identifier(name: &str) -> String {
+    let mut out: String = name.chars().map(|c| if c.is_ascii_alphanumeric() || c == '_' { c } else { '_' }).collect();
+    if out.is_empty() || out.starts_with(|c: char| c.is_ascii_digit()) {
+        out.insert(0, '_');
+    }
+    out
+}
+
+/// xsd datatype CURIE for one of our primitive ranges
+fn xsd_datatype_for(range: &str) -> Option<String> {
+    XSD_TYPES.iter().find(|t| t.1 == range).map(|t| format!("xsd:{}", t.0))
+}
+
+/// JSON Schema type (and format) for one of our primitive ranges
+fn json_schema_type_for(range: &str) -> Option<JsonValue> {
+    if let Some(f) = XSD_FORMATS.iter().find(|f| f.0 == range) {
+        return Some(serde_json::json!({ "type": "string", "format": f.1 }));
+    }
+    let (_, _, json_type, format) = XSD_TYPES.iter().find(|t| t.1 == range)?;
+    let mut schema = serde_json::json!({ "type": json_type });
+    if let Some(format) = format {
+        schema["format"] = JsonValue::String(format.to_string());
+    }
+    Some(schema)
+}
+
+/// Node kind implied by a LinkML identifier range
+fn node_kind_for_range(range: &str) -> Option<NodeKind> {
+    match range {
+        "uriorcurie" => Some(NodeKind::Iri),
+        "nodeidentifier" => Some(NodeKind::NonLiteral),
+        _ => None,
+    }
+}
+
+/// JSON Schema pattern for an IRI or blank node label
+const NON_LITERAL_PATTERN: &str = "^(_:|[A-Za-z][A-Za-z0-9+.-]*:)";
+
+/// JSON Schema pattern for any BCP 47 language tag
+pub(crate) const LANGUAGE_TAG_PATTERN: &str = "^[A-Za-z]{1,8}(-[A-Za-z0-9]{1,8})*$";
+
+/// JSON Schema pattern for the language tags allowed by `PropertyInfo::languages`,
+/// e.g. `en fr~` -> `^(en|fr(-.+)?)$`
+fn language_tag_pattern(languages: &[String]) -> String {
+    if languages.iter().any(|l| l == "~") {
+        return LANGUAGE_TAG_PATTERN.to_string();
+    }
+    let alternatives: Vec<String> = languages.iter().map(|l| match l.strip_suffix('~') {
+        Some(stem) => format!("{}(-.+)?", stem),
+        None => l.clone(),
+    }).collect();
+    format!("^({})$", alternatives.join("|"))
+}
+
+/// Write a name, CURIE or IRI as an IRI term (same syntax in ShExC and Turtle)
+fn iri_term(name: &str) -> String {
+    if name.starts_with("http://") || name.starts_with("https://") {
+        format!("<{}>", name)
+    } else if name.contains(':') {
+        name.to_string()
+    } else {
+        format!("ex:{}", name)
+    }
+}
+
+/// Write a value set member: IRIs as `<...>`, everything else as a string literal
+pub(crate) fn value_term(v: &str) -> String {
+    Prefixes::default().value_term(v)
+}
+
+/// A quoted string literal, valid in both ShExC and Turtle
+pub(crate) fn string_literal(v: &str) -> String {
+    format!("\"{}\"", v.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n").replace('\r', "\\r"))
+}
+
+/// Regex matching the listed IRIs and those under the stems, for JSON Schema and sh:pattern
+fn iri_stem_pattern(values: &[String], stems: &[IriStem]) -> String {
+    let alternatives: Vec<String> = values.iter().map(|v| format!("{}$", regex_escape(v)))
+        .chain(stems.iter().map(|s| regex_escape(&s.stem)))
+        .collect();
+    format!("^({})", alternatives.join("|"))
+}
+
+/// Regex matching the IRIs the stems exclude, if any
+fn iri_exclusion_pattern(stems: &[IriStem]) -> Option<String> {
+    let alternatives: Vec<String> = stems.iter().flat_map(|s| s.exclusions.iter()).map(|e| match e.strip_suffix('~') {
+        Some(stem) => regex_escape(stem),
+        None => format!("{}$", regex_escape(e)),
+    }).collect();
+    if alternatives.is_empty() { None } else { Some(format!("^({})", alternatives.join("|"))) }
+}
+
+fn regex_escape(s: &str) -> String {
+    let mut out = String::new();
+    for c in s.chars() {
+        if "\\^$.|?*+()[]{}".contains(c) {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out
+}
+
+/// ShExC rdfs:label and rdfs:comment annotations, e.g. ` // rdfs:label "Name"`
+pub(crate) fn shex_annotations(title: &Option<String>, description: &Option<String>) -> String {
+    let mut out = String::new();
+    if let Some(title) = title { out.push_str(&format!(" // rdfs:label {}", string_literal(title))); }
+    if let Some(description) = description { out.push_str(&format!(" // rdfs:comment {}", string_literal(description))); }
+    out
+}
+
+/// A regular expression as the body of a ShExC `/.../` literal: unescaped slashes
+/// and line breaks are escaped, while existing escapes such as `\/` and `\d` are kept
+pub(crate) fn shex_regex(pattern: &str) -> String {
+    let mut out = String::new();
+    let mut chars = pattern.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                out.push(c);
+                out.extend(chars.next());
+            }
+            '/' => out.push_str("\\/"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+/// ShExC value expressions combined as a range expression requires: ShEx has
+/// no exclusive or, so exactly one of them is each one ANDed with NOT the others
+pub(crate) fn shex_junction(expr: &RangeExpr, atoms: &[String]) -> String {
+    match expr {
+        RangeExpr::AllOf(_) => atoms.join(" AND "),
+        RangeExpr::AnyOf(_) => atoms.join(" OR "),
+        RangeExpr::ExactlyOneOf(_) if atoms.len() < 2 => atoms.join(" OR "),
+        RangeExpr::ExactlyOneOf(_) => atoms.iter().enumerate().map(|(i, atom)| {
+            let others: Vec<String> = atoms.iter().enumerate().filter(|(j, _)| *j != i).map(|(_, other)| format!("NOT {}", other)).collect();
+            format!("( {} AND {} )", atom, others.join(" AND "))
+        }).collect::<Vec<_>>().join(" OR "),
+    }
+}
+
+/// ShExC facets following a node constraint, e.g. ` MINLENGTH 1 /^[a-z]+$/`
+fn shex_facets(f: &Facets) -> String {
+    let mut out = String::new();
+    if let Some(n) = f.min_length { out.push_str(&format!(" MINLENGTH {}", n)); }
+    if let Some(n) = f.max_length { out.push_str(&format!(" MAXLENGTH {}", n)); }
+    if let Some(pattern) = &f.pattern { out.push_str(&format!(" /{}/", shex_regex(pattern))); }
+    if let Some(n) = &f.min_inclusive { out.push_str(&format!(" MININCLUSIVE {}", n)); }
+    if let Some(n) = &f.min_exclusive { out.push_str(&format!(" MINEXCLUSIVE {}", n)); }
+    if let Some(n) = &f.max_inclusive { out.push_str(&format!(" MAXINCLUSIVE {}", n)); }
+    if let Some(n) = &f.max_exclusive { out.push_str(&format!(" MAXEXCLUSIVE {}", n)); }
+    if let Some(n) = f.total_digits { out.push_str(&format!(" TOTALDIGITS {}", n)); }
+    if let Some(n) = f.fraction_digits { out.push_str(&format!(" FRACTIONDIGITS {}", n)); }
+    out
+}
+
+/// JSON Schema `title` and `description` annotations
+fn json_schema_docs(title: &Option<String>, description: &Option<String>, obj: &mut serde_json::Map<String, JsonValue>) {
+    if let Some(title) = title { obj.insert("title".to_string(), title.clone().into()); }
+    if let Some(description) = description { obj.insert("description".to_string(), description.clone().into()); }
+}
+
+/// The JSON Schema a rule's conditions make: its required properties, and the values they take
+fn json_schema_condition(conditions: &[PropertyInfo]) -> JsonValue {
+    let mut schema = serde_json::Map::new();
+    let required: Vec<JsonValue> = conditions.iter().filter(|p| p.min.unwrap_or(0) > 0).map(|p| JsonValue::String(p.name.clone())).collect();
+    if !required.is_empty() {
+        schema.insert("required".to_string(), JsonValue::Array(required));
+    }
+    let properties: serde_json::Map<String, JsonValue> = conditions.iter().filter(|p| !p.values.is_empty()).map(|p| {
+        let value = match p.values.as_slice() {
+            [value] => serde_json::json!({ "const": value }),
+            values => serde_json::json!({ "enum": values }),
+        };
+        (p.name.clone(), value)
+    }).collect();
+    if !properties.is_empty() {
+        schema.insert("properties".to_string(), JsonValue::Object(properties));
+    }
+    JsonValue::Object(schema)
+}
+
+/// The `oneOf` branches of a OneOf group: each alternative is told apart by its
+/// required properties or, when all of them are optional, by having at least one
+/// of them; an empty alternative is the node matching no other
+fn json_schema_alternatives(alternatives: &[Vec<PropertyInfo>]) -> JsonValue {
+    let branch = |alt: &[PropertyInfo]| {
+        let mut names: Vec<&str> = alt.iter().filter(|p| p.min.unwrap_or(0) > 0).map(|p| p.name.as_str()).collect();
+        if !names.is_empty() {
+            return Some(json!({ "required": names }));
+        }
+        for p in alt.iter() {
+            if !names.contains(&p.name.as_str()) { names.push(&p.name); }
+        }
+        match names.as_slice() {
+            [] => None,
+            [name] => Some(json!({ "required": [name] })),
+            _ => Some(json!({ "anyOf": names.iter().map(|n| json!({ "required": [n] })).collect::<Vec<_>>() })),
+        }
+    };
+    let branches: Vec<Option<JsonValue>> = alternatives.iter().map(|alt| branch(alt)).collect();
+    let others: Vec<JsonValue> = branches.iter().flatten().cloned().collect();
+    JsonValue::Array(branches.into_iter().map(|b| b.unwrap_or_else(|| json!({ "not": { "anyOf": others } }))).collect())
+}
+
+/// Semantic actions as the `x-shex-semacts` extension keyword, which validators ignore
+fn json_schema_sem_acts(sem_acts: &[SemAct], obj: &mut serde_json::Map<String, JsonValue>) {
+    if !sem_acts.is_empty() {
+        obj.insert(SEM_ACTS_KEYWORD.to_string(), serde_json::to_value(sem_acts).unwrap_or(JsonValue::Null));
+    }
+}
+
+/// JSON Schema extension keyword holding the IRI of a definition's class
+pub(crate) const RDF_CLASS_KEYWORD: &str = "x-rdf-class";
+
+/// JSON Schema extension keyword holding the IRI of a property's predicate
+pub(crate) const RDF_PREDICATE_KEYWORD: &str = "x-rdf-predicate";
+
+/// JSON Schema extension keyword holding the datatype IRI of a property's literal values
+pub(crate) const RDF_DATATYPE_KEYWORD: &str = "x-rdf-datatype";
+
+/// The datatype IRI of a property's literal values: rdf:langString for language-tagged
+/// strings, the xsd datatype of a primitive range, or a range that is itself a datatype IRI
+fn rdf_datatype(p: &PropertyInfo) -> Option<String> {
+    if !p.languages.is_empty() {
+        return Some(RDF_LANG_STRING.to_string());
+    }
+    match XSD_TYPES.iter().find(|t| t.1 == p.range) {
+        Some(t) => Some(format!("{}{}", XSD, t.0)),
+        None => (p.node_kind.is_none() && p.range.contains(':')).then(|| p.range.clone()),
+    }
+}
+
+/// JSON Schema extension keyword holding ShEx semantic actions
+pub(crate) const SEM_ACTS_KEYWORD: &str = "x-shex-semacts";
+
+/// JSON Schema extension keyword marking the property that holds an instance's IRI
+pub(crate) const IDENTIFIER_KEYWORD: &str = "x-identifier";
+
+/// JSON Schema validation keywords for a property's facets; digit counts have no counterpart
+fn json_schema_facets(f: &Facets, obj: &mut serde_json::Map<String, JsonValue>) {
+    if let Some(n) = f.min_length { obj.insert("minLength".to_string(), n.into()); }
+    if let Some(n) = f.max_length { obj.insert("maxLength".to_string(), n.into()); }
+    if let Some(pattern) = &f.pattern { obj.insert("pattern".to_string(), pattern.clone().into()); }
+    if let Some(n) = &f.min_inclusive { obj.insert("minimum".to_string(), JsonValue::Number(n.clone())); }
+    if let Some(n) = &f.min_exclusive { obj.insert("exclusiveMinimum".to_string(), JsonValue::Number(n.clone())); }
+    if let Some(n) = &f.max_inclusive { obj.insert("maximum".to_string(), JsonValue::Number(n.clone())); }
+    if let Some(n) = &f.max_exclusive { obj.insert("exclusiveMaximum".to_string(), JsonValue::Number(n.clone())); }
+}
+
+/// ShEx repetition suffix for a min/max pair
+fn shex_cardinality(min: Option<u64>, max: Option<u64>) -> String {
+    match (min.unwrap_or(0), max) {
+        (1, Some(1)) => "".to_string(),
+        (0, Some(1)) => " ?".to_string(),
+        (0, None) => " *".to_string(),
+        (1, None) => " +".to_string(),
+        (m, None) => format!(" {{{},}}", m),
+        (m, Some(n)) if m == n => format!(" {{{}}}", m),
+        (m, Some(n)) => format!(" {{{},{}}}", m, n),
+    }
 }
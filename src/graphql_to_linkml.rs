@@ -0,0 +1,227 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::convert::{self, PropertyInfo, ShapeInfo};
+
+/// Convert a GraphQL SDL document to LinkML YAML.
+pub fn graphql_to_linkml(input: &Path, sdl: &str) -> anyhow::Result<String> {
+    let shapes = shapes_from_graphql(sdl)?;
+    convert::build_linkml_doc(input, &shapes)
+}
+
+/// Read the object types of a GraphQL SDL document into our ShapeInfo vector.
+/// `type`, `interface` and `input` definitions become shapes; `enum`s become
+/// value sets on the fields that use them. Field arguments and directives are ignored.
+pub fn shapes_from_graphql(sdl: &str) -> anyhow::Result<Vec<ShapeInfo>> {
+    let tokens = tokenize(sdl)?;
+    let mut pos = 0;
+
+    let mut types: Vec<(String, Vec<GraphqlField>)> = Vec::new();
+    let mut enums: HashMap<String, Vec<String>> = HashMap::new();
+
+    while pos < tokens.len() {
+        let keyword = tokens[pos].as_str();
+        pos += 1;
+        match keyword {
+            "type" | "interface" | "input" => {
+                let name = expect_name(&tokens, &mut pos)?;
+                skip_until_block(&tokens, &mut pos);
+                let mut fields = Vec::new();
+                if tokens.get(pos).map(String::as_str) == Some("{") {
+                    pos += 1;
+                    while pos < tokens.len() && tokens[pos] != "}" {
+                        fields.push(parse_field(&tokens, &mut pos)?);
+                    }
+                    pos += 1;
+                }
+                // `extend type` adds fields to an existing definition
+                match types.iter_mut().find(|(n, _)| *n == name) {
+                    Some((_, existing)) => existing.extend(fields),
+                    None => types.push((name, fields)),
+                }
+            }
+            "enum" => {
+                let name = expect_name(&tokens, &mut pos)?;
+                skip_until_block(&tokens, &mut pos);
+                let mut values = Vec::new();
+                if tokens.get(pos).map(String::as_str) == Some("{") {
+                    pos += 1;
+                    while pos < tokens.len() && tokens[pos] != "}" {
+                        if tokens[pos] == "@" {
+                            skip_directive(&tokens, &mut pos);
+                        } else {
+                            values.push(tokens[pos].clone());
+                            pos += 1;
+                        }
+                    }
+                    pos += 1;
+                }
+                enums.insert(name, values);
+            }
+            "{" => {
+                // `schema { ... }`, `extend ... { ... }` and the like
+                skip_block(&tokens, &mut pos, "{", "}");
+            }
+            _ => {
+                // scalar, union, directive, schema, extend, ... are not shapes
+            }
+        }
+    }
+
+    let type_names: Vec<&str> = types.iter().map(|(n, _)| n.as_str()).collect();
+    let shapes = types.iter().map(|(name, fields)| {
+        let properties = fields.iter().map(|f| {
+            let (range, values) = match f.type_name.as_str() {
+                "Int" => ("integer".to_string(), Vec::new()),
+                "Float" => ("number".to_string(), Vec::new()),
+                "Boolean" => ("boolean".to_string(), Vec::new()),
+                t if type_names.contains(&t) => (t.to_string(), Vec::new()),
+                t => ("string".to_string(), enums.get(t).cloned().unwrap_or_default()),
+            };
+            PropertyInfo {
+                name: f.name.clone(),
+                predicate: f.name.clone(),
+                range,
+                min: Some(if f.non_null { 1 } else { 0 }),
+                max: if f.list { None } else { Some(1) },
+                values,
+                ..Default::default()
+            }
+        }).collect();
+        ShapeInfo { id: name.clone(), name: name.clone(), properties, ..Default::default() }
+    }).collect();
+
+    Ok(shapes)
+}
+
+struct GraphqlField {
+    name: String,
+    type_name: String,
+    non_null: bool,
+    list: bool,
+}
+
+fn parse_field(tokens: &[String], pos: &mut usize) -> anyhow::Result<GraphqlField> {
+    let name = expect_name(tokens, pos)?;
+    if tokens.get(*pos).map(String::as_str) == Some("(") {
+        skip_block(tokens, pos, "(", ")");
+    }
+    if tokens.get(*pos).map(String::as_str) != Some(":") {
+        anyhow::bail!("expected `:` after GraphQL field `{}`", name);
+    }
+    *pos += 1;
+
+    let mut list = false;
+    if tokens.get(*pos).map(String::as_str) == Some("[") {
+        list = true;
+        *pos += 1;
+    }
+    let type_name = expect_name(tokens, pos)?;
+    if list {
+        // inner nullability doesn't change the cardinality
+        while *pos < tokens.len() && tokens[*pos] != "]" { *pos += 1; }
+        *pos += 1;
+    }
+    let non_null = tokens.get(*pos).map(String::as_str) == Some("!");
+    if non_null { *pos += 1; }
+
+    // default values on input fields
+    if tokens.get(*pos).map(String::as_str) == Some("=") {
+        *pos += 1;
+        match tokens.get(*pos).map(String::as_str) {
+            Some("[") => skip_block(tokens, pos, "[", "]"),
+            Some("{") => skip_block(tokens, pos, "{", "}"),
+            _ => *pos += 1,
+        }
+    }
+    while tokens.get(*pos).map(String::as_str) == Some("@") {
+        skip_directive(tokens, pos);
+    }
+
+    Ok(GraphqlField { name, type_name, non_null, list })
+}
+
+fn expect_name(tokens: &[String], pos: &mut usize) -> anyhow::Result<String> {
+    match tokens.get(*pos) {
+        Some(t) if t.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_') => {
+            *pos += 1;
+            Ok(t.clone())
+        }
+        other => anyhow::bail!("expected a GraphQL name, found {:?}", other),
+    }
+}
+
+/// Skip `implements A & B` and directives up to the opening `{` of a definition
+fn skip_until_block(tokens: &[String], pos: &mut usize) {
+    while *pos < tokens.len() && tokens[*pos] != "{" {
+        if is_definition_keyword(&tokens[*pos]) { return; }
+        if tokens[*pos] == "@" {
+            skip_directive(tokens, pos);
+        } else {
+            *pos += 1;
+        }
+    }
+}
+
+fn skip_directive(tokens: &[String], pos: &mut usize) {
+    *pos += 2; // `@` and the directive name
+    if tokens.get(*pos).map(String::as_str) == Some("(") {
+        skip_block(tokens, pos, "(", ")");
+    }
+}
+
+fn skip_block(tokens: &[String], pos: &mut usize, open: &str, close: &str) {
+    let mut depth = 0;
+    while *pos < tokens.len() {
+        if tokens[*pos] == open { depth += 1; }
+        if tokens[*pos] == close { depth -= 1; }
+        *pos += 1;
+        if depth == 0 { return; }
+    }
+}
+
+fn is_definition_keyword(t: &str) -> bool {
+    matches!(t, "type" | "interface" | "input" | "enum" | "scalar" | "union" | "directive" | "schema" | "extend")
+}
+
+/// Split SDL into names and punctuators, dropping comments, commas and string descriptions
+fn tokenize(sdl: &str) -> anyhow::Result<Vec<String>> {
+    let mut tokens = Vec::new();
+    let chars: Vec<char> = sdl.chars().collect();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() || c == ',' {
+            i += 1;
+        } else if c == '#' {
+            while i < chars.len() && chars[i] != '\n' { i += 1; }
+        } else if c == '"' {
+            // a string default value stands in as a single token; descriptions vanish
+            if tokens.last().map(String::as_str) == Some("=") {
+                tokens.push("\"\"".to_string());
+            }
+            if chars[i..].starts_with(&['"', '"', '"']) {
+                i += 3;
+                while i < chars.len() && !chars[i..].starts_with(&['"', '"', '"']) { i += 1; }
+                i += 3;
+            } else {
+                i += 1;
+                while i < chars.len() && chars[i] != '"' {
+                    if chars[i] == '\\' { i += 1; }
+                    i += 1;
+                }
+                i += 1;
+            }
+        } else if c.is_ascii_alphanumeric() || c == '_' || c == '-' || c == '.' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_ascii_alphanumeric() || chars[i] == '_' || chars[i] == '-' || chars[i] == '.') { i += 1; }
+            tokens.push(chars[start..i].iter().collect());
+        } else if "{}()[]:!=@|&$".contains(c) {
+            tokens.push(c.to_string());
+            i += 1;
+        } else {
+            anyhow::bail!("unexpected character {:?} in GraphQL SDL", c);
+        }
+    }
+    Ok(tokens)
+}
@@ -0,0 +1,569 @@
+use anyhow::Context;
+use serde_json::Map as JsonMap;
+use serde_json::Value as JsonValue;
+use std::path::Path;
+
+use crate::convert::{self, Facets, Inlining, PropertyInfo, SemAct, ShapeConstraint, ShapeInfo};
+
+/// Convert a JSON Schema (draft-07 or 2020-12) document string to LinkML YAML.
+/// Each entry in `definitions` / `$defs` becomes a class, as does the root
+/// schema itself when it declares `properties`.
+pub fn json_schema_to_linkml(json_str: &str) -> anyhow::Result<String> {
+    let doc: JsonValue = serde_json::from_str(json_str).context("parsing JSON Schema")?;
+    let shapes = shapes_from_json_schema(&resolve_refs(&doc, None, false)?)?;
+    let id = doc.get("$id").and_then(|v| v.as_str()).unwrap_or("schema");
+    convert::build_linkml_doc(Path::new(id), &shapes)
+}
+
+/// Read the object definitions of a JSON Schema into our ShapeInfo vector
+pub fn shapes_from_json_schema(doc: &JsonValue) -> anyhow::Result<Vec<ShapeInfo>> {
+    let root = doc.as_object().context("JSON Schema root must be an object")?;
+    let mut shapes = Vec::new();
+
+    // draft-07 uses `definitions`, 2020-12 uses `$defs`; accept either (or both)
+    for key in ["definitions", "$defs"] {
+        if let Some(JsonValue::Object(defs)) = root.get(key) {
+            for (name, def) in defs.iter() {
+                if let Some(defobj) = def.as_object() {
+                    if is_object_schema(defobj) {
+                        shapes.push(shape_from_object(name, defobj));
+                    } else if let Some(external) = external_ref(defobj) {
+                        shapes.push(ShapeInfo { id: external.to_string(), external: true, ..shape_from_object(name, defobj) });
+                    }
+                }
+            }
+        }
+    }
+
+    // a root schema with its own properties describes a shape too
+    if root.contains_key("properties") {
+        let name = root.get("title").and_then(|v| v.as_str()).unwrap_or("Root");
+        shapes.push(shape_from_object(name, root));
+    }
+
+    // a top-level `$ref` names the shape documents are validated against
+    if root.contains_key("$ref") && !root.contains_key("properties") {
+        let start = range_from_schema(doc);
+        shapes.iter_mut().filter(|s| s.name == start).for_each(|s| s.start = true);
+    }
+
+    Ok(shapes)
+}
+
+/// A JSON Schema with its `$ref`s resolved as far as the shape model needs: a `$ref` to an
+/// object schema stays a reference to that shape, while one to any other schema, e.g. an
+/// `enum`, is replaced by it. The documents `$ref`s point into have their definitions merged
+/// in, the document's own taking precedence, and those `$ref`s made local: files, read relative
+/// to `dir`, always, and URLs only with `fetch`. Without a `dir` there is nowhere to read files
+/// from, so `$ref`s to them are left as they are, as are URLs without `fetch`;
+/// `unresolved_ref_warnings` names them. A `$ref` that can't be resolved, or a schema that would
+/// contain itself, is an error.
+pub fn resolve_refs(doc: &JsonValue, dir: Option<&Path>, fetch: bool) -> anyhow::Result<JsonValue> {
+    let mut doc = doc.clone();
+    // a file in the working directory has an empty parent
+    let base = dir.map(|d| if d.as_os_str().is_empty() { Path::new(".") } else { d }).map(|d| d.to_string_lossy().into_owned());
+    let mut imported = Vec::new();
+    load_referenced(&mut doc, base.as_deref(), fetch, &mut imported)?;
+    for (location, other) in imported.iter() {
+        merge_definitions(&mut doc, location, other);
+    }
+    let locations: Vec<&str> = imported.iter().map(|(location, _)| location.as_str()).collect();
+    let keyword = definitions_keyword(&doc);
+    for_each_ref(&mut doc, &mut |reference| {
+        let (location, fragment) = reference.split_once('#').unwrap_or((reference.as_str(), ""));
+        if locations.contains(&location) {
+            *reference = match fragment {
+                "" => format!("#/{}/{}", keyword, document_name(location)),
+                fragment => format!("#{}", fragment),
+            };
+        }
+    });
+    let root = doc.clone();
+    inline_refs(&mut doc, &root, &mut Vec::new())?;
+    Ok(doc)
+}
+
+/// Make the `$ref`s in `doc` to other documents absolute, given `doc`'s relative references
+/// resolve against `base`, a directory or a URL ending in `/`, and load each of those documents,
+/// and those they refer to, once into `imported`. Those at URLs are only loaded with `fetch`.
+fn load_referenced(doc: &mut JsonValue, base: Option<&str>, fetch: bool, imported: &mut Vec<(String, JsonValue)>) -> anyhow::Result<()> {
+    let mut locations = Vec::new();
+    for_each_ref(doc, &mut |reference| {
+        let Some((location, fragment)) = reference.split_once('#').or(Some((reference.as_str(), ""))).filter(|(l, _)| !l.is_empty()) else { return };
+        let location = match base {
+            _ if is_url(location) || Path::new(location).is_absolute() => location.to_string(),
+            Some(base) if is_url(base) => format!("{}{}", base, location),
+            Some(base) => Path::new(base).join(location).to_string_lossy().into_owned(),
+            None => return,
+        };
+        if is_url(&location) && !fetch {
+            return;
+        }
+        *reference = format!("{}#{}", location, fragment);
+        if !locations.contains(&location) { locations.push(location); }
+    });
+    for location in locations {
+        // a document already loaded, perhaps one referring back to this one, is only merged once
+        if imported.iter().any(|(l, _)| l == &location) {
+            continue;
+        }
+        let text = if is_url(&location) {
+            fetch_document(&location)?
+        } else {
+            std::fs::read_to_string(&location).with_context(|| format!("reading {}", location))?
+        };
+        let mut other: JsonValue = serde_json::from_str(&text).with_context(|| format!("parsing JSON Schema {}", location))?;
+        // its local references are into it, wherever its definitions end up
+        for_each_ref(&mut other, &mut |reference| {
+            if reference.starts_with('#') { *reference = format!("{}{}", location, reference); }
+        });
+        imported.push((location.clone(), JsonValue::Null));
+        let index = imported.len() - 1;
+        let base = if is_url(&location) {
+            convert::sibling_iri(&location, "")
+        } else {
+            let parent = Path::new(&location).parent().filter(|p| !p.as_os_str().is_empty());
+            parent.unwrap_or(Path::new(".")).to_string_lossy().into_owned()
+        };
+        load_referenced(&mut other, Some(&base), fetch, imported)?;
+        imported[index].1 = other;
+    }
+    Ok(())
+}
+
+/// Warnings naming the `$ref`s into other documents that `resolve_refs` left as they are,
+/// other than definitions that only point at an external shape
+pub fn unresolved_ref_warnings(doc: &JsonValue) -> Vec<String> {
+    let mut doc = doc.clone();
+    for key in ["definitions", "$defs"] {
+        if let Some(JsonValue::Object(defs)) = doc.get_mut(key) {
+            defs.retain(|_, def| !def.as_object().is_some_and(|d| external_ref(d).is_some()));
+        }
+    }
+    let mut references: Vec<String> = Vec::new();
+    for_each_ref(&mut doc, &mut |reference| {
+        if !reference.starts_with('#') && !references.contains(reference) { references.push(reference.clone()); }
+    });
+    references.iter()
+        .map(|r| format!("$ref {} is left unresolved{}", r, if is_url(r) { " without --fetch-imports" } else { "" }))
+        .collect()
+}
+
+/// Add the definitions of the document at `location` to `doc`'s, and the document itself if
+/// it's an object schema, unless `doc` defines them already other than by a `$ref` to them
+fn merge_definitions(doc: &mut JsonValue, location: &str, other: &JsonValue) {
+    let keyword = definitions_keyword(doc);
+    let mut entries: Vec<(&str, String, JsonValue)> = Vec::new();
+    for key in ["definitions", "$defs"] {
+        if let Some(JsonValue::Object(defs)) = other.get(key) {
+            entries.extend(defs.iter().map(|(name, def)| (key, name.clone(), def.clone())));
+        }
+    }
+    if let Some(root) = other.as_object().filter(|o| is_object_schema(o)) {
+        let mut root = root.clone();
+        root.remove("definitions");
+        root.remove("$defs");
+        entries.push((keyword, document_name(location), JsonValue::Object(root)));
+    }
+    let Some(doc) = doc.as_object_mut() else { return };
+    for (key, name, def) in entries {
+        let JsonValue::Object(defs) = doc.entry(key).or_insert_with(|| JsonValue::Object(JsonMap::new())) else { continue };
+        let pointer = defs.get(&name).and_then(|d| d.get("$ref")).and_then(|r| r.as_str());
+        if !defs.contains_key(&name) || pointer.is_some_and(|r| r.starts_with(&format!("{}#", location))) {
+            defs.insert(name, def);
+        }
+    }
+}
+
+/// Replace `$ref`s to schemas other than object schemas with those schemas,
+/// `expanding` being the references already being replaced
+fn inline_refs(value: &mut JsonValue, root: &JsonValue, expanding: &mut Vec<String>) -> anyhow::Result<()> {
+    if let Some(reference) = value.get("$ref").and_then(|v| v.as_str()).filter(|r| r.starts_with('#')).map(str::to_string) {
+        let target = ref_target(root, &reference).with_context(|| format!("unresolved $ref {}", reference))?;
+        if !target.as_object().is_some_and(is_object_schema) {
+            if expanding.contains(&reference) {
+                anyhow::bail!("cyclic $ref {}", reference);
+            }
+            // the referenced schema stands in for the reference, under any keywords beside it
+            let mut inlined = target.clone();
+            if let (Some(inlined), Some(siblings)) = (inlined.as_object_mut(), value.as_object()) {
+                for (key, v) in siblings.iter().filter(|(key, _)| *key != "$ref") {
+                    inlined.insert(key.clone(), v.clone());
+                }
+            }
+            *value = inlined;
+            expanding.push(reference);
+            let inlined = inline_refs(value, root, expanding);
+            expanding.pop();
+            return inlined;
+        }
+    }
+    match value {
+        JsonValue::Object(obj) => obj.values_mut().try_for_each(|v| inline_refs(v, root, expanding)),
+        JsonValue::Array(arr) => arr.iter_mut().try_for_each(|v| inline_refs(v, root, expanding)),
+        _ => Ok(()),
+    }
+}
+
+/// The schema a local `$ref` points at: a JSON pointer such as `#/$defs/Person`,
+/// or a definition's `$anchor` or plain-name `$id`, such as `#Person`
+fn ref_target<'a>(root: &'a JsonValue, reference: &str) -> Option<&'a JsonValue> {
+    let fragment = reference.strip_prefix('#')?;
+    if fragment.is_empty() || fragment.starts_with('/') {
+        return root.pointer(fragment);
+    }
+    ["definitions", "$defs"].iter()
+        .filter_map(|key| root.get(*key).and_then(|defs| defs.as_object()))
+        .flat_map(|defs| defs.values())
+        .find(|def| def.get("$anchor").and_then(|a| a.as_str()) == Some(fragment)
+            || def.get("$id").and_then(|a| a.as_str()) == Some(reference))
+}
+
+/// Call `f` on every `$ref` in a schema
+fn for_each_ref(value: &mut JsonValue, f: &mut impl FnMut(&mut String)) {
+    match value {
+        JsonValue::Object(obj) => {
+            for (key, v) in obj.iter_mut() {
+                match v {
+                    JsonValue::String(reference) if key == "$ref" => f(reference),
+                    v => for_each_ref(v, f),
+                }
+            }
+        }
+        JsonValue::Array(arr) => arr.iter_mut().for_each(|v| for_each_ref(v, f)),
+        _ => {}
+    }
+}
+
+/// Where a document keeps its definitions: `$defs` if it has them, else `definitions`
+fn definitions_keyword(doc: &JsonValue) -> &'static str {
+    if doc.get("$defs").is_some() { "$defs" } else { "definitions" }
+}
+
+/// The definition name of a whole document: its file name without extension
+fn document_name(location: &str) -> String {
+    let file = location.rsplit('/').next().unwrap_or(location);
+    file.split('.').next().unwrap_or(file).to_string()
+}
+
+fn is_url(location: &str) -> bool {
+    location.starts_with("http://") || location.starts_with("https://")
+}
+
+/// Fetch a JSON Schema document a `$ref` points into
+fn fetch_document(url: &str) -> anyhow::Result<String> {
+    reqwest::blocking::Client::new()
+        .get(url)
+        .header(reqwest::header::USER_AGENT, concat!("shex2linkml/", env!("CARGO_PKG_VERSION")))
+        .send()
+        .and_then(|r| r.error_for_status())
+        .and_then(|r| r.text())
+        .with_context(|| format!("fetching {}", url))
+}
+
+pub(crate) fn is_object_schema(obj: &JsonMap<String, JsonValue>) -> bool {
+    obj.get("type").and_then(|v| v.as_str()) == Some("object") || obj.contains_key("properties") || obj.contains_key("allOf")
+}
+
+/// The URI of a definition that only refers to a schema outside this document,
+/// as `build_json_schema` writes for an external shape
+fn external_ref(obj: &JsonMap<String, JsonValue>) -> Option<&str> {
+    match obj.get("$ref").and_then(|v| v.as_str()) {
+        Some(uri) if obj.len() == 1 && !uri.starts_with('#') => Some(uri),
+        _ => None,
+    }
+}
+
+pub(crate) fn shape_from_object(name: &str, obj: &JsonMap<String, JsonValue>) -> ShapeInfo {
+    let required: Vec<&str> = match obj.get("required") {
+        Some(JsonValue::Array(arr)) => arr.iter().filter_map(|v| v.as_str()).collect(),
+        _ => Vec::new(),
+    };
+
+    let mut properties = Vec::new();
+    let mut identifier = None;
+    if let Some(JsonValue::Object(props)) = obj.get("properties") {
+        // `true` only admits a property declared elsewhere, e.g. inherited by a closed schema
+        for (prop_name, prop_schema) in props.iter().filter(|(_, v)| !v.is_boolean()) {
+            // the `x-identifier` property holds the instance's IRI
+            if prop_schema.get(convert::IDENTIFIER_KEYWORD) == Some(&JsonValue::Bool(true)) {
+                identifier = Some(prop_name.clone());
+                continue;
+            }
+            let is_required = required.contains(&prop_name.as_str());
+            properties.push(prop_from_schema(prop_name, prop_schema, is_required));
+        }
+    }
+
+    // `allOf` `$ref`s are parents; inline members contribute their own properties and rules
+    let mut extends = Vec::new();
+    let mut rules = rules_from_schema(name, obj).0;
+    if let Some(JsonValue::Array(all_of)) = obj.get("allOf") {
+        for member in all_of.iter() {
+            if member.get("$ref").is_some() {
+                extends.push(range_from_schema(member));
+            } else if let Some(member) = member.as_object() {
+                let member = shape_from_object(name, member);
+                properties.extend(member.properties);
+                rules.extend(member.constraints.into_iter().filter(|c| matches!(c, ShapeConstraint::Rule { .. })));
+            }
+        }
+    }
+    // a rule's conditions are on the shape's own properties, wherever the rule was written
+    for rule in rules.iter_mut() {
+        let ShapeConstraint::Rule { preconditions, postconditions, elseconditions } = rule else { continue };
+        for c in preconditions.iter_mut().chain(postconditions).chain(elseconditions) {
+            if let Some(p) = properties.iter().find(|p| p.name == c.name) {
+                *c = PropertyInfo { min: c.min, values: c.values.clone(), ..p.clone() };
+            }
+        }
+    }
+
+    // `anyOf` and `not` over `$ref`s are shape-level OR and NOT
+    let refs = |v: &JsonValue| -> Vec<String> {
+        v.as_array().into_iter().flatten().filter(|m| m.get("$ref").is_some()).map(range_from_schema).collect()
+    };
+    let mut constraints = Vec::new();
+    if let Some(any_of) = obj.get("anyOf").map(refs).filter(|names| !names.is_empty()) {
+        constraints.push(ShapeConstraint::AnyOf(any_of));
+    }
+    match obj.get("not") {
+        Some(not) if not.get("$ref").is_some() => constraints.push(ShapeConstraint::Not(range_from_schema(not))),
+        Some(not) => constraints.extend(not.get("anyOf").map(refs).unwrap_or_default().into_iter().map(ShapeConstraint::Not)),
+        None => {}
+    }
+    constraints.extend(rules);
+
+    // 2020-12's unevaluatedProperties also admits the properties of the `allOf`s
+    let closed = ["additionalProperties", "unevaluatedProperties"].iter().any(|k| obj.get(*k) == Some(&JsonValue::Bool(false)));
+
+    ShapeInfo {
+        // `x-rdf-class` keeps the class IRI the shape was written from
+        id: string(obj.get(convert::RDF_CLASS_KEYWORD)).unwrap_or_else(|| name.to_string()),
+        name: name.to_string(),
+        properties,
+        extends,
+        constraints,
+        closed,
+        // a title that only repeats the name adds nothing
+        title: string(obj.get("title")).filter(|t| t != name),
+        description: string(obj.get("description")),
+        sem_acts: sem_acts_from_schema(obj.get(convert::SEM_ACTS_KEYWORD)),
+        identifier,
+        ..Default::default()
+    }
+}
+
+/// The rules of a schema's `if`/`then`/`else`, `dependentRequired` and draft-07 array
+/// `dependencies`, and warnings naming what of them the rules leave out: conditions
+/// keep only which properties are required and the `const` or `enum` values they take
+fn rules_from_schema(shape: &str, obj: &JsonMap<String, JsonValue>) -> (Vec<ShapeConstraint>, Vec<String>) {
+    let mut rules = Vec::new();
+    let mut warnings = Vec::new();
+    if let Some(condition) = obj.get("if") {
+        let preconditions = conditions_from_schema(shape, "if", condition, &mut warnings);
+        let mut conditions = |key: &str| obj.get(key).map(|c| conditions_from_schema(shape, key, c, &mut warnings)).unwrap_or_default();
+        let (postconditions, elseconditions) = (conditions("then"), conditions("else"));
+        if !postconditions.is_empty() || !elseconditions.is_empty() {
+            rules.push(ShapeConstraint::Rule { preconditions, postconditions, elseconditions });
+        }
+    }
+    let present = |name: &str| PropertyInfo { min: Some(1), ..prop_from_schema(name, &JsonValue::Null, false) };
+    for key in ["dependentRequired", "dependencies"] {
+        let Some(JsonValue::Object(dependents)) = obj.get(key) else { continue };
+        for (name, dependent) in dependents.iter() {
+            match dependent {
+                JsonValue::Array(required) => rules.push(ShapeConstraint::Rule {
+                    preconditions: vec![present(name)],
+                    postconditions: required.iter().filter_map(|r| r.as_str()).map(present).collect(),
+                    elseconditions: Vec::new(),
+                }),
+                _ => warnings.push(format!("{}: the schema `{}` depends on isn't kept", shape, name)),
+            }
+        }
+    }
+    if let Some(JsonValue::Object(dependents)) = obj.get("dependentSchemas") {
+        warnings.extend(dependents.keys().map(|name| format!("{}: the schema `{}` depends on isn't kept", shape, name)));
+    }
+    (rules, warnings)
+}
+
+/// The conditions an `if`, `then` or `else` schema sets, warning of the keywords it loses
+fn conditions_from_schema(shape: &str, key: &str, schema: &JsonValue, warnings: &mut Vec<String>) -> Vec<PropertyInfo> {
+    let Some(schema) = schema.as_object() else {
+        warnings.push(format!("{}: `{}` isn't kept", shape, key));
+        return Vec::new();
+    };
+    for keyword in schema.keys().filter(|k| !["required", "properties", "type"].contains(&k.as_str())) {
+        warnings.push(format!("{}: `{}` in `{}` isn't kept", shape, keyword, key));
+    }
+    let mut conditions: Vec<PropertyInfo> = match schema.get("required") {
+        Some(JsonValue::Array(required)) => required.iter().filter_map(|r| r.as_str())
+            .map(|name| PropertyInfo { min: Some(1), ..prop_from_schema(name, &JsonValue::Null, false) })
+            .collect(),
+        _ => Vec::new(),
+    };
+    if let Some(JsonValue::Object(props)) = schema.get("properties") {
+        for (name, prop_schema) in props.iter() {
+            let lost = prop_schema.as_object().into_iter().flat_map(|o| o.keys()).filter(|k| !["const", "enum"].contains(&k.as_str()));
+            warnings.extend(lost.map(|keyword| format!("{}: `{}` of `{}` in `{}` isn't kept", shape, keyword, name, key)));
+            let values = values_from_schema(prop_schema);
+            if values.is_empty() {
+                continue;
+            }
+            match conditions.iter_mut().find(|c| &c.name == name) {
+                Some(c) => c.values = values,
+                None => conditions.push(PropertyInfo { min: Some(0), values, ..prop_from_schema(name, &JsonValue::Null, false) }),
+            }
+        }
+    }
+    conditions
+}
+
+/// Warnings naming the parts of the conditionals in a JSON Schema's definitions
+/// (and root) that its rules can't keep, e.g. an `if` on a property's `minimum`
+pub fn conditional_warnings(doc: &JsonValue) -> Vec<String> {
+    let mut schemas: Vec<(&str, &JsonMap<String, JsonValue>)> = Vec::new();
+    for key in ["definitions", "$defs"] {
+        if let Some(JsonValue::Object(defs)) = doc.get(key) {
+            schemas.extend(defs.iter().filter_map(|(name, def)| Some((name.as_str(), def.as_object()?))));
+        }
+    }
+    if let Some(root) = doc.as_object().filter(|root| root.contains_key("properties")) {
+        schemas.push((root.get("title").and_then(|v| v.as_str()).unwrap_or("Root"), root));
+    }
+    let mut warnings = Vec::new();
+    while let Some((name, schema)) = schemas.pop() {
+        warnings.extend(rules_from_schema(name, schema).1);
+        if let Some(JsonValue::Array(all_of)) = schema.get("allOf") {
+            schemas.extend(all_of.iter().filter_map(|member| Some((name, member.as_object()?))));
+        }
+    }
+    warnings
+}
+
+fn prop_from_schema(name: &str, schema: &JsonValue, required: bool) -> PropertyInfo {
+    let min = Some(if required { 1 } else { 0 });
+
+    // arrays carry their cardinality in minItems/maxItems and their range in `items`
+    if schema.get("type").and_then(|v| v.as_str()) == Some("array") {
+        let items = schema.get("items").unwrap_or(&JsonValue::Null);
+        let min_items = schema.get("minItems").and_then(|v| v.as_u64());
+        let max_items = schema.get("maxItems").and_then(|v| v.as_u64());
+        return PropertyInfo {
+            min: if required { Some(min_items.unwrap_or(1).max(1)) } else { Some(min_items.unwrap_or(0)) },
+            max: max_items,
+            predicate: predicate(name, schema),
+            sem_acts: sem_acts_from_schema(schema.get(convert::SEM_ACTS_KEYWORD)),
+            ..prop_from_schema(name, items, false)
+        };
+    }
+
+    // an object of `$ref`ed values keyed by their identifiers is several inlined ones
+    if let Some(values) = schema.get("additionalProperties").filter(|v| v.get("$ref").is_some()) {
+        let min_properties = schema.get("minProperties").and_then(|v| v.as_u64());
+        let max_properties = schema.get("maxProperties").and_then(|v| v.as_u64());
+        return PropertyInfo {
+            min: if required { Some(min_properties.unwrap_or(1).max(1)) } else { Some(min_properties.unwrap_or(0)) },
+            max: max_properties,
+            predicate: predicate(name, schema),
+            sem_acts: sem_acts_from_schema(schema.get(convert::SEM_ACTS_KEYWORD)),
+            inlining: Some(Inlining::Inlined),
+            ..prop_from_schema(name, values, false)
+        };
+    }
+
+    // a JSON-LD value object is a language-tagged string
+    if let (Some(value), Some(language)) = (schema.pointer("/properties/@value"), schema.pointer("/properties/@language")) {
+        return PropertyInfo { predicate: predicate(name, schema), languages: languages_from_schema(language), ..prop_from_schema(name, value, required) };
+    }
+
+    // `x-rdf-datatype` keeps the datatype the range was written from
+    let datatype = string(schema.get(convert::RDF_DATATYPE_KEYWORD));
+    let lang_string = datatype.as_deref() == Some(convert::RDF_LANG_STRING);
+    PropertyInfo {
+        name: name.to_string(),
+        predicate: predicate(name, schema),
+        range: datatype.as_deref().map_or_else(|| range_from_schema(schema), convert::range_for_datatype),
+        min,
+        max: Some(1),
+        values: values_from_schema(schema),
+        facets: facets_from_schema(schema),
+        languages: if lang_string { vec!["~".to_string()] } else { Vec::new() },
+        title: string(schema.get("title")).filter(|t| t != name),
+        description: string(schema.get("description")),
+        sem_acts: sem_acts_from_schema(schema.get(convert::SEM_ACTS_KEYWORD)),
+        ..Default::default()
+    }
+}
+
+/// A property's predicate: the `x-rdf-predicate` IRI it was written from, or its name
+fn predicate(name: &str, schema: &JsonValue) -> String {
+    string(schema.get(convert::RDF_PREDICATE_KEYWORD)).unwrap_or_else(|| name.to_string())
+}
+
+fn string(v: Option<&JsonValue>) -> Option<String> {
+    v.and_then(|v| v.as_str()).map(str::to_string)
+}
+
+/// ShEx semantic actions from the `x-shex-semacts` extension keyword
+fn sem_acts_from_schema(v: Option<&JsonValue>) -> Vec<SemAct> {
+    v.and_then(|v| serde_json::from_value(v.clone()).ok()).unwrap_or_default()
+}
+
+fn range_from_schema(schema: &JsonValue) -> String {
+    if let Some(reference) = schema.get("$ref").and_then(|v| v.as_str()) {
+        // `#/definitions/Foo`, `#/$defs/Foo`, `#Foo` or `other.json#/definitions/Foo` all name `Foo`
+        return reference.rsplit(['/', '#']).next().unwrap_or(reference).to_string();
+    }
+    let format = schema.get("format").and_then(|v| v.as_str());
+    // an unknown format still leaves the type's plain range
+    schema.get("type").and_then(|v| v.as_str())
+        .and_then(|t| convert::range_for_json_type(t, format).or_else(|| convert::range_for_json_type(t, None)))
+        .unwrap_or("string")
+        .to_string()
+}
+
+fn facets_from_schema(schema: &JsonValue) -> Facets {
+    let number = |key: &str| match schema.get(key) {
+        Some(JsonValue::Number(n)) => Some(n.clone()),
+        _ => None,
+    };
+    Facets {
+        min_length: schema.get("minLength").and_then(|v| v.as_u64()),
+        max_length: schema.get("maxLength").and_then(|v| v.as_u64()),
+        pattern: schema.get("pattern").and_then(|v| v.as_str()).map(str::to_string),
+        min_inclusive: number("minimum"),
+        min_exclusive: number("exclusiveMinimum"),
+        max_inclusive: number("maximum"),
+        max_exclusive: number("exclusiveMaximum"),
+        ..Default::default()
+    }
+}
+
+/// Language tags from an `@language` pattern written by `build_json_schema_with_roots`,
+/// e.g. `^(en|fr(-.+)?)$` -> `en fr~`; any other pattern allows any tag
+fn languages_from_schema(language: &JsonValue) -> Vec<String> {
+    let pattern = language.get("pattern").and_then(|v| v.as_str()).unwrap_or_default();
+    match pattern.strip_prefix("^(").and_then(|p| p.strip_suffix(")$")) {
+        Some(alternatives) if pattern != convert::LANGUAGE_TAG_PATTERN => alternatives.split('|')
+            .map(|a| a.strip_suffix("(-.+)?").map_or_else(|| a.to_string(), |stem| format!("{}~", stem)))
+            .collect(),
+        _ => vec!["~".to_string()],
+    }
+}
+
+fn values_from_schema(schema: &JsonValue) -> Vec<String> {
+    // `const` is a one-member enum
+    let members = schema.get("const").map(|c| JsonValue::Array(vec![c.clone()]));
+    match members.as_ref().or(schema.get("enum")) {
+        Some(JsonValue::Array(arr)) => arr
+            .iter()
+            .map(|v| match v {
+                JsonValue::String(s) => s.clone(),
+                other => other.to_string(),
+            })
+            .collect(),
+        _ => Vec::new(),
+    }
+}
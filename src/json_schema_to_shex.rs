@@ -0,0 +1,15 @@
+use anyhow::Context;
+use serde_json::Value as JsonValue;
+
+use crate::convert;
+use crate::json_schema_to_linkml::{resolve_refs, shapes_from_json_schema};
+
+/// Convert a JSON Schema document string to a ShEx compact string.
+/// Definitions become shapes, `required` properties get cardinality 1, arrays
+/// become `*`/`+` (or `{m,n}` with minItems/maxItems), `$ref`s become shape
+/// references and `enum`s become value sets.
+pub fn json_schema_to_shex(json_str: &str) -> anyhow::Result<String> {
+    let doc: JsonValue = serde_json::from_str(json_str).context("parsing JSON Schema")?;
+    let shapes = shapes_from_json_schema(&resolve_refs(&doc, None, false)?)?;
+    Ok(convert::build_shex(&shapes))
+}
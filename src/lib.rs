@@ -1,10 +1,25 @@
-use anyhow::Context;
-use serde::{Deserialize, Serialize};
-use serde_json::Value as JsonValue;
-use serde_yaml::Value as YamlValue;
-
 pub mod convert;
+pub mod graphql_to_linkml;
+pub mod json_schema_to_linkml;
+pub mod json_schema_to_shex;
+pub mod linkml_to_shacl;
 pub mod linkml_to_shex;
+pub mod naming;
+pub mod openapi_to_linkml;
+pub mod shacl_to_linkml;
+pub mod shape_map;
+pub mod wikidata;
+pub mod xsd_to_linkml;
 
 pub use convert::*;
+pub use graphql_to_linkml::*;
+pub use json_schema_to_linkml::*;
+pub use json_schema_to_shex::*;
+pub use linkml_to_shacl::*;
 pub use linkml_to_shex::*;
+pub use naming::*;
+pub use openapi_to_linkml::*;
+pub use shacl_to_linkml::*;
+pub use shape_map::*;
+pub use wikidata::*;
+pub use xsd_to_linkml::*;
@@ -0,0 +1,554 @@
+use std::path::Path;
+
+use anyhow::Context;
+use serde_yaml::Value as YamlValue;
+
+use crate::convert::{self, Facets, Inlining, IriStem, Prefixes, PropertyInfo, RangeExpr, SemAct, ShapeConstraint, ShapeInfo};
+
+/// Convert a LinkML YAML content string to a SHACL shapes graph (Turtle),
+/// going straight through the shape model rather than via ShEx.
+pub fn linkml_yaml_to_shacl(yaml_str: &str) -> anyhow::Result<String> {
+    let shapes = shapes_from_linkml(yaml_str)?;
+    Ok(convert::build_shacl_with_prefixes(&shapes, &prefixes_from_linkml(yaml_str)?))
+}
+
+/// Read LinkML `prefixes` (plain or `prefix_reference` form). The `default_prefix`
+/// namespace is the base; a `base` prefix standing in for it is not repeated.
+pub fn prefixes_from_linkml(yaml_str: &str) -> anyhow::Result<Prefixes> {
+    let doc = parse_linkml(yaml_str)?;
+    let mut declared: Vec<(String, String)> = match doc.get("prefixes") {
+        Some(YamlValue::Mapping(m)) => m.iter().filter_map(|(prefix, ns)| {
+            let ns = ns.as_str().or_else(|| ns.get("prefix_reference")?.as_str())?;
+            Some((prefix.as_str()?.to_string(), ns.to_string()))
+        }).collect(),
+        _ => Vec::new(),
+    };
+    let default_prefix = string(&doc, "default_prefix");
+    let base = default_prefix.as_ref().and_then(|d| declared.iter().find(|(p, _)| p == d)).map(|(_, ns)| ns.clone());
+    if default_prefix.as_deref() == Some("base") {
+        declared.retain(|(p, _)| p != "base");
+    }
+    Ok(Prefixes { base, prefixes: declared })
+}
+
+/// Read LinkML `classes` (and the `slots` and `enums` they use) into our ShapeInfo vector.
+/// `is_a`, `mixins` and classes the class is `all_of` become the shape's `extends`.
+/// Cardinality comes from `required`/`multivalued`, refined by `minimum_cardinality`/`maximum_cardinality`; `slot_uri` and `class_uri` are used as the predicate and id when present.
+/// A class's `slot_usage` refines the global slot definitions for that class.
+/// `pattern`, or else an interpolated `structured_pattern`, becomes the pattern facet.
+/// An `identifier: true` slot becomes the shape's `identifier` instead of a property.
+/// A custom type in `types` ranges over its datatype, with its pattern and bounds as facets.
+/// A slot without a `range` takes the schema's `default_range`; one without a `slot_uri`
+/// is a predicate relative to the `default_prefix` namespace.
+/// `inlined` and `inlined_as_list` say whether values of a class range are nested or referred to.
+pub fn shapes_from_linkml(yaml_str: &str) -> anyhow::Result<Vec<ShapeInfo>> {
+    let doc = parse_linkml(yaml_str)?;
+
+    let classes = match doc.get("classes") {
+        Some(YamlValue::Mapping(m)) => m.clone(),
+        _ => anyhow::bail!("LinkML YAML missing `classes` mapping"),
+    };
+    let slots = match doc.get("slots") {
+        Some(YamlValue::Mapping(m)) => m.clone(),
+        _ => serde_yaml::Mapping::new(),
+    };
+    let section = |key: &str| match doc.get(key) {
+        Some(YamlValue::Mapping(m)) => m.clone(),
+        _ => serde_yaml::Mapping::new(),
+    };
+    let definitions = Definitions {
+        enums: section("enums"),
+        settings: section("settings"),
+        types: section("types"),
+        prefixes: prefixes_from_linkml(yaml_str)?,
+        default_range: string(&doc, "default_range").unwrap_or_else(|| "string".to_string()),
+    };
+
+    let mut shapes = Vec::new();
+    for (class_name_val, class_entry) in classes.iter() {
+        let Some(class_name) = class_name_val.as_str() else { continue };
+        let id = class_entry.get("class_uri").and_then(|v| v.as_str()).unwrap_or(class_name);
+
+        let mut properties = Vec::new();
+        let mut identifier = None;
+        if let Some(YamlValue::Sequence(sarr)) = class_entry.get("slots") {
+            for slot_name in sarr.iter().filter_map(|s| s.as_str()) {
+                let slot_def = slot_for_class(&slots, class_entry, slot_name);
+                // the identifier slot names the instance itself rather than a property of it
+                if is_identifier(&slot_def) {
+                    identifier = Some(slot_name.to_string());
+                    continue;
+                }
+                properties.push(prop_from_slot(slot_name, &slot_def, &definitions));
+            }
+        }
+
+        // `is_a` first, then any mixins
+        let mut extends: Vec<String> = class_entry.get("is_a").and_then(|v| v.as_str()).map(str::to_string).into_iter().collect();
+        if let Some(YamlValue::Sequence(mixins)) = class_entry.get("mixins") {
+            extends.extend(mixins.iter().filter_map(|m| m.as_str()).map(str::to_string));
+        }
+
+        // `exactly_one_of` and `any_of`, directly or under `all_of`, become OneOf over the
+        // named slots and AnyOf over the `is_a` classes; each `none_of` class becomes a Not
+        let groups = |key: &str| -> Vec<Vec<YamlValue>> {
+            let nested = match class_entry.get("all_of") {
+                Some(YamlValue::Sequence(all_of)) => all_of.iter().filter_map(|a| a.get(key)).collect(),
+                _ => Vec::new(),
+            };
+            class_entry.get(key).into_iter().chain(nested).filter_map(|g| g.as_sequence()).cloned().collect()
+        };
+        let is_a = |expr: &YamlValue| expr.get("is_a").and_then(|v| v.as_str()).map(str::to_string);
+        // a class that's all of some others extends each of them
+        if let Some(YamlValue::Sequence(all_of)) = class_entry.get("all_of") {
+            extends.extend(all_of.iter().filter_map(is_a).filter(|name| !extends.contains(name)).collect::<Vec<_>>());
+        }
+        let mut constraints: Vec<ShapeConstraint> = groups("exactly_one_of").iter()
+            .map(|branches| ShapeConstraint::OneOf(branches.iter().map(|b| alternative_from_branch(b, &properties)).collect()))
+            .collect();
+        constraints.extend(groups("any_of").iter().map(|exprs| ShapeConstraint::AnyOf(exprs.iter().filter_map(is_a).collect())));
+        constraints.extend(groups("none_of").iter().flatten().filter_map(is_a).map(ShapeConstraint::Not));
+        if let Some(YamlValue::Sequence(rules)) = class_entry.get("rules") {
+            constraints.extend(rules.iter().map(|rule| {
+                let conditions = |key: &str| rule.get(key).map(|c| conditions_from_expression(c, &properties)).unwrap_or_default();
+                ShapeConstraint::Rule {
+                    preconditions: conditions("preconditions"),
+                    postconditions: conditions("postconditions"),
+                    elseconditions: conditions("elseconditions"),
+                }
+            }));
+        }
+
+        shapes.push(ShapeInfo {
+            id: id.to_string(),
+            name: class_name.to_string(),
+            properties,
+            extends,
+            constraints,
+            closed: is_closed(class_entry),
+            start: class_entry.get("tree_root").and_then(|v| v.as_bool()).unwrap_or(false),
+            is_abstract: class_entry.get("abstract").and_then(|v| v.as_bool()).unwrap_or(false),
+            external: is_external(class_entry),
+            title: string(class_entry, "title"),
+            description: string(class_entry, "description"),
+            sem_acts: sem_acts(class_entry),
+            identifier,
+        });
+    }
+
+    Ok(shapes)
+}
+
+/// A slot's definition as a class uses it: the global one with the class's
+/// `slot_usage` refinements, if any, laid over it
+pub(crate) fn slot_for_class(slots: &serde_yaml::Mapping, class_entry: &YamlValue, slot_name: &str) -> YamlValue {
+    let mut slot_def = slots.get(slot_name).cloned().unwrap_or(YamlValue::Null);
+    if let Some(YamlValue::Mapping(usage)) = class_entry.get("slot_usage").and_then(|u| u.get(slot_name)) {
+        if !slot_def.is_mapping() {
+            slot_def = YamlValue::Mapping(serde_yaml::Mapping::new());
+        }
+        if let YamlValue::Mapping(def) = &mut slot_def {
+            for (k, v) in usage.iter() {
+                def.insert(k.clone(), v.clone());
+            }
+        }
+    }
+    slot_def
+}
+
+/// Whether a slot is the class's `identifier`, whose value is the instance's IRI
+pub(crate) fn is_identifier(slot_def: &YamlValue) -> bool {
+    slot_def.get("identifier").and_then(|v| v.as_bool()).unwrap_or(false)
+}
+
+/// The class properties named in an `exactly_one_of` branch's `slot_conditions`,
+/// required within the branch when the condition says so
+fn alternative_from_branch(branch: &YamlValue, properties: &[PropertyInfo]) -> Vec<PropertyInfo> {
+    let Some(YamlValue::Mapping(conditions)) = branch.get("slot_conditions") else { return Vec::new() };
+    conditions.iter().filter_map(|(name, condition)| {
+        let p = properties.iter().find(|p| Some(p.name.as_str()) == name.as_str())?;
+        let required = condition.get("required").and_then(|v| v.as_bool()).unwrap_or(false);
+        Some(PropertyInfo { min: Some(if required { 1 } else { 0 }), ..p.clone() })
+    }).collect()
+}
+
+/// The conditions of a rule's pre-, post- or elseconditions: the slots of its
+/// `slot_conditions`, required or not, with the `equals_string` values they take
+fn conditions_from_expression(expression: &YamlValue, properties: &[PropertyInfo]) -> Vec<PropertyInfo> {
+    let Some(YamlValue::Mapping(conditions)) = expression.get("slot_conditions") else { return Vec::new() };
+    conditions.iter().filter_map(|(name, condition)| {
+        let p = properties.iter().find(|p| Some(p.name.as_str()) == name.as_str())?;
+        let required = condition.get("required").and_then(|v| v.as_bool()).unwrap_or(false);
+        let equals = |c: &YamlValue| c.get("equals_string").and_then(|v| v.as_str()).map(str::to_string);
+        let values = match condition.get("any_of") {
+            Some(YamlValue::Sequence(any_of)) => any_of.iter().filter_map(equals).collect(),
+            _ => equals(condition).into_iter().collect(),
+        };
+        Some(PropertyInfo { min: Some(if required { 1 } else { 0 }), values, ..p.clone() })
+    }).collect()
+}
+
+/// Whether a class carries the `closed` annotation written by `build_linkml_doc`,
+/// either as a bare value or in LinkML's expanded `{tag, value}` form
+pub(crate) fn is_closed(class_entry: &YamlValue) -> bool {
+    flag_annotation(class_entry, "closed")
+}
+
+/// Whether a class carries the `external` annotation written by `build_linkml_doc`
+pub(crate) fn is_external(class_entry: &YamlValue) -> bool {
+    flag_annotation(class_entry, "external")
+}
+
+fn flag_annotation(class_entry: &YamlValue, tag: &str) -> bool {
+    match class_entry.get("annotations").and_then(|a| a.get(tag)) {
+        Some(YamlValue::Bool(b)) => *b,
+        Some(YamlValue::String(s)) => s == "true",
+        Some(YamlValue::Mapping(m)) => m.get("value").is_some_and(|v| v.as_bool() == Some(true) || v.as_str() == Some("true")),
+        _ => false,
+    }
+}
+
+/// A string annotation on a class or slot, bare or in the expanded `{tag, value}` form
+pub(crate) fn annotation<'a>(entry: &'a YamlValue, tag: &str) -> Option<&'a str> {
+    match entry.get("annotations").and_then(|a| a.get(tag)) {
+        Some(YamlValue::Mapping(m)) => m.get("value").and_then(|v| v.as_str()),
+        Some(v) => v.as_str(),
+        None => None,
+    }
+}
+
+/// Parse a LinkML schema in either its YAML or its JSON serialization.
+/// An import of `linkml:types` is resolved with the bundled copy of the built-in types.
+pub fn parse_linkml(text: &str) -> anyhow::Result<YamlValue> {
+    let mut doc = parse_linkml_document(text)?;
+    if imports(&doc).iter().any(|i| i == LINKML_TYPES_IMPORT) {
+        merge_schema(&mut doc, &parse_linkml_document(LINKML_TYPES)?);
+    }
+    Ok(doc)
+}
+
+fn parse_linkml_document(text: &str) -> anyhow::Result<YamlValue> {
+    // JSON is mostly YAML, but e.g. tab indentation isn't, so don't rely on that
+    if text.trim_start().starts_with('{') {
+        serde_json::from_str(text).context("parsing linkml json")
+    } else {
+        serde_yaml::from_str(text).context("parsing linkml yaml")
+    }
+}
+
+/// The LinkML built-in types, imported as `linkml:types`
+const LINKML_TYPES: &str = include_str!("linkml_types.yaml");
+const LINKML_TYPES_IMPORT: &str = "linkml:types";
+
+/// A LinkML schema with its `imports` merged in, serialized as YAML.
+/// `linkml:types` is the bundled copy; other imports are read from `dir`, relative
+/// to which they're named (with or without `.yaml`), or, with `fetch`, from their URL.
+/// A CURIE import is a URL under its prefix's namespace.
+/// The importing schema's own prefixes, classes, slots, enums and types take precedence.
+pub fn resolve_imports(text: &str, dir: Option<&Path>, fetch: bool) -> anyhow::Result<String> {
+    let mut doc = parse_linkml_document(text)?;
+    let mut seen = Vec::new();
+    resolve_imports_into(&mut doc, dir, fetch, &mut seen)?;
+    serde_yaml::to_string(&doc).context("serialize LinkML YAML")
+}
+
+fn resolve_imports_into(doc: &mut YamlValue, dir: Option<&Path>, fetch: bool, seen: &mut Vec<String>) -> anyhow::Result<()> {
+    for import in imports(doc) {
+        if seen.contains(&import) {
+            continue;
+        }
+        seen.push(import.clone());
+        if import == LINKML_TYPES_IMPORT {
+            merge_schema(doc, &parse_linkml_document(LINKML_TYPES)?);
+            continue;
+        }
+        // a CURIE under a declared prefix names a URL
+        let url = import.split_once(':')
+            .and_then(|(prefix, local)| Some(format!("{}{}", doc.get("prefixes")?.get(prefix)?.as_str()?, local)))
+            .filter(|_| !import.starts_with("http://") && !import.starts_with("https://"))
+            .unwrap_or_else(|| import.clone());
+        let (text, import_dir) = if url.starts_with("http://") || url.starts_with("https://") {
+            if !fetch {
+                anyhow::bail!("LinkML import {} is remote; fetching imports wasn't enabled", import);
+            }
+            (fetch_import(&url)?, dir.map(Path::to_path_buf))
+        } else {
+            let dir = dir.unwrap_or(Path::new("."));
+            let path = [dir.join(&url), dir.join(format!("{}.yaml", url))].into_iter()
+                .find(|p| p.is_file())
+                .with_context(|| format!("LinkML import {} not found in {}", import, dir.display()))?;
+            let text = std::fs::read_to_string(&path).with_context(|| format!("reading {}", path.display()))?;
+            (text, path.parent().map(Path::to_path_buf))
+        };
+        let mut imported = parse_linkml_document(&text).with_context(|| format!("in LinkML import {}", import))?;
+        resolve_imports_into(&mut imported, import_dir.as_deref(), fetch, seen)?;
+        merge_schema(doc, &imported);
+    }
+    Ok(())
+}
+
+/// Several LinkML schemas as one, serialized as YAML: each is merged into the first
+/// as an import would be, so earlier schemas' definitions take precedence
+pub fn merge_linkml(texts: &[String]) -> anyhow::Result<String> {
+    let mut schemas = texts.iter().map(|text| parse_linkml_document(text));
+    let mut doc = schemas.next().context("no LinkML schemas to merge")??;
+    for schema in schemas {
+        merge_schema(&mut doc, &schema?);
+    }
+    serde_yaml::to_string(&doc).context("serialize LinkML YAML")
+}
+
+/// The schema's own `id`, the IRI it is published under
+pub fn schema_id(text: &str) -> anyhow::Result<Option<String>> {
+    Ok(string(&parse_linkml_document(text)?, "id"))
+}
+
+/// The ids of the classes a LinkML schema defines itself, as `shapes_from_linkml` gives them
+pub fn class_ids(text: &str) -> anyhow::Result<Vec<String>> {
+    let doc = parse_linkml_document(text)?;
+    Ok(match doc.get("classes") {
+        Some(YamlValue::Mapping(classes)) => classes.iter().filter_map(|(name, entry)| {
+            entry.get("class_uri").and_then(|v| v.as_str()).or(name.as_str()).map(str::to_string)
+        }).collect(),
+        _ => Vec::new(),
+    })
+}
+
+/// Fetch a remote import, as given or with `.yaml` appended
+fn fetch_import(url: &str) -> anyhow::Result<String> {
+    let client = reqwest::blocking::Client::new();
+    let get = |url: &str| client.get(url)
+        .header(reqwest::header::USER_AGENT, concat!("shex2linkml/", env!("CARGO_PKG_VERSION")))
+        .send()
+        .and_then(|r| r.error_for_status())
+        .and_then(|r| r.text());
+    get(url).or_else(|_| get(&format!("{}.yaml", url))).with_context(|| format!("fetching {}", url))
+}
+
+/// A schema's `imports`
+fn imports(doc: &YamlValue) -> Vec<String> {
+    match doc.get("imports") {
+        Some(YamlValue::Sequence(imports)) => imports.iter().filter_map(|i| i.as_str()).map(str::to_string).collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Add an imported schema's definitions to those `doc` doesn't define itself
+fn merge_schema(doc: &mut YamlValue, imported: &YamlValue) {
+    let YamlValue::Mapping(root) = doc else { return };
+    for key in ["prefixes", "classes", "slots", "enums", "types", "settings"] {
+        let Some(YamlValue::Mapping(entries)) = imported.get(key) else { continue };
+        let section = root.entry(YamlValue::String(key.to_string())).or_insert(YamlValue::Mapping(serde_yaml::Mapping::new()));
+        if let YamlValue::Mapping(section) = section {
+            for (name, entry) in entries.iter() {
+                if !section.contains_key(name) {
+                    section.insert(name.clone(), entry.clone());
+                }
+            }
+        }
+    }
+}
+
+/// A slot's `minimum_cardinality` or `maximum_cardinality`, or the `min_count` or
+/// `max_count` earlier versions of `build_linkml_doc` wrote in their place;
+/// an `exact_cardinality` is both
+pub(crate) fn cardinality(slot_def: &YamlValue, key: &str, legacy_key: &str) -> Option<u64> {
+    slot_def.get(key).or_else(|| slot_def.get(legacy_key)).or_else(|| slot_def.get("exact_cardinality")).and_then(|v| v.as_u64())
+}
+
+/// The schema-level definitions a slot can refer to
+struct Definitions {
+    enums: serde_yaml::Mapping,
+    settings: serde_yaml::Mapping,
+    types: serde_yaml::Mapping,
+    prefixes: Prefixes,
+    /// the schema's `default_range`, for slots without a `range`
+    default_range: String,
+}
+
+fn prop_from_slot(slot_name: &str, slot_def: &YamlValue, definitions: &Definitions) -> PropertyInfo {
+    let range_expr = range_expr(slot_def);
+    let first = range_expr.as_ref().and_then(|e| e.ranges().first()).map(String::as_str);
+    let range = slot_def.get("range").and_then(|v| v.as_str()).or(first).unwrap_or(&definitions.default_range);
+    let required = slot_def.get("required").and_then(|v| v.as_bool()).unwrap_or(false);
+    let multivalued = slot_def.get("multivalued").and_then(|v| v.as_bool()).unwrap_or(false);
+
+    let min = cardinality(slot_def, "minimum_cardinality", "min_count").unwrap_or(if required { 1 } else { 0 });
+    let max = match cardinality(slot_def, "maximum_cardinality", "max_count") {
+        Some(n) => Some(n),
+        None if multivalued => None,
+        None => Some(1),
+    };
+
+    // a custom type ranges over the datatype of its nearest `uri`, unless that's an
+    // xsd datatype with a built-in counterpart, which the type is then a `typeof`
+    let (lineage, builtin) = type_lineage(&definitions.types, range);
+    let datatype = inherited(&lineage, "uri").and_then(|v| v.as_str())
+        .map(|uri| definitions.prefixes.expand(uri).unwrap_or_else(|| uri.to_string()));
+    let type_range = match (datatype, builtin) {
+        _ if lineage.is_empty() => range.to_string(),
+        (Some(iri), Some(builtin)) if iri.starts_with(convert::XSD) => builtin.to_string(),
+        (Some(iri), _) => convert::range_for_datatype(&iri),
+        (None, builtin) => builtin.unwrap_or("string").to_string(),
+    };
+
+    // enum ranges become value sets over strings
+    let (range, values) = match definitions.enums.get(range).and_then(|e| e.get("permissible_values")) {
+        Some(YamlValue::Mapping(pvs)) => (
+            "string".to_string(),
+            pvs.iter().filter_map(|(k, _)| k.as_str().map(str::to_string)).collect(),
+        ),
+        // `equals_string` is a one-member value set
+        _ => (type_range, string(slot_def, "equals_string").into_iter().collect()),
+    };
+    // the slot's own facets take precedence over its type's
+    let facet = |key: &str| slot_def.get(key).or_else(|| inherited(&lineage, key));
+
+    PropertyInfo {
+        name: slot_name.to_string(),
+        predicate: slot_def.get("slot_uri").and_then(|v| v.as_str()).unwrap_or(slot_name).to_string(),
+        range,
+        min: Some(min),
+        max,
+        values,
+        facets: Facets {
+            pattern: slot_pattern(slot_def, &definitions.settings).or_else(|| inherited(&lineage, "pattern")?.as_str().map(str::to_string)),
+            min_inclusive: facet("minimum_value").and_then(yaml_number),
+            max_inclusive: facet("maximum_value").and_then(yaml_number),
+            ..Default::default()
+        },
+        languages: annotation(slot_def, "languages")
+            .map(|v| v.split_whitespace().map(str::to_string).collect())
+            .unwrap_or_default(),
+        iri_stems: iri_stems(slot_def),
+        title: string(slot_def, "title"),
+        description: string(slot_def, "description"),
+        sem_acts: sem_acts(slot_def),
+        range_expr,
+        inlining: inlining(slot_def),
+        ..Default::default()
+    }
+}
+
+/// A slot's regular expression: its `pattern`, else its `structured_pattern` syntax,
+/// with `{name}` references to the schema's `settings` filled in when `interpolated`
+pub(crate) fn slot_pattern(slot_def: &YamlValue, settings: &serde_yaml::Mapping) -> Option<String> {
+    if let Some(pattern) = string(slot_def, "pattern") {
+        return Some(pattern);
+    }
+    let structured = slot_def.get("structured_pattern")?;
+    let syntax = string(structured, "syntax")?;
+    if !structured.get("interpolated").and_then(|v| v.as_bool()).unwrap_or(false) {
+        return Some(syntax);
+    }
+    // `{...}` that doesn't name a setting, e.g. a `{2,3}` quantifier, is left alone
+    let mut out = String::new();
+    let mut rest = syntax.as_str();
+    while let Some(open) = rest.find('{') {
+        out.push_str(&rest[..open]);
+        let after = &rest[open + 1..];
+        match after.find('}').and_then(|close| Some((close, settings.get(&after[..close])?.as_str()?))) {
+            Some((close, value)) => {
+                out.push_str(value);
+                rest = &after[close + 1..];
+            }
+            None => {
+                out.push('{');
+                rest = after;
+            }
+        }
+    }
+    out.push_str(rest);
+    Some(out)
+}
+
+/// A slot's `any_of`, `exactly_one_of` or `all_of` over `range`s
+pub(crate) fn range_expr(slot_def: &YamlValue) -> Option<RangeExpr> {
+    let ranges = |key: &str| -> Option<Vec<String>> {
+        let expressions = slot_def.get(key)?.as_sequence()?;
+        let ranges: Vec<String> = expressions.iter().filter_map(|e| e.get("range")?.as_str()).map(str::to_string).collect();
+        (!ranges.is_empty()).then_some(ranges)
+    };
+    ranges("any_of").map(RangeExpr::AnyOf)
+        .or_else(|| ranges("exactly_one_of").map(RangeExpr::ExactlyOneOf))
+        .or_else(|| ranges("all_of").map(RangeExpr::AllOf))
+}
+
+/// A slot's `inlined` and `inlined_as_list`, if it sets either; a list is inlined
+pub(crate) fn inlining(slot_def: &YamlValue) -> Option<Inlining> {
+    let flag = |key: &str| slot_def.get(key).and_then(|v| v.as_bool());
+    match (flag("inlined"), flag("inlined_as_list")) {
+        (_, Some(true)) => Some(Inlining::InlinedAsList),
+        (Some(true), _) => Some(Inlining::Inlined),
+        (Some(false), _) => Some(Inlining::Reference),
+        _ => None,
+    }
+}
+
+/// The LinkML built-in types, at which a custom type's `typeof` chain ends
+const BUILTIN_TYPES: &[&str] = &[
+    "string", "integer", "boolean", "float", "double", "decimal", "time", "date", "datetime", "date_or_datetime",
+    "uriorcurie", "curie", "uri", "ncname", "objectidentifier", "nodeidentifier", "jsonpointer", "jsonpath", "sparqlpath",
+];
+
+/// Each built-in type and the IRI of its `uri`, from the bundled `linkml:types`
+pub(crate) fn builtin_type_iris() -> anyhow::Result<Vec<(String, String)>> {
+    let doc = parse_linkml_document(LINKML_TYPES)?;
+    let types = doc.get("types").and_then(|t| t.as_mapping()).context("bundled linkml:types has no `types`")?;
+    Ok(types.iter().filter_map(|(name, t)| {
+        let (prefix, local) = t.get("uri")?.as_str()?.split_once(':')?;
+        let ns = doc.get("prefixes")?.get(prefix)?.as_str()?;
+        Some((name.as_str()?.to_string(), format!("{}{}", ns, local)))
+    }).collect())
+}
+
+/// The definitions of a custom type and of the types it's a `typeof`, nearest first,
+/// and the built-in type the chain ends at, if any
+pub(crate) fn type_lineage<'t>(types: &'t serde_yaml::Mapping, range: &'t str) -> (Vec<&'t YamlValue>, Option<&'t str>) {
+    let mut lineage = Vec::new();
+    let mut name = range;
+    while !BUILTIN_TYPES.contains(&name) {
+        match types.get(name) {
+            // a cycle of `typeof`s ends nowhere
+            Some(t) if lineage.len() <= types.len() => lineage.push(t),
+            _ => return (lineage, None),
+        }
+        match lineage.last().and_then(|t| t.get("typeof")).and_then(|v| v.as_str()) {
+            Some(parent) => name = parent,
+            None => return (lineage, None),
+        }
+    }
+    (lineage, Some(name))
+}
+
+/// The nearest of a type lineage's definitions of `key`
+pub(crate) fn inherited<'t>(lineage: &[&'t YamlValue], key: &str) -> Option<&'t YamlValue> {
+    lineage.iter().find_map(|t| t.get(key))
+}
+
+fn string(entry: &YamlValue, key: &str) -> Option<String> {
+    entry.get(key).and_then(|v| v.as_str()).map(str::to_string)
+}
+
+/// The structured `iri_stems` annotation written by `build_linkml_doc`
+pub(crate) fn iri_stems(slot_def: &YamlValue) -> Vec<IriStem> {
+    match slot_def.get("annotations").and_then(|a| a.get("iri_stems")) {
+        Some(v) => serde_yaml::from_value(v.get("value").unwrap_or(v).clone()).unwrap_or_default(),
+        None => Vec::new(),
+    }
+}
+
+/// The structured `semantic_actions` annotation of a class or slot, written by `build_linkml_doc`
+pub(crate) fn sem_acts(entry: &YamlValue) -> Vec<SemAct> {
+    match entry.get("annotations").and_then(|a| a.get("semantic_actions")) {
+        Some(v) => serde_yaml::from_value(v.get("value").unwrap_or(v).clone()).unwrap_or_default(),
+        None => Vec::new(),
+    }
+}
+
+pub(crate) fn yaml_number(v: &YamlValue) -> Option<serde_json::Number> {
+    match v {
+        YamlValue::Number(n) => serde_json::from_str(&n.to_string()).ok(),
+        _ => None,
+    }
+}
@@ -1,12 +1,198 @@
 use anyhow::Context;
+use iri_s::IriS;
+use prefixmap::{Deref, IriRef, PrefixMap};
 use serde_yaml::Value as YamlValue;
+use shex_ast::iri_exclusion::IriExclusion;
+use shex_ast::{Annotation, IriRefOrWildcard, LangOrWildcard, NodeConstraint, NodeKind, NumericFacet, ObjectValue, Schema, Shape, ShapeDecl, ShapeExpr, ShapeExprLabel, StringFacet, TripleExpr, ValueSetValue, XsFacet};
+use srdf::lang::Lang;
+use srdf::numeric_literal::NumericLiteral;
 
-/// Convert a LinkML YAML content string to a ShEx compact string.
+use crate::convert::{IriStem, RangeExpr, SemAct};
+use crate::naming::NameMap;
+
+/// Output syntax for generated ShEx
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ShexSyntax {
+    /// ShEx compact syntax (ShExC)
+    #[default]
+    Compact,
+    /// ShEx JSON syntax (ShExJ), e.g. for shex.js
+    Json,
+}
+
+/// How LinkML is written as ShEx
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ShexOptions {
+    pub syntax: ShexSyntax,
+    /// copy inherited slots into each class rather than writing EXTENDS
+    pub flatten: bool,
+    /// write every shape CLOSED, as a LinkML class admits only its slots,
+    /// rather than just those of classes with the `closed` annotation
+    pub closed: bool,
+    /// shape and predicate IRIs for classes and for slots without a `slot_uri`
+    pub names: NameMap,
+    /// how ShExC is laid out
+    pub style: ShexcStyle,
+    /// write shapes in label order and their triple constraints in predicate order,
+    /// rather than the order of the schema's classes and slots
+    pub sort: bool,
+}
+
+/// How ShExC is laid out, so generated schemas can match a house style
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShexcStyle {
+    /// spaces before a triple constraint on a line of its own
+    pub indent: usize,
+    /// write each shape on one line, e.g. `<A> { ex:a . ; ex:b . }`
+    pub compact: bool,
+    /// declare prefixes in alphabetical order rather than the schema's
+    pub sort_prefixes: bool,
+    /// the widest a compact shape may be before its constraints go one per line
+    pub max_width: Option<usize>,
+}
+
+impl Default for ShexcStyle {
+    fn default() -> Self {
+        ShexcStyle { indent: 2, compact: false, sort_prefixes: false, max_width: None }
+    }
+}
+
+/// Convert a LinkML YAML (or JSON) content string to a ShEx compact string.
 /// This is a best-effort conversion assuming LinkML `classes` and `slots` sections
 /// exist. Predicates will be generated using the `prefixes` mapping when available
-/// (e.g., `ex:propertyName`), otherwise as `http://example.org/propertyName`.
+/// (e.g., `ex:propertyName`), otherwise as `<http://example.org/propertyName>`.
+/// The prefixes are declared, so the output parses as ShExC.
 pub fn linkml_yaml_to_shex(yaml_str: &str) -> anyhow::Result<String> {
-    let doc: YamlValue = serde_yaml::from_str(yaml_str).context("parsing linkml yaml")?;
+    linkml_yaml_to_shex_as(yaml_str, ShexSyntax::Compact)
+}
+
+/// Convert a LinkML YAML content string to ShEx in the requested syntax.
+pub fn linkml_yaml_to_shex_as(yaml_str: &str, syntax: ShexSyntax) -> anyhow::Result<String> {
+    linkml_yaml_to_shex_with(yaml_str, &ShexOptions { syntax, ..Default::default() })
+}
+
+/// Convert a LinkML YAML content string to ShEx as `options` say.
+pub fn linkml_yaml_to_shex_with(yaml_str: &str, options: &ShexOptions) -> anyhow::Result<String> {
+    let (mut shapes, prefixes) = shex_shapes_from_linkml(yaml_str, &options.names)?;
+    if options.flatten {
+        flatten(&mut shapes);
+    }
+    if options.closed {
+        shapes.iter_mut().for_each(|s| s.closed = true);
+    }
+    if options.sort {
+        shapes.sort_by(|a, b| a.label.cmp(&b.label));
+        for s in shapes.iter_mut() {
+            s.constraints.iter_mut().for_each(|cs| cs.sort_by(|a, b| a.predicate_iri.cmp(&b.predicate_iri)));
+        }
+    }
+    let schema = shex_schema(&shapes, &prefixes)?;
+    match options.syntax {
+        ShexSyntax::Compact => Ok(write_shexc(&schema, &recommended(&shapes), &options.style)),
+        ShexSyntax::Json => serde_json::to_string_pretty(&shexj_schema(&schema)?).context("serialize ShExJ"),
+    }
+}
+
+/// A shape to be written, one per LinkML class
+struct ShexShape {
+    label: String,
+    /// `is_a` then `mixins`, written as EXTENDS
+    extends: Vec<String>,
+    /// `any_of`, `exactly_one_of` and `all_of` over `is_a` classes, by label, ANDed with the shape
+    junctions: Vec<RangeExpr>,
+    /// `none_of` classes, by label, each ANDed with the shape as a NOT
+    none_of: Vec<String>,
+    /// from the `closed` annotation, written as CLOSED
+    closed: bool,
+    /// `tree_root`, written as the schema's START
+    start: bool,
+    /// `abstract`, written as ABSTRACT
+    is_abstract: bool,
+    /// from the `external` annotation, written as EXTERNAL
+    external: bool,
+    /// `None` when the class has no `slots` sequence
+    constraints: Option<Vec<ShexConstraint>>,
+    /// `exactly_one_of` groups, one list of constraints per alternative
+    one_of: Vec<Vec<Vec<ShexConstraint>>>,
+    /// `slot_usage` of inherited slots, which only a flattened shape can apply
+    refinements: Vec<ShexConstraint>,
+    /// `title` and `description`, written as rdfs:label and rdfs:comment annotations
+    title: Option<String>,
+    description: Option<String>,
+    /// from the `semantic_actions` annotation
+    sem_acts: Vec<SemAct>,
+    /// the class has an `identifier` slot, so its instances are IRIs
+    identified: bool,
+}
+
+#[derive(Clone)]
+struct ShexConstraint {
+    /// predicate as written in ShExC (CURIE or IRI)
+    predicate: String,
+    /// predicate expanded to a full IRI for ShExJ
+    predicate_iri: String,
+    /// datatype IRI, if any
+    datatype: Option<String>,
+    /// node kind of an identifier or `uriorcurie` range, in place of `datatype`
+    node_kind: Option<NodeKind>,
+    /// a class range, by shape label, written as a shape reference
+    shape: Option<String>,
+    /// `pattern` or interpolated `structured_pattern`, written as a regex facet
+    pattern: Option<String>,
+    /// `any_of`, `exactly_one_of` or `all_of` ranges, in place of `datatype`
+    range_expr: Option<(RangeExpr, Vec<RangeRef>)>,
+    /// `minimum_value` and `maximum_value`, written as MININCLUSIVE and MAXINCLUSIVE
+    min_inclusive: Option<serde_json::Number>,
+    max_inclusive: Option<serde_json::Number>,
+    /// permissible values when the range is an enum
+    values: Vec<String>,
+    /// language tags from the slot's `languages` annotation, e.g. `en fr~`
+    languages: Vec<String>,
+    /// IRI stems from the slot's `iri_stems` annotation
+    iri_stems: Vec<IriStem>,
+    min: u64,
+    /// None when unbounded
+    max: Option<u64>,
+    /// LinkML `recommended`, which ShEx can't enforce, so it's noted in a ShExC comment
+    recommended: bool,
+    title: Option<String>,
+    description: Option<String>,
+    /// from the slot's `semantic_actions` annotation
+    sem_acts: Vec<SemAct>,
+}
+
+impl ShexConstraint {
+    /// The datatype IRIs the constraint is written with; value sets take precedence over ranges
+    fn datatypes(&self) -> Vec<&str> {
+        if !self.languages.is_empty() || !self.values.is_empty() || !self.iri_stems.is_empty() {
+            Vec::new()
+        } else if let Some((_, refs)) = &self.range_expr {
+            refs.iter().filter_map(|r| match r {
+                RangeRef::Datatype(dt) => Some(dt.as_str()),
+                _ => None,
+            }).collect()
+        } else {
+            self.datatype.iter().map(String::as_str).collect()
+        }
+    }
+}
+
+/// One of the ranges in a slot's range expression
+#[derive(Clone)]
+enum RangeRef {
+    /// a class, by shape label
+    Shape(String),
+    /// a datatype IRI
+    Datatype(String),
+    /// an identifier or `uriorcurie` range
+    NodeKind(NodeKind),
+    /// a range ShEx can't constrain
+    Any,
+}
+
+/// The shapes for a LinkML schema's classes, and the prefixes their CURIEs use
+fn shex_shapes_from_linkml(yaml_str: &str, names: &NameMap) -> anyhow::Result<(Vec<ShexShape>, Vec<(String, String)>)> {
+    let doc = crate::linkml_to_shacl::parse_linkml(yaml_str)?;
 
     // Extract prefixes map
     let prefixes = match doc.get("prefixes") {
@@ -25,61 +211,739 @@ pub fn linkml_yaml_to_shex(yaml_str: &str) -> anyhow::Result<String> {
         Some(YamlValue::Mapping(m)) => m.clone(),
         _ => serde_yaml::Mapping::new(),
     };
+    let enums = match doc.get("enums") {
+        Some(YamlValue::Mapping(m)) => m.clone(),
+        _ => serde_yaml::Mapping::new(),
+    };
+    let settings = match doc.get("settings") {
+        Some(YamlValue::Mapping(m)) => m.clone(),
+        _ => serde_yaml::Mapping::new(),
+    };
+    // including the built-in types when the schema imports `linkml:types`
+    let types = match doc.get("types") {
+        Some(YamlValue::Mapping(m)) => m.clone(),
+        _ => serde_yaml::Mapping::new(),
+    };
 
-    // Helper to expand a slot name into a predicate IRI/curie
-    let pred_for = |slot_name: &str| -> String {
-        // If a prefix `ex` exists, use it
-        if let Some((pfx, iri)) = prefixes.get(0) {
-            format!("{}:{}", pfx, slot_name)
-        } else {
-            format!("http://example.org/{}", slot_name)
+    // the IRI for a CURIE under a declared prefix
+    let expand = |curie: &str| -> Option<String> {
+        let (pfx, local) = curie.split_once(':')?;
+        prefixes.iter().find(|(p, _)| p == pfx).map(|(_, ns)| format!("{}{}", ns, local))
+    };
+
+    let builtin_iris = crate::linkml_to_shacl::builtin_type_iris()?;
+
+    // the datatype of a range: its type's `uri`, or that of the nearest type it's a `typeof`,
+    // else the `uri` the built-in type has in linkml:types, whether or not the schema imports it.
+    // A uriorcurie is an IRI, and LinkML gives identifier types `shex:` URIs, which are node kinds.
+    let datatype_for = |lineage: &[&YamlValue], builtin: Option<&str>| -> Option<RangeRef> {
+        let uri = crate::linkml_to_shacl::inherited(lineage, "uri").and_then(|v| v.as_str());
+        let iri = match (uri, builtin) {
+            (Some(uri), _) => expand(uri).unwrap_or_else(|| uri.to_string()),
+            (None, Some("uriorcurie")) => return Some(RangeRef::NodeKind(NodeKind::Iri)),
+            (None, builtin) => builtin_iris.iter().find(|(name, _)| Some(name.as_str()) == builtin)?.1.clone(),
+        };
+        match iri.strip_prefix(SHEX) {
+            Some("iri") => Some(RangeRef::NodeKind(NodeKind::Iri)),
+            Some("bnode") => Some(RangeRef::NodeKind(NodeKind::BNode)),
+            Some("nonLiteral") => Some(RangeRef::NodeKind(NodeKind::NonLiteral)),
+            Some("literal") => Some(RangeRef::NodeKind(NodeKind::Literal)),
+            Some(_) => None,
+            None => Some(RangeRef::Datatype(iri)),
         }
     };
 
-    // Build ShEx compact: one shape per class
-    let mut out = String::new();
+    // Helper to expand a slot name into a predicate curie and IRI
+    let pred_for = |slot_name: &str, slot: Option<&YamlValue>| -> (String, String) {
+        // a `slot_uri` names the predicate outright, as does a slot named by its IRI or CURIE
+        let slot_uri = slot.and_then(|slot| slot.get("slot_uri")).and_then(|v| v.as_str());
+        for uri in slot_uri.into_iter().chain(std::iter::once(slot_name)) {
+            if uri.starts_with("http://") || uri.starts_with("https://") {
+                return (format!("<{}>", uri), uri.to_string());
+            }
+            if let Some(iri) = expand(uri) {
+                return (uri.to_string(), iri);
+            }
+        }
+        if let Some(iri) = names.predicate(slot_name) {
+            return (format!("<{}>", iri), iri.to_string());
+        }
+        // else mint one in the `default_prefix` namespace, or failing that the first declared
+        let default_prefix = doc.get("default_prefix").and_then(|v| v.as_str());
+        let minting = prefixes.iter().find(|(pfx, _)| Some(pfx.as_str()) == default_prefix).or(prefixes.first());
+        let iri = match minting {
+            Some((pfx, ns)) if crate::convert::is_curie_local(slot_name) => return (format!("{}:{}", pfx, slot_name), format!("{}{}", ns, slot_name)),
+            Some((_, ns)) => format!("{}{}", ns, slot_name),
+            None => format!("http://example.org/{}", slot_name),
+        };
+        (format!("<{}>", iri), iri)
+    };
+
+    // a class is labelled by its `class_uri`, else its mapped shape IRI, and one named by a CURIE by its IRI
+    let shape_label = |class_name: &str| {
+        let class_uri = classes.get(class_name).and_then(|c| c.get("class_uri")).and_then(|v| v.as_str());
+        class_uri.map(|uri| expand(uri).unwrap_or_else(|| uri.to_string()))
+            .or_else(|| names.shape_iri(class_name).map(str::to_string))
+            .or_else(|| expand(class_name))
+            .unwrap_or_else(|| class_name.to_string())
+    };
+
+    // slots without a `range` take the schema's `default_range`
+    let default_range = doc.get("default_range").and_then(|v| v.as_str()).unwrap_or("string");
+
+    // Constraint for a slot or attribute, from its definition's range and cardinality
+    let constraint_for = |slot_name: &str, slot: Option<&YamlValue>| -> ShexConstraint {
+        let (range_str, minc, maxc) = match slot {
+            Some(def @ YamlValue::Mapping(m)) => {
+                let range = m.get("range").and_then(|v| v.as_str()).unwrap_or(default_range).to_string();
+                // without a minimum, a required slot needs one value
+                let minc = match crate::linkml_to_shacl::cardinality(def, "minimum_cardinality", "min_count") {
+                    Some(n) => n,
+                    None if m.get("required").and_then(|v| v.as_bool()) == Some(true) => 1,
+                    None => 0,
+                };
+                // without a maximum, a multivalued slot is unbounded
+                let maxc = match crate::linkml_to_shacl::cardinality(def, "maximum_cardinality", "max_count") {
+                    Some(n) => Some(n),
+                    None if m.get("multivalued").and_then(|v| v.as_bool()) == Some(true) => None,
+                    None => Some(1),
+                };
+                (range, minc, maxc)
+            }
+            _ => (default_range.to_string(), 0, Some(1)),
+        };
+
+        let (predicate, predicate_iri) = pred_for(slot_name, slot);
+        // a class range is a reference to its shape; a type's is a datatype or node kind
+        let shape = classes.contains_key(range_str.as_str()).then(|| shape_label(&range_str));
+        let (lineage, builtin) = crate::linkml_to_shacl::type_lineage(&types, &range_str);
+        let (datatype, node_kind) = match datatype_for(&lineage, builtin) {
+            Some(RangeRef::Datatype(dt)) => (Some(dt), None),
+            Some(RangeRef::NodeKind(kind)) => (None, Some(kind)),
+            _ => (None, None),
+        };
+        // each range of an `any_of`, `exactly_one_of` or `all_of` is a class or a datatype
+        let range_expr = slot.and_then(crate::linkml_to_shacl::range_expr).map(|expr| {
+            let refs = expr.ranges().iter().map(|r| {
+                if classes.contains_key(r.as_str()) {
+                    return RangeRef::Shape(shape_label(r));
+                }
+                let (lineage, builtin) = crate::linkml_to_shacl::type_lineage(&types, r);
+                datatype_for(&lineage, builtin).unwrap_or(RangeRef::Any)
+            }).collect();
+            (expr, refs)
+        });
+        // enum ranges become value sets of each permissible value's `meaning` IRI, else its text
+        let values = match enums.get(range_str.as_str()).and_then(|e| e.get("permissible_values")) {
+            Some(YamlValue::Mapping(pvs)) => pvs.iter().filter_map(|(k, pv)| {
+                match pv.get("meaning").and_then(|m| m.as_str()) {
+                    Some(meaning) => Some(expand(meaning).unwrap_or_else(|| meaning.to_string())),
+                    None => k.as_str().map(str::to_string),
+                }
+            }).collect(),
+            // `equals_string` is a one-member value set
+            _ => slot.and_then(|slot| slot.get("equals_string")).and_then(|v| v.as_str()).map(str::to_string).into_iter().collect(),
+        };
+
+        let languages = slot
+            .and_then(|slot| crate::linkml_to_shacl::annotation(slot, "languages"))
+            .map(|v| v.split_whitespace().map(str::to_string).collect())
+            .unwrap_or_default();
+
+        let iri_stems = slot.map(crate::linkml_to_shacl::iri_stems).unwrap_or_default();
+
+        let doc = |key: &str| slot.and_then(|slot| slot.get(key)).and_then(|v| v.as_str()).map(str::to_string);
+        // the slot's own facets take precedence over its type's
+        let facet = |key: &str| slot.and_then(|slot| slot.get(key)).or_else(|| crate::linkml_to_shacl::inherited(&lineage, key));
+        let bound = |key: &str| facet(key).and_then(crate::linkml_to_shacl::yaml_number);
+
+        ShexConstraint {
+            predicate,
+            predicate_iri,
+            datatype,
+            node_kind,
+            shape,
+            pattern: slot.and_then(|slot| crate::linkml_to_shacl::slot_pattern(slot, &settings))
+                .or_else(|| facet("pattern")?.as_str().map(str::to_string)),
+            range_expr,
+            min_inclusive: bound("minimum_value"),
+            max_inclusive: bound("maximum_value"),
+            values,
+            languages,
+            iri_stems,
+            min: minc,
+            max: maxc,
+            recommended: slot.and_then(|slot| slot.get("recommended")).and_then(|v| v.as_bool()).unwrap_or(false),
+            title: doc("title"),
+            description: doc("description"),
+            sem_acts: slot.map(crate::linkml_to_shacl::sem_acts).unwrap_or_default(),
+        }
+    };
+
+    // one shape per class
+    let mut shapes = Vec::new();
 
     for (class_name_val, class_entry) in classes.iter() {
         if let YamlValue::String(class_name) = class_name_val {
-            out.push_str(&format!("<{}> IRI
-", class_name));
-            // slots: sequence of slot names
+            let doc = |key: &str| class_entry.get(key).and_then(|v| v.as_str()).map(str::to_string);
+            let mut shape = ShexShape {
+                label: shape_label(class_name),
+                extends: Vec::new(),
+                junctions: Vec::new(),
+                none_of: Vec::new(),
+                closed: crate::linkml_to_shacl::is_closed(class_entry),
+                start: class_entry.get("tree_root").and_then(|v| v.as_bool()).unwrap_or(false),
+                is_abstract: class_entry.get("abstract").and_then(|v| v.as_bool()).unwrap_or(false),
+                external: crate::linkml_to_shacl::is_external(class_entry),
+                constraints: None,
+                one_of: Vec::new(),
+                refinements: Vec::new(),
+                title: doc("title"),
+                description: doc("description"),
+                sem_acts: crate::linkml_to_shacl::sem_acts(class_entry),
+                identified: false,
+            };
+            // slots: sequence of slot names; attributes: slots defined in the class
             if let YamlValue::Mapping(map) = class_entry {
-                if let Some(slots_val) = map.get(&YamlValue::String("slots".to_string())) {
-                    if let YamlValue::Sequence(sarr) = slots_val {
-                        out.push_str("{
-");
-                        for s in sarr.iter() {
-                            if let YamlValue::String(slot_name) = s {
-                                // lookup slot definition for range/cardinality
-                                let slot_def = slots.get(&YamlValue::String(slot_name.clone()));
-                                let (range_str, minc, maxc) = match slot_def {
-                                    Some(YamlValue::Mapping(m)) => {
-                                        let range = m.get(&YamlValue::String("range".to_string())).and_then(|v| v.as_str()).map(|s| s.to_string()).unwrap_or("string".to_string());
-                                        let minc = m.get(&YamlValue::String("min_count".to_string())).and_then(|v| v.as_i64()).unwrap_or(0);
-                                        let maxc = m.get(&YamlValue::String("max_count".to_string())).and_then(|v| v.as_i64()).unwrap_or(1);
-                                        (range, minc, maxc)
-                                    }
-                                    _ => ("string".to_string(), 0, 1),
-                                };
-
-                                let pred = pred_for(slot_name);
-                                let qc = if minc == 0 && maxc > 1 { "*" } else if minc == 1 && maxc > 1 { "+" } else if minc == 1 && maxc == 1 { "" } else { "?" };
-                                // Map range back to a ShEx nodeConstraint: datatype -> xsd, otherwise assume @<shape> or IRI
-                                let constraint = if range_str == "string" { "" } else if range_str == "integer" { " xsd:integer" } else { "" };
-
-                                out.push_str(&format!("  {} {}{} ;
-", pred, constraint, qc));
-                            }
-                        }
-                        out.push_str("}
-
-");
-                    }
+                let attributes = match map.get("attributes") {
+                    Some(YamlValue::Mapping(m)) => m.clone(),
+                    _ => serde_yaml::Mapping::new(),
+                };
+                // a slot's definition for this class, refined by its `slot_usage`
+                let mut class_slots = slots.clone();
+                class_slots.extend(attributes.clone());
+                let definition = |name: &str| crate::linkml_to_shacl::slot_for_class(&class_slots, class_entry, name);
+                if let Some(YamlValue::String(parent)) = map.get("is_a") {
+                    shape.extends.push(shape_label(parent));
+                }
+                if let Some(YamlValue::Sequence(mixins)) = map.get("mixins") {
+                    shape.extends.extend(mixins.iter().filter_map(|m| m.as_str()).map(shape_label));
                 }
+                // `any_of`, `exactly_one_of` and `all_of` over classes, and `none_of`
+                let is_a = |expr: &YamlValue| expr.get("is_a").and_then(|v| v.as_str()).map(shape_label);
+                for key in ["any_of", "exactly_one_of", "all_of"] {
+                    let labels: Vec<String> = map.get(key).and_then(|v| v.as_sequence()).into_iter().flatten().filter_map(is_a).collect();
+                    if labels.is_empty() { continue; }
+                    shape.junctions.push(match key {
+                        "any_of" => RangeExpr::AnyOf(labels),
+                        "exactly_one_of" => RangeExpr::ExactlyOneOf(labels),
+                        _ => RangeExpr::AllOf(labels),
+                    });
+                }
+                shape.none_of = map.get("none_of").and_then(|v| v.as_sequence()).into_iter().flatten().filter_map(is_a).collect();
+                // `exactly_one_of` slot conditions, directly or under `all_of`
+                let nested = match map.get("all_of") {
+                    Some(YamlValue::Sequence(all_of)) => all_of.iter().filter_map(|a| a.get("exactly_one_of")).collect(),
+                    _ => Vec::new(),
+                };
+                for branches in map.get("exactly_one_of").into_iter().chain(nested).filter_map(|b| b.as_sequence()) {
+                    shape.one_of.push(branches.iter().map(|branch| match branch.get("slot_conditions") {
+                        Some(YamlValue::Mapping(conditions)) => conditions.iter().filter_map(|(name, condition)| {
+                            let required = condition.get("required").and_then(|v| v.as_bool()).unwrap_or(false);
+                            let name = name.as_str()?;
+                            Some(ShexConstraint { min: if required { 1 } else { 0 }, ..constraint_for(name, Some(&definition(name))) })
+                        }).collect(),
+                        _ => Vec::new(),
+                    }).collect());
+                }
+                let slot_names: Vec<&str> = match map.get("slots") {
+                    Some(YamlValue::Sequence(sarr)) => sarr.iter().filter_map(|s| s.as_str()).collect(),
+                    _ => Vec::new(),
+                };
+                if map.get("slots").is_some_and(|s| s.is_sequence()) || !attributes.is_empty() {
+                    // slots in a OneOf group are written inside the group
+                    let grouped: Vec<&str> = shape.one_of.iter().flatten().flatten().map(|c| c.predicate.as_str()).collect();
+                    // the identifier slot is the focus node's IRI rather than a triple
+                    let (identifiers, own): (Vec<&str>, Vec<&str>) = slot_names.iter().copied()
+                        .chain(attributes.keys().filter_map(|name| name.as_str()))
+                        .partition(|name| crate::linkml_to_shacl::is_identifier(&definition(name)));
+                    shape.identified = !identifiers.is_empty();
+                    let constraints = own.into_iter()
+                        .map(|name| constraint_for(name, Some(&definition(name))))
+                        .filter(|c| !grouped.contains(&c.predicate.as_str()))
+                        .collect();
+                    shape.constraints = Some(constraints);
+                } else if !shape.one_of.is_empty() {
+                    shape.constraints = Some(Vec::new());
+                }
+                if let Some(YamlValue::Mapping(usage)) = map.get("slot_usage") {
+                    shape.refinements = usage.keys().filter_map(|name| name.as_str())
+                        .filter(|name| !slot_names.contains(name) && !attributes.contains_key(*name))
+                        .map(|name| constraint_for(name, Some(&definition(name))))
+                        .collect();
+                }
+            }
+            shapes.push(shape);
+        }
+    }
+
+    Ok((shapes, prefixes))
+}
+
+/// Replace each shape's EXTENDS with copies of its ancestors' constraints, nearest
+/// parent's first, refined by the shape's `slot_usage`; mixins follow `is_a` as they do in `extends`
+fn flatten(shapes: &mut [ShexShape]) {
+    // a shape's constraints and OneOf groups, inherited ones included
+    fn all_constraints(shapes: &[ShexShape], label: &str, seen: &mut Vec<String>) -> (Vec<ShexConstraint>, Vec<Vec<Vec<ShexConstraint>>>) {
+        let (mut constraints, mut one_of) = (Vec::new(), Vec::new());
+        let Some(shape) = shapes.iter().find(|s| s.label == label) else { return (constraints, one_of) };
+        for parent in shape.extends.iter() {
+            if seen.contains(parent) { continue; }
+            seen.push(parent.clone());
+            let (c, o) = all_constraints(shapes, parent, seen);
+            constraints.extend(c);
+            one_of.extend(o);
+        }
+        for c in constraints.iter_mut() {
+            if let Some(refined) = shape.refinements.iter().find(|r| r.predicate_iri == c.predicate_iri) {
+                *c = refined.clone();
             }
         }
+        constraints.extend(shape.constraints.iter().flatten().cloned());
+        one_of.extend(shape.one_of.iter().cloned());
+        (constraints, one_of)
+    }
+
+    let flattened: Vec<_> = shapes.iter().map(|s| all_constraints(shapes, &s.label, &mut vec![s.label.clone()])).collect();
+    for (shape, (constraints, one_of)) in shapes.iter_mut().zip(flattened) {
+        if shape.extends.is_empty() { continue; }
+        shape.extends.clear();
+        shape.constraints = Some(constraints);
+        shape.one_of = one_of;
+    }
+}
+
+/// The AST of the shapes, which both ShExC and ShExJ are written from. CURIEs stay
+/// prefixed names, as in a parsed schema, under the doc's prefixes and `xsd:`
+fn shex_schema(shapes: &[ShexShape], prefixes: &[(String, String)]) -> anyhow::Result<Schema> {
+    let mut prefixmap = PrefixMap::new();
+    for (prefix, ns) in prefixes.iter() {
+        prefixmap.insert(prefix, &IriS::new_unchecked(ns)).map_err(|e| anyhow::anyhow!("prefix {}: {}", prefix, e))?;
+    }
+    // datatypes are written as `xsd:` CURIEs, so declare xsd if any are
+    let uses_xsd = shapes.iter()
+        .flat_map(|s| s.constraints.iter().flatten().chain(s.one_of.iter().flatten().flatten()))
+        .any(|c| c.datatypes().iter().any(|dt| dt.starts_with(XSD)));
+    if uses_xsd && !prefixmap.map.contains_key("xsd") {
+        prefixmap.insert("xsd", &IriS::new_unchecked(XSD)).map_err(|e| anyhow::anyhow!("prefix xsd: {}", e))?;
+    }
+    let decls = shapes.iter().map(shex_decl).collect::<anyhow::Result<Vec<_>>>()?;
+    let start = shapes.iter().find(|s| s.start).map(|s| shape_ref(&s.label));
+    // the schema isn't read from anywhere, so has no source IRI
+    Ok(Schema::new(&IriS::new_unchecked(""))
+        .with_prefixmap(Some(prefixmap))
+        .with_start(start)
+        .with_shapes(Some(decls)))
+}
+
+/// A class's declaration: the shape, ANDed with an identified class's node kind and its class expressions
+fn shex_decl(shape: &ShexShape) -> anyhow::Result<ShapeDecl> {
+    let label = ShapeExprLabel::iri_unchecked(&shape.label);
+    if shape.external {
+        return Ok(ShapeDecl::new(label, ShapeExpr::external(), shape.is_abstract));
+    }
+    let mut tes = shape.constraints.iter().flatten().map(shex_triple_constraint).collect::<anyhow::Result<Vec<_>>>()?;
+    for group in shape.one_of.iter() {
+        let mut branches = Vec::new();
+        for alt in group.iter() {
+            branches.extend(each_of(alt.iter().map(shex_triple_constraint).collect::<anyhow::Result<Vec<_>>>()?));
+        }
+        tes.push(TripleExpr::one_of(branches));
     }
+    let mut body = Shape::new(shape.closed.then_some(true), None, each_of(tes))
+        .with_sem_acts(shex_sem_acts(&shape.sem_acts))
+        .with_annotations(shex_annotations(&shape.title, &shape.description));
+    if !shape.extends.is_empty() {
+        body = body.with_extends(Some(shape.extends.iter().map(|e| ShapeExprLabel::iri_unchecked(e)).collect()));
+    }
+    let mut conjuncts = Vec::new();
+    if shape.identified {
+        conjuncts.push(ShapeExpr::node_constraint(NodeConstraint::new().with_node_kind(NodeKind::Iri)));
+    }
+    conjuncts.extend(shape.junctions.iter().map(|expr| shex_junction(expr, expr.ranges().iter().map(|l| shape_ref(l)).collect())));
+    conjuncts.extend(shape.none_of.iter().map(|l| ShapeExpr::shape_not(shape_ref(l))));
+    let shape_expr = if conjuncts.is_empty() {
+        ShapeExpr::shape(body)
+    } else {
+        conjuncts.push(ShapeExpr::shape(body));
+        ShapeExpr::and(conjuncts)
+    };
+    Ok(ShapeDecl::new(label, shape_expr, shape.is_abstract))
+}
+
+fn shex_triple_constraint(c: &ShexConstraint) -> anyhow::Result<TripleExpr> {
+    let value_expr = if !c.languages.is_empty() {
+        let values = c.languages.iter().map(|l| -> anyhow::Result<ValueSetValue> {
+            let lang = |tag: &str| Lang::new(tag).with_context(|| format!("language tag {}", l));
+            Ok(match l.strip_suffix('~') {
+                Some("") => ValueSetValue::LanguageStemRange { stem: LangOrWildcard::Wildcard, exclusions: Some(Vec::new()) },
+                Some(stem) => ValueSetValue::language_stem(lang(stem)?),
+                None => ValueSetValue::language(lang(l)?),
+            })
+        }).collect::<anyhow::Result<Vec<_>>>()?;
+        Some(ShapeExpr::node_constraint(NodeConstraint::new().with_values(values)))
+    } else if !c.values.is_empty() || !c.iri_stems.is_empty() {
+        // IRIs are IRIs in value sets; anything else is a string
+        let values = c.values.iter().map(|v| {
+            if v.starts_with("http://") || v.starts_with("https://") {
+                ValueSetValue::iri(IriRef::iri(IriS::new_unchecked(v)))
+            } else {
+                ValueSetValue::string_literal(v, None)
+            }
+        }).chain(c.iri_stems.iter().map(shex_iri_stem)).collect();
+        Some(ShapeExpr::node_constraint(NodeConstraint::new().with_values(values)))
+    } else if let Some((expr, refs)) = &c.range_expr {
+        let atoms = refs.iter().map(|r| match r {
+            RangeRef::Shape(label) => shape_ref(label),
+            RangeRef::Datatype(dt) => ShapeExpr::node_constraint(NodeConstraint::new().with_datatype(datatype(dt))),
+            RangeRef::NodeKind(kind) => ShapeExpr::node_constraint(NodeConstraint::new().with_node_kind(kind.clone())),
+            RangeRef::Any => ShapeExpr::node_constraint(NodeConstraint::new()),
+        }).collect();
+        Some(shex_junction(expr, atoms))
+    } else if let Some(label) = &c.shape {
+        Some(shape_ref(label))
+    } else {
+        // a node kind or datatype and facets, or any value
+        let mut nc = NodeConstraint::new();
+        if let Some(kind) = &c.node_kind { nc = nc.with_node_kind(kind.clone()); }
+        if let Some(dt) = &c.datatype { nc = nc.with_datatype(datatype(dt)); }
+        if let Some(pattern) = &c.pattern { nc = nc.with_pattern(pattern); }
+        if let Some(n) = &c.min_inclusive { nc = nc.with_min_inclusive(numeric_literal(n)); }
+        if let Some(n) = &c.max_inclusive { nc = nc.with_max_inclusive(numeric_literal(n)); }
+        (nc != NodeConstraint::new()).then(|| ShapeExpr::node_constraint(nc))
+    };
+    // `{1,1}` is left unsaid, and ShExJ writes an unbounded max as -1
+    let (min, max) = match (c.min, c.max) {
+        (1, Some(1)) => (None, None),
+        (min, max) => (Some(min as i32), Some(max.map_or(-1, |n| n as i32))),
+    };
+    Ok(TripleExpr::triple_constraint(None, None, iri_ref(&c.predicate, &c.predicate_iri), value_expr, min, max)
+        .with_sem_acts(shex_sem_acts(&c.sem_acts))
+        .with_annotations(shex_annotations(&c.title, &c.description)))
+}
+
+/// A single triple expression stands alone; several are wrapped in an EachOf
+fn each_of(mut tes: Vec<TripleExpr>) -> Option<TripleExpr> {
+    match tes.len() {
+        0 => None,
+        1 => tes.pop(),
+        _ => Some(TripleExpr::each_of(tes)),
+    }
+}
+
+/// Shape expressions combined as a range expression requires: ShEx has no
+/// exclusive or, so exactly one of them is each one ANDed with NOT the others
+fn shex_junction(expr: &RangeExpr, mut atoms: Vec<ShapeExpr>) -> ShapeExpr {
+    if atoms.len() == 1 {
+        return atoms.remove(0);
+    }
+    match expr {
+        RangeExpr::AllOf(_) => ShapeExpr::and(atoms),
+        RangeExpr::AnyOf(_) => ShapeExpr::or(atoms),
+        RangeExpr::ExactlyOneOf(_) => ShapeExpr::or(atoms.iter().enumerate().map(|(i, atom)| {
+            let others = atoms.iter().enumerate().filter(|(j, _)| *j != i).map(|(_, other)| ShapeExpr::shape_not(other.clone()));
+            ShapeExpr::and(std::iter::once(atom.clone()).chain(others).collect())
+        }).collect()),
+    }
+}
+
+fn shape_ref(label: &str) -> ShapeExpr {
+    ShapeExpr::shape_ref(ShapeExprLabel::iri_unchecked(label))
+}
+
+/// An IRI as it's written: a CURIE stays a prefixed name, `<...>` an IRI
+fn iri_ref(written: &str, iri: &str) -> IriRef {
+    match written.split_once(':') {
+        Some((prefix, local)) if !written.starts_with('<') => IriRef::prefixed(prefix, local),
+        _ => IriRef::iri(IriS::new_unchecked(iri)),
+    }
+}
+
+/// A datatype, with `xsd:` the one prefix assumed
+fn datatype(dt: &str) -> IriRef {
+    match dt.strip_prefix(XSD) {
+        Some(local) => IriRef::prefixed("xsd", local),
+        None => IriRef::iri(IriS::new_unchecked(dt)),
+    }
+}
+
+fn numeric_literal(n: &serde_json::Number) -> NumericLiteral {
+    match n.as_i64() {
+        Some(i) => NumericLiteral::integer(i as isize),
+        None => NumericLiteral::double(n.as_f64().unwrap_or_default()),
+    }
+}
+
+/// A value set member for an IRI stem; the empty stem is the wildcard
+fn shex_iri_stem(s: &IriStem) -> ValueSetValue {
+    let iri = |i: &str| IriRef::iri(IriS::new_unchecked(i));
+    if s.exclusions.is_empty() && !s.stem.is_empty() {
+        return ValueSetValue::IriStem { stem: iri(&s.stem) };
+    }
+    let stem = if s.stem.is_empty() { IriRefOrWildcard::Wildcard } else { IriRefOrWildcard::IriRef(iri(&s.stem)) };
+    let exclusions = s.exclusions.iter().map(|e| match e.strip_suffix('~') {
+        Some(stem) => IriExclusion::IriStem(iri(stem)),
+        None => IriExclusion::Iri(iri(e)),
+    }).collect();
+    ValueSetValue::IriStemRange { stem, exclusions: Some(exclusions) }
+}
+
+const XSD: &str = "http://www.w3.org/2001/XMLSchema#";
+const SHEX: &str = "http://www.w3.org/ns/shex#";
+
+const RDFS_LABEL: &str = "http://www.w3.org/2000/01/rdf-schema#label";
+const RDFS_COMMENT: &str = "http://www.w3.org/2000/01/rdf-schema#comment";
+
+/// `title` and `description` as rdfs:label and rdfs:comment annotations, if there are any
+fn shex_annotations(title: &Option<String>, description: &Option<String>) -> Option<Vec<Annotation>> {
+    let annotations: Vec<Annotation> = [(RDFS_LABEL, title), (RDFS_COMMENT, description)].iter()
+        .filter_map(|(predicate, text)| Some(Annotation::new(IriRef::iri(IriS::new_unchecked(predicate)), ObjectValue::str(text.as_ref()?))))
+        .collect();
+    (!annotations.is_empty()).then_some(annotations)
+}
+
+fn shex_sem_acts(sem_acts: &[SemAct]) -> Option<Vec<shex_ast::SemAct>> {
+    let acts: Vec<shex_ast::SemAct> = sem_acts.iter()
+        .map(|a| shex_ast::SemAct::new(IriRef::iri(IriS::new_unchecked(&a.name)), a.code.clone()))
+        .collect();
+    (!acts.is_empty()).then_some(acts)
+}
+
+/// The schema as ShExJ has it, with its prefixed names expanded as ShExJ has no prefixes
+fn shexj_schema(schema: &Schema) -> anyhow::Result<Schema> {
+    let prefixmap = schema.prefixmap();
+    let decls = schema.shapes().unwrap_or_default().iter()
+        .map(|decl| decl.deref(&None, &prefixmap))
+        .collect::<Result<Vec<_>, _>>()
+        .context("expand prefixed names")?;
+    Ok(schema.clone().with_prefixmap(None).with_shapes(Some(decls)))
+}
+
+/// ShExC for a schema: its PREFIX declarations, START, then one declaration per shape.
+/// rudof's compact writer can't write OneOf, stems or language tags yet, and drops
+/// ABSTRACT, so the AST is written here. Constraints on the `recommended` (shape, predicate)
+/// pairs get a comment saying so, as ShEx can't enforce them.
+fn write_shexc(schema: &Schema, recommended: &[(String, String)], style: &ShexcStyle) -> String {
+    let mut out = String::new();
+    let prefixmap = schema.prefixmap().unwrap_or_default();
+    let mut prefixes: Vec<_> = prefixmap.map.iter().collect();
+    if style.sort_prefixes {
+        prefixes.sort_by(|a, b| a.0.cmp(b.0));
+    }
+    for (prefix, ns) in prefixes {
+        out.push_str(&format!("PREFIX {}: <{}>\n", prefix, ns.as_str()));
+    }
+    out.push('\n');
+    if let Some(start) = schema.start() {
+        out.push_str(&format!("start = {}\n\n", shexc_expr(&start)));
+    }
+    for decl in schema.shapes().unwrap_or_default().iter() {
+        out.push_str(&shexc_decl(decl, recommended, style));
+        out.push_str("\n\n");
+    }
+    out
+}
+
+/// The (shape, predicate) pairs of `recommended` slots, as ShExC writes them
+fn recommended(shapes: &[ShexShape]) -> Vec<(String, String)> {
+    shapes.iter()
+        .flat_map(|s| s.constraints.iter().flatten().filter(|c| c.recommended).map(|c| (s.label.clone(), c.predicate.clone())))
+        .collect()
+}
+
+/// A shape declaration, e.g. `<A> IRI AND ( @<B> OR @<C> ) AND EXTENDS @<D> {`, then
+/// its triple constraints one per line, separated rather than terminated by `;`.
+/// A compact style writes them on the declaration's line, if that fits and has no
+/// `# recommended` comments, which would run to its end.
+fn shexc_decl(decl: &ShapeDecl, recommended: &[(String, String)], style: &ShexcStyle) -> String {
+    let label = shexc_label(&decl.id);
+    let mut out = if decl.is_abstract { format!("ABSTRACT {}", label) } else { label };
+    let conjuncts: Vec<&ShapeExpr> = match &decl.shape_expr {
+        ShapeExpr::ShapeAnd { shape_exprs } => shape_exprs.iter().map(|c| &c.se).collect(),
+        se => vec![se],
+    };
+    let (shape, others) = match conjuncts.split_last() {
+        Some((ShapeExpr::Shape(s), others)) => (Some(s), others),
+        _ => (None, &conjuncts[..]),
+    };
+    let mut head: Vec<String> = others.iter().map(|c| shexc_operand(c)).collect();
+    // an empty shape matches any node, so it needn't follow other conjuncts
+    let Some(shape) = shape.filter(|s| head.is_empty() || **s != Shape::default()) else {
+        return format!("{} {}", out, head.join(" AND "));
+    };
+    head.push(format!("{}{{", shexc_qualifiers(shape)));
+    out.push_str(&format!(" {}", head.join(" AND ")));
+    let tes: Vec<&TripleExpr> = match shape.expression.as_ref().map(|e| &e.te) {
+        Some(TripleExpr::EachOf { expressions, .. }) => expressions.iter().map(|e| &e.te).collect(),
+        Some(te) => vec![te],
+        None => Vec::new(),
+    };
+    let shape_label = decl.id.to_string();
+    let notes: Vec<&str> = tes.iter().map(|te| match te {
+        TripleExpr::TripleConstraint { predicate, .. } if recommended.contains(&(shape_label.clone(), shexc_iri_ref(predicate))) => " # recommended",
+        _ => "",
+    }).collect();
+    let tes: Vec<String> = tes.iter().map(|te| shexc_triple_expr(te)).collect();
+    let close = format!("}}{}{}", shexc_annotations(&shape.annotations), shexc_sem_acts(&shape.sem_acts));
+    if style.compact && notes.iter().all(|n| n.is_empty()) {
+        let body = if tes.is_empty() { " ".to_string() } else { format!(" {} ", tes.join(" ; ")) };
+        let line = format!("{}{}{}", out, body, close);
+        if style.max_width.is_none_or(|width| line.chars().count() <= width) {
+            return line;
+        }
+    }
+    out.push('\n');
+    for (i, (te, note)) in tes.iter().zip(notes).enumerate() {
+        let separator = if i + 1 < tes.len() { " ;" } else { "" };
+        out.push_str(&format!("{}{}{}{}\n", " ".repeat(style.indent), te, separator, note));
+    }
+    out.push_str(&close);
+    out
+}
+
+/// EXTENDS and CLOSED, which precede a shape's `{`
+fn shexc_qualifiers(shape: &Shape) -> String {
+    shape.extends.iter().flatten().map(|e| format!("EXTENDS @{} ", shexc_label(e)))
+        .chain(shape.is_closed().then(|| "CLOSED ".to_string()))
+        .collect()
+}
+
+fn shexc_triple_expr(te: &TripleExpr) -> String {
+    match te {
+        TripleExpr::EachOf { expressions, .. } => expressions.iter().map(|e| shexc_triple_expr(&e.te)).collect::<Vec<_>>().join(" ; "),
+        TripleExpr::OneOf { expressions, .. } => format!("( {} )", expressions.iter().map(|e| shexc_triple_expr(&e.te)).collect::<Vec<_>>().join(" | ")),
+        TripleExpr::TripleConstraint { predicate, value_expr, min, max, sem_acts, annotations, .. } => format!("{} {}{}{}{}",
+            shexc_iri_ref(predicate),
+            value_expr.as_deref().map_or(".".to_string(), shexc_operand),
+            shexc_cardinality(*min, *max),
+            shexc_annotations(annotations),
+            shexc_sem_acts(sem_acts)),
+        TripleExpr::TripleExprRef(label) => format!("&{}", label),
+    }
+}
+
+fn shexc_cardinality(min: Option<i32>, max: Option<i32>) -> String {
+    match (min.unwrap_or(1), max.unwrap_or(1)) {
+        (1, 1) => "".to_string(),
+        (0, 1) => " ?".to_string(),
+        (0, -1) => " *".to_string(),
+        (1, -1) => " +".to_string(),
+        (m, -1) => format!(" {{{},}}", m),
+        (m, n) if m == n => format!(" {{{}}}", m),
+        (m, n) => format!(" {{{},{}}}", m, n),
+    }
+}
+
+/// A shape expression as an operand, with AND and OR parenthesized
+fn shexc_operand(se: &ShapeExpr) -> String {
+    match se {
+        ShapeExpr::ShapeAnd { .. } | ShapeExpr::ShapeOr { .. } => format!("( {} )", shexc_expr(se)),
+        _ => shexc_expr(se),
+    }
+}
+
+fn shexc_expr(se: &ShapeExpr) -> String {
+    match se {
+        ShapeExpr::ShapeAnd { shape_exprs } => shape_exprs.iter().map(|e| shexc_operand(&e.se)).collect::<Vec<_>>().join(" AND "),
+        ShapeExpr::ShapeOr { shape_exprs } => shape_exprs.iter().map(|e| shexc_operand(&e.se)).collect::<Vec<_>>().join(" OR "),
+        ShapeExpr::ShapeNot { shape_expr } => format!("NOT {}", shexc_operand(&shape_expr.se)),
+        ShapeExpr::NodeConstraint(nc) => shexc_node_constraint(nc),
+        ShapeExpr::Shape(s) => format!("{}{{ {} }}", shexc_qualifiers(s), s.expression.as_ref().map_or(String::new(), |e| shexc_triple_expr(&e.te))),
+        ShapeExpr::External => "EXTERNAL".to_string(),
+        ShapeExpr::Ref(label) => format!("@{}", shexc_label(label)),
+    }
+}
+
+/// A node kind, datatype, value set and facets, or `.` for any value
+fn shexc_node_constraint(nc: &NodeConstraint) -> String {
+    let kind = nc.node_kind().map(|k| match k {
+        NodeKind::Iri => "IRI",
+        NodeKind::BNode => "BNODE",
+        NodeKind::NonLiteral => "NONLITERAL",
+        NodeKind::Literal => "LITERAL",
+    }.to_string());
+    let values = nc.values().map(|vs| format!("[ {} ]", vs.iter().map(shexc_value).collect::<Vec<_>>().join(" ")));
+    let parts: Vec<String> = kind.into_iter()
+        .chain(nc.datatype().map(|dt| shexc_iri_ref(&dt)))
+        .chain(values)
+        .chain(nc.xs_facet().unwrap_or_default().iter().map(shexc_facet))
+        .collect();
+    if parts.is_empty() { ".".to_string() } else { parts.join(" ") }
+}
+
+fn shexc_value(v: &ValueSetValue) -> String {
+    match v {
+        ValueSetValue::ObjectValue(ObjectValue::IriRef(i)) => shexc_iri_ref(i),
+        ValueSetValue::ObjectValue(ObjectValue::Literal(l)) => crate::convert::string_literal(&l.lexical_form()),
+        ValueSetValue::IriStem { stem } => format!("{}~", shexc_iri_ref(stem)),
+        ValueSetValue::IriStemRange { stem, exclusions } => {
+            let mut out = match stem {
+                IriRefOrWildcard::IriRef(i) => format!("{}~", shexc_iri_ref(i)),
+                IriRefOrWildcard::Wildcard => ".".to_string(),
+            };
+            for e in exclusions.iter().flatten() {
+                out.push_str(&match e {
+                    IriExclusion::Iri(i) => format!(" - {}", shexc_iri_ref(i)),
+                    IriExclusion::IriStem(i) => format!(" - {}~", shexc_iri_ref(i)),
+                });
+            }
+            out
+        }
+        ValueSetValue::Language { language_tag } => format!("@{}", language_tag),
+        ValueSetValue::LanguageStem { stem } | ValueSetValue::LanguageStemRange { stem, .. } => match stem {
+            LangOrWildcard::Lang(l) => format!("@{}~", l),
+            LangOrWildcard::Wildcard => "@~".to_string(),
+        },
+        // LinkML has no literal stems, so none are built
+        ValueSetValue::LiteralStem { .. } | ValueSetValue::LiteralStemRange { .. } => String::new(),
+    }
+}
+
+fn shexc_facet(facet: &XsFacet) -> String {
+    match facet {
+        XsFacet::StringFacet(StringFacet::Length(n)) => format!("LENGTH {}", n),
+        XsFacet::StringFacet(StringFacet::MinLength(n)) => format!("MINLENGTH {}", n),
+        XsFacet::StringFacet(StringFacet::MaxLength(n)) => format!("MAXLENGTH {}", n),
+        XsFacet::StringFacet(StringFacet::Pattern(p)) => format!("/{}/{}", crate::convert::shex_regex(p.regex()), p.flags().unwrap_or("")),
+        XsFacet::NumericFacet(NumericFacet::MinInclusive(n)) => format!("MININCLUSIVE {}", n),
+        XsFacet::NumericFacet(NumericFacet::MinExclusive(n)) => format!("MINEXCLUSIVE {}", n),
+        XsFacet::NumericFacet(NumericFacet::MaxInclusive(n)) => format!("MAXINCLUSIVE {}", n),
+        XsFacet::NumericFacet(NumericFacet::MaxExclusive(n)) => format!("MAXEXCLUSIVE {}", n),
+        XsFacet::NumericFacet(NumericFacet::TotalDigits(n)) => format!("TOTALDIGITS {}", n),
+        XsFacet::NumericFacet(NumericFacet::FractionDigits(n)) => format!("FRACTIONDIGITS {}", n),
+    }
+}
+
+/// A prefixed name as it is, else an IRI in angle brackets
+fn shexc_iri_ref(iri: &IriRef) -> String {
+    match iri {
+        IriRef::Iri(iri) => format!("<{}>", iri.as_str()),
+        IriRef::Prefixed { prefix, local } => format!("{}:{}", prefix, local),
+    }
+}
+
+fn shexc_label(label: &ShapeExprLabel) -> String {
+    match label {
+        ShapeExprLabel::IriRef { value } => shexc_iri_ref(value),
+        other => other.to_string(),
+    }
+}
+
+/// Annotations, e.g. ` // <http://www.w3.org/2000/01/rdf-schema#label> "Name"`
+fn shexc_annotations(annotations: &Option<Vec<Annotation>>) -> String {
+    annotations.iter().flatten().map(|a| {
+        let object = match a.object() {
+            ObjectValue::IriRef(i) => shexc_iri_ref(&i),
+            ObjectValue::Literal(l) => crate::convert::string_literal(&l.lexical_form()),
+        };
+        format!(" // {} {}", shexc_iri_ref(&a.predicate()), object)
+    }).collect()
+}
 
-    Ok(out)
+/// Semantic actions, e.g. ` %<http://example.org/js>{ print(o) %}`, escaping `%` and `\` in the code
+fn shexc_sem_acts(sem_acts: &Option<Vec<shex_ast::SemAct>>) -> String {
+    sem_acts.iter().flatten().map(|a| match a.code() {
+        Some(code) => format!(" %{}{{{}%}}", shexc_iri_ref(&a.name()), code.replace('\\', "\\\\").replace('%', "\\%")),
+        None => format!(" %{}%", shexc_iri_ref(&a.name())),
+    }).collect()
 }
@@ -1,38 +1,399 @@
-use clap::Parser;
+use clap::{Parser, ValueEnum};
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use anyhow::Context;
-use shex2linkml::{convert, convert::*, linkml_to_shex, linkml_to_shex::*};
-use iri_s::IriS;
+use shex2linkml::{convert, convert::{Prefixes, ShapeInfo}, graphql_to_linkml, json_schema_to_linkml, linkml_to_shacl, linkml_to_shex, naming, openapi_to_linkml, shacl_to_linkml, shape_map, wikidata, xsd_to_linkml};
+
+/// Schema languages we can read into the intermediate shape model
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum InputFormat {
+    Shex,
+    Linkml,
+    Jsonschema,
+    Shacl,
+    Graphql,
+    Xsd,
+    Openapi,
+    /// Wikidata EntitySchema: INPUT is an id like `E42` or its URL
+    Wikidata,
+}
+
+/// Schema languages we can emit from the intermediate shape model
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum OutputFormat {
+    Linkml,
+    Jsonschema,
+    Shex,
+    Shacl,
+    Owl,
+    Graphql,
+    Protobuf,
+    Typescript,
+    Pydantic,
+    Rust,
+    Xsd,
+    Bigquery,
+    Rdfs,
+    Sparql,
+    Zod,
+    Kotlin,
+    Skos,
+    Jsonld,
+}
+
+/// Diagram notations we can render from the intermediate shape model
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum DiagramFormat {
+    Mermaid,
+    Plantuml,
+    Dot,
+}
+
+/// Serialization for LinkML output
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum LinkmlFormat {
+    Yaml,
+    Json,
+}
+
+/// Serialization for JSON Schema output
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum JsonschemaFormat {
+    Json,
+    Yaml,
+}
+
+/// How --name-collisions tells apart predicates that share a local name
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum NameCollisions {
+    /// prefix-qualified names, e.g. `foaf_name` and `schema_name`
+    Qualify,
+    /// `name`, `name2`, ...
+    Number,
+    /// one shared name
+    Keep,
+}
+
+/// Case convention for --class-case and --slot-case
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum NameCase {
+    /// as in the source schema
+    Preserve,
+    /// `given_name`
+    Snake,
+    /// `givenName`
+    Camel,
+    /// `GivenName`
+    Pascal,
+}
+
+/// Languages whose reserved words --escape-keywords avoids
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum Keywords {
+    Python,
+    Rust,
+    Kotlin,
+}
+
+/// Draft of JSON Schema output
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum JsonSchemaDraft {
+    #[value(name = "07")]
+    Draft07,
+    #[value(name = "2020-12")]
+    Draft2020_12,
+}
+
+/// Top level of JSON Schema output
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum JsonSchemaRoot {
+    /// `$ref` the root or START shape
+    Start,
+    /// only the definitions
+    Definitions,
+    /// `oneOf` over the top-level shapes, leaving out inline shapes and those others extend
+    OneOf,
+}
+
+/// Whether JSON Schema objects accept undeclared properties
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum AdditionalProperties {
+    /// always
+    Allow,
+    /// never
+    Deny,
+    /// unless the shape is CLOSED
+    FromShex,
+}
+
+/// Syntax for --back-to-shex output
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum ShexFormat {
+    Compact,
+    Json,
+}
 
 #[derive(Parser, Debug)]
-#[command(author, version, about = "Convert between ShEx (compact), LinkML, and JSON Schema using rudof AST")] 
+#[command(author, version, about = "Convert between ShEx (compact), LinkML, and JSON Schema using rudof AST")]
 struct Args {
-    /// Input ShEx (compact) file to convert to LinkML + JSON Schema
+    /// Input schema file (ShEx compact by default, see --from), or an EntitySchema id with --from wikidata
     #[arg(value_name = "INPUT", required = false)]
     input: Option<PathBuf>,
 
+    /// More LinkML schemas converted with INPUT, whose classes may refer to each other's;
+    /// a directory, here or as INPUT, stands for the LinkML files in it
+    #[arg(value_name = "MORE")]
+    more: Vec<PathBuf>,
+
+    /// Format of INPUT
+    #[arg(long, value_enum, default_value_t = InputFormat::Shex)]
+    from: InputFormat,
+
+    /// Optional ShEx shape map naming the shapes to convert; shapes they don't reach are dropped
+    #[arg(long)]
+    shape_map: Option<PathBuf>,
+
+    /// How to name properties whose different predicates share a local name
+    #[arg(long, value_enum, default_value_t = NameCollisions::Qualify)]
+    name_collisions: NameCollisions,
+
+    /// Case of class names
+    #[arg(long, value_enum, default_value_t = NameCase::Preserve)]
+    class_case: NameCase,
+
+    /// Case of slot names
+    #[arg(long, value_enum, default_value_t = NameCase::Preserve)]
+    slot_case: NameCase,
+
+    /// Name classes and slots after their CURIEs (`foaf_name`) rather than local names
+    #[arg(long)]
+    qualify_names: bool,
+
+    /// Append `_` to names that are reserved words in these languages
+    #[arg(long, value_enum)]
+    escape_keywords: Vec<Keywords>,
+
+    /// Prefix map (Turtle @prefix file, JSON-LD context or rudof prefix map) used to write CURIEs,
+    /// alongside the input schema's own declarations
+    #[arg(long)]
+    prefixes: Option<PathBuf>,
+
+    /// Name classes and slots by CURIE and JSON Schema properties by predicate IRI, so names map back losslessly
+    #[arg(long)]
+    iri_names: bool,
+
+    /// Move IRIs to a new namespace, `old-namespace=new-namespace`; may be repeated
+    #[arg(long, value_name = "OLD=NEW")]
+    rewrite: Vec<String>,
+
+    /// YAML or JSON crosswalk of shape and predicate IRIs to class and slot names, used both ways
+    #[arg(long)]
+    names: Option<PathBuf>,
+
+    /// Output format(s) to write; defaults to LinkML and JSON Schema unless only --diagram is given
+    #[arg(long, value_enum)]
+    to: Vec<OutputFormat>,
+
     /// Optional LinkML output path
     #[arg(long)]
     linkml: Option<PathBuf>,
 
+    /// Serialization for LinkML output
+    #[arg(long, value_enum, default_value_t = LinkmlFormat::Yaml)]
+    linkml_format: LinkmlFormat,
+
     /// Optional JSON Schema output path
     #[arg(long)]
     jsonschema: Option<PathBuf>,
 
+    /// Serialization for JSON Schema output
+    #[arg(long, value_enum, default_value_t = JsonschemaFormat::Json)]
+    jsonschema_format: JsonschemaFormat,
+
+    /// Write JSON Schema as a file per shape in this directory, with an index, rather than one document
+    #[arg(long)]
+    jsonschema_dir: Option<PathBuf>,
+
+    /// Write language-tagged strings to JSON Schema as JSON-LD value objects,
+    /// checking `@language` against the tags the property allows
+    #[arg(long)]
+    json_language_tags: bool,
+
+    /// JSON Schema draft to write
+    #[arg(long, value_enum, default_value_t = JsonSchemaDraft::Draft07)]
+    json_schema_draft: JsonSchemaDraft,
+
+    /// `$id` of JSON Schema output; defaults to the LinkML schema's `id` or the ShEx BASE
+    #[arg(long)]
+    schema_id: Option<String>,
+
+    /// What JSON Schema output validates a document against at its top level
+    #[arg(long, value_enum, default_value_t = JsonSchemaRoot::Start)]
+    json_schema_root: JsonSchemaRoot,
+
+    /// Keep class, predicate and datatype IRIs in JSON Schema `x-rdf-*` keywords
+    #[arg(long)]
+    json_rdf_keywords: bool,
+
+    /// Title every JSON Schema definition and property and give value sets examples, for editor completions
+    #[arg(long)]
+    json_schema_annotate: bool,
+
+    /// Write dates, times, durations and URIs to JSON Schema without `format`, for validators that assert it
+    #[arg(long)]
+    json_plain_strings: bool,
+
+    /// Whether JSON Schema objects accept properties their shapes don't declare
+    #[arg(long, value_enum, default_value_t = AdditionalProperties::FromShex)]
+    additional_properties: AdditionalProperties,
+
+    /// Optional ShEx compact output path
+    #[arg(long)]
+    shex: Option<PathBuf>,
+
+    /// Optional SHACL (Turtle) output path
+    #[arg(long)]
+    shacl: Option<PathBuf>,
+
+    /// Optional OWL ontology (Turtle) output path
+    #[arg(long)]
+    owl: Option<PathBuf>,
+
+    /// Optional GraphQL SDL output path
+    #[arg(long)]
+    graphql: Option<PathBuf>,
+
+    /// Optional protobuf (proto3) output path
+    #[arg(long)]
+    protobuf: Option<PathBuf>,
+
+    /// Optional TypeScript declarations (.d.ts) output path
+    #[arg(long)]
+    typescript: Option<PathBuf>,
+
+    /// Optional Pydantic (Python) models output path
+    #[arg(long)]
+    pydantic: Option<PathBuf>,
+
+    /// Optional Rust structs output path
+    #[arg(long)]
+    rust: Option<PathBuf>,
+
+    /// Optional XML Schema (XSD) output path
+    #[arg(long)]
+    xsd: Option<PathBuf>,
+
+    /// Optional BigQuery table schema (JSON) output path
+    #[arg(long)]
+    bigquery: Option<PathBuf>,
+
+    /// Optional RDFS vocabulary (Turtle) output path
+    #[arg(long)]
+    rdfs: Option<PathBuf>,
+
+    /// Optional directory for per-shape SPARQL queries (`<Shape>.rq`)
+    #[arg(long)]
+    sparql: Option<PathBuf>,
+
+    /// Write SPARQL CONSTRUCT rather than SELECT queries
+    #[arg(long)]
+    sparql_construct: bool,
+
+    /// Optional Zod schemas (TypeScript) output path
+    #[arg(long)]
+    zod: Option<PathBuf>,
+
+    /// Optional Kotlin data classes output path
+    #[arg(long)]
+    kotlin: Option<PathBuf>,
+
+    /// Optional SKOS concept schemes (Turtle) output path, one per value set
+    #[arg(long)]
+    skos: Option<PathBuf>,
+
+    /// Optional directory for per-shape JSON-LD frames (`<Shape>.jsonld`)
+    #[arg(long)]
+    jsonld: Option<PathBuf>,
+
+    /// Diagram(s) to write alongside the schema outputs
+    #[arg(long, value_enum)]
+    diagram: Vec<DiagramFormat>,
+
+    /// Group diagram classes by the namespace of their shape IRIs (PlantUML and DOT)
+    #[arg(long)]
+    cluster_namespaces: bool,
+
     /// Optional back-conversion: convert LinkML YAML back to ShEx compact and write here
     #[arg(long)]
     back_to_shex: Option<PathBuf>,
+
+    /// Syntax for --back-to-shex output: ShEx compact or ShExJ
+    #[arg(long, value_enum, default_value_t = ShexFormat::Compact)]
+    shex_format: ShexFormat,
+
+    /// With --back-to-shex, copy inherited slots into each shape instead of writing EXTENDS
+    #[arg(long)]
+    flatten: bool,
+
+    /// With --back-to-shex, write every shape CLOSED, not just classes annotated `closed`
+    #[arg(long)]
+    closed: bool,
+
+    /// With --back-to-shex, spaces before each triple constraint
+    #[arg(long, default_value_t = 2)]
+    shex_indent: usize,
+
+    /// With --back-to-shex, write each shape on one line where it fits
+    #[arg(long)]
+    shex_compact: bool,
+
+    /// With --back-to-shex, declare prefixes in alphabetical order
+    #[arg(long)]
+    sort_prefixes: bool,
+
+    /// With --shex-compact, the widest line a shape may take before its constraints go one per line
+    #[arg(long)]
+    shex_max_width: Option<usize>,
+
+    /// Write shapes, properties and prefixes in name order rather than the input's order
+    #[arg(long)]
+    sort: bool,
+
+    /// Fetch LinkML imports given as URLs, and the documents at URLs JSON Schema `$ref`s
+    /// point into; `linkml:types` and local files need no fetching
+    #[arg(long)]
+    fetch_imports: bool,
+
+    /// With several LinkML schemas, write ShEx and JSON Schema beside each one, IMPORTing and
+    /// `$ref`ing the others' for the classes it uses from them, rather than one merged schema
+    #[arg(long)]
+    per_file: bool,
 }
 
 fn main() -> anyhow::Result<()> {
     let args = Args::parse();
+    let names = match &args.names {
+        Some(path) => naming::read_name_map(path).with_context(|| format!("reading name map {}", path.display()))?,
+        None => naming::NameMap::default(),
+    };
 
     if let Some(linkml_in) = args.back_to_shex {
         // The user asked only for LinkML -> ShEx conversion
         let l = fs::read_to_string(&linkml_in).context("reading LinkML")?;
-        let shex = linkml_to_shex::linkml_yaml_to_shex(&l)?;
-        let out = linkml_in.with_extension("shex");
+        let l = linkml_to_shacl::resolve_imports(&l, linkml_in.parent(), args.fetch_imports)?;
+        let (syntax, ext) = match args.shex_format {
+            ShexFormat::Compact => (linkml_to_shex::ShexSyntax::Compact, "shex"),
+            ShexFormat::Json => (linkml_to_shex::ShexSyntax::Json, "json"),
+        };
+        let style = linkml_to_shex::ShexcStyle {
+            indent: args.shex_indent,
+            compact: args.shex_compact,
+            sort_prefixes: args.sort_prefixes || args.sort,
+            max_width: args.shex_max_width,
+        };
+        let options = linkml_to_shex::ShexOptions { syntax, flatten: args.flatten, closed: args.closed, names, style, sort: args.sort };
+        let shex = linkml_to_shex::linkml_yaml_to_shex_with(&l, &options)?;
+        let out = linkml_in.with_extension(ext);
         fs::write(&out, shex)?;
         println!("Wrote ShEx -> {}", out.display());
         return Ok(());
@@ -40,35 +401,409 @@ fn main() -> anyhow::Result<()> {
 
     let input = match args.input {
         Some(p) => p,
-        None => anyhow::bail!("No input ShEx provided. Use the --help for details."),
+        None => anyhow::bail!("No input schema provided. Use the --help for details."),
     };
 
-    let input_str = fs::read_to_string(&input)?;
+    // A Wikidata EntitySchema is fetched rather than read, and named by its id for output paths;
+    // several LinkML schemas are merged, noting the classes each defines
+    let mut parts: Vec<(PathBuf, Vec<String>)> = Vec::new();
+    let (input, input_str) = match args.from {
+        InputFormat::Wikidata => {
+            let id = wikidata::entity_schema_id(&input.to_string_lossy())?;
+            let text = wikidata::fetch_entity_schema(&id)?;
+            (PathBuf::from(id), text)
+        }
+        InputFormat::Linkml => {
+            let mut texts = Vec::new();
+            for file in linkml_files(std::iter::once(&input).chain(args.more.iter()))? {
+                let text = fs::read_to_string(&file).with_context(|| format!("reading {}", file.display()))?;
+                parts.push((file.clone(), linkml_to_shacl::class_ids(&text)?));
+                texts.push(linkml_to_shacl::resolve_imports(&text, file.parent(), args.fetch_imports)?);
+            }
+            let text = if texts.len() == 1 { texts.remove(0) } else { linkml_to_shacl::merge_linkml(&texts)? };
+            (input, text)
+        }
+        _ => {
+            let text = fs::read_to_string(&input)?;
+            (input, text)
+        }
+    };
 
-    // Parse ShEx compact syntax into AST using rudof's compact parser
-    // The parser types come from `shex_compact` and `shex_ast` crates.
-    let base_iri = iri_s::iris::IriS::from_path(input.as_path()).unwrap(); // _or_else(|e| -> anyhow::bail!(e))
-    let schema: shex_ast::Schema = shex_compact::ShExParser::parse(&input_str, None, &base_iri)
-        .map_err(|e| anyhow::anyhow!("failed to parse ShEx: {:?}", e))?;
+    // Convert the input -> intermediate shape model
+    let (shapes, prefixes) = match args.from {
+        InputFormat::Shex => read_shex(&input, &input_str)?,
+        InputFormat::Linkml => (linkml_to_shacl::shapes_from_linkml(&input_str)?, linkml_to_shacl::prefixes_from_linkml(&input_str)?),
+        InputFormat::Jsonschema => {
+            let doc: serde_json::Value = serde_json::from_str(&input_str).context("parsing JSON Schema")?;
+            let doc = json_schema_to_linkml::resolve_refs(&doc, input.parent(), args.fetch_imports)?;
+            for warning in json_schema_to_linkml::unresolved_ref_warnings(&doc) {
+                eprintln!("warning: {}", warning);
+            }
+            for warning in json_schema_to_linkml::conditional_warnings(&doc) {
+                eprintln!("warning: {}", warning);
+            }
+            (json_schema_to_linkml::shapes_from_json_schema(&doc)?, Prefixes::default())
+        }
+        InputFormat::Shacl => (shacl_to_linkml::shapes_from_shacl(&input_str)?, Prefixes::default()),
+        InputFormat::Graphql => (graphql_to_linkml::shapes_from_graphql(&input_str)?, Prefixes::default()),
+        InputFormat::Xsd => (xsd_to_linkml::shapes_from_xsd(&input_str)?, Prefixes::default()),
+        InputFormat::Openapi => (openapi_to_linkml::shapes_from_openapi(&input_str)?, Prefixes::default()),
+        InputFormat::Wikidata => {
+            let base_iri = iri_s::iris::IriS::new_unchecked(&wikidata::entity_schema_text_url(&input.to_string_lossy())?);
+            parse_shex(&input_str, &base_iri)?
+        }
+    };
 
-    // Convert AST -> intermediate shape model
-    let shapes = convert::shapes_from_rudof_ast(&schema)?;
+    // the IRI the source schema names itself by, which JSON Schema output keeps as its $id
+    let schema_id = match args.from {
+        InputFormat::Linkml => linkml_to_shacl::schema_id(&input_str)?,
+        _ => None,
+    };
 
-    // Build LinkML
-    let linkml = convert::build_linkml_doc(&input, &shapes)?;
+    let mut prefixes = prefixes;
+    if let Some(path) = &args.prefixes {
+        prefixes.merge(convert::read_prefix_file(path).with_context(|| format!("reading prefixes {}", path.display()))?);
+    }
 
-    // Build JSON Schema
-    let json_schema = convert::build_json_schema(&input, &shapes);
+    // the input each shape comes from; those from none of them, i.e. from imports, go with the first
+    let owners: Vec<usize> = shapes.iter().map(|s| parts.iter().position(|(_, ids)| ids.contains(&s.id)).unwrap_or(0)).collect();
 
-    // Write outputs
-    let linkml_path = args.linkml.unwrap_or_else(|| input.with_extension("-linkml.yaml"));
-    let json_path = args.jsonschema.unwrap_or_else(|| input.with_extension("-jsonschema.json"));
+    // Predicates from different namespaces mustn't end up sharing a property name
+    let mut shapes = shapes;
+    let case = |case: NameCase| match case {
+        NameCase::Preserve => naming::NameCase::Preserve,
+        NameCase::Snake => naming::NameCase::Snake,
+        NameCase::Camel => naming::NameCase::Camel,
+        NameCase::Pascal => naming::NameCase::Pascal,
+    };
+    let naming_options = naming::NamingOptions {
+        class_case: case(args.class_case),
+        slot_case: case(args.slot_case),
+        qualify: args.qualify_names,
+        collisions: match args.name_collisions {
+            NameCollisions::Qualify => naming::NameCollisions::Qualify,
+            NameCollisions::Number => naming::NameCollisions::Number,
+            NameCollisions::Keep => naming::NameCollisions::Keep,
+        },
+        escape_keywords: args.escape_keywords.iter().map(|k| match k {
+            Keywords::Python => naming::Keywords::Python,
+            Keywords::Rust => naming::Keywords::Rust,
+            Keywords::Kotlin => naming::Keywords::Kotlin,
+        }).collect(),
+        names,
+        iri_names: args.iri_names,
+    };
+    for (predicate, name) in naming::apply_naming(&mut shapes, &prefixes, &naming_options) {
+        eprintln!("Named <{}> `{}`", predicate, name);
+    }
 
-    fs::write(&linkml_path, linkml)?;
-    fs::write(&json_path, serde_json::to_string_pretty(&json_schema)?)?;
+    // Namespaces move after naming, so name maps refer to the source schema's IRIs
+    let rewrites = args.rewrite.iter().map(|r| convert::parse_rewrite(r)).collect::<anyhow::Result<Vec<_>>>()?;
+    convert::rewrite_namespaces(&mut shapes, &mut prefixes, &rewrites);
 
-    println!("Wrote LinkML -> {}", linkml_path.display());
-    println!("Wrote JSON Schema -> {}", json_path.display());
+    // each input's shapes by their final names, for writing them per file
+    let parts: Vec<(PathBuf, Vec<String>)> = parts.into_iter().enumerate().map(|(i, (file, _))| {
+        let names = shapes.iter().zip(owners.iter()).filter(|(_, owner)| **owner == i).map(|(s, _)| s.name.clone()).collect();
+        (file, names)
+    }).collect();
+    let per_file = args.per_file && parts.len() > 1;
+    let owner = |s: &ShapeInfo| parts.iter().find(|(_, names)| names.contains(&s.name)).map_or(input.as_path(), |(file, _)| file.as_path());
+
+    // A shape map picks the roots; keep those and whatever they reference
+    let mut roots: Vec<String> = Vec::new();
+    if let Some(map_path) = &args.shape_map {
+        for association in shape_map::read_shape_map(map_path)? {
+            let shape = association.find_shape(&shapes).with_context(|| match association.start {
+                true => "shape map names START, but the schema has no start shape".to_string(),
+                false => format!("shape map names unknown shape `{}`", association.shape),
+            })?;
+            if !roots.contains(&shape.name) { roots.push(shape.name.clone()); }
+        }
+    }
+    let mut shapes = if roots.is_empty() { shapes } else { convert::select_shapes(&shapes, &roots)? };
+    if args.sort {
+        convert::sort_schema(&mut shapes, &mut prefixes);
+    }
+
+    // Explicit output paths imply the corresponding format
+    let mut targets = args.to.clone();
+    if args.linkml.is_some() && !targets.contains(&OutputFormat::Linkml) { targets.push(OutputFormat::Linkml); }
+    if (args.jsonschema.is_some() || args.jsonschema_dir.is_some()) && !targets.contains(&OutputFormat::Jsonschema) { targets.push(OutputFormat::Jsonschema); }
+    if args.shex.is_some() && !targets.contains(&OutputFormat::Shex) { targets.push(OutputFormat::Shex); }
+    if args.shacl.is_some() && !targets.contains(&OutputFormat::Shacl) { targets.push(OutputFormat::Shacl); }
+    if args.owl.is_some() && !targets.contains(&OutputFormat::Owl) { targets.push(OutputFormat::Owl); }
+    if args.graphql.is_some() && !targets.contains(&OutputFormat::Graphql) { targets.push(OutputFormat::Graphql); }
+    if args.protobuf.is_some() && !targets.contains(&OutputFormat::Protobuf) { targets.push(OutputFormat::Protobuf); }
+    if args.typescript.is_some() && !targets.contains(&OutputFormat::Typescript) { targets.push(OutputFormat::Typescript); }
+    if args.pydantic.is_some() && !targets.contains(&OutputFormat::Pydantic) { targets.push(OutputFormat::Pydantic); }
+    if args.rust.is_some() && !targets.contains(&OutputFormat::Rust) { targets.push(OutputFormat::Rust); }
+    if args.xsd.is_some() && !targets.contains(&OutputFormat::Xsd) { targets.push(OutputFormat::Xsd); }
+    if args.bigquery.is_some() && !targets.contains(&OutputFormat::Bigquery) { targets.push(OutputFormat::Bigquery); }
+    if args.rdfs.is_some() && !targets.contains(&OutputFormat::Rdfs) { targets.push(OutputFormat::Rdfs); }
+    if args.sparql.is_some() && !targets.contains(&OutputFormat::Sparql) { targets.push(OutputFormat::Sparql); }
+    if args.zod.is_some() && !targets.contains(&OutputFormat::Zod) { targets.push(OutputFormat::Zod); }
+    if args.kotlin.is_some() && !targets.contains(&OutputFormat::Kotlin) { targets.push(OutputFormat::Kotlin); }
+    if args.skos.is_some() && !targets.contains(&OutputFormat::Skos) { targets.push(OutputFormat::Skos); }
+    if args.jsonld.is_some() && !targets.contains(&OutputFormat::Jsonld) { targets.push(OutputFormat::Jsonld); }
+    if targets.is_empty() && args.diagram.is_empty() {
+        targets = vec![OutputFormat::Linkml, OutputFormat::Jsonschema];
+    }
+
+    let json_options = convert::JsonSchemaOptions {
+        roots: roots.clone(),
+        language_tags: args.json_language_tags,
+        draft: match args.json_schema_draft {
+            JsonSchemaDraft::Draft07 => convert::JsonSchemaDraft::Draft07,
+            JsonSchemaDraft::Draft2020_12 => convert::JsonSchemaDraft::Draft2020_12,
+        },
+        id: args.schema_id.clone().or(schema_id).or_else(|| prefixes.base.clone()),
+        root: match args.json_schema_root {
+            JsonSchemaRoot::Start => convert::JsonSchemaRoot::Start,
+            JsonSchemaRoot::Definitions => convert::JsonSchemaRoot::Definitions,
+            JsonSchemaRoot::OneOf => convert::JsonSchemaRoot::OneOf,
+        },
+        rdf_keywords: args.json_rdf_keywords,
+        annotate: args.json_schema_annotate,
+        plain_strings: args.json_plain_strings,
+        additional_properties: match args.additional_properties {
+            AdditionalProperties::Allow => convert::AdditionalProperties::Allow,
+            AdditionalProperties::Deny => convert::AdditionalProperties::Deny,
+            AdditionalProperties::FromShex => convert::AdditionalProperties::FromShex,
+        },
+    };
+
+    let json_ext = match args.jsonschema_format {
+        JsonschemaFormat::Json => "json",
+        JsonschemaFormat::Yaml => "yaml",
+    };
+    let json_suffix = format!("-jsonschema.{}", json_ext);
+
+    for target in targets {
+        match target {
+            OutputFormat::Linkml => {
+                let (linkml, ext) = match args.linkml_format {
+                    LinkmlFormat::Yaml => (convert::build_linkml_doc_with_prefixes(&input, &shapes, &prefixes)?, "-linkml.yaml"),
+                    LinkmlFormat::Json => (convert::build_linkml_json_with_prefixes(&input, &shapes, &prefixes)?, "-linkml.json"),
+                };
+                let linkml_path = args.linkml.clone().unwrap_or_else(|| input.with_extension(ext));
+                fs::write(&linkml_path, linkml)?;
+                println!("Wrote LinkML -> {}", linkml_path.display());
+            }
+            OutputFormat::Jsonschema => {
+                if let Some(json_dir) = &args.jsonschema_dir {
+                    fs::create_dir_all(json_dir)?;
+                    let shapes = if args.iri_names { naming::with_iri_property_names(&shapes) } else { shapes.clone() };
+                    for (name, json_schema) in convert::build_json_schema_bundle(&input, &shapes, &json_options, json_ext) {
+                        fs::write(json_dir.join(name), json_schema_text(&json_schema, args.jsonschema_format)?)?;
+                    }
+                    println!("Wrote JSON Schema -> {}", json_dir.display());
+                } else if per_file {
+                    for (file, names) in parts.iter() {
+                        let part = convert::schema_part(&shapes, names, |s| {
+                            format!("{}#/{}/{}", output_name(owner(s), &json_suffix), json_options.draft.definitions_keyword(), convert::json_pointer_token(&s.name))
+                        });
+                        let part = if args.iri_names { naming::with_iri_property_names(&part) } else { part };
+                        let roots: Vec<String> = roots.iter().filter(|r| names.contains(r)).cloned().collect();
+                        // name each file's $id after it, so the relative refs between files resolve against it
+                        let json_path = file.with_extension(&json_suffix);
+                        let id = convert::sibling_iri(json_options.id.as_deref().unwrap_or(convert::DEFAULT_JSON_SCHEMA_ID), &output_name(file, &json_suffix));
+                        let json_schema = convert::build_json_schema_with(file, &part, &convert::JsonSchemaOptions { roots, id: Some(id), ..json_options.clone() });
+                        fs::write(&json_path, json_schema_text(&json_schema, args.jsonschema_format)?)?;
+                        println!("Wrote JSON Schema -> {}", json_path.display());
+                    }
+                } else {
+                    let json_schema = if args.iri_names {
+                        convert::build_json_schema_with(&input, &naming::with_iri_property_names(&shapes), &json_options)
+                    } else {
+                        convert::build_json_schema_with(&input, &shapes, &json_options)
+                    };
+                    let json_path = args.jsonschema.clone().unwrap_or_else(|| input.with_extension(&json_suffix));
+                    fs::write(&json_path, json_schema_text(&json_schema, args.jsonschema_format)?)?;
+                    println!("Wrote JSON Schema -> {}", json_path.display());
+                }
+            }
+            OutputFormat::Shex if per_file => {
+                for (file, names) in parts.iter() {
+                    let part = convert::schema_part(&shapes, names, |s| output_name(owner(s), "-shex.shex"));
+                    let (own, elsewhere): (Vec<ShapeInfo>, Vec<ShapeInfo>) = part.into_iter().partition(|s| names.contains(&s.name));
+                    let mut imports: Vec<String> = Vec::new();
+                    for s in elsewhere {
+                        if !imports.contains(&s.id) { imports.push(s.id); }
+                    }
+                    let shex_path = file.with_extension("-shex.shex");
+                    fs::write(&shex_path, convert::build_shex_with_imports(&own, &prefixes, &imports))?;
+                    println!("Wrote ShEx -> {}", shex_path.display());
+                }
+            }
+            OutputFormat::Shex => {
+                let shex = convert::build_shex_with_prefixes(&shapes, &prefixes);
+                let shex_path = args.shex.clone().unwrap_or_else(|| input.with_extension("-shex.shex"));
+                fs::write(&shex_path, shex)?;
+                println!("Wrote ShEx -> {}", shex_path.display());
+            }
+            OutputFormat::Shacl => {
+                let shacl = convert::build_shacl_with_prefixes(&shapes, &prefixes);
+                let shacl_path = args.shacl.clone().unwrap_or_else(|| input.with_extension("-shacl.ttl"));
+                fs::write(&shacl_path, shacl)?;
+                println!("Wrote SHACL -> {}", shacl_path.display());
+            }
+            OutputFormat::Owl => {
+                let owl = convert::build_owl_with_prefixes(&shapes, &prefixes);
+                let owl_path = args.owl.clone().unwrap_or_else(|| input.with_extension("-owl.ttl"));
+                fs::write(&owl_path, owl)?;
+                println!("Wrote OWL -> {}", owl_path.display());
+            }
+            OutputFormat::Graphql => {
+                let graphql = convert::build_graphql(&shapes);
+                let graphql_path = args.graphql.clone().unwrap_or_else(|| input.with_extension("-graphql.graphql"));
+                fs::write(&graphql_path, graphql)?;
+                println!("Wrote GraphQL -> {}", graphql_path.display());
+            }
+            OutputFormat::Protobuf => {
+                let proto = convert::build_protobuf(&shapes);
+                let proto_path = args.protobuf.clone().unwrap_or_else(|| input.with_extension("-protobuf.proto"));
+                fs::write(&proto_path, proto)?;
+                println!("Wrote protobuf -> {}", proto_path.display());
+            }
+            OutputFormat::Typescript => {
+                let ts = convert::build_typescript(&shapes);
+                let ts_path = args.typescript.clone().unwrap_or_else(|| input.with_extension("-typescript.d.ts"));
+                fs::write(&ts_path, ts)?;
+                println!("Wrote TypeScript -> {}", ts_path.display());
+            }
+            OutputFormat::Pydantic => {
+                let py = convert::build_pydantic(&shapes);
+                let py_path = args.pydantic.clone().unwrap_or_else(|| input.with_extension("-pydantic.py"));
+                fs::write(&py_path, py)?;
+                println!("Wrote Pydantic -> {}", py_path.display());
+            }
+            OutputFormat::Rust => {
+                let rs = convert::build_rust(&shapes);
+                let rs_path = args.rust.clone().unwrap_or_else(|| input.with_extension("-structs.rs"));
+                fs::write(&rs_path, rs)?;
+                println!("Wrote Rust -> {}", rs_path.display());
+            }
+            OutputFormat::Xsd => {
+                let xsd = convert::build_xsd(&shapes);
+                let xsd_path = args.xsd.clone().unwrap_or_else(|| input.with_extension("-xsd.xsd"));
+                fs::write(&xsd_path, xsd)?;
+                println!("Wrote XSD -> {}", xsd_path.display());
+            }
+            OutputFormat::Bigquery => {
+                let bq = convert::build_bigquery(&shapes);
+                let bq_path = args.bigquery.clone().unwrap_or_else(|| input.with_extension("-bigquery.json"));
+                fs::write(&bq_path, serde_json::to_string_pretty(&bq)?)?;
+                println!("Wrote BigQuery schema -> {}", bq_path.display());
+            }
+            OutputFormat::Rdfs => {
+                let rdfs = convert::build_rdfs_with_prefixes(&shapes, &prefixes);
+                let rdfs_path = args.rdfs.clone().unwrap_or_else(|| input.with_extension("-rdfs.ttl"));
+                fs::write(&rdfs_path, rdfs)?;
+                println!("Wrote RDFS -> {}", rdfs_path.display());
+            }
+            OutputFormat::Sparql => {
+                let sparql_dir = args.sparql.clone().unwrap_or_else(|| input.with_extension("-sparql"));
+                fs::create_dir_all(&sparql_dir)?;
+                for (name, query) in convert::build_sparql_with_prefixes(&shapes, args.sparql_construct, &prefixes) {
+                    fs::write(sparql_dir.join(format!("{}.rq", name)), query)?;
+                }
+                println!("Wrote SPARQL -> {}", sparql_dir.display());
+            }
+            OutputFormat::Zod => {
+                let zod = convert::build_zod(&shapes);
+                let zod_path = args.zod.clone().unwrap_or_else(|| input.with_extension("-zod.ts"));
+                fs::write(&zod_path, zod)?;
+                println!("Wrote Zod -> {}", zod_path.display());
+            }
+            OutputFormat::Kotlin => {
+                let kt = convert::build_kotlin(&shapes);
+                let kt_path = args.kotlin.clone().unwrap_or_else(|| input.with_extension("-classes.kt"));
+                fs::write(&kt_path, kt)?;
+                println!("Wrote Kotlin -> {}", kt_path.display());
+            }
+            OutputFormat::Skos => {
+                let skos = convert::build_skos_with_prefixes(&shapes, &prefixes);
+                let skos_path = args.skos.clone().unwrap_or_else(|| input.with_extension("-skos.ttl"));
+                fs::write(&skos_path, skos)?;
+                println!("Wrote SKOS -> {}", skos_path.display());
+            }
+            OutputFormat::Jsonld => {
+                let frame_dir = args.jsonld.clone().unwrap_or_else(|| input.with_extension("-frames"));
+                fs::create_dir_all(&frame_dir)?;
+                for (name, frame) in convert::build_jsonld_frames_with_prefixes(&shapes, &prefixes) {
+                    fs::write(frame_dir.join(format!("{}.jsonld", name)), serde_json::to_string_pretty(&frame)?)?;
+                }
+                println!("Wrote JSON-LD frames -> {}", frame_dir.display());
+            }
+        }
+    }
+
+    for diagram in args.diagram.iter() {
+        match diagram {
+            DiagramFormat::Mermaid => {
+                let mermaid = convert::build_mermaid(&shapes);
+                let mermaid_path = input.with_extension("-diagram.mmd");
+                fs::write(&mermaid_path, mermaid)?;
+                println!("Wrote Mermaid diagram -> {}", mermaid_path.display());
+            }
+            DiagramFormat::Plantuml => {
+                let puml = convert::build_plantuml(&shapes, args.cluster_namespaces);
+                let puml_path = input.with_extension("-diagram.puml");
+                fs::write(&puml_path, puml)?;
+                println!("Wrote PlantUML diagram -> {}", puml_path.display());
+            }
+            DiagramFormat::Dot => {
+                let dot = convert::build_dot(&shapes, args.cluster_namespaces);
+                let dot_path = input.with_extension("-diagram.dot");
+                fs::write(&dot_path, dot)?;
+                println!("Wrote DOT diagram -> {}", dot_path.display());
+            }
+        }
+    }
 
     Ok(())
 }
+
+/// The given LinkML files, with each directory standing for the `.yaml` and `.yml` files in it
+fn linkml_files<'a>(paths: impl Iterator<Item = &'a PathBuf>) -> anyhow::Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for path in paths {
+        if path.is_dir() {
+            let mut entries: Vec<PathBuf> = fs::read_dir(path).with_context(|| format!("reading {}", path.display()))?
+                .filter_map(|entry| Some(entry.ok()?.path()))
+                .filter(|p| p.extension().is_some_and(|ext| ext == "yaml" || ext == "yml"))
+                .collect();
+            entries.sort();
+            files.extend(entries);
+        } else {
+            files.push(path.clone());
+        }
+    }
+    Ok(files)
+}
+
+/// A JSON Schema document serialized as JSON or YAML
+fn json_schema_text(json_schema: &serde_json::Value, format: JsonschemaFormat) -> anyhow::Result<String> {
+    Ok(match format {
+        JsonschemaFormat::Json => serde_json::to_string_pretty(json_schema)?,
+        JsonschemaFormat::Yaml => serde_yaml::to_string(json_schema).context("serialize JSON Schema YAML")?,
+    })
+}
+
+/// The file name of `file`'s output with extension `ext`, for a sibling output to refer to
+fn output_name(file: &Path, ext: &str) -> String {
+    file.with_extension(ext).file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default()
+}
+
+/// Parse ShEx compact syntax into AST using rudof's compact parser and extract shapes
+fn read_shex(input: &Path, input_str: &str) -> anyhow::Result<(Vec<ShapeInfo>, Prefixes)> {
+    // The parser types come from `shex_compact` and `shex_ast` crates.
+    let base_iri = iri_s::iris::IriS::from_path(input).unwrap(); // _or_else(|e| -> anyhow::bail!(e))
+    parse_shex(input_str, &base_iri)
+}
+
+fn parse_shex(input_str: &str, base_iri: &iri_s::iris::IriS) -> anyhow::Result<(Vec<ShapeInfo>, Prefixes)> {
+    let schema: shex_ast::Schema = shex_compact::ShExParser::parse(input_str, None, base_iri)
+        .map_err(|e| anyhow::anyhow!("failed to parse ShEx: {:?}", e))?;
+    Ok((convert::shapes_from_rudof_ast(&schema)?, convert::prefixes_from_rudof_ast(&schema)))
+}
@@ -0,0 +1,328 @@
+use serde_yaml::Value as YamlValue;
+use std::path::Path;
+
+use crate::convert::{identifier, Prefixes, PropertyInfo, ShapeConstraint, ShapeInfo, KOTLIN_KEYWORDS, PYTHON_KEYWORDS, RUST_KEYWORDS};
+
+/// How shape labels and predicates become class and slot names
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct NamingOptions {
+    /// case of class names, made from shape labels
+    pub class_case: NameCase,
+    /// case of slot names, made from predicates
+    pub slot_case: NameCase,
+    /// name everything after its CURIE (`foaf_name`, `ex_Person`) rather than its local name
+    pub qualify: bool,
+    /// how predicates that share a local name are told apart
+    pub collisions: NameCollisions,
+    /// languages whose reserved words names must avoid; a clashing name gets a trailing `_`
+    pub escape_keywords: Vec<Keywords>,
+    /// curated names, which win over all of the above
+    pub names: NameMap,
+    /// name classes and slots by their CURIEs (`foaf:name`), or full IRIs where no
+    /// prefix covers them, so every name maps back to its IRI; ignores the options above
+    pub iri_names: bool,
+}
+
+/// Case convention for generated names
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NameCase {
+    /// as in the source schema
+    #[default]
+    Preserve,
+    /// `given_name`
+    Snake,
+    /// `givenName`
+    Camel,
+    /// `GivenName`
+    Pascal,
+}
+
+/// A target language's reserved words
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Keywords {
+    Python,
+    Rust,
+    Kotlin,
+}
+
+impl Keywords {
+    fn words(self) -> &'static [&'static str] {
+        match self {
+            Keywords::Python => PYTHON_KEYWORDS,
+            Keywords::Rust => RUST_KEYWORDS,
+            Keywords::Kotlin => KOTLIN_KEYWORDS,
+        }
+    }
+}
+
+/// Rename shapes and properties following `options`, updating every reference to a
+/// renamed shape. Returns the `(predicate, new name)` of each property renamed to
+/// tell it from another predicate with the same local name.
+pub fn apply_naming(shapes: &mut [ShapeInfo], prefixes: &Prefixes, options: &NamingOptions) -> Vec<(String, String)> {
+    if options.iri_names {
+        for_each_property(shapes, |p| p.name = prefixes.compact(&p.predicate).unwrap_or_else(|| p.predicate.clone()));
+        // nested shapes have no IRI to be named by
+        let renamed: Vec<(String, String)> = shapes.iter()
+            .filter(|s| s.id.contains(':'))
+            .map(|s| (s.name.clone(), prefixes.compact(&s.id).unwrap_or_else(|| s.id.clone())))
+            .collect();
+        rename_shapes(shapes, &renamed);
+        return Vec::new();
+    }
+    if options.qualify {
+        for_each_property(shapes, |p| {
+            if let Some(q) = qualified(prefixes, &p.predicate) { p.name = q; }
+        });
+        let renamed: Vec<(String, String)> = shapes.iter().filter_map(|s| Some((s.name.clone(), qualified(prefixes, &s.id)?))).collect();
+        rename_shapes(shapes, &renamed);
+    }
+    let slot_name = |name: &str| escape_keywords(cased(name, options.slot_case), &options.escape_keywords);
+    for_each_property(shapes, |p| p.name = slot_name(&p.name));
+    for s in shapes.iter_mut() {
+        s.identifier = s.identifier.as_deref().map(slot_name);
+    }
+    // after casing, which can bring names together (`fooBar`, `foo_bar`)
+    let collisions = disambiguate(shapes, prefixes, options.collisions, slot_name);
+    let mut taken: Vec<String> = Vec::new();
+    let mut renamed: Vec<(String, String)> = Vec::new();
+    for s in shapes.iter() {
+        let name = escape_keywords(cased(&s.name, options.class_case), &options.escape_keywords);
+        let name = if taken.contains(&name) {
+            (2..).map(|i| format!("{}{}", name, i)).find(|n| !taken.contains(n)).unwrap_or(name)
+        } else {
+            name
+        };
+        taken.push(name.clone());
+        if name != s.name { renamed.push((s.name.clone(), name)); }
+    }
+    rename_shapes(shapes, &renamed);
+    let names = &options.names;
+    for_each_property(shapes, |p| {
+        if let Some(name) = names.slot_name(&p.predicate) { p.name = name.to_string(); }
+    });
+    let renamed: Vec<(String, String)> = shapes.iter().filter_map(|s| Some((s.name.clone(), names.class_name(&s.id)?.to_string()))).collect();
+    rename_shapes(shapes, &renamed);
+    collisions.into_iter()
+        .filter(|(predicate, _)| names.slot_name(predicate).is_none())
+        .collect()
+}
+
+/// Class and slot names curated in a crosswalk file, keyed by full IRI
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct NameMap {
+    /// (shape IRI, class name)
+    pub shapes: Vec<(String, String)>,
+    /// (predicate IRI, slot name)
+    pub predicates: Vec<(String, String)>,
+}
+
+impl NameMap {
+    /// The class name for a shape IRI
+    pub fn class_name(&self, shape: &str) -> Option<&str> {
+        self.shapes.iter().find(|(iri, _)| iri == shape).map(|(_, name)| name.as_str())
+    }
+
+    /// The slot name for a predicate IRI
+    pub fn slot_name(&self, predicate: &str) -> Option<&str> {
+        self.predicates.iter().find(|(iri, _)| iri == predicate).map(|(_, name)| name.as_str())
+    }
+
+    /// The shape IRI a class name was mapped from
+    pub fn shape_iri(&self, class_name: &str) -> Option<&str> {
+        self.shapes.iter().find(|(_, name)| name == class_name).map(|(iri, _)| iri.as_str())
+    }
+
+    /// The predicate IRI a slot name was mapped from
+    pub fn predicate(&self, slot_name: &str) -> Option<&str> {
+        self.predicates.iter().find(|(_, name)| name == slot_name).map(|(iri, _)| iri.as_str())
+    }
+}
+
+/// Read a name crosswalk file, see `parse_name_map`.
+pub fn read_name_map(path: &Path) -> anyhow::Result<NameMap> {
+    let text = std::fs::read_to_string(path)?;
+    parse_name_map(&text)
+}
+
+/// Parse a YAML (or JSON) name crosswalk: `shapes` and `predicates` map IRIs to
+/// class and slot names. Keys may be `<IRI>`s or CURIEs under the file's own
+/// `prefixes`. Each name must come from one IRI so it can be mapped back.
+pub fn parse_name_map(text: &str) -> anyhow::Result<NameMap> {
+    let doc: YamlValue = serde_yaml::from_str(text)?;
+    let strings = |key: &str| -> anyhow::Result<Vec<(String, String)>> {
+        match doc.get(key) {
+            None => Ok(Vec::new()),
+            Some(YamlValue::Mapping(m)) => m.iter().map(|(k, v)| match (k.as_str(), v.as_str()) {
+                (Some(k), Some(v)) => Ok((k.to_string(), v.to_string())),
+                _ => anyhow::bail!("name map `{}` entries must map strings to strings", key),
+            }).collect(),
+            Some(_) => anyhow::bail!("name map `{}` must be a mapping", key),
+        }
+    };
+    let prefixes = strings("prefixes")?;
+    let expand = |key: String| -> String {
+        if let Some(iri) = key.strip_prefix('<').and_then(|k| k.strip_suffix('>')) {
+            return iri.to_string();
+        }
+        let expanded = key.split_once(':').and_then(|(pfx, local)| {
+            prefixes.iter().find(|(p, _)| p == pfx).map(|(_, ns)| format!("{}{}", ns, local))
+        });
+        expanded.unwrap_or(key)
+    };
+    let mut map = NameMap::default();
+    for (key, entries) in [("shapes", &mut map.shapes), ("predicates", &mut map.predicates)] {
+        for (iri, name) in strings(key)? {
+            let iri = expand(iri);
+            if let Some((other, _)) = entries.iter().find(|(_, n)| *n == name) {
+                anyhow::bail!("name map gives `{}` to both <{}> and <{}>", name, other, iri);
+            }
+            entries.push((iri, name));
+        }
+    }
+    Ok(map)
+}
+
+/// A copy of `shapes` whose properties are named by their full predicate IRIs,
+/// e.g. for JSON Schema property names that map back to predicates
+pub fn with_iri_property_names(shapes: &[ShapeInfo]) -> Vec<ShapeInfo> {
+    let mut shapes = shapes.to_vec();
+    for_each_property(&mut shapes, |p| p.name = p.predicate.clone());
+    shapes
+}
+
+/// A name made from the CURIE for an IRI, e.g. `foaf:name` -> `foaf_name`
+fn qualified(prefixes: &Prefixes, iri: &str) -> Option<String> {
+    prefixes.compact(iri).map(|curie| identifier(&curie.replace(':', "_")))
+}
+
+/// Rename shapes `(old, new)`, along with their uses as parents, ranges and in OR/NOT
+fn rename_shapes(shapes: &mut [ShapeInfo], renamed: &[(String, String)]) {
+    if renamed.is_empty() {
+        return;
+    }
+    let new_name = |name: &str| renamed.iter().find(|(old, _)| old == name).map(|(_, new)| new.clone());
+    for s in shapes.iter_mut() {
+        let names = std::iter::once(&mut s.name).chain(s.extends.iter_mut()).chain(s.constraints.iter_mut().flat_map(|c| match c {
+            ShapeConstraint::AnyOf(names) => names.iter_mut().collect::<Vec<_>>(),
+            ShapeConstraint::Not(name) => vec![name],
+            ShapeConstraint::OneOf(_) | ShapeConstraint::Rule { .. } => Vec::new(),
+        }));
+        for name in names {
+            if let Some(n) = new_name(name) { *name = n; }
+        }
+    }
+    for_each_property(shapes, |p| {
+        let ranges = std::iter::once(&mut p.range).chain(p.range_expr.iter_mut().flat_map(|e| e.ranges_mut().iter_mut()));
+        for range in ranges {
+            if let Some(n) = new_name(range) { *range = n; }
+        }
+    });
+}
+
+/// Apply `f` to every property, those in OneOf alternatives and rule conditions included
+fn for_each_property(shapes: &mut [ShapeInfo], mut f: impl FnMut(&mut PropertyInfo)) {
+    for s in shapes.iter_mut() {
+        let alternatives = s.constraints.iter_mut().flat_map(|c| match c {
+            ShapeConstraint::OneOf(alternatives) => alternatives.iter_mut().flatten().collect::<Vec<_>>(),
+            ShapeConstraint::Rule { preconditions, postconditions, elseconditions } => preconditions.iter_mut().chain(postconditions).chain(elseconditions).collect(),
+            ShapeConstraint::Not(_) | ShapeConstraint::AnyOf(_) => Vec::new(),
+        });
+        for p in s.properties.iter_mut().chain(alternatives) {
+            f(p);
+        }
+    }
+}
+
+/// A name in the given case, split into words at `_`, `-` and other punctuation
+/// and where a lower-case letter or digit meets an upper-case one
+pub fn cased(name: &str, case: NameCase) -> String {
+    let mut words: Vec<String> = Vec::new();
+    let mut word = String::new();
+    let mut after_lower = false;
+    for c in name.chars() {
+        if !c.is_alphanumeric() || (c.is_uppercase() && after_lower) {
+            if !word.is_empty() { words.push(std::mem::take(&mut word)); }
+        }
+        after_lower = c.is_lowercase() || c.is_ascii_digit();
+        if c.is_alphanumeric() { word.push(c); }
+    }
+    if !word.is_empty() { words.push(word); }
+    let capitalized = |w: &String| {
+        let mut chars = w.chars();
+        chars.next().map_or_else(String::new, |c| c.to_uppercase().chain(chars.flat_map(char::to_lowercase)).collect())
+    };
+    match case {
+        _ if words.is_empty() => name.to_string(),
+        NameCase::Preserve => name.to_string(),
+        NameCase::Snake => words.iter().map(|w| w.to_lowercase()).collect::<Vec<_>>().join("_"),
+        NameCase::Camel => words.iter().enumerate().map(|(i, w)| if i == 0 { w.to_lowercase() } else { capitalized(w) }).collect(),
+        NameCase::Pascal => words.iter().map(capitalized).collect(),
+    }
+}
+
+fn escape_keywords(name: String, keywords: &[Keywords]) -> String {
+    if keywords.iter().any(|k| k.words().contains(&name.as_str())) { format!("{}_", name) } else { name }
+}
+
+/// How properties whose predicates share a local name (`foaf:name`, `schema:name`) are told apart
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NameCollisions {
+    /// name each after its CURIE, e.g. `foaf_name` and `schema_name`
+    #[default]
+    Qualify,
+    /// the first keeps the local name, the others are numbered: `name`, `name2`
+    Number,
+    /// leave them sharing the name, so they merge like a repeated predicate
+    Keep,
+}
+
+/// Rename properties whose different predicates have the same local name, following
+/// `strategy`. A predicate no prefix covers is numbered even when qualifying.
+/// Returns the `(predicate, new name)` of each property renamed.
+pub fn disambiguate_names(shapes: &mut [ShapeInfo], prefixes: &Prefixes, strategy: NameCollisions) -> Vec<(String, String)> {
+    disambiguate(shapes, prefixes, strategy, str::to_string)
+}
+
+/// `disambiguate_names`, with each new name made by `slot_name`, e.g. to keep its case
+fn disambiguate(shapes: &mut [ShapeInfo], prefixes: &Prefixes, strategy: NameCollisions, slot_name: impl Fn(&str) -> String) -> Vec<(String, String)> {
+    // every (name, predicate) in use, OneOf alternatives included, in first-seen order
+    let mut uses: Vec<(String, String)> = Vec::new();
+    for s in shapes.iter() {
+        let alternatives = s.constraints.iter().flat_map(|c| match c {
+            ShapeConstraint::OneOf(alternatives) => alternatives.iter().flatten().collect::<Vec<_>>(),
+            ShapeConstraint::Rule { preconditions, postconditions, elseconditions } => preconditions.iter().chain(postconditions).chain(elseconditions).collect(),
+            ShapeConstraint::Not(_) | ShapeConstraint::AnyOf(_) => Vec::new(),
+        });
+        for p in s.properties.iter().chain(alternatives) {
+            let key = (p.name.clone(), p.predicate.clone());
+            if !uses.contains(&key) { uses.push(key); }
+        }
+    }
+    if strategy == NameCollisions::Keep {
+        return Vec::new();
+    }
+    let mut taken: Vec<String> = uses.iter().map(|(name, _)| name.clone()).collect();
+    let mut renames: Vec<(String, String, String)> = Vec::new();
+    for (name, predicate) in uses.iter() {
+        let rivals: Vec<&String> = uses.iter().filter(|(n, _)| n == name).map(|(_, p)| p).collect();
+        if rivals.len() < 2 || (strategy == NameCollisions::Number && rivals[0] == predicate) {
+            continue;
+        }
+        let qualified = match strategy {
+            NameCollisions::Qualify => qualified(prefixes, predicate),
+            _ => None,
+        };
+        let new_name = match qualified.map(|q| slot_name(&q)) {
+            Some(q) if !taken.contains(&q) => q,
+            _ => (2..).map(|i| slot_name(&format!("{}{}", name, i))).find(|n| !taken.contains(n)).unwrap_or_else(|| name.clone()),
+        };
+        taken.push(new_name.clone());
+        renames.push((name.clone(), predicate.clone(), new_name));
+    }
+    for_each_property(shapes, |p| {
+        if let Some((_, _, new_name)) = renames.iter().find(|(n, pred, _)| *n == p.name && *pred == p.predicate) {
+            p.name = new_name.clone();
+        }
+    });
+    renames.into_iter().map(|(_, predicate, new_name)| (predicate, new_name)).collect()
+}
@@ -0,0 +1,37 @@
+use anyhow::Context;
+use serde_json::Value as JsonValue;
+use std::path::Path;
+
+use crate::convert::{self, ShapeInfo};
+use crate::json_schema_to_linkml::{is_object_schema, shape_from_object};
+
+/// Convert an OpenAPI document (JSON or YAML) to LinkML YAML.
+pub fn openapi_to_linkml(input: &Path, text: &str) -> anyhow::Result<String> {
+    let shapes = shapes_from_openapi(text)?;
+    convert::build_linkml_doc(input, &shapes)
+}
+
+/// Read the object schemas of an OpenAPI document into our ShapeInfo vector.
+/// OpenAPI 3 keeps them in `components.schemas`, Swagger 2 in `definitions`;
+/// paths and operations are ignored.
+pub fn shapes_from_openapi(text: &str) -> anyhow::Result<Vec<ShapeInfo>> {
+    // JSON is YAML, so one parser covers both spellings
+    let doc: JsonValue = serde_yaml::from_str(text).context("parsing OpenAPI document")?;
+    if doc.get("openapi").is_none() && doc.get("swagger").is_none() {
+        anyhow::bail!("not an OpenAPI document: missing `openapi` or `swagger` version");
+    }
+
+    let schemas = doc.get("components").and_then(|c| c.get("schemas")).or_else(|| doc.get("definitions"));
+    let mut shapes = Vec::new();
+    if let Some(JsonValue::Object(schemas)) = schemas {
+        for (name, schema) in schemas.iter() {
+            if let Some(obj) = schema.as_object() {
+                if is_object_schema(obj) {
+                    shapes.push(shape_from_object(name, obj));
+                }
+            }
+        }
+    }
+
+    Ok(shapes)
+}
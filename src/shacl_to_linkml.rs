@@ -0,0 +1,140 @@
+use anyhow::Context;
+use oxrdf::vocab::rdf;
+use oxrdf::{Graph, NamedNode, NamedOrBlankNodeRef, TermRef};
+use std::path::Path;
+
+use crate::convert::{self, NodeKind, PropertyInfo, ShapeInfo};
+
+const SH: &str = "http://www.w3.org/ns/shacl#";
+
+/// Convert a SHACL shapes graph (Turtle) to LinkML YAML.
+pub fn shacl_to_linkml(input: &Path, turtle: &str) -> anyhow::Result<String> {
+    let shapes = shapes_from_shacl(turtle)?;
+    convert::build_linkml_doc(input, &shapes)
+}
+
+/// Read the node shapes of a SHACL shapes graph (Turtle) into our ShapeInfo vector.
+/// Only simple predicate paths are understood; other property shapes are skipped.
+pub fn shapes_from_shacl(turtle: &str) -> anyhow::Result<Vec<ShapeInfo>> {
+    let mut graph = Graph::new();
+    for triple in oxttl::TurtleParser::new().for_slice(turtle) {
+        let triple = triple.context("parsing SHACL Turtle")?;
+        graph.insert(&triple);
+    }
+
+    let node_shape = sh("NodeShape");
+    let mut shapes = Vec::new();
+    for subject in graph.subjects_for_predicate_object(rdf::TYPE, &node_shape) {
+        let NamedOrBlankNodeRef::NamedNode(shape_iri) = subject else { continue };
+        let mut properties = Vec::new();
+        for ps in graph.objects_for_subject_predicate(subject, &sh("property")) {
+            if let Some(ps) = as_subject(ps) {
+                if let Some(p) = prop_from_property_shape(&graph, ps) {
+                    properties.push(p);
+                }
+            }
+        }
+        shapes.push(ShapeInfo {
+            id: shape_iri.as_str().to_string(),
+            name: convert::local_name(shape_iri.as_str()),
+            properties,
+            ..Default::default()
+        });
+    }
+
+    Ok(shapes)
+}
+
+fn prop_from_property_shape(graph: &Graph, ps: NamedOrBlankNodeRef<'_>) -> Option<PropertyInfo> {
+    let Some(TermRef::NamedNode(path)) = graph.object_for_subject_predicate(ps, &sh("path")) else {
+        return None;
+    };
+    let predicate = path.as_str().to_string();
+
+    let node_kind = match graph.object_for_subject_predicate(ps, &sh("nodeKind")) {
+        Some(TermRef::NamedNode(k)) => match k.as_str().strip_prefix(SH) {
+            Some("IRI") => Some(NodeKind::Iri),
+            Some("BlankNode") => Some(NodeKind::BNode),
+            Some("BlankNodeOrIRI") => Some(NodeKind::NonLiteral),
+            Some("Literal") => Some(NodeKind::Literal),
+            _ => None,
+        },
+        _ => None,
+    };
+
+    let range = if let Some(TermRef::NamedNode(dt)) = graph.object_for_subject_predicate(ps, &sh("datatype")) {
+        convert::range_for_datatype(dt.as_str())
+    } else if let Some(TermRef::NamedNode(target)) = graph
+        .object_for_subject_predicate(ps, &sh("node"))
+        .or_else(|| graph.object_for_subject_predicate(ps, &sh("class")))
+    {
+        convert::local_name(target.as_str())
+    } else {
+        match node_kind {
+            Some(NodeKind::Iri) => "uriorcurie",
+            Some(NodeKind::BNode) | Some(NodeKind::NonLiteral) => "nodeidentifier",
+            _ => "string",
+        }.to_string()
+    };
+
+    let count = |name: &str| match graph.object_for_subject_predicate(ps, &sh(name)) {
+        Some(TermRef::Literal(l)) => l.value().parse::<u64>().ok(),
+        _ => None,
+    };
+
+    let values = match graph.object_for_subject_predicate(ps, &sh("in")).and_then(as_subject) {
+        Some(list) => list_values(graph, list),
+        None => Vec::new(),
+    };
+
+    // sh:languageIn matches subtags too, so each tag reads back as a stem
+    let mut languages: Vec<String> = match graph.object_for_subject_predicate(ps, &sh("languageIn")).and_then(as_subject) {
+        Some(list) => list_values(graph, list).into_iter().map(|l| format!("{}~", l)).collect(),
+        None => Vec::new(),
+    };
+    let lang_string = matches!(graph.object_for_subject_predicate(ps, &sh("datatype")), Some(TermRef::NamedNode(dt)) if dt.as_str() == convert::RDF_LANG_STRING);
+    if languages.is_empty() && lang_string {
+        languages.push("~".to_string());
+    }
+
+    Some(PropertyInfo {
+        name: convert::local_name(&predicate),
+        predicate,
+        range,
+        min: Some(count("minCount").unwrap_or(0)),
+        max: count("maxCount"),
+        values,
+        node_kind,
+        languages,
+        ..Default::default()
+    })
+}
+
+/// Walk an RDF collection, returning IRIs and literal lexical forms
+fn list_values<'a>(graph: &'a Graph, mut node: NamedOrBlankNodeRef<'a>) -> Vec<String> {
+    let mut values = Vec::new();
+    while let Some(first) = graph.object_for_subject_predicate(node, rdf::FIRST) {
+        match first {
+            TermRef::NamedNode(n) => values.push(n.as_str().to_string()),
+            TermRef::Literal(l) => values.push(l.value().to_string()),
+            _ => {}
+        }
+        match graph.object_for_subject_predicate(node, rdf::REST).and_then(as_subject) {
+            Some(rest) => node = rest,
+            None => break,
+        }
+    }
+    values
+}
+
+fn as_subject(term: TermRef<'_>) -> Option<NamedOrBlankNodeRef<'_>> {
+    match term {
+        TermRef::NamedNode(n) => Some(n.into()),
+        TermRef::BlankNode(b) => Some(b.into()),
+        _ => None,
+    }
+}
+
+fn sh(local: &str) -> NamedNode {
+    NamedNode::new_unchecked(format!("{}{}", SH, local))
+}
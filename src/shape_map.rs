@@ -0,0 +1,85 @@
+use std::path::Path;
+
+use crate::convert::{self, ShapeInfo};
+
+/// One `node@shape` association from a ShEx shape map
+#[derive(Debug, Clone, PartialEq)]
+pub struct ShapeAssociation {
+    /// node selector as written: an IRI, literal, blank node or `{FOCUS ...}` query
+    pub node: String,
+    /// shape label with any `<>` removed; a prefixed name is kept as written
+    pub shape: String,
+    /// `START` in place of a label: the schema's start shape
+    pub start: bool,
+}
+
+impl ShapeAssociation {
+    /// The shape this association names, or the start shape for `START`
+    pub fn find_shape<'a>(&self, shapes: &'a [ShapeInfo]) -> Option<&'a ShapeInfo> {
+        if self.start {
+            shapes.iter().find(|s| s.start)
+        } else {
+            convert::find_shape(shapes, &self.shape)
+        }
+    }
+}
+
+/// Read a ShEx shape map file (e.g. `<http://a.example/n1>@<http://a.example/S1>, ...`)
+pub fn read_shape_map(path: &Path) -> anyhow::Result<Vec<ShapeAssociation>> {
+    let text = std::fs::read_to_string(path)?;
+    parse_shape_map(&text)
+}
+
+/// Parse a ShEx shape map into its associations.
+/// Commas and `@`s inside IRIs, literals and query maps are not separators;
+/// the shape is whatever follows the last top-level `@`.
+pub fn parse_shape_map(text: &str) -> anyhow::Result<Vec<ShapeAssociation>> {
+    let mut associations = Vec::new();
+    for entry in split_top_level(text, ',') {
+        let entry = entry.trim();
+        if entry.is_empty() { continue; }
+        let at = split_top_level(entry, '@');
+        if at.len() < 2 {
+            anyhow::bail!("shape map association without `@shape`: {}", entry);
+        }
+        let shape = at[at.len() - 1].trim();
+        let start = shape == "START";
+        let shape = shape.strip_prefix('<').and_then(|s| s.strip_suffix('>')).unwrap_or(shape);
+        let node = entry[..entry.len() - at[at.len() - 1].len() - 1].trim();
+        associations.push(ShapeAssociation { node: node.to_string(), shape: shape.to_string(), start });
+    }
+    Ok(associations)
+}
+
+/// Split on `sep` outside of `<...>`, `"..."` and `{...}`
+fn split_top_level(text: &str, sep: char) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let (mut in_iri, mut in_string, mut braces) = (false, false, 0);
+    let mut start = 0;
+    let mut escaped = false;
+    for (i, c) in text.char_indices() {
+        if in_string {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        match c {
+            '"' => in_string = true,
+            '<' if !in_iri => in_iri = true,
+            '>' if in_iri => in_iri = false,
+            '{' if !in_iri => braces += 1,
+            '}' if !in_iri => braces -= 1,
+            c if c == sep && !in_iri && braces == 0 => {
+                parts.push(&text[start..i]);
+                start = i + c.len_utf8();
+            }
+            _ => {}
+        }
+    }
+    parts.push(&text[start..]);
+    parts
+}
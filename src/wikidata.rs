@@ -0,0 +1,38 @@
+use anyhow::Context;
+
+/// Where Wikidata serves the raw ShExC of an EntitySchema
+const ENTITY_SCHEMA_TEXT: &str = "https://www.wikidata.org/wiki/Special:EntitySchemaText/";
+
+/// Normalize an EntitySchema reference to its identifier, e.g. `E42`.
+/// Accepts a bare id or any of the Wikidata URLs for it
+/// (`.../wiki/EntitySchema:E42`, `.../entity/E42`, `.../Special:EntitySchemaText/E42`).
+pub fn entity_schema_id(spec: &str) -> anyhow::Result<String> {
+    let spec = spec.trim().trim_end_matches('/');
+    let tail = spec.rsplit(['/', ':']).next().unwrap_or(spec);
+    let tail = tail.split(['?', '#']).next().unwrap_or(tail);
+    let is_id = tail.len() > 1
+        && tail.starts_with(['E', 'e'])
+        && tail[1..].chars().all(|c| c.is_ascii_digit());
+    if !is_id {
+        anyhow::bail!("not a Wikidata EntitySchema identifier or URL: {}", spec);
+    }
+    Ok(format!("E{}", &tail[1..]))
+}
+
+/// URL of the ShExC text for an EntitySchema reference
+pub fn entity_schema_text_url(spec: &str) -> anyhow::Result<String> {
+    Ok(format!("{}{}", ENTITY_SCHEMA_TEXT, entity_schema_id(spec)?))
+}
+
+/// Fetch the ShExC of a Wikidata EntitySchema
+pub fn fetch_entity_schema(spec: &str) -> anyhow::Result<String> {
+    let url = entity_schema_text_url(spec)?;
+    let response = reqwest::blocking::Client::new()
+        .get(&url)
+        .header(reqwest::header::USER_AGENT, concat!("shex2linkml/", env!("CARGO_PKG_VERSION")))
+        .send()
+        .with_context(|| format!("fetching {}", url))?
+        .error_for_status()
+        .with_context(|| format!("fetching {}", url))?;
+    response.text().with_context(|| format!("reading {}", url))
+}
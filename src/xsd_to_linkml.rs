@@ -0,0 +1,234 @@
+use anyhow::Context;
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::convert::{self, PropertyInfo, ShapeInfo};
+
+const XSD: &str = "http://www.w3.org/2001/XMLSchema#";
+
+/// Convert an XML Schema document to LinkML YAML.
+pub fn xsd_to_linkml(input: &Path, xsd: &str) -> anyhow::Result<String> {
+    let shapes = shapes_from_xsd(xsd)?;
+    convert::build_linkml_doc(input, &shapes)
+}
+
+/// Read the complex types of an XML Schema into our ShapeInfo vector.
+/// Named complexTypes and global elements with anonymous complexTypes become
+/// shapes; child elements and attributes become properties. Types extended
+/// via complexContent inherit their base type's properties.
+pub fn shapes_from_xsd(xsd: &str) -> anyhow::Result<Vec<ShapeInfo>> {
+    let root = parse_xml(xsd)?;
+    if root.name != "schema" {
+        anyhow::bail!("expected an xs:schema document, found <{}>", root.name);
+    }
+
+    // global simple types (for enumerations) and elements (for ref=)
+    let simple_types: HashMap<&str, &XmlNode> = root.children.iter()
+        .filter(|c| c.name == "simpleType")
+        .filter_map(|c| c.attr("name").map(|n| (n, c)))
+        .collect();
+    let global_elements: HashMap<&str, &XmlNode> = root.children.iter()
+        .filter(|c| c.name == "element")
+        .filter_map(|c| c.attr("name").map(|n| (n, c)))
+        .collect();
+
+    let mut types: Vec<(String, &XmlNode)> = Vec::new();
+    for child in root.children.iter() {
+        match (child.name.as_str(), child.attr("name")) {
+            ("complexType", Some(name)) => types.push((name.to_string(), child)),
+            ("element", Some(name)) => {
+                if let Some(ct) = child.child("complexType") {
+                    types.push((name.to_string(), ct));
+                }
+            }
+            _ => {}
+        }
+    }
+    let type_names: Vec<&str> = types.iter().map(|(n, _)| n.as_str()).collect();
+
+    let ctx = XsdContext { simple_types, global_elements, type_names };
+    let mut shapes: Vec<ShapeInfo> = Vec::new();
+    let mut bases: Vec<Option<String>> = Vec::new();
+    for (name, ct) in types.iter() {
+        let mut properties = Vec::new();
+        let mut base = None;
+        collect_properties(ct, &ctx, &mut properties, &mut base);
+        shapes.push(ShapeInfo { id: name.clone(), name: name.clone(), properties, ..Default::default() });
+        bases.push(base);
+    }
+
+    // flatten complexContent extensions, base properties first
+    let own: Vec<Vec<PropertyInfo>> = shapes.iter().map(|s| s.properties.clone()).collect();
+    for (i, shape) in shapes.iter_mut().enumerate() {
+        let mut inherited = Vec::new();
+        let mut seen = vec![i];
+        let mut next = bases[i].clone();
+        while let Some(base) = next {
+            match ctx.type_names.iter().position(|n| *n == base) {
+                Some(j) if !seen.contains(&j) => {
+                    inherited.splice(0..0, own[j].iter().cloned());
+                    seen.push(j);
+                    next = bases[j].clone();
+                }
+                _ => break,
+            }
+        }
+        inherited.append(&mut shape.properties);
+        shape.properties = inherited;
+    }
+
+    Ok(shapes)
+}
+
+struct XsdContext<'a> {
+    simple_types: HashMap<&'a str, &'a XmlNode>,
+    global_elements: HashMap<&'a str, &'a XmlNode>,
+    type_names: Vec<&'a str>,
+}
+
+fn collect_properties(node: &XmlNode, ctx: &XsdContext, out: &mut Vec<PropertyInfo>, base: &mut Option<String>) {
+    for child in node.children.iter() {
+        match child.name.as_str() {
+            "sequence" | "all" | "choice" | "complexContent" => collect_properties(child, ctx, out, base),
+            "extension" | "restriction" => {
+                if let Some(b) = child.attr("base") { *base = Some(strip_prefix(b).to_string()); }
+                collect_properties(child, ctx, out, base);
+            }
+            "element" => out.push(prop_from_element(child, ctx)),
+            "attribute" => {
+                if let Some(name) = child.attr("name") {
+                    let (range, values) = range_for(child, ctx);
+                    out.push(PropertyInfo {
+                        name: name.to_string(),
+                        predicate: name.to_string(),
+                        range,
+                        min: Some(if child.attr("use") == Some("required") { 1 } else { 0 }),
+                        max: Some(1),
+                        values,
+                        ..Default::default()
+                    });
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn prop_from_element(el: &XmlNode, ctx: &XsdContext) -> PropertyInfo {
+    // `ref="Foo"` uses the global element Foo's name and type
+    let (name, decl) = match el.attr("ref") {
+        Some(r) => {
+            let r = strip_prefix(r);
+            (r.to_string(), ctx.global_elements.get(r).copied().unwrap_or(el))
+        }
+        None => (el.attr("name").unwrap_or("").to_string(), el),
+    };
+    let (range, values) = if decl.child("complexType").is_some() && ctx.type_names.contains(&name.as_str()) {
+        (name.clone(), Vec::new())
+    } else {
+        range_for(decl, ctx)
+    };
+    let min = el.attr("minOccurs").and_then(|v| v.parse().ok()).unwrap_or(1);
+    let max = match el.attr("maxOccurs") {
+        Some("unbounded") => None,
+        Some(v) => v.parse().ok(),
+        None => Some(1),
+    };
+    PropertyInfo { name: name.clone(), predicate: name, range, min: Some(min), max, values, ..Default::default() }
+}
+
+/// Range and enumerated values for an element/attribute declaration
+fn range_for(decl: &XmlNode, ctx: &XsdContext) -> (String, Vec<String>) {
+    if let Some(t) = decl.attr("type") {
+        let local = strip_prefix(t);
+        if ctx.type_names.contains(&local) {
+            return (local.to_string(), Vec::new());
+        }
+        if let Some(st) = ctx.simple_types.get(local) {
+            return range_for_simple_type(st);
+        }
+        return (convert::range_for_datatype(&format!("{}{}", XSD, local)), Vec::new());
+    }
+    match decl.child("simpleType") {
+        Some(st) => range_for_simple_type(st),
+        None => ("string".to_string(), Vec::new()),
+    }
+}
+
+fn range_for_simple_type(st: &XmlNode) -> (String, Vec<String>) {
+    match st.child("restriction") {
+        Some(r) => {
+            let range = r.attr("base")
+                .map(|b| convert::range_for_datatype(&format!("{}{}", XSD, strip_prefix(b))))
+                .unwrap_or_else(|| "string".to_string());
+            let values = r.children.iter()
+                .filter(|c| c.name == "enumeration")
+                .filter_map(|c| c.attr("value").map(str::to_string))
+                .collect();
+            (range, values)
+        }
+        None => ("string".to_string(), Vec::new()),
+    }
+}
+
+fn strip_prefix(qname: &str) -> &str {
+    qname.rsplit(':').next().unwrap_or(qname)
+}
+
+/// Just enough of a DOM to walk an XML Schema: local names, attributes, children
+struct XmlNode {
+    name: String,
+    attrs: Vec<(String, String)>,
+    children: Vec<XmlNode>,
+}
+
+impl XmlNode {
+    fn attr(&self, name: &str) -> Option<&str> {
+        self.attrs.iter().find(|(k, _)| k == name).map(|(_, v)| v.as_str())
+    }
+
+    fn child(&self, name: &str) -> Option<&XmlNode> {
+        self.children.iter().find(|c| c.name == name)
+    }
+}
+
+fn parse_xml(xml: &str) -> anyhow::Result<XmlNode> {
+    let mut reader = Reader::from_str(xml);
+    reader.config_mut().trim_text(true);
+
+    let mut stack: Vec<XmlNode> = Vec::new();
+    loop {
+        match reader.read_event().context("parsing XSD")? {
+            Event::Start(e) => stack.push(node_from(&e)?),
+            Event::Empty(e) => {
+                let node = node_from(&e)?;
+                match stack.last_mut() {
+                    Some(parent) => parent.children.push(node),
+                    None => return Ok(node),
+                }
+            }
+            Event::End(_) => {
+                let node = stack.pop().context("unbalanced XSD document")?;
+                match stack.last_mut() {
+                    Some(parent) => parent.children.push(node),
+                    None => return Ok(node),
+                }
+            }
+            Event::Eof => anyhow::bail!("XSD document has no root element"),
+            _ => {}
+        }
+    }
+}
+
+fn node_from(e: &BytesStart) -> anyhow::Result<XmlNode> {
+    let name = String::from_utf8_lossy(e.local_name().as_ref()).to_string();
+    let mut attrs = Vec::new();
+    for attr in e.attributes() {
+        let attr = attr.context("reading XSD attribute")?;
+        let key = String::from_utf8_lossy(attr.key.local_name().as_ref()).to_string();
+        attrs.push((key, attr.unescape_value().context("reading XSD attribute")?.to_string()));
+    }
+    Ok(XmlNode { name, attrs, children: Vec::new() })
+}
@@ -0,0 +1,39 @@
+use shex2linkml::{convert, graphql_to_linkml};
+
+const SDL: &str = r#"
+"""A person"""
+type Person implements Node @key(fields: "id") {
+  id: ID!
+  name: String!   # the display name
+  age: Int
+  knows(first: Int = 10, after: String = "x"): [Person!]!
+  status: Status
+}
+
+interface Node { id: ID! }
+
+enum Status { ACTIVE RETIRED }
+
+scalar Date
+"#;
+
+#[test]
+fn test_shapes_from_graphql() {
+    let shapes = graphql_to_linkml::shapes_from_graphql(SDL).unwrap();
+    assert_eq!(shapes.iter().map(|s| s.name.as_str()).collect::<Vec<_>>(), vec!["Person", "Node"]);
+
+    let person = &shapes[0];
+    let prop = |n: &str| person.properties.iter().find(|p| p.name == n).unwrap();
+    assert_eq!((prop("name").range.as_str(), prop("name").min, prop("name").max), ("string", Some(1), Some(1)));
+    assert_eq!((prop("age").range.as_str(), prop("age").min), ("integer", Some(0)));
+    assert_eq!((prop("knows").range.as_str(), prop("knows").max), ("Person", None));
+    assert_eq!(prop("status").values, vec!["ACTIVE", "RETIRED"]);
+}
+
+#[test]
+fn test_graphql_roundtrip() {
+    let shapes = graphql_to_linkml::shapes_from_graphql(SDL).unwrap();
+    let sdl = convert::build_graphql(&shapes);
+    assert!(sdl.contains("  knows: [Person!]!\n"));
+    assert_eq!(graphql_to_linkml::shapes_from_graphql(&sdl).unwrap().len(), 2);
+}
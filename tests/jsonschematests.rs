@@ -0,0 +1,431 @@
+use serde_json::json;
+use serde_yaml::Value as Yaml;
+use shex2linkml::json_schema_to_linkml;
+
+#[test]
+fn test_json_schema_to_linkml() {
+    let schema = json!({
+        "$schema": "https://json-schema.org/draft/2020-12/schema",
+        "$id": "http://example.org/people",
+        "$defs": {
+            "Person": {
+                "type": "object",
+                "properties": {
+                    "name": { "type": "string" },
+                    "age": { "type": "integer" },
+                    "color": { "enum": ["red", "green"] },
+                    "knows": { "type": "array", "items": { "$ref": "#/$defs/Person" } }
+                },
+                "required": ["name"]
+            }
+        }
+    });
+
+    let linkml = json_schema_to_linkml::json_schema_to_linkml(&schema.to_string()).unwrap();
+    let doc: Yaml = serde_yaml::from_str(&linkml).unwrap();
+
+    assert_eq!(doc["id"].as_str(), Some("people"));
+    assert!(doc["classes"]["Person"]["slots"].as_sequence().unwrap().len() == 4);
+    assert_eq!(doc["slots"]["name"]["required"].as_bool(), Some(true));
+    assert_eq!(doc["slots"]["age"]["range"].as_str(), Some("integer"));
+    assert_eq!(doc["slots"]["knows"]["range"].as_str(), Some("Person"));
+    assert_eq!(doc["slots"]["color"]["range"].as_str(), Some("ColorEnum"));
+    assert!(doc["enums"]["ColorEnum"]["permissible_values"].get("green").is_some());
+}
+
+#[test]
+fn test_json_schema_to_shex() {
+    let schema = json!({
+        "$schema": "http://json-schema.org/draft-07/schema#",
+        "definitions": {
+            "Person": {
+                "type": "object",
+                "properties": {
+                    "name": { "type": "string" },
+                    "nick": { "type": "array", "items": { "type": "string" } },
+                    "email": { "type": "array", "items": { "type": "string" }, "minItems": 1 },
+                    "knows": { "$ref": "#/definitions/Person" },
+                    "status": { "enum": ["active", "retired"] }
+                },
+                "required": ["name", "email"]
+            }
+        }
+    });
+
+    let shex = shex2linkml::json_schema_to_shex(&schema.to_string()).unwrap();
+
+    assert!(shex.contains("ex:Person {"));
+    assert!(shex.contains("ex:name xsd:string ;"));
+    assert!(shex.contains("ex:nick xsd:string *"));
+    assert!(shex.contains("ex:email xsd:string +"));
+    assert!(shex.contains("ex:knows @ex:Person ?"));
+    assert!(shex.contains("ex:status [ \"active\" \"retired\" ] ?"));
+
+    // the generated schema must be parseable ShExC
+    let base = iri_s::iris::IriS::new_unchecked("http://example.org/");
+    shex_compact::ShExParser::parse(&shex, None, &base).expect("generated ShEx parses");
+}
+
+#[test]
+fn test_json_schema_all_of() {
+    let schema = json!({
+        "definitions": {
+            "Named": { "type": "object", "properties": { "name": { "type": "string" } } },
+            "Person": {
+                "allOf": [
+                    { "$ref": "#/definitions/Named" },
+                    { "properties": { "age": { "type": "integer" } } }
+                ]
+            }
+        }
+    });
+
+    let shapes = shex2linkml::json_schema_to_linkml::shapes_from_json_schema(&schema).unwrap();
+    let person = shapes.iter().find(|s| s.name == "Person").unwrap();
+    assert_eq!(person.extends, vec!["Named"]);
+    assert_eq!(person.properties[0].name, "age");
+}
+
+#[test]
+fn test_json_schema_draft_2020_12() {
+    let shex = r#"
+        PREFIX ex: <http://example.org/>
+        PREFIX xsd: <http://www.w3.org/2001/XMLSchema#>
+        ex:Agent { ex:name xsd:string }
+        ex:Person EXTENDS @ex:Agent CLOSED { ex:age xsd:integer ? ; ex:knows @ex:Person * }
+    "#;
+    let base = iri_s::iris::IriS::new_unchecked("http://example.org/");
+    let schema = shex_compact::ShExParser::parse(shex, None, &base).expect("parse shex");
+    let shapes = shex2linkml::convert::shapes_from_rudof_ast(&schema).unwrap();
+    let options = shex2linkml::convert::JsonSchemaOptions { draft: shex2linkml::convert::JsonSchemaDraft::Draft2020_12, ..Default::default() };
+    let js = shex2linkml::convert::build_json_schema_with(std::path::Path::new("people"), &shapes, &options);
+    assert_eq!(js["$schema"], "https://json-schema.org/draft/2020-12/schema");
+    assert!(js.get("definitions").is_none());
+    let person = &js["$defs"]["Person"];
+    assert_eq!(person["allOf"], json!([{ "$ref": "#/$defs/Agent" }]));
+    assert_eq!(person["properties"]["knows"]["items"], json!({ "$ref": "#/$defs/Person" }));
+    // unevaluatedProperties sees the inherited properties, so they needn't be listed
+    assert_eq!(person["unevaluatedProperties"], json!(false));
+    assert!(person.get("additionalProperties").is_none());
+    assert!(person["properties"].get("name").is_none());
+
+    let reread = json_schema_to_linkml::shapes_from_json_schema(&js).unwrap();
+    let person = reread.iter().find(|s| s.name == "Person").unwrap();
+    assert!(person.closed);
+    assert_eq!(person.extends, vec!["Agent"]);
+}
+
+#[test]
+fn test_json_schema_id_and_anchors() {
+    let shex = r#"
+        PREFIX ex: <http://example.org/>
+        PREFIX xsd: <http://www.w3.org/2001/XMLSchema#>
+        ex:Person { ex:name xsd:string }
+    "#;
+    let base = iri_s::iris::IriS::new_unchecked("http://example.org/");
+    let schema = shex_compact::ShExParser::parse(shex, None, &base).expect("parse shex");
+    let shapes = shex2linkml::convert::shapes_from_rudof_ast(&schema).unwrap();
+    let js = shex2linkml::convert::build_json_schema(std::path::Path::new("people"), &shapes);
+    assert_eq!(js["$id"], shex2linkml::convert::DEFAULT_JSON_SCHEMA_ID);
+    assert_eq!(js["definitions"]["Person"]["$id"], "#Person");
+
+    let options = shex2linkml::convert::JsonSchemaOptions {
+        draft: shex2linkml::convert::JsonSchemaDraft::Draft2020_12,
+        id: Some("https://example.org/people.json".to_string()),
+        ..Default::default()
+    };
+    let js = shex2linkml::convert::build_json_schema_with(std::path::Path::new("people"), &shapes, &options);
+    assert_eq!(js["$id"], "https://example.org/people.json");
+    assert_eq!(js["$defs"]["Person"]["$anchor"], "Person");
+    assert!(js["$defs"]["Person"].get("$id").is_none());
+    assert_eq!(shex2linkml::convert::JsonSchemaDraft::Draft2020_12.anchor("9lives"), None);
+}
+
+#[test]
+fn test_json_schema_root_options() {
+    let shex = r#"
+        PREFIX ex: <http://example.org/>
+        PREFIX xsd: <http://www.w3.org/2001/XMLSchema#>
+        start = @ex:Person
+        ex:Person { ex:name xsd:string }
+        ex:Organization { ex:name xsd:string }
+    "#;
+    let base = iri_s::iris::IriS::new_unchecked("http://example.org/");
+    let schema = shex_compact::ShExParser::parse(shex, None, &base).expect("parse shex");
+    let shapes = shex2linkml::convert::shapes_from_rudof_ast(&schema).unwrap();
+    let build = |root| {
+        let options = shex2linkml::convert::JsonSchemaOptions { root, ..Default::default() };
+        shex2linkml::convert::build_json_schema_with(std::path::Path::new("people"), &shapes, &options)
+    };
+    let js = build(shex2linkml::convert::JsonSchemaRoot::Start);
+    assert_eq!(js["$ref"], "#/definitions/Person");
+    let js = build(shex2linkml::convert::JsonSchemaRoot::Definitions);
+    assert!(js.get("$ref").is_none() && js.get("anyOf").is_none() && js.get("oneOf").is_none());
+    let js = build(shex2linkml::convert::JsonSchemaRoot::OneOf);
+    assert!(js.get("$ref").is_none());
+    assert_eq!(js["oneOf"], json!([{ "$ref": "#/definitions/Person" }, { "$ref": "#/definitions/Organization" }]));
+}
+
+#[test]
+fn test_json_schema_one_of_root_validates() {
+    let shex = r#"
+        PREFIX ex: <http://example.org/>
+        PREFIX xsd: <http://www.w3.org/2001/XMLSchema#>
+        ex:Person { ex:name xsd:string ; ex:address { ex:street xsd:string } ? }
+        ex:Employee EXTENDS @ex:Person { ex:employer xsd:string }
+        ex:Organization { ex:legalName xsd:string }
+    "#;
+    let base = iri_s::iris::IriS::new_unchecked("http://example.org/");
+    let schema = shex_compact::ShExParser::parse(shex, None, &base).expect("parse shex");
+    let shapes = shex2linkml::convert::shapes_from_rudof_ast(&schema).unwrap();
+    let options = shex2linkml::convert::JsonSchemaOptions { root: shex2linkml::convert::JsonSchemaRoot::OneOf, ..Default::default() };
+    let js = shex2linkml::convert::build_json_schema_with(std::path::Path::new("people"), &shapes, &options);
+    // an employee's document matches Person too, and the inline address shape is only part of a Person
+    assert_eq!(js["oneOf"], json!([{ "$ref": "#/definitions/Employee" }, { "$ref": "#/definitions/Organization" }]));
+    assert!(jsonschema::is_valid(&js, &json!({ "name": "Ann", "employer": "ACME", "address": { "street": "Main St" } })));
+    assert!(jsonschema::is_valid(&js, &json!({ "legalName": "ACME" })));
+    assert!(!jsonschema::is_valid(&js, &json!({ "street": "Main St" })));
+}
+
+#[test]
+fn test_json_schema_bundle() {
+    let shex = r#"
+        PREFIX ex: <http://example.org/>
+        PREFIX xsd: <http://www.w3.org/2001/XMLSchema#>
+        start = @ex:Person
+        ex:Person { ex:name xsd:string ; ex:worksFor @ex:Organization ? }
+        ex:Organization { ex:name xsd:string }
+    "#;
+    let base = iri_s::iris::IriS::new_unchecked("http://example.org/");
+    let schema = shex_compact::ShExParser::parse(shex, None, &base).expect("parse shex");
+    let shapes = shex2linkml::convert::shapes_from_rudof_ast(&schema).unwrap();
+    let options = shex2linkml::convert::JsonSchemaOptions { id: Some("https://example.org/schemas/people.json".to_string()), ..Default::default() };
+    let files = shex2linkml::convert::build_json_schema_bundle(std::path::Path::new("people"), &shapes, &options, "json");
+    let names: Vec<&str> = files.iter().map(|(name, _)| name.as_str()).collect();
+    assert_eq!(names, vec!["Person.schema.json", "Organization.schema.json", "index.schema.json"]);
+
+    let person = &files[0].1;
+    assert_eq!(person["$id"], "https://example.org/schemas/Person.schema.json");
+    assert_eq!(person["$ref"], "#/definitions/Person");
+    assert_eq!(person["definitions"]["Organization"], json!({ "$ref": "Organization.schema.json" }));
+    assert!(person["definitions"]["Person"]["properties"].get("worksFor").is_some());
+    assert!(files[1].1["definitions"].get("Person").is_none());
+
+    let index = &files[2].1;
+    assert_eq!(index["$id"], "https://example.org/schemas/index.schema.json");
+    assert_eq!(index["definitions"]["Person"], json!({ "$ref": "Person.schema.json" }));
+    assert_eq!(index["$ref"], "#/definitions/Person");
+
+    // IRI and CURIE names make file names of their own
+    let mut shapes = shapes;
+    shapes[0].name = "http://example.org/Person".to_string();
+    shapes[1].name = "ex:Organization".to_string();
+    shapes[0].properties[1].range = "ex:Organization".to_string();
+    let files = shex2linkml::convert::build_json_schema_bundle(std::path::Path::new("people"), &shapes, &options, "json");
+    let names: Vec<&str> = files.iter().map(|(name, _)| name.as_str()).collect();
+    assert_eq!(names, vec!["http___example_org_Person.schema.json", "ex_Organization.schema.json", "index.schema.json"]);
+    assert_eq!(files[0].1["definitions"]["ex:Organization"], json!({ "$ref": "ex_Organization.schema.json" }));
+    assert_eq!(files[2].1["definitions"]["http://example.org/Person"], json!({ "$ref": "http___example_org_Person.schema.json" }));
+    assert_eq!(files[2].1["$ref"], "#/definitions/http:~1~1example.org~1Person");
+}
+
+#[test]
+fn test_json_schema_rdf_keywords() {
+    let shex = r#"
+        PREFIX ex: <http://example.org/>
+        PREFIX xsd: <http://www.w3.org/2001/XMLSchema#>
+        ex:Person { ex:name xsd:string ; ex:born xsd:dateTime ? ; ex:knows @ex:Person * }
+    "#;
+    let base = iri_s::iris::IriS::new_unchecked("http://example.org/");
+    let schema = shex_compact::ShExParser::parse(shex, None, &base).expect("parse shex");
+    let shapes = shex2linkml::convert::shapes_from_rudof_ast(&schema).unwrap();
+    let js = shex2linkml::convert::build_json_schema(std::path::Path::new("people"), &shapes);
+    assert!(js["definitions"]["Person"].get("x-rdf-class").is_none());
+
+    let options = shex2linkml::convert::JsonSchemaOptions { rdf_keywords: true, ..Default::default() };
+    let js = shex2linkml::convert::build_json_schema_with(std::path::Path::new("people"), &shapes, &options);
+    let person = &js["definitions"]["Person"];
+    assert_eq!(person["x-rdf-class"], "http://example.org/Person");
+    assert_eq!(person["properties"]["born"]["x-rdf-predicate"], "http://example.org/born");
+    assert_eq!(person["properties"]["born"]["x-rdf-datatype"], "http://www.w3.org/2001/XMLSchema#dateTime");
+    assert_eq!(person["properties"]["knows"]["x-rdf-predicate"], "http://example.org/knows");
+    assert!(person["properties"]["knows"]["items"].get("x-rdf-datatype").is_none());
+
+    // the IRIs come back on reading the schema
+    let reread = json_schema_to_linkml::shapes_from_json_schema(&js).unwrap();
+    assert_eq!(reread[0].id, "http://example.org/Person");
+    let born = reread[0].properties.iter().find(|p| p.name == "born").unwrap();
+    assert_eq!((born.predicate.as_str(), born.range.as_str()), ("http://example.org/born", "datetime"));
+    let knows = reread[0].properties.iter().find(|p| p.name == "knows").unwrap();
+    assert_eq!((knows.predicate.as_str(), knows.range.as_str()), ("http://example.org/knows", "Person"));
+}
+
+#[test]
+fn test_json_schema_annotate() {
+    let shex = r#"
+        PREFIX ex: <http://example.org/>
+        PREFIX xsd: <http://www.w3.org/2001/XMLSchema#>
+        PREFIX rdfs: <http://www.w3.org/2000/01/rdf-schema#>
+        ex:Paint { ex:finish ["matte" "gloss"] ; ex:name xsd:string // rdfs:label "Paint name" }
+    "#;
+    let base = iri_s::iris::IriS::new_unchecked("http://example.org/");
+    let schema = shex_compact::ShExParser::parse(shex, None, &base).expect("parse shex");
+    let shapes = shex2linkml::convert::shapes_from_rudof_ast(&schema).unwrap();
+    let options = shex2linkml::convert::JsonSchemaOptions { annotate: true, ..Default::default() };
+    let js = shex2linkml::convert::build_json_schema_with(std::path::Path::new("paint"), &shapes, &options);
+    let paint = &js["definitions"]["Paint"];
+    assert_eq!(paint["title"], "Paint");
+    assert_eq!(paint["properties"]["finish"]["title"], "finish");
+    assert_eq!(paint["properties"]["finish"]["examples"], json!(["matte", "gloss"]));
+    assert_eq!(paint["properties"]["name"]["title"], "Paint name");
+
+    // titles that only repeat names aren't read back as labels
+    let reread = json_schema_to_linkml::shapes_from_json_schema(&js).unwrap();
+    assert_eq!(reread[0].title, None);
+    let name = reread[0].properties.iter().find(|p| p.name == "name").unwrap();
+    assert_eq!(name.title.as_deref(), Some("Paint name"));
+}
+
+#[test]
+fn test_json_schema_additional_properties() {
+    let shex = r#"
+        PREFIX ex: <http://example.org/>
+        PREFIX xsd: <http://www.w3.org/2001/XMLSchema#>
+        ex:Agent { ex:name xsd:string }
+        ex:Person CLOSED { ex:age xsd:integer }
+    "#;
+    let base = iri_s::iris::IriS::new_unchecked("http://example.org/");
+    let schema = shex_compact::ShExParser::parse(shex, None, &base).expect("parse shex");
+    let shapes = shex2linkml::convert::shapes_from_rudof_ast(&schema).unwrap();
+    let closed = |policy| {
+        let options = shex2linkml::convert::JsonSchemaOptions { additional_properties: policy, ..Default::default() };
+        let js = shex2linkml::convert::build_json_schema_with(std::path::Path::new("people"), &shapes, &options);
+        ["Agent", "Person"].map(|name| js["definitions"][name].get("additionalProperties") == Some(&json!(false)))
+    };
+    assert_eq!(closed(shex2linkml::convert::AdditionalProperties::FromShex), [false, true]);
+    assert_eq!(closed(shex2linkml::convert::AdditionalProperties::Allow), [false, false]);
+    assert_eq!(closed(shex2linkml::convert::AdditionalProperties::Deny), [true, true]);
+}
+
+#[test]
+fn test_json_schema_ref_resolution() {
+    let schema = json!({
+        "$ref": "#Person",
+        "$defs": {
+            "Color": { "type": "string", "enum": ["red", "green"] },
+            "Shade": { "$ref": "#/$defs/Color" },
+            "Person": {
+                "$anchor": "Person",
+                "type": "object",
+                "properties": {
+                    "favorite": { "$ref": "#/$defs/Shade", "description": "best liked" },
+                    "knows": { "type": "array", "items": { "$ref": "#Person" } },
+                    "address": { "$ref": "address.json#/definitions/Address" }
+                }
+            }
+        }
+    });
+    // local references to value schemas are inlined; those to shapes stay references
+    let resolved = json_schema_to_linkml::resolve_refs(&schema, None, false).unwrap();
+    let props = &resolved["$defs"]["Person"]["properties"];
+    assert_eq!(props["favorite"], json!({ "type": "string", "enum": ["red", "green"], "description": "best liked" }));
+    assert_eq!(props["knows"]["items"], json!({ "$ref": "#Person" }));
+    // with no directory to read it from, the other document's is left as it is
+    assert_eq!(props["address"], json!({ "$ref": "address.json#/definitions/Address" }));
+    assert_eq!(json_schema_to_linkml::unresolved_ref_warnings(&resolved), vec!["$ref address.json#/definitions/Address is left unresolved"]);
+    let shapes = json_schema_to_linkml::shapes_from_json_schema(&resolved).unwrap();
+    let person = shapes.iter().find(|s| s.name == "Person").unwrap();
+    assert!(person.start);
+    let prop = |name: &str| person.properties.iter().find(|p| p.name == name).unwrap();
+    assert_eq!(prop("favorite").values, vec!["red", "green"]);
+    assert_eq!(prop("knows").range, "Person");
+
+    // other files' definitions are merged in, with or without fetching
+    let dir = std::env::temp_dir().join("shex2linkml-refs");
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("address.json"), json!({
+        "definitions": {
+            "Address": { "type": "object", "properties": { "city": { "$ref": "#/definitions/City" } } },
+            "City": { "type": "string", "maxLength": 40 }
+        }
+    }).to_string()).unwrap();
+    let resolved = json_schema_to_linkml::resolve_refs(&schema, Some(&dir), false).unwrap();
+    assert_eq!(resolved["$defs"]["Person"]["properties"]["address"], json!({ "$ref": "#/definitions/Address" }));
+    assert!(json_schema_to_linkml::unresolved_ref_warnings(&resolved).is_empty());
+    assert_eq!(resolved["definitions"]["Address"]["properties"]["city"], json!({ "type": "string", "maxLength": 40 }));
+    let shapes = json_schema_to_linkml::shapes_from_json_schema(&resolved).unwrap();
+    assert!(shapes.iter().any(|s| s.name == "Address"));
+
+    // documents at URLs are only fetched when asked for
+    let mut remote = schema.clone();
+    remote["$defs"]["Person"]["properties"]["address"]["$ref"] = json!("https://example.org/address.json#/definitions/Address");
+    let resolved = json_schema_to_linkml::resolve_refs(&remote, Some(&dir), false).unwrap();
+    assert_eq!(resolved["$defs"]["Person"]["properties"]["address"], json!({ "$ref": "https://example.org/address.json#/definitions/Address" }));
+    assert_eq!(json_schema_to_linkml::unresolved_ref_warnings(&resolved),
+        vec!["$ref https://example.org/address.json#/definitions/Address is left unresolved without --fetch-imports"]);
+
+    // references that go nowhere or round in circles are errors
+    assert!(json_schema_to_linkml::resolve_refs(&json!({ "$ref": "#/$defs/Missing" }), None, false).is_err());
+    let cyclic = json!({ "$defs": { "A": { "$ref": "#/$defs/B" }, "B": { "$ref": "#/$defs/A" } } });
+    assert!(json_schema_to_linkml::resolve_refs(&cyclic, None, false).is_err());
+    assert!(json_schema_to_linkml::resolve_refs(&schema, Some(&dir.join("nowhere")), false).is_err());
+}
+
+#[test]
+fn test_json_schema_conditionals() {
+    let schema = json!({
+        "definitions": {
+            "Payment": {
+                "type": "object",
+                "properties": {
+                    "method": { "enum": ["card", "cash"] },
+                    "cardNumber": { "type": "string" },
+                    "amount": { "type": "number" },
+                    "billing": { "type": "string" }
+                },
+                "if": { "properties": { "method": { "const": "card" } }, "required": ["method"] },
+                "then": { "required": ["cardNumber"] },
+                "dependentRequired": { "cardNumber": ["billing"] },
+                "dependentSchemas": { "amount": { "required": ["method"] } }
+            }
+        }
+    });
+
+    let shapes = json_schema_to_linkml::shapes_from_json_schema(&schema).unwrap();
+    let rules: Vec<_> = shapes[0].constraints.iter().filter_map(|c| match c {
+        shex2linkml::convert::ShapeConstraint::Rule { preconditions, postconditions, .. } => Some((preconditions, postconditions)),
+        _ => None,
+    }).collect();
+    assert_eq!(rules.len(), 2);
+    assert_eq!(rules[0].0[0].values, vec!["card"]);
+    assert_eq!(rules[0].1[0].name, "cardNumber");
+    assert_eq!(rules[1].1[0].name, "billing");
+
+    let linkml = json_schema_to_linkml::json_schema_to_linkml(&schema.to_string()).unwrap();
+    let doc: Yaml = serde_yaml::from_str(&linkml).unwrap();
+    let rule = &doc["classes"]["Payment"]["rules"][0];
+    assert_eq!(rule["preconditions"]["slot_conditions"]["method"]["equals_string"].as_str(), Some("card"));
+    assert_eq!(rule["postconditions"]["slot_conditions"]["cardNumber"]["required"].as_bool(), Some(true));
+
+    // ShEx approximates a rule as the OR of its precondition failing and its postcondition holding
+    let shex = shex2linkml::json_schema_to_shex(&schema.to_string()).unwrap();
+    assert!(shex.contains("NOT {"));
+    let base = iri_s::iris::IriS::new_unchecked("http://example.org/");
+    shex_compact::ShExParser::parse(&shex, None, &base).expect("generated ShEx parses");
+
+    let js = shex2linkml::convert::build_json_schema(std::path::Path::new("payments"), &shapes);
+    let payment = &js["definitions"]["Payment"];
+    assert_eq!(payment["if"]["properties"]["method"]["const"], "card");
+    assert_eq!(payment["then"]["required"], json!(["cardNumber"]));
+    assert_eq!(payment["dependencies"]["cardNumber"], json!(["billing"]));
+    assert!(payment.get("dependentRequired").is_none());
+    let options = shex2linkml::convert::JsonSchemaOptions { draft: shex2linkml::convert::JsonSchemaDraft::Draft2020_12, ..Default::default() };
+    let js = shex2linkml::convert::build_json_schema_with(std::path::Path::new("payments"), &shapes, &options);
+    let payment = &js["$defs"]["Payment"];
+    assert_eq!(payment["dependentRequired"]["cardNumber"], json!(["billing"]));
+    assert!(payment.get("dependencies").is_none());
+
+    // what the rules can't keep is reported
+    let warnings = json_schema_to_linkml::conditional_warnings(&schema);
+    assert_eq!(warnings, vec!["Payment: the schema `amount` depends on isn't kept"]);
+}
@@ -0,0 +1,1010 @@
+use shex2linkml::{linkml_to_shacl, linkml_to_shex};
+
+const PERSON_LINKML: &str = r#"
+id: people
+prefixes:
+  ex: http://example.org/
+classes:
+  Person:
+    class_uri: ex:Person
+    slots:
+      - name
+      - nick
+      - status
+      - knows
+slots:
+  name:
+    slot_uri: ex:name
+    range: string
+    required: true
+  nick:
+    range: string
+    multivalued: true
+  status:
+    range: StatusEnum
+  knows:
+    range: Person
+    min_count: 0
+    max_count: 5
+enums:
+  StatusEnum:
+    permissible_values:
+      active: {}
+      retired: {}
+"#;
+
+#[test]
+fn test_shapes_from_linkml() {
+    let shapes = linkml_to_shacl::shapes_from_linkml(PERSON_LINKML).unwrap();
+    assert_eq!(shapes[0].id, "ex:Person");
+
+    let prop = |n: &str| shapes[0].properties.iter().find(|p| p.name == n).unwrap();
+    assert_eq!((prop("name").predicate.as_str(), prop("name").min, prop("name").max), ("ex:name", Some(1), Some(1)));
+    assert_eq!((prop("nick").min, prop("nick").max), (Some(0), None));
+    assert_eq!(prop("status").values, vec!["active", "retired"]);
+    assert_eq!((prop("knows").range.as_str(), prop("knows").max), ("Person", Some(5)));
+}
+
+#[test]
+fn test_linkml_to_shacl() {
+    let shacl = linkml_to_shacl::linkml_yaml_to_shacl(PERSON_LINKML).unwrap();
+    assert!(shacl.contains("sh:path ex:name ;\n    sh:datatype xsd:string ;\n    sh:minCount 1 ;\n    sh:maxCount 1"));
+    assert!(shacl.contains("sh:path ex:status ;\n    sh:in ( \"active\" \"retired\" )"));
+    assert!(shacl.contains("sh:path ex:knows ;\n    sh:node ex:Person ;\n    sh:maxCount 5"));
+}
+
+#[test]
+fn test_linkml_to_shexj() {
+    let shexj = linkml_to_shex::linkml_yaml_to_shex_as(PERSON_LINKML, linkml_to_shex::ShexSyntax::Json).unwrap();
+    let doc: serde_json::Value = serde_json::from_str(&shexj).unwrap();
+    assert_eq!(doc["type"], "Schema");
+    let decl = &doc["shapes"][0];
+    // `class_uri` names the shape
+    assert_eq!(decl["id"], "http://example.org/Person");
+    let tcs = decl["shapeExpr"]["expression"]["expressions"].as_array().unwrap();
+    assert_eq!(tcs.len(), 4);
+    assert_eq!(tcs[0]["type"], "TripleConstraint");
+    assert_eq!(tcs[0]["predicate"], "http://example.org/name");
+    assert_eq!(tcs[3]["max"], 5);
+    // a class range refers to the class's shape
+    assert_eq!(tcs[3]["valueExpr"], "http://example.org/Person");
+    // a multivalued slot without max_count is unbounded
+    assert_eq!((tcs[1]["min"].clone(), tcs[1]["max"].clone()), (serde_json::json!(0), serde_json::json!(-1)));
+}
+
+#[test]
+fn test_linkml_json_form() {
+    // tab-indented JSON isn't valid YAML, so this exercises the JSON path
+    let doc: serde_yaml::Value = serde_yaml::from_str(PERSON_LINKML).unwrap();
+    let json = serde_json::to_string_pretty(&doc).unwrap().replace("  ", "\t");
+
+    let shapes = linkml_to_shacl::shapes_from_linkml(&json).unwrap();
+    assert_eq!(shapes, linkml_to_shacl::shapes_from_linkml(PERSON_LINKML).unwrap());
+    assert_eq!(
+        linkml_to_shex::linkml_yaml_to_shex(&json).unwrap(),
+        linkml_to_shex::linkml_yaml_to_shex(PERSON_LINKML).unwrap()
+    );
+
+    let emitted = shex2linkml::convert::build_linkml_json(std::path::Path::new("people.shex"), &shapes).unwrap();
+    let emitted: serde_json::Value = serde_json::from_str(&emitted).unwrap();
+    assert_eq!(emitted["id"], "people");
+    assert_eq!(emitted["slots"]["knows"]["maximum_cardinality"], 5);
+}
+
+const HIERARCHY_LINKML: &str = r#"
+id: hierarchy
+prefixes:
+  ex: http://example.org/
+classes:
+  Named:
+    slots:
+      - name
+  Agent:
+    slots:
+      - id
+  Person:
+    is_a: Named
+    mixins:
+      - Agent
+    slots:
+      - age
+  Employee:
+    is_a: Person
+slots:
+  name:
+    range: string
+  id:
+    range: string
+  age:
+    range: integer
+"#;
+
+#[test]
+fn test_linkml_inheritance() {
+    let shapes = linkml_to_shacl::shapes_from_linkml(HIERARCHY_LINKML).unwrap();
+    assert_eq!(shapes[2].extends, vec!["Named", "Agent"]);
+    assert_eq!(shapes[2].properties.len(), 1);
+
+    // LinkML -> ShEx regenerates EXTENDS
+    let shex = linkml_to_shex::linkml_yaml_to_shex(HIERARCHY_LINKML).unwrap();
+    assert!(shex.contains("<Person> EXTENDS @<Named> EXTENDS @<Agent> {\n"));
+    assert!(shex.contains("<Employee> EXTENDS @<Person> {\n}\n"));
+
+    // shape model -> ShEx, LinkML and JSON Schema keep the hierarchy
+    let base = iri_s::iris::IriS::new_unchecked("http://example.org/");
+    let shex = shex2linkml::convert::build_shex(&shapes);
+    assert!(shex.contains("ex:Person EXTENDS @ex:Named EXTENDS @ex:Agent {"));
+    shex_compact::ShExParser::parse(&shex, None, &base).expect("generated ShEx parses");
+
+    let path = std::path::Path::new("hierarchy.yaml");
+    let linkml: serde_yaml::Value = serde_yaml::from_str(&shex2linkml::convert::build_linkml_doc(path, &shapes).unwrap()).unwrap();
+    assert_eq!(linkml["classes"]["Person"]["is_a"].as_str(), Some("Named"));
+    assert_eq!(linkml["classes"]["Person"]["mixins"][0].as_str(), Some("Agent"));
+
+    let js = shex2linkml::convert::build_json_schema(path, &shapes);
+    assert_eq!(js["definitions"]["Employee"]["allOf"], serde_json::json!([{ "$ref": "#/definitions/Person" }]));
+}
+
+const ATTRIBUTES_LINKML: &str = r#"
+id: attributes
+prefixes:
+  ex: http://example.org/
+classes:
+  Person:
+    slots:
+      - name
+    attributes:
+      age:
+        range: integer
+        required: true
+      aliases:
+        multivalued: true
+      employer:
+        slot_uri: ex:worksFor
+        range: Organization
+  Organization:
+    attributes:
+      label:
+slots:
+  name:
+    range: string
+"#;
+
+#[test]
+fn test_linkml_attributes() {
+    let shex = linkml_to_shex::linkml_yaml_to_shex(ATTRIBUTES_LINKML).unwrap();
+    assert!(shex.contains("<Person> {\n  ex:name xsd:string ? ;\n  ex:age xsd:integer ;\n  ex:aliases xsd:string * ;\n  ex:worksFor @<Organization> ?\n}\n"));
+    assert!(shex.contains("<Organization> {\n  ex:label xsd:string ?\n}\n"));
+
+    let shexj = linkml_to_shex::linkml_yaml_to_shex_as(ATTRIBUTES_LINKML, linkml_to_shex::ShexSyntax::Json).unwrap();
+    let doc: serde_json::Value = serde_json::from_str(&shexj).unwrap();
+    let tcs = doc["shapes"][0]["shapeExpr"]["expression"]["expressions"].as_array().unwrap();
+    assert_eq!(tcs[1]["predicate"], "http://example.org/age");
+    assert!(tcs[1].get("min").is_none());
+    assert_eq!(tcs[3]["predicate"], "http://example.org/worksFor");
+}
+
+#[test]
+fn test_linkml_cardinality_keys() {
+    let shex = r#"
+        PREFIX ex: <http://example.org/>
+        PREFIX xsd: <http://www.w3.org/2001/XMLSchema#>
+        ex:Person { ex:name xsd:string ; ex:nick xsd:string ? ; ex:email xsd:string + ; ex:tag xsd:string * ; ex:phone xsd:string {2,5} }
+    "#;
+    let base = iri_s::iris::IriS::new_unchecked("http://example.org/");
+    let schema = shex_compact::ShExParser::parse(shex, None, &base).expect("parse shex");
+    let shapes = shex2linkml::convert::shapes_from_rudof_ast(&schema).unwrap();
+    let linkml = shex2linkml::convert::build_linkml_doc(std::path::Path::new("person"), &shapes).unwrap();
+    let doc: serde_yaml::Value = serde_yaml::from_str(&linkml).unwrap();
+    let keys = |slot: &str| -> Vec<String> {
+        doc["slots"][slot].as_mapping().unwrap().keys().filter_map(|k| k.as_str())
+            .filter(|k| ["required", "multivalued", "minimum_cardinality", "maximum_cardinality"].contains(k))
+            .map(str::to_string).collect()
+    };
+    assert_eq!(keys("name"), vec!["required"]);
+    assert!(keys("nick").is_empty());
+    assert_eq!(keys("email"), vec!["required", "multivalued"]);
+    assert_eq!(keys("tag"), vec!["multivalued"]);
+    assert_eq!(keys("phone"), vec!["required", "minimum_cardinality", "multivalued", "maximum_cardinality"]);
+    assert!(!linkml.contains("min_count") && !linkml.contains("max_count"));
+
+    // and back again
+    let read_back = linkml_to_shacl::shapes_from_linkml(&linkml).unwrap();
+    let cards: Vec<(Option<u64>, Option<u64>)> = read_back[0].properties.iter().map(|p| (p.min, p.max)).collect();
+    assert_eq!(cards, vec![(Some(1), Some(1)), (Some(0), Some(1)), (Some(1), None), (Some(0), None), (Some(2), Some(5))]);
+    let shexc = linkml_to_shex::linkml_yaml_to_shex(&linkml
+        .replace("    slot_uri: ex:nick\n", "    slot_uri: ex:nick\n    recommended: true\n")
+        .replace("    slot_uri: ex:tag\n", "    slot_uri: ex:tag\n    exact_cardinality: 3\n")).unwrap();
+    assert!(shexc.contains("  ex:email xsd:string + ;\n"));
+    assert!(shexc.contains("  ex:tag xsd:string {3} ;\n"));
+    assert!(shexc.contains("  ex:phone xsd:string {2,5}\n"));
+    assert!(shexc.contains("  ex:nick xsd:string ? ; # recommended\n"));
+}
+
+#[test]
+fn test_linkml_flatten() {
+    let options = linkml_to_shex::ShexOptions { flatten: true, ..Default::default() };
+    let shex = linkml_to_shex::linkml_yaml_to_shex_with(HIERARCHY_LINKML, &options).unwrap();
+    assert!(!shex.contains("EXTENDS"));
+    // parents' slots come first, is_a before mixins
+    assert!(shex.contains("<Person> {\n  ex:name xsd:string ? ;\n  ex:id xsd:string ? ;\n  ex:age xsd:integer ?\n}\n"));
+    assert!(shex.contains("<Employee> {\n  ex:name xsd:string ? ;\n  ex:id xsd:string ? ;\n  ex:age xsd:integer ?\n}\n"));
+    assert!(shex.contains("<Named> {\n  ex:name xsd:string ?\n}\n"));
+}
+
+#[test]
+fn test_linkml_closed_option() {
+    let shex = linkml_to_shex::linkml_yaml_to_shex(HIERARCHY_LINKML).unwrap();
+    assert!(!shex.contains("CLOSED"));
+    let options = linkml_to_shex::ShexOptions { closed: true, ..Default::default() };
+    let shex = linkml_to_shex::linkml_yaml_to_shex_with(HIERARCHY_LINKML, &options).unwrap();
+    assert!(shex.contains("<Named> CLOSED {\n"), "{}", shex);
+    assert!(shex.contains("<Employee> EXTENDS @<Person> CLOSED {\n}\n"), "{}", shex);
+}
+
+#[test]
+fn test_linkml_shexc_style() {
+    let style = |style: linkml_to_shex::ShexcStyle| {
+        linkml_to_shex::linkml_yaml_to_shex_with(HIERARCHY_LINKML, &linkml_to_shex::ShexOptions { style, ..Default::default() }).unwrap()
+    };
+    let shex = style(linkml_to_shex::ShexcStyle { indent: 4, ..Default::default() });
+    assert!(shex.contains("<Named> {\n    ex:name xsd:string ?\n}\n"), "{}", shex);
+
+    let compact = linkml_to_shex::ShexcStyle { compact: true, ..Default::default() };
+    let shex = style(compact.clone());
+    assert!(shex.contains("\n<Named> { ex:name xsd:string ? }\n"), "{}", shex);
+    assert!(shex.contains("\n<Employee> EXTENDS @<Person> { }\n"), "{}", shex);
+    let base = iri_s::iris::IriS::new_unchecked("http://example.org/");
+    shex_compact::ShExParser::parse(&shex, None, &base).unwrap_or_else(|e| panic!("{:?} parsing\n{}", e, shex));
+
+    // shapes too wide for the line go one constraint per line
+    let shex = style(linkml_to_shex::ShexcStyle { max_width: Some(40), ..compact });
+    assert!(shex.contains("\n<Named> { ex:name xsd:string ? }\n"), "{}", shex);
+    assert!(shex.contains("\n<Person> EXTENDS @<Named> EXTENDS @<Agent> {\n  ex:age xsd:integer ?\n}\n"), "{}", shex);
+
+    let unsorted = PERSON_LINKML.replace("prefixes:\n", "prefixes:\n  zz: http://zz.example/\n");
+    let options = linkml_to_shex::ShexOptions { style: linkml_to_shex::ShexcStyle { sort_prefixes: true, ..Default::default() }, ..Default::default() };
+    let shex = linkml_to_shex::linkml_yaml_to_shex_with(&unsorted, &options).unwrap();
+    assert!(shex.starts_with("PREFIX ex: <http://example.org/>\nPREFIX xsd: <http://www.w3.org/2001/XMLSchema#>\nPREFIX zz: <http://zz.example/>\n"), "{}", shex);
+}
+
+#[test]
+fn test_linkml_shex_prefixes() {
+    // the schema's prefixes head the output, with xsd only when a datatype needs it
+    let shex = linkml_to_shex::linkml_yaml_to_shex(HIERARCHY_LINKML).unwrap();
+    assert!(shex.starts_with("PREFIX ex: <http://example.org/>\nPREFIX xsd: <http://www.w3.org/2001/XMLSchema#>\n"), "{}", shex);
+    let shex = linkml_to_shex::linkml_yaml_to_shex(ENUM_LINKML).unwrap();
+    assert!(shex.starts_with("PREFIX ex: <http://example.org/>\nPREFIX obo: <http://purl.obolibrary.org/obo/>\n"), "{}", shex);
+    assert!(!shex.contains("PREFIX xsd:"), "{}", shex);
+}
+
+const ENUM_LINKML: &str = r#"
+id: enums
+prefixes:
+  ex: http://example.org/
+  obo: http://purl.obolibrary.org/obo/
+classes:
+  Sample:
+    class_uri: obo:OBI_0000747
+    slots:
+      - status
+      - organ
+slots:
+  status:
+    range: StatusEnum
+    required: true
+  organ:
+    range: OrganEnum
+enums:
+  StatusEnum:
+    permissible_values:
+      active:
+      retired: {}
+  OrganEnum:
+    permissible_values:
+      heart:
+        meaning: obo:UBERON_0000948
+      liver:
+        meaning: http://purl.obolibrary.org/obo/UBERON_0002107
+      other:
+"#;
+
+#[test]
+fn test_linkml_enum_value_sets() {
+    let shex = linkml_to_shex::linkml_yaml_to_shex(ENUM_LINKML).unwrap();
+    assert!(shex.contains("<http://purl.obolibrary.org/obo/OBI_0000747> {\n"));
+    assert!(shex.contains("  ex:status [ \"active\" \"retired\" ] ;\n"));
+    assert!(shex.contains("  ex:organ [ <http://purl.obolibrary.org/obo/UBERON_0000948> <http://purl.obolibrary.org/obo/UBERON_0002107> \"other\" ] ?\n"));
+
+    let shexj = linkml_to_shex::linkml_yaml_to_shex_as(ENUM_LINKML, linkml_to_shex::ShexSyntax::Json).unwrap();
+    let doc: serde_json::Value = serde_json::from_str(&shexj).unwrap();
+    let organ = &doc["shapes"][0]["shapeExpr"]["expression"]["expressions"][1];
+    assert_eq!(organ["valueExpr"]["values"], serde_json::json!([
+        "http://purl.obolibrary.org/obo/UBERON_0000948",
+        "http://purl.obolibrary.org/obo/UBERON_0002107",
+        { "value": "other" }
+    ]));
+}
+
+const SLOT_USAGE_LINKML: &str = r#"
+id: usage
+prefixes:
+  ex: http://example.org/
+classes:
+  Reading:
+    slots:
+      - value
+      - note
+    slot_usage:
+      value:
+        range: integer
+        required: true
+  Label:
+    slots:
+      - value
+  Person:
+    slots:
+      - name
+  Employee:
+    is_a: Person
+    slot_usage:
+      name:
+        required: true
+slots:
+  value:
+    range: string
+  note:
+    range: string
+  name:
+    range: string
+"#;
+
+#[test]
+fn test_linkml_slot_usage_to_shex() {
+    let shex = linkml_to_shex::linkml_yaml_to_shex(SLOT_USAGE_LINKML).unwrap();
+    assert!(shex.contains("<Reading> {\n  ex:value xsd:integer ;\n  ex:note xsd:string ?\n}\n"));
+    assert!(shex.contains("<Label> {\n  ex:value xsd:string ?\n}\n"));
+    // EXTENDS can't refine an inherited slot
+    assert!(shex.contains("<Employee> EXTENDS @<Person> {\n}\n"));
+
+    // a flattened shape can
+    let options = linkml_to_shex::ShexOptions { flatten: true, ..Default::default() };
+    let flat = linkml_to_shex::linkml_yaml_to_shex_with(SLOT_USAGE_LINKML, &options).unwrap();
+    assert!(flat.contains("<Employee> {\n  ex:name xsd:string\n}\n"));
+    assert!(flat.contains("<Person> {\n  ex:name xsd:string ?\n}\n"));
+}
+
+const IDENTIFIER_LINKML: &str = r#"
+id: identified
+prefixes:
+  ex: http://example.org/
+classes:
+  Person:
+    slots:
+      - id
+      - name
+slots:
+  id:
+    identifier: true
+    range: uriorcurie
+  name:
+    range: string
+    required: true
+"#;
+
+#[test]
+fn test_linkml_identifier() {
+    let shapes = linkml_to_shacl::shapes_from_linkml(IDENTIFIER_LINKML).unwrap();
+    assert_eq!(shapes[0].identifier.as_deref(), Some("id"));
+    assert_eq!(shapes[0].properties.iter().map(|p| p.name.as_str()).collect::<Vec<_>>(), vec!["name"]);
+
+    // the identifier is the node's IRI, not a triple
+    let shex = linkml_to_shex::linkml_yaml_to_shex(IDENTIFIER_LINKML).unwrap();
+    assert!(shex.contains("<Person> IRI AND {\n  ex:name xsd:string\n}\n"));
+    let shexj = linkml_to_shex::linkml_yaml_to_shex_as(IDENTIFIER_LINKML, linkml_to_shex::ShexSyntax::Json).unwrap();
+    let doc: serde_json::Value = serde_json::from_str(&shexj).unwrap();
+    assert_eq!(doc["shapes"][0]["shapeExpr"]["shapeExprs"][0], serde_json::json!({ "type": "NodeConstraint", "nodeKind": "iri" }));
+
+    let shex = shex2linkml::convert::build_shex(&shapes);
+    assert!(shex.contains("ex:Person IRI {\n"));
+    let shacl = shex2linkml::convert::build_shacl(&shapes);
+    assert!(shacl.contains("ex:Person a sh:NodeShape ;\n  sh:nodeKind sh:IRI"));
+
+    // JSON Schema requires it and flags it, which reads back as the identifier
+    let schema = shex2linkml::convert::build_json_schema(std::path::Path::new("identified.yaml"), &shapes);
+    let person = &schema["definitions"]["Person"];
+    assert_eq!(person["properties"]["id"], serde_json::json!({ "type": "string", "format": "iri", "x-identifier": true }));
+    assert!(person["required"].as_array().unwrap().contains(&serde_json::json!("id")));
+    let back = shex2linkml::json_schema_to_linkml::shapes_from_json_schema(&schema).unwrap();
+    assert_eq!(back.iter().find(|s| s.name == "Person").unwrap().identifier.as_deref(), Some("id"));
+
+    // and LinkML writes it back as an identifier slot
+    let linkml = shex2linkml::convert::build_linkml_doc(std::path::Path::new("identified.yaml"), &shapes).unwrap();
+    assert!(linkml.contains("  id:\n    identifier: true\n    range: uriorcurie\n"));
+}
+
+const PATTERN_LINKML: &str = r#"
+id: patterns
+prefixes:
+  ex: http://example.org/
+settings:
+  float: "\\d+(\\.\\d+)?"
+  unit: "(cm|mm)"
+classes:
+  Sample:
+    slots:
+      - path
+      - length
+      - code
+slots:
+  path:
+    pattern: "^/data/[a-z]+$"
+  length:
+    structured_pattern:
+      syntax: "^{float} {unit}$"
+      interpolated: true
+  code:
+    pattern: "^[A-Z]{2}\\/[0-9]{3}$"
+"#;
+
+#[test]
+fn test_linkml_patterns() {
+    // slashes are escaped in ShExC unless they already are
+    let shex = linkml_to_shex::linkml_yaml_to_shex(PATTERN_LINKML).unwrap();
+    assert!(shex.contains("  ex:path xsd:string /^\\/data\\/[a-z]+$/ ? ;\n"));
+    assert!(shex.contains("  ex:length xsd:string /^\\d+(\\.\\d+)? (cm|mm)$/ ? ;\n"));
+    assert!(shex.contains("  ex:code xsd:string /^[A-Z]{2}\\/[0-9]{3}$/ ?\n"));
+
+    let shexj = linkml_to_shex::linkml_yaml_to_shex_as(PATTERN_LINKML, linkml_to_shex::ShexSyntax::Json).unwrap();
+    let doc: serde_json::Value = serde_json::from_str(&shexj).unwrap();
+    let path = &doc["shapes"][0]["shapeExpr"]["expression"]["expressions"][0];
+    assert_eq!(path["valueExpr"], serde_json::json!({ "type": "NodeConstraint", "datatype": "http://www.w3.org/2001/XMLSchema#string", "pattern": "^/data/[a-z]+$" }));
+
+    // JSON Schema patterns need no escaping beyond JSON's own
+    let shapes = linkml_to_shacl::shapes_from_linkml(PATTERN_LINKML).unwrap();
+    let schema = shex2linkml::convert::build_json_schema(std::path::Path::new("patterns.yaml"), &shapes);
+    let props = &schema["definitions"]["Sample"]["properties"];
+    assert_eq!(props["path"]["pattern"], "^/data/[a-z]+$");
+    assert_eq!(props["length"]["pattern"], "^\\d+(\\.\\d+)? (cm|mm)$");
+    assert_eq!(props["code"]["pattern"], "^[A-Z]{2}\\/[0-9]{3}$");
+}
+
+const BOUNDS_LINKML: &str = r#"
+id: bounds
+prefixes:
+  ex: http://example.org/
+classes:
+  Person:
+    slots:
+      - age
+      - score
+slots:
+  age:
+    range: integer
+    required: true
+    minimum_value: 0
+    maximum_value: 150
+  score:
+    range: float
+    minimum_value: 0.5
+"#;
+
+#[test]
+fn test_linkml_value_bounds() {
+    let shex = linkml_to_shex::linkml_yaml_to_shex(BOUNDS_LINKML).unwrap();
+    assert!(shex.contains("  ex:age xsd:integer MININCLUSIVE 0 MAXINCLUSIVE 150 ;\n"));
+    assert!(shex.contains("  ex:score xsd:float MININCLUSIVE 0.5 ?\n"));
+
+    let shexj = linkml_to_shex::linkml_yaml_to_shex_as(BOUNDS_LINKML, linkml_to_shex::ShexSyntax::Json).unwrap();
+    let doc: serde_json::Value = serde_json::from_str(&shexj).unwrap();
+    let age = &doc["shapes"][0]["shapeExpr"]["expression"]["expressions"][0];
+    assert_eq!(age["valueExpr"]["mininclusive"], 0);
+    assert_eq!(age["valueExpr"]["maxinclusive"], 150);
+
+    let shapes = linkml_to_shacl::shapes_from_linkml(BOUNDS_LINKML).unwrap();
+    let schema = shex2linkml::convert::build_json_schema(std::path::Path::new("bounds.yaml"), &shapes);
+    let props = &schema["definitions"]["Person"]["properties"];
+    assert_eq!(props["age"]["minimum"], 0);
+    assert_eq!(props["age"]["maximum"], 150);
+    assert_eq!(props["score"]["minimum"], 0.5);
+}
+
+const IMPORTS_LINKML: &str = r#"
+id: imports
+prefixes:
+  ex: http://example.org/
+imports:
+  - linkml:types
+  - core
+classes:
+  Person:
+    is_a: Thing
+    slots:
+      - born
+      - homepage
+      - age
+slots:
+  born:
+    range: date
+  homepage:
+    range: uriorcurie
+  age:
+    range: integer
+"#;
+
+const CORE_LINKML: &str = r#"
+id: core
+prefixes:
+  ex: http://example.org/
+imports:
+  - linkml:types
+classes:
+  Thing:
+    slots:
+      - label
+slots:
+  label:
+    range: string
+"#;
+
+#[test]
+fn test_linkml_imports() {
+    // the bundled linkml:types gives the built-in ranges their datatypes
+    let shex = linkml_to_shex::linkml_yaml_to_shex(IMPORTS_LINKML).unwrap();
+    assert!(shex.contains("  ex:born xsd:date ? ;\n"));
+    assert!(shex.contains("  ex:homepage IRI ? ;\n"));
+    assert!(shex.contains("  ex:age xsd:integer ?\n"));
+
+    // other imports are read from beside the importing schema
+    let dir = std::env::temp_dir().join("shex2linkml-imports");
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("core.yaml"), CORE_LINKML).unwrap();
+    let resolved = linkml_to_shacl::resolve_imports(IMPORTS_LINKML, Some(&dir), false).unwrap();
+    let shex = linkml_to_shex::linkml_yaml_to_shex(&resolved).unwrap();
+    assert!(shex.contains("<Thing> {\n  ex:label xsd:string ?\n}\n"));
+    let shapes = linkml_to_shacl::shapes_from_linkml(&resolved).unwrap();
+    assert!(shapes.iter().any(|s| s.name == "Thing"));
+
+    // a missing import is an error rather than silently dropped
+    let missing = linkml_to_shacl::resolve_imports(IMPORTS_LINKML, Some(&dir.join("nowhere")), false);
+    assert!(missing.is_err());
+    // and remote ones are only fetched when asked
+    let remote = IMPORTS_LINKML.replace("  - core\n", "  - https://example.org/schemas/core\n");
+    assert!(linkml_to_shacl::resolve_imports(&remote, Some(&dir), false).is_err());
+}
+
+const BUILTINS_LINKML: &str = r#"
+id: builtins
+prefixes:
+  ex: http://example.org/
+classes:
+  Event:
+    slots:
+      - cancelled
+      - day
+      - start
+      - fee
+      - page
+      - venue
+      - organizer
+      - topic
+slots:
+  cancelled:
+    range: boolean
+  day:
+    range: date
+  start:
+    range: datetime
+  fee:
+    range: decimal
+  page:
+    range: uri
+  venue:
+    range: uriorcurie
+  organizer:
+    range: nodeidentifier
+  topic:
+    any_of:
+      - range: objectidentifier
+      - range: string
+"#;
+
+#[test]
+fn test_linkml_builtin_types() {
+    // built-in ranges have datatypes without importing linkml:types; identifier ranges are node kinds
+    let shex = linkml_to_shex::linkml_yaml_to_shex(BUILTINS_LINKML).unwrap();
+    for tc in ["ex:cancelled xsd:boolean ?", "ex:day xsd:date ?", "ex:start xsd:dateTime ?", "ex:fee xsd:decimal ?",
+        "ex:page xsd:anyURI ?", "ex:venue IRI ?", "ex:organizer NONLITERAL ?", "ex:topic ( IRI OR xsd:string ) ?"] {
+        assert!(shex.contains(tc), "{} not in {}", tc, shex);
+    }
+
+    let shexj = linkml_to_shex::linkml_yaml_to_shex_as(BUILTINS_LINKML, linkml_to_shex::ShexSyntax::Json).unwrap();
+    let doc: serde_json::Value = serde_json::from_str(&shexj).unwrap();
+    let tcs = &doc["shapes"][0]["shapeExpr"]["expression"]["expressions"];
+    assert_eq!(tcs[0]["valueExpr"], serde_json::json!({ "type": "NodeConstraint", "datatype": "http://www.w3.org/2001/XMLSchema#boolean" }));
+    assert_eq!(tcs[5]["valueExpr"], serde_json::json!({ "type": "NodeConstraint", "nodeKind": "iri" }));
+}
+
+const TYPES_LINKML: &str = r#"
+id: types
+prefixes:
+  ex: http://example.org/
+  geo: http://www.opengis.net/ont/geosparql#
+  xsd: http://www.w3.org/2001/XMLSchema#
+types:
+  Orcid:
+    typeof: string
+    pattern: "^\\d{4}-\\d{4}-\\d{4}-\\d{3}[0-9X]$"
+  Percentage:
+    typeof: float
+    uri: xsd:float
+    minimum_value: 0
+    maximum_value: 100
+  Wkt:
+    typeof: string
+    uri: geo:wktLiteral
+classes:
+  Person:
+    slots:
+      - orcid
+      - score
+      - location
+slots:
+  orcid:
+    range: Orcid
+  score:
+    range: Percentage
+  location:
+    range: Wkt
+"#;
+
+#[test]
+fn test_linkml_custom_types() {
+    let shex = linkml_to_shex::linkml_yaml_to_shex(TYPES_LINKML).unwrap();
+    assert!(shex.contains("  ex:orcid xsd:string /^\\d{4}-\\d{4}-\\d{4}-\\d{3}[0-9X]$/ ? ;\n"));
+    assert!(shex.contains("  ex:score xsd:float MININCLUSIVE 0 MAXINCLUSIVE 100 ? ;\n"));
+    assert!(shex.contains("  ex:location <http://www.opengis.net/ont/geosparql#wktLiteral> ?\n"));
+
+    let shapes = linkml_to_shacl::shapes_from_linkml(TYPES_LINKML).unwrap();
+    let schema = shex2linkml::convert::build_json_schema(std::path::Path::new("types.yaml"), &shapes);
+    let props = &schema["definitions"]["Person"]["properties"];
+    assert_eq!(props["orcid"], serde_json::json!({ "type": "string", "pattern": "^\\d{4}-\\d{4}-\\d{4}-\\d{3}[0-9X]$" }));
+    assert_eq!(props["score"], serde_json::json!({ "type": "number", "minimum": 0, "maximum": 100 }));
+    assert_eq!(props["location"], serde_json::json!({ "type": "string" }));
+
+    // a datatype with no built-in counterpart gets a type of its own
+    let prefixes = linkml_to_shacl::prefixes_from_linkml(TYPES_LINKML).unwrap();
+    let linkml = shex2linkml::convert::build_linkml_doc_with_prefixes(std::path::Path::new("types.yaml"), &shapes, &prefixes).unwrap();
+    let doc: serde_yaml::Value = serde_yaml::from_str(&linkml).unwrap();
+    assert_eq!(doc["slots"]["location"]["range"], serde_yaml::Value::from("wktLiteral"));
+    assert_eq!(doc["types"]["wktLiteral"], serde_yaml::from_str::<serde_yaml::Value>("{typeof: string, uri: 'geo:wktLiteral'}").unwrap());
+}
+
+const BOOLEAN_LINKML: &str = r#"
+id: logic
+prefixes:
+  ex: http://example.org/
+classes:
+  Person:
+    slots:
+      - name
+  Organization:
+    slots:
+      - name
+  Agent:
+    any_of:
+      - is_a: Person
+      - is_a: Organization
+  Robot:
+    none_of:
+      - is_a: Person
+    slots:
+      - name
+  Membership:
+    slots:
+      - member
+      - size
+      - tag
+slots:
+  name:
+    range: string
+  member:
+    any_of:
+      - range: Person
+      - range: Organization
+  size:
+    exactly_one_of:
+      - range: integer
+      - range: Person
+  tag:
+    all_of:
+      - range: Person
+      - range: Organization
+"#;
+
+#[test]
+fn test_linkml_boolean_expressions() {
+    let shex = linkml_to_shex::linkml_yaml_to_shex(BOOLEAN_LINKML).unwrap();
+    assert!(shex.contains("<Agent> ( @<Person> OR @<Organization> )\n"));
+    assert!(shex.contains("<Robot> NOT @<Person> AND {\n  ex:name xsd:string ?\n}\n"));
+    assert!(shex.contains("  ex:member ( @<Person> OR @<Organization> ) ? ;\n"));
+    // ShEx has no exclusive or
+    assert!(shex.contains("  ex:size ( ( xsd:integer AND NOT @<Person> ) OR ( @<Person> AND NOT xsd:integer ) ) ? ;\n"));
+    assert!(shex.contains("  ex:tag ( @<Person> AND @<Organization> ) ?\n"));
+
+    let shexj = linkml_to_shex::linkml_yaml_to_shex_as(BOOLEAN_LINKML, linkml_to_shex::ShexSyntax::Json).unwrap();
+    let doc: serde_json::Value = serde_json::from_str(&shexj).unwrap();
+    assert_eq!(doc["shapes"][2]["shapeExpr"]["shapeExprs"][0], serde_json::json!({ "type": "ShapeOr", "shapeExprs": ["Person", "Organization"] }));
+    let member = &doc["shapes"][4]["shapeExpr"]["expression"]["expressions"][0];
+    assert_eq!(member["valueExpr"], serde_json::json!({ "type": "ShapeOr", "shapeExprs": ["Person", "Organization"] }));
+
+    let shapes = linkml_to_shacl::shapes_from_linkml(BOOLEAN_LINKML).unwrap();
+    let schema = shex2linkml::convert::build_json_schema(std::path::Path::new("logic.yaml"), &shapes);
+    let props = &schema["definitions"]["Membership"]["properties"];
+    let person = serde_json::json!({ "$ref": "#/definitions/Person" });
+    let organization = serde_json::json!({ "$ref": "#/definitions/Organization" });
+    assert_eq!(props["member"], serde_json::json!({ "anyOf": [person, organization] }));
+    assert_eq!(props["size"], serde_json::json!({ "oneOf": [{ "type": "integer" }, person] }));
+    assert_eq!(props["tag"], serde_json::json!({ "allOf": [person, organization] }));
+
+    // and they're written back as LinkML slot expressions
+    let linkml = shex2linkml::convert::build_linkml_doc(std::path::Path::new("logic.yaml"), &shapes).unwrap();
+    let doc: serde_yaml::Value = serde_yaml::from_str(&linkml).unwrap();
+    assert_eq!(doc["slots"]["size"]["exactly_one_of"], serde_yaml::from_str::<serde_yaml::Value>("[{range: integer}, {range: Person}]").unwrap());
+    assert!(doc["slots"]["size"].get("range").is_none());
+}
+
+const DEFAULTS_LINKML: &str = r#"
+id: defaults
+prefixes:
+  ex: http://example.org/
+  schema: http://schema.org/
+default_prefix: schema
+default_range: integer
+classes:
+  Person:
+    slots:
+      - age
+      - name
+slots:
+  age:
+    required: true
+  name:
+    range: string
+"#;
+
+#[test]
+fn test_linkml_defaults() {
+    let shex = linkml_to_shex::linkml_yaml_to_shex(DEFAULTS_LINKML).unwrap();
+    assert!(shex.contains("  schema:age xsd:integer ;\n"));
+    assert!(shex.contains("  schema:name "));
+    assert!(!shex.contains("ex:age") && !shex.contains("ex:name"));
+
+    let shapes = linkml_to_shacl::shapes_from_linkml(DEFAULTS_LINKML).unwrap();
+    let props = &shapes[0].properties;
+    assert_eq!(props[0].range, "integer");
+    assert_eq!(props[1].range, "string");
+}
+
+const ROOTS_LINKML: &str = r#"
+id: roots
+prefixes:
+  ex: http://example.org/
+default_prefix: ex
+classes:
+  Agent:
+    abstract: true
+    slots:
+      - name
+  Person:
+    is_a: Agent
+    tree_root: true
+slots:
+  name:
+    range: string
+"#;
+
+#[test]
+fn test_linkml_abstract_and_tree_root() {
+    let shex = linkml_to_shex::linkml_yaml_to_shex(ROOTS_LINKML).unwrap();
+    assert!(shex.contains("\nstart = @<Person>\n"), "{}", shex);
+    assert!(shex.contains("ABSTRACT <Agent> {"), "{}", shex);
+    assert!(!shex.contains("ABSTRACT <Person>"), "{}", shex);
+
+    let shexj = linkml_to_shex::linkml_yaml_to_shex_as(ROOTS_LINKML, linkml_to_shex::ShexSyntax::Json).unwrap();
+    let doc: serde_json::Value = serde_json::from_str(&shexj).unwrap();
+    assert_eq!(doc["start"], "Person");
+    assert_eq!(doc["shapes"][0]["abstract"], true);
+    assert_eq!(doc["shapes"][1]["abstract"], false);
+
+    let shapes = linkml_to_shacl::shapes_from_linkml(ROOTS_LINKML).unwrap();
+    let out = shex2linkml::convert::build_shex(&shapes);
+    assert!(out.contains("\nstart = @"), "{}", out);
+    assert_eq!(out.matches("ABSTRACT ").count(), 1, "{}", out);
+}
+
+const DOCUMENTED_LINKML: &str = r#"
+id: documented
+prefixes:
+  ex: http://example.org/
+default_prefix: ex
+classes:
+  Person:
+    title: Person
+    description: A human being
+    slots:
+      - name
+  Thing:
+    description: Anything at all
+slots:
+  name:
+    title: Full name
+    description: Given and family name
+    required: true
+"#;
+
+#[test]
+fn test_linkml_descriptions() {
+    let shex = linkml_to_shex::linkml_yaml_to_shex(DOCUMENTED_LINKML).unwrap();
+    assert!(shex.contains(" // <http://www.w3.org/2000/01/rdf-schema#label> \"Full name\" // <http://www.w3.org/2000/01/rdf-schema#comment> \"Given and family name\"\n"), "{}", shex);
+    assert!(shex.contains("} // <http://www.w3.org/2000/01/rdf-schema#label> \"Person\" // <http://www.w3.org/2000/01/rdf-schema#comment> \"A human being\"\n"), "{}", shex);
+    // a class without slots still needs a body to carry its annotations
+    assert!(shex.contains("<Thing> {\n} // <http://www.w3.org/2000/01/rdf-schema#comment> \"Anything at all\"\n"), "{}", shex);
+
+    let shexj = linkml_to_shex::linkml_yaml_to_shex_as(DOCUMENTED_LINKML, linkml_to_shex::ShexSyntax::Json).unwrap();
+    let doc: serde_json::Value = serde_json::from_str(&shexj).unwrap();
+    let person = &doc["shapes"][0]["shapeExpr"];
+    assert_eq!(person["annotations"][1]["object"]["value"], "A human being");
+    assert_eq!(person["expression"]["annotations"][0]["object"]["value"], "Full name");
+}
+
+const INLINED_LINKML: &str = r#"
+id: inlined
+prefixes:
+  ex: http://example.org/
+default_prefix: ex
+classes:
+  Person:
+    slots:
+      - id
+      - address
+      - friends
+      - pets
+      - employer
+  Address:
+    slots:
+      - city
+  Pet:
+    slots:
+      - id
+  Organization:
+    slots:
+      - id
+slots:
+  id:
+    identifier: true
+  address:
+    range: Address
+    inlined: true
+  friends:
+    range: Person
+    multivalued: true
+    inlined: false
+  pets:
+    range: Pet
+    multivalued: true
+    inlined: true
+  employer:
+    range: Organization
+  city:
+    range: string
+"#;
+
+#[test]
+fn test_linkml_inlined() {
+    let shapes = linkml_to_shacl::shapes_from_linkml(INLINED_LINKML).unwrap();
+    let inlining: Vec<_> = shapes[0].properties.iter().map(|p| p.inlining).collect();
+    use shex2linkml::convert::Inlining;
+    assert_eq!(inlining, vec![Some(Inlining::Inlined), Some(Inlining::Reference), Some(Inlining::Inlined), None]);
+
+    // an inlined object is a nested shape, others are references
+    let shex = shex2linkml::convert::build_shex(&shapes);
+    assert!(shex.contains("  ex:address { ex:city xsd:string ? } ?"), "{}", shex);
+    assert!(shex.contains("  ex:friends @ex:Person *"), "{}", shex);
+    assert!(shex.contains("  ex:pets IRI { } *"), "{}", shex);
+    assert!(shex.contains("  ex:employer @ex:Organization ?"), "{}", shex);
+
+    // JSON Schema embeds inlined objects, keyed by identifier when there are several,
+    // and refers to the others by IRI
+    let schema = shex2linkml::convert::build_json_schema(std::path::Path::new("inlined.yaml"), &shapes);
+    let props = &schema["definitions"]["Person"]["properties"];
+    assert_eq!(props["address"]["$ref"], "#/definitions/Address");
+    assert_eq!(props["friends"]["items"], serde_json::json!({ "type": "string", "format": "iri" }));
+    assert_eq!(props["pets"]["additionalProperties"]["$ref"], "#/definitions/Pet");
+    assert_eq!(props["employer"], serde_json::json!({ "type": "string", "format": "iri" }));
+
+    let back = shex2linkml::shapes_from_json_schema(&schema).unwrap();
+    let pets = back.iter().find(|s| s.name == "Person").unwrap().properties.iter().find(|p| p.name == "pets").unwrap();
+    assert_eq!((pets.range.as_str(), pets.max, pets.inlining), ("Pet", None, Some(Inlining::Inlined)));
+
+    let linkml = shex2linkml::convert::build_linkml_doc(std::path::Path::new("inlined.yaml"), &shapes).unwrap();
+    let doc: serde_yaml::Value = serde_yaml::from_str(&linkml).unwrap();
+    assert_eq!(doc["slots"]["friends"]["inlined"], serde_yaml::Value::Bool(false));
+    assert!(doc["slots"]["employer"].get("inlined").is_none());
+}
+
+const PEOPLE_LINKML: &str = r#"
+id: people
+prefixes:
+  ex: http://example.org/
+default_prefix: ex
+classes:
+  Person:
+    slots:
+      - name
+      - address
+slots:
+  name:
+    range: string
+  address:
+    range: Address
+"#;
+
+const PLACES_LINKML: &str = r#"
+id: places
+prefixes:
+  ex: http://example.org/
+default_prefix: ex
+classes:
+  Address:
+    slots:
+      - city
+slots:
+  city:
+    range: string
+"#;
+
+#[test]
+fn test_linkml_multiple_schemas() {
+    let merged = linkml_to_shacl::merge_linkml(&[PEOPLE_LINKML.to_string(), PLACES_LINKML.to_string()]).unwrap();
+    let shapes = linkml_to_shacl::shapes_from_linkml(&merged).unwrap();
+    assert_eq!(shapes.iter().map(|s| s.name.as_str()).collect::<Vec<_>>(), vec!["Person", "Address"]);
+    assert_eq!(linkml_to_shacl::class_ids(PLACES_LINKML).unwrap(), vec!["Address"]);
+
+    // merged, the reference is to a shape in the same schema
+    let schema = shex2linkml::convert::build_json_schema(std::path::Path::new("merged.yaml"), &shapes);
+    assert_eq!(schema["definitions"]["Person"]["properties"]["address"]["$ref"], "#/definitions/Address");
+
+    // per file, it's to the other schema's output
+    let people = shex2linkml::convert::schema_part(&shapes, &["Person".to_string()], |s| format!("places.json#/definitions/{}", s.name));
+    assert_eq!(people.iter().map(|s| (s.name.as_str(), s.external)).collect::<Vec<_>>(), vec![("Person", false), ("Address", true)]);
+    let schema = shex2linkml::convert::build_json_schema(std::path::Path::new("people.yaml"), &people);
+    assert_eq!(schema["definitions"]["Address"]["$ref"], "places.json#/definitions/Address");
+
+    let shex = shex2linkml::convert::build_shex_with_imports(&people[..1], &Default::default(), &["places.shex".to_string()]);
+    assert!(shex.contains("\nIMPORT <places.shex>\n"), "{}", shex);
+    assert!(shex.contains("  ex:address @ex:Address ?\n"), "{}", shex);
+    assert!(!shex.contains("ex:Address {"), "{}", shex);
+}
+
+#[test]
+fn test_linkml_shexc_reparses() {
+    let base = iri_s::iris::IriS::new_unchecked("http://example.org/");
+    let flat = linkml_to_shex::ShexOptions { flatten: true, ..Default::default() };
+    for linkml in [PERSON_LINKML, HIERARCHY_LINKML, ATTRIBUTES_LINKML, ENUM_LINKML, SLOT_USAGE_LINKML, IDENTIFIER_LINKML,
+        PATTERN_LINKML, BOUNDS_LINKML, BUILTINS_LINKML, TYPES_LINKML, BOOLEAN_LINKML, DEFAULTS_LINKML, ROOTS_LINKML, DOCUMENTED_LINKML, INLINED_LINKML] {
+        for shex in [linkml_to_shex::linkml_yaml_to_shex(linkml).unwrap(), linkml_to_shex::linkml_yaml_to_shex_with(linkml, &flat).unwrap()] {
+            let schema = shex_compact::ShExParser::parse(&shex, None, &base).unwrap_or_else(|e| panic!("{:?} parsing\n{}", e, shex));
+            shex2linkml::convert::shapes_from_rudof_ast(&schema).unwrap_or_else(|e| panic!("{} reading\n{}", e, shex));
+        }
+    }
+}
+
+#[test]
+fn test_linkml_shexj_matches_shexc() {
+    // both syntaxes are written from the one schema, so they read back alike
+    let base = iri_s::iris::IriS::new_unchecked("http://example.org/");
+    let shexc = shex_compact::ShExParser::parse(&linkml_to_shex::linkml_yaml_to_shex(PERSON_LINKML).unwrap(), None, &base).unwrap();
+    let shexj: shex_ast::Schema = serde_json::from_str(&linkml_to_shex::linkml_yaml_to_shex_as(PERSON_LINKML, linkml_to_shex::ShexSyntax::Json).unwrap()).unwrap();
+    assert_eq!(shex2linkml::convert::shapes_from_rudof_ast(&shexj).unwrap(), shex2linkml::convert::shapes_from_rudof_ast(&shexc).unwrap());
+}
@@ -0,0 +1,48 @@
+use shex2linkml::openapi_to_linkml;
+
+#[test]
+fn test_shapes_from_openapi() {
+    let openapi = r#"
+openapi: 3.0.3
+info:
+  title: Pets
+  version: "1.0"
+paths: {}
+components:
+  schemas:
+    Pet:
+      type: object
+      required: [name]
+      properties:
+        name:
+          type: string
+        status:
+          type: string
+          enum: [available, sold]
+        owner:
+          $ref: '#/components/schemas/Owner'
+    Owner:
+      type: object
+      properties:
+        pets:
+          type: array
+          items:
+            $ref: '#/components/schemas/Pet'
+    PetId:
+      type: integer
+"#;
+    let shapes = openapi_to_linkml::shapes_from_openapi(openapi).unwrap();
+    assert_eq!(shapes.len(), 2);
+
+    let pet = shapes.iter().find(|s| s.name == "Pet").unwrap();
+    let name = pet.properties.iter().find(|p| p.name == "name").unwrap();
+    assert_eq!(name.min, Some(1));
+    let status = pet.properties.iter().find(|p| p.name == "status").unwrap();
+    assert_eq!(status.values, vec!["available", "sold"]);
+    let owner = pet.properties.iter().find(|p| p.name == "owner").unwrap();
+    assert_eq!(owner.range, "Owner");
+
+    let owner = shapes.iter().find(|s| s.name == "Owner").unwrap();
+    assert_eq!(owner.properties[0].range, "Pet");
+    assert_eq!(owner.properties[0].max, None);
+}
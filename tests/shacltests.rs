@@ -0,0 +1,30 @@
+use shex2linkml::shacl_to_linkml;
+
+const PERSON_SHAPES: &str = r#"
+@prefix ex: <http://example.org/> .
+@prefix sh: <http://www.w3.org/ns/shacl#> .
+@prefix xsd: <http://www.w3.org/2001/XMLSchema#> .
+
+ex:Person a sh:NodeShape ;
+  sh:property [ sh:path ex:name ; sh:datatype xsd:string ; sh:minCount 1 ; sh:maxCount 1 ] ;
+  sh:property [ sh:path ex:age ; sh:datatype xsd:integer ; sh:maxCount 1 ] ;
+  sh:property [ sh:path ex:status ; sh:in ( "active" "retired" ) ] ;
+  sh:property [ sh:path ex:knows ; sh:node ex:Person ] .
+"#;
+
+#[test]
+fn test_shapes_from_shacl() {
+    let shapes = shacl_to_linkml::shapes_from_shacl(PERSON_SHAPES).unwrap();
+    assert_eq!(shapes.len(), 1);
+    let person = &shapes[0];
+    assert_eq!(person.name, "Person");
+
+    let prop = |n: &str| person.properties.iter().find(|p| p.name == n).unwrap();
+    assert_eq!(prop("name").range, "string");
+    assert_eq!((prop("name").min, prop("name").max), (Some(1), Some(1)));
+    assert_eq!(prop("age").range, "integer");
+    assert_eq!((prop("age").min, prop("age").max), (Some(0), Some(1)));
+    assert_eq!(prop("status").values, vec!["active", "retired"]);
+    assert_eq!(prop("knows").range, "Person");
+    assert_eq!(prop("knows").max, None);
+}
@@ -0,0 +1,72 @@
+use shex2linkml::convert::{self, PropertyInfo, ShapeInfo};
+use shex2linkml::shape_map;
+use std::path::Path;
+
+fn shape(name: &str, refs: &[&str]) -> ShapeInfo {
+    ShapeInfo {
+        id: format!("http://example.org/{}", name),
+        name: name.to_string(),
+        properties: refs.iter().map(|r| PropertyInfo {
+            name: r.to_lowercase(),
+            predicate: format!("http://example.org/{}", r.to_lowercase()),
+            range: r.to_string(),
+            min: Some(0),
+            max: Some(1),
+            ..Default::default()
+        }).collect(),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_parse_shape_map() {
+    let map = shape_map::parse_shape_map(
+        "<http://a.example/n1>@<http://example.org/Person>, \"chat, or not\"@fr@ex:Team,\n{FOCUS ex:p _}@<http://example.org/Team>",
+    ).unwrap();
+
+    assert_eq!(map.len(), 3);
+    assert_eq!(map[0].node, "<http://a.example/n1>");
+    assert_eq!(map[0].shape, "http://example.org/Person");
+    assert_eq!(map[1].node, "\"chat, or not\"@fr");
+    assert_eq!(map[1].shape, "ex:Team");
+    assert_eq!(map[2].node, "{FOCUS ex:p _}");
+    assert!(!map[2].start);
+
+    // START is the schema's start shape; <START> is a shape labelled START
+    let map = shape_map::parse_shape_map("<n1>@START, <n2>@<START>").unwrap();
+    assert!(map[0].start);
+    assert!(!map[1].start);
+    let mut shapes = vec![shape("Person", &[]), shape("Team", &[])];
+    assert!(map[0].find_shape(&shapes).is_none());
+    shapes[1].start = true;
+    assert_eq!(map[0].find_shape(&shapes).unwrap().name, "Team");
+}
+
+#[test]
+fn test_select_shapes_from_roots() {
+    let shapes = vec![shape("Person", &["Address"]), shape("Address", &[]), shape("Team", &["Person"])];
+
+    let selected = convert::select_shapes(&shapes, &["http://example.org/Person".to_string()]).unwrap();
+    let names: Vec<&str> = selected.iter().map(|s| s.name.as_str()).collect();
+    assert_eq!(names, vec!["Person", "Address"]);
+    assert!(convert::select_shapes(&shapes, &["Nope".to_string()]).is_err());
+
+    let js = convert::build_json_schema_with_roots(Path::new("x"), &selected, &["Person".to_string()], false);
+    assert_eq!(js["$ref"], "#/definitions/Person");
+    assert!(js["definitions"]["Team"].is_null());
+}
+
+#[test]
+fn test_select_shapes_keeps_parents_and_constraint_shapes() {
+    let mut employee = shape("Employee", &[]);
+    employee.extends = vec!["Person".to_string()];
+    employee.constraints = vec![convert::ShapeConstraint::Not("Robot".to_string())];
+    let shapes = vec![shape("Person", &["Address"]), shape("Address", &[]), shape("Robot", &[]), shape("Team", &[]), employee];
+
+    let selected = convert::select_shapes(&shapes, &["Employee".to_string()]).unwrap();
+    let names: Vec<&str> = selected.iter().map(|s| s.name.as_str()).collect();
+    assert_eq!(names, vec!["Person", "Address", "Robot", "Employee"]);
+    let js = convert::build_json_schema_with_roots(Path::new("x"), &selected, &["Employee".to_string()], false);
+    assert_eq!(js["definitions"]["Employee"]["allOf"][0]["$ref"], "#/definitions/Person");
+    assert!(js["definitions"]["Person"].is_object());
+}
@@ -1,15 +1,13 @@
-use std::fs;
 use std::path::Path;
 //use clap::ValueHint::Url;
 use serde_yaml::Value as Yaml;
-use serde_json::Value as Json;
-use shex2linkml::{convert, self};
-use url;
+use shex2linkml::{convert, naming, self};
 
 #[test]
 fn test_basic_roundtrip() {
     let shex = r#"
         PREFIX ex: <http://example.org/ns/2#>
+        PREFIX xsd: <http://www.w3.org/2001/XMLSchema#>
         ex:Person {
           ex:name xsd:string ;
           ex:age xsd:integer ? ;
@@ -23,18 +21,1447 @@ fn test_basic_roundtrip() {
     let shapes = convert::shapes_from_rudof_ast(&schema);
     let base_string = base_iri.to_string();
     let path = Path::new(base_string.as_str());
-    let linkml = convert::build_linkml_doc(path, shapes.unwrap().as_slice()).unwrap();
-
-    // Serialize LinkML
-    let linkml_yaml = serde_yaml::to_string(&linkml).unwrap();
+    let linkml_yaml = convert::build_linkml_doc(path, shapes.unwrap().as_slice()).unwrap();
 
     // Convert LinkML back to ShEx
-    let linkml_value: Yaml = serde_yaml::from_str(&linkml_yaml).unwrap();
+    let _linkml_value: Yaml = serde_yaml::from_str(&linkml_yaml).unwrap();
     let shex2 = shex2linkml::linkml_yaml_to_shex(linkml_yaml.as_str()).unwrap();
 
     // Ensure output contains expected shape label
     assert!(shex2.contains("Person"));
 }
+
+#[test]
+fn test_shapes_from_ast() {
+    let shex = r#"
+        PREFIX ex: <http://example.org/>
+        PREFIX xsd: <http://www.w3.org/2001/XMLSchema#>
+        ex:Agent {
+          ex:name xsd:string ;
+        }
+        ex:Person EXTENDS @ex:Agent {
+          ex:age xsd:integer ? ;
+          ex:status [ ex:Active ex:Retired ] ;
+          ex:knows @ex:Person * ;
+          ( ex:email xsd:string | ex:phone xsd:string )
+        }
+        ex:Robot NOT @ex:Person
+    "#;
+
+    let base = iri_s::iris::IriS::new_unchecked("http://example.org/");
+    let schema = shex_compact::ShExParser::parse(shex, None, &base).expect("parse shex");
+    let shapes = convert::shapes_from_rudof_ast(&schema).unwrap();
+
+    let names: Vec<&str> = shapes.iter().map(|s| s.name.as_str()).collect();
+    assert_eq!(names, vec!["Agent", "Person", "Robot"]);
+
+    let agent = &shapes[0];
+    assert_eq!(agent.properties.len(), 1);
+    assert_eq!((agent.properties[0].min, agent.properties[0].max), (Some(1), Some(1)));
+
+    let person = &shapes[1];
+    assert_eq!(person.extends, vec!["Agent"]);
+    let prop = |name: &str| person.properties.iter().find(|p| p.name == name).unwrap();
+    assert_eq!(prop("age").range, "integer");
+    assert_eq!((prop("age").min, prop("age").max), (Some(0), Some(1)));
+    assert_eq!(prop("status").values, vec!["http://example.org/Active", "http://example.org/Retired"]);
+    assert_eq!(prop("knows").range, "Person");
+    assert_eq!(prop("knows").max, None);
+    assert_eq!(prop("email").min, Some(0));
+    assert!(matches!(&person.constraints[..], [convert::ShapeConstraint::OneOf(alts)] if alts.len() == 2));
+
+    assert_eq!(shapes[2].constraints, vec![convert::ShapeConstraint::Not("Person".to_string())]);
+}
+#[test]
+fn test_nested_each_of() {
+    let shex = r#"
+        PREFIX ex: <http://example.org/>
+        PREFIX xsd: <http://www.w3.org/2001/XMLSchema#>
+        ex:Address {
+          ex:street xsd:string ;
+          ( ex:city xsd:string ;
+            ( ex:lat xsd:decimal ; ex:long xsd:decimal ) ?
+          ) ;
+          ( ex:note xsd:string ; ex:tag xsd:string ) *
+        }
+    "#;
+
+    let base = iri_s::iris::IriS::new_unchecked("http://example.org/");
+    let schema = shex_compact::ShExParser::parse(shex, None, &base).expect("parse shex");
+    let shapes = convert::shapes_from_rudof_ast(&schema).unwrap();
+    let props: Vec<(&str, Option<u64>, Option<u64>)> = shapes[0].properties.iter().map(|p| (p.name.as_str(), p.min, p.max)).collect();
+    assert_eq!(props, vec![
+        ("street", Some(1), Some(1)),
+        ("city", Some(1), Some(1)),
+        ("lat", Some(0), Some(1)),
+        ("long", Some(0), Some(1)),
+        ("note", Some(0), None),
+        ("tag", Some(0), None),
+    ]);
+
+    let linkml: Yaml = serde_yaml::from_str(&convert::build_linkml_doc(Path::new("address"), &shapes).unwrap()).unwrap();
+    assert_eq!(linkml["classes"]["Address"]["slots"].as_sequence().unwrap().len(), 6);
+    assert_eq!(linkml["slots"]["street"]["required"], Yaml::Bool(true));
+    assert!(linkml["slots"]["lat"].get("required").is_none());
+
+    let json_schema = convert::build_json_schema(Path::new("address"), &shapes);
+    let address = &json_schema["definitions"]["Address"];
+    assert_eq!(address["properties"]["long"]["type"], "number");
+    assert_eq!(address["required"], serde_json::json!(["street", "city"]));
+}
+
+#[test]
+fn test_one_of_roundtrip() {
+    let shex = r#"
+        PREFIX ex: <http://example.org/>
+        PREFIX xsd: <http://www.w3.org/2001/XMLSchema#>
+        ex:Contact {
+          ex:name xsd:string ;
+          ( ex:email xsd:string | ex:phone xsd:string ; ex:extension xsd:integer ? )
+        }
+    "#;
+
+    let base = iri_s::iris::IriS::new_unchecked("http://example.org/");
+    let schema = shex_compact::ShExParser::parse(shex, None, &base).expect("parse shex");
+    let shapes = convert::shapes_from_rudof_ast(&schema).unwrap();
+
+    let json_schema = convert::build_json_schema(Path::new("contact"), &shapes);
+    let contact = &json_schema["definitions"]["Contact"];
+    assert_eq!(contact["oneOf"], serde_json::json!([{ "required": ["email"] }, { "required": ["phone"] }]));
+    assert_eq!(contact["required"], serde_json::json!(["name"]));
+
+    let linkml_yaml = convert::build_linkml_doc(Path::new("contact"), &shapes).unwrap();
+    let linkml: Yaml = serde_yaml::from_str(&linkml_yaml).unwrap();
+    let branches = linkml["classes"]["Contact"]["exactly_one_of"].as_sequence().unwrap();
+    assert_eq!(branches.len(), 2);
+    assert_eq!(branches[1]["slot_conditions"]["phone"]["required"], Yaml::Bool(true));
+    assert!(branches[1]["slot_conditions"]["extension"].get("required").is_none());
+
+    // back through the shape model, the group is written as `|` and still parses
+    let alternatives = |shape: &convert::ShapeInfo| -> Vec<Vec<(String, Option<u64>)>> {
+        match &shape.constraints[..] {
+            [convert::ShapeConstraint::OneOf(alts)] => alts.iter().map(|alt| alt.iter().map(|p| (p.name.clone(), p.min)).collect()).collect(),
+            other => panic!("expected a single OneOf, got {:?}", other),
+        }
+    };
+    let reread = shex2linkml::shapes_from_linkml(&linkml_yaml).unwrap();
+    assert_eq!(alternatives(&reread[0]), alternatives(&shapes[0]));
+    let shex2 = convert::build_shex(&reread);
+    assert!(shex2.contains("( ex:email xsd:string | ex:phone xsd:string ; ex:extension xsd:integer ? )"));
+    let schema2 = shex_compact::ShExParser::parse(&shex2, None, &base).expect("parse generated shex");
+    assert_eq!(alternatives(&convert::shapes_from_rudof_ast(&schema2).unwrap()[0]), alternatives(&shapes[0]));
+
+    // and likewise by the direct LinkML to ShEx writer
+    let shex3 = shex2linkml::linkml_yaml_to_shex(&linkml_yaml).unwrap();
+    assert!(shex3.contains("( ex:email xsd:string | ex:phone xsd:string ; ex:extension xsd:integer ? )"));
+}
+
+#[test]
+fn test_one_of_optional_alternatives() {
+    let shex = r#"
+        PREFIX ex: <http://example.org/>
+        PREFIX xsd: <http://www.w3.org/2001/XMLSchema#>
+        ex:Contact { ( ex:email xsd:string ? ; ex:fax xsd:string ? | ex:phone xsd:string ) }
+        ex:Place { ( ex:street xsd:string ? | ex:lat xsd:decimal ) }
+    "#;
+
+    let base = iri_s::iris::IriS::new_unchecked("http://example.org/");
+    let schema = shex_compact::ShExParser::parse(shex, None, &base).expect("parse shex");
+    let shapes = convert::shapes_from_rudof_ast(&schema).unwrap();
+
+    // `required: []` would match every instance, so an all-optional branch needs one of its properties
+    let json_schema = convert::build_json_schema(Path::new("contact"), &shapes);
+    assert_eq!(json_schema["definitions"]["Contact"]["oneOf"], serde_json::json!([
+        { "anyOf": [{ "required": ["email"] }, { "required": ["fax"] }] },
+        { "required": ["phone"] }
+    ]));
+    assert_eq!(json_schema["definitions"]["Place"]["oneOf"], serde_json::json!([{ "required": ["street"] }, { "required": ["lat"] }]));
+}
+
+#[test]
+fn test_shape_reference_ranges() {
+    let shex = r#"
+        PREFIX ex: <http://example.org/>
+        PREFIX xsd: <http://www.w3.org/2001/XMLSchema#>
+        ex:Person {
+          ex:name xsd:string ;
+          ex:employer @ex:Organization ? ;
+          ex:status @ex:Status
+        }
+        ex:Organization {
+          ex:label xsd:string
+        }
+        ex:Status [ ex:Active ex:Retired ]
+    "#;
+
+    let base = iri_s::iris::IriS::new_unchecked("http://example.org/");
+    let schema = shex_compact::ShExParser::parse(shex, None, &base).expect("parse shex");
+    let shapes = convert::shapes_from_rudof_ast(&schema).unwrap();
+
+    let json_schema = convert::build_json_schema(Path::new("people"), &shapes);
+    let person = &json_schema["definitions"]["Person"]["properties"];
+    assert_eq!(person["employer"], serde_json::json!({ "$ref": "#/definitions/Organization" }));
+    // a reference to a value set declaration is inlined rather than treated as a shape
+    assert_eq!(person["status"]["enum"], serde_json::json!(["http://example.org/Active", "http://example.org/Retired"]));
+
+    let linkml: Yaml = serde_yaml::from_str(&convert::build_linkml_doc(Path::new("people"), &shapes).unwrap()).unwrap();
+    assert_eq!(linkml["slots"]["employer"]["range"], Yaml::from("Organization"));
+    assert_eq!(linkml["slots"]["status"]["range"], Yaml::from("StatusEnum"));
+}
+
+#[test]
+fn test_value_set_roundtrip() {
+    let shex = r#"
+        PREFIX ex: <http://example.org/>
+        ex:Paint {
+          ex:color [ ex:red ex:green ex:blue ] ;
+          ex:finish [ "matte" "gloss" ] ?
+        }
+    "#;
+
+    let base = iri_s::iris::IriS::new_unchecked("http://example.org/");
+    let schema = shex_compact::ShExParser::parse(shex, None, &base).expect("parse shex");
+    let shapes = convert::shapes_from_rudof_ast(&schema).unwrap();
+
+    let json_schema = convert::build_json_schema(Path::new("paint"), &shapes);
+    let paint = &json_schema["definitions"]["Paint"]["properties"];
+    assert_eq!(paint["color"]["enum"], serde_json::json!(["http://example.org/red", "http://example.org/green", "http://example.org/blue"]));
+    assert_eq!(paint["finish"]["enum"], serde_json::json!(["matte", "gloss"]));
+
+    let linkml_yaml = convert::build_linkml_doc(Path::new("paint"), &shapes).unwrap();
+    let linkml: Yaml = serde_yaml::from_str(&linkml_yaml).unwrap();
+    assert_eq!(linkml["slots"]["finish"]["range"], Yaml::from("FinishEnum"));
+    assert_eq!(linkml["enums"]["FinishEnum"]["permissible_values"].as_mapping().unwrap().len(), 2);
+
+    // LinkML enums come back as value sets
+    let shex2 = convert::build_shex(&shex2linkml::shapes_from_linkml(&linkml_yaml).unwrap());
+    assert!(shex2.contains("[ <http://example.org/red> <http://example.org/green> <http://example.org/blue> ]"));
+    assert!(shex2.contains("[ \"matte\" \"gloss\" ] ?"));
+    let shex3 = shex2linkml::linkml_yaml_to_shex(&linkml_yaml).unwrap();
+    assert!(shex3.contains("[ \"matte\" \"gloss\" ] ?"));
+    let shexj: serde_json::Value = serde_json::from_str(
+        &shex2linkml::linkml_yaml_to_shex_as(&linkml_yaml, shex2linkml::ShexSyntax::Json).unwrap(),
+    ).unwrap();
+    let color = &shexj["shapes"][0]["shapeExpr"]["expression"]["expressions"][0];
+    assert_eq!(color["valueExpr"]["values"][0], "http://example.org/red");
+}
+
+#[test]
+fn test_closed_roundtrip() {
+    let shex = r#"
+        PREFIX ex: <http://example.org/>
+        PREFIX xsd: <http://www.w3.org/2001/XMLSchema#>
+        ex:Agent {
+          ex:name xsd:string
+        }
+        ex:Person EXTENDS @ex:Agent CLOSED {
+          ex:age xsd:integer ?
+        }
+    "#;
+
+    let base = iri_s::iris::IriS::new_unchecked("http://example.org/");
+    let schema = shex_compact::ShExParser::parse(shex, None, &base).expect("parse shex");
+    let shapes = convert::shapes_from_rudof_ast(&schema).unwrap();
+    assert_eq!(shapes.iter().map(|s| s.closed).collect::<Vec<_>>(), vec![false, true]);
+
+    let json_schema = convert::build_json_schema(Path::new("people"), &shapes);
+    let person = &json_schema["definitions"]["Person"];
+    assert_eq!(person["additionalProperties"], serde_json::json!(false));
+    // the inherited property is still admitted
+    assert_eq!(person["properties"]["name"], serde_json::json!(true));
+    assert!(json_schema["definitions"]["Agent"].get("additionalProperties").is_none());
+    let reread = shex2linkml::shapes_from_json_schema(&json_schema).unwrap();
+    let person_again = reread.iter().find(|s| s.name == "Person").unwrap();
+    assert!(person_again.closed);
+    assert_eq!(person_again.properties.iter().map(|p| p.name.as_str()).collect::<Vec<_>>(), vec!["age"]);
+
+    let linkml_yaml = convert::build_linkml_doc(Path::new("people"), &shapes).unwrap();
+    let linkml: Yaml = serde_yaml::from_str(&linkml_yaml).unwrap();
+    assert_eq!(linkml["classes"]["Person"]["annotations"]["closed"], Yaml::Bool(true));
+
+    let shex2 = convert::build_shex(&shex2linkml::shapes_from_linkml(&linkml_yaml).unwrap());
+    assert!(shex2.contains("ex:Person EXTENDS @ex:Agent CLOSED {"));
+    let schema2 = shex_compact::ShExParser::parse(&shex2, None, &base).expect("parse generated shex");
+    assert!(convert::shapes_from_rudof_ast(&schema2).unwrap()[1].closed);
+    let shex3 = shex2linkml::linkml_yaml_to_shex(&linkml_yaml).unwrap();
+    assert!(shex3.contains("<http://example.org/Person> EXTENDS @<http://example.org/Agent> CLOSED {\n"));
+}
+
+#[test]
+fn test_shape_boolean_combinations() {
+    let shex = r#"
+        PREFIX ex: <http://example.org/>
+        PREFIX xsd: <http://www.w3.org/2001/XMLSchema#>
+        ex:Animal {
+          ex:name xsd:string
+        }
+        ex:Cat {
+          ex:lives xsd:integer
+        }
+        ex:Dog {
+          ex:breed xsd:string
+        }
+        ex:Robot {
+          ex:model xsd:string
+        }
+        ex:Pet @ex:Animal AND ( @ex:Cat OR @ex:Dog OR { ex:species xsd:string } ) AND NOT @ex:Robot
+    "#;
+
+    let base = iri_s::iris::IriS::new_unchecked("http://example.org/");
+    let schema = shex_compact::ShExParser::parse(shex, None, &base).expect("parse shex");
+    let shapes = convert::shapes_from_rudof_ast(&schema).unwrap();
+    let pet = shapes.iter().find(|s| s.name == "Pet").unwrap();
+    assert_eq!(pet.extends, vec!["Animal"]);
+    let expected = vec![
+        convert::ShapeConstraint::AnyOf(vec!["Cat".to_string(), "Dog".to_string(), "PetOption3".to_string()]),
+        convert::ShapeConstraint::Not("Robot".to_string()),
+    ];
+    assert_eq!(pet.constraints, expected);
+    // the inline branch is lifted into a shape of its own
+    assert_eq!(shapes.iter().find(|s| s.name == "PetOption3").unwrap().properties[0].name, "species");
+
+    let json_schema = convert::build_json_schema(Path::new("pets"), &shapes);
+    let pet_def = &json_schema["definitions"]["Pet"];
+    assert_eq!(pet_def["allOf"], serde_json::json!([{ "$ref": "#/definitions/Animal" }]));
+    assert_eq!(pet_def["anyOf"][1], serde_json::json!({ "$ref": "#/definitions/Dog" }));
+    assert_eq!(pet_def["not"], serde_json::json!({ "$ref": "#/definitions/Robot" }));
+    let from_json = shex2linkml::shapes_from_json_schema(&json_schema).unwrap();
+    assert_eq!(from_json.iter().find(|s| s.name == "Pet").unwrap().constraints, expected);
+
+    let linkml_yaml = convert::build_linkml_doc(Path::new("pets"), &shapes).unwrap();
+    let linkml: Yaml = serde_yaml::from_str(&linkml_yaml).unwrap();
+    assert_eq!(linkml["classes"]["Pet"]["any_of"][0]["is_a"], Yaml::from("Cat"));
+    assert_eq!(linkml["classes"]["Pet"]["none_of"][0]["is_a"], Yaml::from("Robot"));
+
+    let from_linkml = shex2linkml::shapes_from_linkml(&linkml_yaml).unwrap();
+    assert_eq!(from_linkml.iter().find(|s| s.name == "Pet").unwrap().constraints, expected);
+    let shex2 = convert::build_shex(&from_linkml);
+    assert!(shex2.contains("ex:Pet ( @ex:Cat OR @ex:Dog OR @ex:PetOption3 ) AND NOT @ex:Robot AND EXTENDS @ex:Animal {"));
+    let schema2 = shex_compact::ShExParser::parse(&shex2, None, &base).expect("parse generated shex");
+    let reread = convert::shapes_from_rudof_ast(&schema2).unwrap();
+    let pet2 = reread.iter().find(|s| s.name == "Pet").unwrap();
+    assert_eq!((&pet2.extends, &pet2.constraints), (&pet.extends, &expected));
+
+    assert!(convert::build_shacl(&shapes).contains("sh:or ( ex:Cat ex:Dog ex:PetOption3 )"));
+}
+
+#[test]
+fn test_node_kinds() {
+    let shex = r#"
+        PREFIX ex: <http://example.org/>
+        ex:Resource {
+          ex:homepage IRI ;
+          ex:anon BNODE ? ;
+          ex:node NONLITERAL ? ;
+          ex:label LITERAL ?
+        }
+    "#;
+
+    let base = iri_s::iris::IriS::new_unchecked("http://example.org/");
+    let schema = shex_compact::ShExParser::parse(shex, None, &base).expect("parse shex");
+    let shapes = convert::shapes_from_rudof_ast(&schema).unwrap();
+    let kinds: Vec<(&str, Option<convert::NodeKind>)> = shapes[0].properties.iter().map(|p| (p.range.as_str(), p.node_kind)).collect();
+    assert_eq!(kinds, vec![
+        ("uriorcurie", Some(convert::NodeKind::Iri)),
+        ("nodeidentifier", Some(convert::NodeKind::BNode)),
+        ("nodeidentifier", Some(convert::NodeKind::NonLiteral)),
+        ("string", Some(convert::NodeKind::Literal)),
+    ]);
+
+    let json_schema = convert::build_json_schema(Path::new("resource"), &shapes);
+    let props = &json_schema["definitions"]["Resource"]["properties"];
+    assert_eq!(props["homepage"], serde_json::json!({ "type": "string", "format": "iri" }));
+    assert_eq!(props["anon"], serde_json::json!({ "type": "string", "pattern": "^_:" }));
+    assert_eq!(props["label"], serde_json::json!({ "type": "string" }));
+
+    let linkml: Yaml = serde_yaml::from_str(&convert::build_linkml_doc(Path::new("resource"), &shapes).unwrap()).unwrap();
+    assert_eq!(linkml["slots"]["homepage"]["range"], Yaml::from("uriorcurie"));
+    assert_eq!(linkml["slots"]["node"]["range"], Yaml::from("nodeidentifier"));
+
+    let shex2 = convert::build_shex(&shapes);
+    for tc in ["homepage> IRI ;", "anon> BNODE ?", "node> NONLITERAL ?", "label> LITERAL ?"] {
+        assert!(shex2.contains(tc), "{} not in {}", tc, shex2);
+    }
+    let shacl = convert::build_shacl(&shapes);
+    assert!(shacl.contains("sh:nodeKind sh:BlankNode"));
+    let from_shacl = shex2linkml::shapes_from_shacl(&shacl).unwrap();
+    for p in shapes[0].properties.iter() {
+        let reread = from_shacl[0].properties.iter().find(|q| q.name == p.name).unwrap();
+        assert_eq!((&reread.range, reread.node_kind), (&p.range, p.node_kind));
+    }
+}
+
+#[test]
+fn test_facets() {
+    // shex_compact reads one string facet per node constraint and integer bounds only
+    let shex = r#"
+        PREFIX ex: <http://example.org/>
+        PREFIX xsd: <http://www.w3.org/2001/XMLSchema#>
+        ex:Account {
+          ex:login xsd:string MINLENGTH 3 ;
+          ex:nick xsd:string MAXLENGTH 16 ;
+          ex:handle xsd:string /^[a-z\/]+$/ ;
+          ex:age xsd:integer MININCLUSIVE 18 MAXEXCLUSIVE 130 ;
+          ex:balance xsd:decimal TOTALDIGITS 10 FRACTIONDIGITS 2 MAXINCLUSIVE 1000
+        }
+    "#;
+
+    let base = iri_s::iris::IriS::new_unchecked("http://example.org/");
+    let schema = shex_compact::ShExParser::parse(shex, None, &base).expect("parse shex");
+    let shapes = convert::shapes_from_rudof_ast(&schema).unwrap();
+    let facets = |name: &str| shapes[0].properties.iter().find(|p| p.name == name).unwrap().facets.clone();
+    assert_eq!(facets("login").min_length, Some(3));
+    assert_eq!(facets("handle").pattern.as_deref(), Some("^[a-z/]+$"));
+    assert_eq!(facets("age").min_inclusive, Some(18.into()));
+    assert_eq!((facets("balance").total_digits, facets("balance").fraction_digits), (Some(10), Some(2)));
+
+    let json_schema = convert::build_json_schema(Path::new("account"), &shapes);
+    let props = &json_schema["definitions"]["Account"]["properties"];
+    assert_eq!(props["nick"], serde_json::json!({ "type": "string", "maxLength": 16 }));
+    assert_eq!(props["handle"], serde_json::json!({ "type": "string", "pattern": "^[a-z/]+$" }));
+    assert_eq!(props["age"], serde_json::json!({ "type": "integer", "minimum": 18, "exclusiveMaximum": 130 }));
+    let from_json = shex2linkml::shapes_from_json_schema(&json_schema).unwrap();
+    assert_eq!(from_json[0].properties.iter().find(|p| p.name == "age").unwrap().facets, facets("age"));
+
+    let linkml_yaml = convert::build_linkml_doc(Path::new("account"), &shapes).unwrap();
+    let linkml: Yaml = serde_yaml::from_str(&linkml_yaml).unwrap();
+    assert_eq!(linkml["slots"]["age"]["minimum_value"], Yaml::from(18));
+    assert_eq!(linkml["slots"]["handle"]["pattern"], Yaml::from("^[a-z/]+$"));
+    let from_linkml = shex2linkml::shapes_from_linkml(&linkml_yaml).unwrap();
+    assert_eq!(from_linkml[0].properties.iter().find(|p| p.name == "balance").unwrap().facets.max_inclusive, Some(1000.into()));
+
+    // written back as ShExC facets that parse to the same values
+    let shex2 = convert::build_shex(&shapes);
+    assert!(shex2.contains("xsd:string /^[a-z\\/]+$/"));
+    assert!(shex2.contains("xsd:integer MININCLUSIVE 18 MAXEXCLUSIVE 130"));
+    let reparsed = convert::shapes_from_rudof_ast(&shex_compact::ShExParser::parse(&shex2, None, &base).expect("parse generated shex")).unwrap();
+    assert_eq!(reparsed[0].properties.iter().map(|p| p.facets.clone()).collect::<Vec<_>>(), shapes[0].properties.iter().map(|p| p.facets.clone()).collect::<Vec<_>>());
+
+    assert!(convert::build_shacl(&shapes).contains("sh:minInclusive 18"));
+}
+
+#[test]
+fn test_xsd_datatypes() {
+    let shex = r#"
+        PREFIX ex: <http://example.org/>
+        PREFIX xsd: <http://www.w3.org/2001/XMLSchema#>
+        ex:Event {
+          ex:day xsd:date ;
+          ex:start xsd:dateTime ;
+          ex:link xsd:anyURI ;
+          ex:score xsd:double ;
+          ex:count xsd:long ;
+          ex:year xsd:gYear ;
+          ex:took xsd:duration
+        }
+    "#;
+
+    let base = iri_s::iris::IriS::new_unchecked("http://example.org/");
+    let schema = shex_compact::ShExParser::parse(shex, None, &base).expect("parse shex");
+    let shapes = convert::shapes_from_rudof_ast(&schema).unwrap();
+    let ranges: Vec<&str> = shapes[0].properties.iter().map(|p| p.range.as_str()).collect();
+    assert_eq!(ranges, vec!["date", "datetime", "uri", "double", "integer", "string", "http://www.w3.org/2001/XMLSchema#duration"]);
+
+    let json_schema = convert::build_json_schema(Path::new("event"), &shapes);
+    let props = &json_schema["definitions"]["Event"]["properties"];
+    assert_eq!(props["day"], serde_json::json!({ "type": "string", "format": "date" }));
+    assert_eq!(props["start"], serde_json::json!({ "type": "string", "format": "date-time" }));
+    assert_eq!(props["link"], serde_json::json!({ "type": "string", "format": "uri" }));
+    assert_eq!(props["score"], serde_json::json!({ "type": "number" }));
+    assert_eq!(props["count"], serde_json::json!({ "type": "integer" }));
+    assert_eq!(props["took"], serde_json::json!({ "type": "string", "format": "duration" }));
+    let from_json = shex2linkml::shapes_from_json_schema(&json_schema).unwrap();
+    let reread = |name: &str| from_json[0].properties.iter().find(|p| p.name == name).unwrap().range.clone();
+    assert_eq!(reread("day"), "date");
+    assert_eq!(reread("start"), "datetime");
+    assert_eq!(reread("link"), "uri");
+    // JSON Schema doesn't tell a double from a decimal
+    assert_eq!(reread("score"), "number");
+    assert_eq!(reread("count"), "integer");
+    assert_eq!(reread("took"), "http://www.w3.org/2001/XMLSchema#duration");
+
+    // validators asserting `format` get plain strings
+    let options = convert::JsonSchemaOptions { plain_strings: true, ..Default::default() };
+    let plain = convert::build_json_schema_with(Path::new("event"), &shapes, &options);
+    let props = &plain["definitions"]["Event"]["properties"];
+    for name in ["day", "start", "link", "took"] {
+        assert_eq!(props[name], serde_json::json!({ "type": "string" }));
+    }
+
+    let shex2 = convert::build_shex(&shapes);
+    for dt in ["xsd:date ;", "xsd:dateTime ;", "xsd:anyURI ;", "xsd:double ;", "xsd:duration"] {
+        assert!(shex2.contains(dt), "{} not in {}", dt, shex2);
+    }
+}
+
+#[test]
+fn test_language_tags() {
+    let shex = r#"
+        PREFIX ex: <http://example.org/>
+        PREFIX rdf: <http://www.w3.org/1999/02/22-rdf-syntax-ns#>
+        ex:Book {
+          ex:title [ @en @fr~ ] ;
+          ex:label rdf:langString ?
+        }
+    "#;
+
+    let base = iri_s::iris::IriS::new_unchecked("http://example.org/");
+    let schema = shex_compact::ShExParser::parse(shex, None, &base).expect("parse shex");
+    let shapes = convert::shapes_from_rudof_ast(&schema).unwrap();
+    let languages = |shapes: &[convert::ShapeInfo], name: &str| {
+        shapes[0].properties.iter().find(|p| p.name == name).unwrap().languages.clone()
+    };
+    assert_eq!(languages(&shapes, "title"), vec!["en", "fr~"]);
+    assert_eq!(languages(&shapes, "label"), vec!["~"]);
+    assert_eq!(shapes[0].properties[1].range, "string");
+
+    let linkml_yaml = convert::build_linkml_doc(Path::new("book"), &shapes).unwrap();
+    let linkml: Yaml = serde_yaml::from_str(&linkml_yaml).unwrap();
+    assert_eq!(linkml["slots"]["title"]["range"], Yaml::String("string".to_string()));
+    assert_eq!(linkml["slots"]["title"]["annotations"]["languages"], Yaml::String("en fr~".to_string()));
+    let from_linkml = shex2linkml::shapes_from_linkml(&linkml_yaml).unwrap();
+    assert_eq!(languages(&from_linkml, "title"), vec!["en", "fr~"]);
+    let back = shex2linkml::linkml_yaml_to_shex(&linkml_yaml).unwrap();
+    assert!(back.contains("[ @en @fr~ ]"), "{}", back);
+
+    // plain strings unless value objects are asked for
+    let plain = convert::build_json_schema(Path::new("book"), &shapes);
+    assert_eq!(plain["definitions"]["Book"]["properties"]["title"], serde_json::json!({ "type": "string" }));
+    let json_schema = convert::build_json_schema_with_roots(Path::new("book"), &shapes, &[], true);
+    let title = &json_schema["definitions"]["Book"]["properties"]["title"];
+    assert_eq!(title["properties"]["@language"]["pattern"], "^(en|fr(-.+)?)$");
+    assert_eq!(title["required"], serde_json::json!(["@value", "@language"]));
+    let from_json = shex2linkml::shapes_from_json_schema(&json_schema).unwrap();
+    assert_eq!(languages(&from_json, "title"), vec!["en", "fr~"]);
+    assert_eq!(languages(&from_json, "label"), vec!["~"]);
+
+    // sh:languageIn matches subtags, so exact tags come back as stems
+    let shacl = convert::build_shacl(&shapes);
+    assert!(shacl.contains("sh:languageIn ( \"en\" \"fr\" )"), "{}", shacl);
+    let from_shacl = shex2linkml::shapes_from_shacl(&shacl).unwrap();
+    assert_eq!(languages(&from_shacl, "title"), vec!["en~", "fr~"]);
+    assert_eq!(languages(&from_shacl, "label"), vec!["~"]);
+
+    let shex2 = convert::build_shex(&shapes);
+    let reparsed = shex_compact::ShExParser::parse(&shex2, None, &base).expect("reparse shex");
+    let reread = convert::shapes_from_rudof_ast(&reparsed).unwrap();
+    assert_eq!(languages(&reread, "title"), vec!["en", "fr~"]);
+    assert_eq!(languages(&reread, "label"), vec!["~"]);
+}
+
+#[test]
+fn test_iri_stems() {
+    let shex = r#"
+        PREFIX ex: <http://example.org/>
+        ex:Work {
+          ex:license [ <http://creativecommons.org/licenses/>~ - <http://creativecommons.org/licenses/by-nd/4.0/> ] ;
+          ex:source [ ex:other ex:~ ] ?
+        }
+    "#;
+
+    let base = iri_s::iris::IriS::new_unchecked("http://example.org/");
+    let schema = shex_compact::ShExParser::parse(shex, None, &base).expect("parse shex");
+    let shapes = convert::shapes_from_rudof_ast(&schema).unwrap();
+    let license = &shapes[0].properties[0];
+    assert_eq!(license.range, "uriorcurie");
+    assert_eq!(license.iri_stems, vec![convert::IriStem {
+        stem: "http://creativecommons.org/licenses/".to_string(),
+        exclusions: vec!["http://creativecommons.org/licenses/by-nd/4.0/".to_string()],
+    }]);
+    let source = &shapes[0].properties[1];
+    assert_eq!(source.values, vec!["http://example.org/other"]);
+    assert_eq!(source.iri_stems[0].stem, "http://example.org/");
+
+    let json_schema = convert::build_json_schema(Path::new("work"), &shapes);
+    let props = &json_schema["definitions"]["Work"]["properties"];
+    assert_eq!(props["license"]["pattern"], r"^(http://creativecommons\.org/licenses/)");
+    assert_eq!(props["license"]["not"]["pattern"], r"^(http://creativecommons\.org/licenses/by-nd/4\.0/$)");
+    assert_eq!(props["source"]["pattern"], r"^(http://example\.org/other$|http://example\.org/)");
+
+    let linkml_yaml = convert::build_linkml_doc(Path::new("work"), &shapes).unwrap();
+    let from_linkml = shex2linkml::shapes_from_linkml(&linkml_yaml).unwrap();
+    assert_eq!(from_linkml[0].properties[0].iri_stems, license.iri_stems);
+    let back = shex2linkml::linkml_yaml_to_shex(&linkml_yaml).unwrap();
+    assert!(back.contains("[ <http://creativecommons.org/licenses/>~ - <http://creativecommons.org/licenses/by-nd/4.0/> ]"), "{}", back);
+
+    let shacl = convert::build_shacl(&shapes);
+    assert!(shacl.contains(r#"sh:pattern "^(http://creativecommons\\.org/licenses/)""#), "{}", shacl);
+    assert!(shex2linkml::shapes_from_shacl(&shacl).is_ok());
+
+    let shex2 = convert::build_shex(&shapes);
+    let reparsed = shex_compact::ShExParser::parse(&shex2, None, &base).expect("reparse shex");
+    let reread = convert::shapes_from_rudof_ast(&reparsed).unwrap();
+    assert_eq!(reread[0].properties[0].iri_stems, license.iri_stems);
+    assert_eq!(reread[0].properties[1].iri_stems, source.iri_stems);
+}
+
+#[test]
+fn test_multivalued_arrays() {
+    let shex = r#"
+        PREFIX ex: <http://example.org/>
+        PREFIX xsd: <http://www.w3.org/2001/XMLSchema#>
+        ex:Paper {
+          ex:title xsd:string ;
+          ex:keyword xsd:string MINLENGTH 2 * ;
+          ex:author @ex:Person {1,3}
+        }
+        ex:Person { ex:name xsd:string }
+    "#;
+
+    let base = iri_s::iris::IriS::new_unchecked("http://example.org/");
+    let schema = shex_compact::ShExParser::parse(shex, None, &base).expect("parse shex");
+    let shapes = convert::shapes_from_rudof_ast(&schema).unwrap();
+
+    let json_schema = convert::build_json_schema(Path::new("paper"), &shapes);
+    let props = &json_schema["definitions"]["Paper"]["properties"];
+    assert_eq!(props["title"], serde_json::json!({ "type": "string" }));
+    assert_eq!(props["keyword"], serde_json::json!({ "type": "array", "items": { "type": "string", "minLength": 2 } }));
+    assert_eq!(props["author"], serde_json::json!({
+        "type": "array", "items": { "$ref": "#/definitions/Person" }, "minItems": 1, "maxItems": 3
+    }));
+    let from_json = shex2linkml::shapes_from_json_schema(&json_schema).unwrap();
+    let paper = from_json.iter().find(|s| s.name == "Paper").unwrap();
+    let card = |name: &str| paper.properties.iter().find(|p| p.name == name).map(|p| (p.range.clone(), p.min, p.max)).unwrap();
+    assert_eq!(card("keyword"), ("string".to_string(), Some(0), None));
+    assert_eq!(card("author"), ("Person".to_string(), Some(1), Some(3)));
+
+    let linkml: Yaml = serde_yaml::from_str(&convert::build_linkml_doc(Path::new("paper"), &shapes).unwrap()).unwrap();
+    assert_eq!(linkml["slots"]["keyword"]["multivalued"], Yaml::Bool(true));
+    assert_eq!(linkml["slots"]["author"]["multivalued"], Yaml::Bool(true));
+    assert!(linkml["slots"]["title"].get("multivalued").is_none());
+}
+
+#[test]
+fn test_unbounded_cardinality() {
+    let shex = r#"
+        PREFIX ex: <http://example.org/>
+        PREFIX xsd: <http://www.w3.org/2001/XMLSchema#>
+        ex:Person {
+          ex:email xsd:string + ;
+          ex:nick xsd:string * ;
+          ex:phone xsd:string {2,} ;
+          ex:knows xsd:string ?
+        }
+    "#;
+
+    let base = iri_s::iris::IriS::new_unchecked("http://example.org/");
+    let schema = shex_compact::ShExParser::parse(shex, None, &base).expect("parse shex");
+    let shapes = convert::shapes_from_rudof_ast(&schema).unwrap();
+    let cards: Vec<(Option<u64>, Option<u64>)> = shapes[0].properties.iter().map(|p| (p.min, p.max)).collect();
+    assert_eq!(cards, vec![(Some(1), None), (Some(0), None), (Some(2), None), (Some(0), Some(1))]);
+
+    let linkml_yaml = convert::build_linkml_doc(Path::new("person"), &shapes).unwrap();
+    let linkml: Yaml = serde_yaml::from_str(&linkml_yaml).unwrap();
+    for slot in ["email", "nick", "phone"] {
+        assert_eq!(linkml["slots"][slot]["multivalued"], Yaml::Bool(true));
+        assert!(linkml["slots"][slot].get("maximum_cardinality").is_none());
+    }
+    assert_eq!(shex2linkml::shapes_from_linkml(&linkml_yaml).unwrap()[0].properties, shapes[0].properties.iter()
+        .map(|p| convert::PropertyInfo { predicate: format!("ex:{}", p.name), ..p.clone() }).collect::<Vec<_>>());
+    let back = shex2linkml::linkml_yaml_to_shex(&linkml_yaml).unwrap();
+    for tc in ["ex:email xsd:string +", "ex:nick xsd:string *", "ex:phone xsd:string {2,}", "ex:knows xsd:string ?"] {
+        assert!(back.contains(tc), "{} not in {}", tc, back);
+    }
+
+    let json_schema = convert::build_json_schema(Path::new("person"), &shapes);
+    let props = &json_schema["definitions"]["Person"]["properties"];
+    assert!(props["email"].get("maxItems").is_none());
+    assert_eq!(props["phone"]["minItems"], 2);
+    assert_eq!(props["knows"]["type"], "string");
+
+    assert_eq!(convert::shapes_from_rudof_ast(&shex_compact::ShExParser::parse(&convert::build_shex(&shapes), None, &base).unwrap()).unwrap(), shapes);
+}
+
+#[test]
+fn test_annotations() {
+    let shex = r#"
+        PREFIX ex: <http://example.org/>
+        PREFIX xsd: <http://www.w3.org/2001/XMLSchema#>
+        PREFIX rdfs: <http://www.w3.org/2000/01/rdf-schema#>
+        ex:Person {
+          ex:name xsd:string // rdfs:label "Full name" // rdfs:comment "Given and family name" ;
+          ex:age xsd:integer ?
+        } // rdfs:label "Person" // rdfs:comment "A human being, \"alive\" or not"
+    "#;
+
+    let base = iri_s::iris::IriS::new_unchecked("http://example.org/");
+    let schema = shex_compact::ShExParser::parse(shex, None, &base).expect("parse shex");
+    let shapes = convert::shapes_from_rudof_ast(&schema).unwrap();
+    let person = &shapes[0];
+    assert_eq!(person.title.as_deref(), Some("Person"));
+    assert_eq!(person.description.as_deref(), Some("A human being, \"alive\" or not"));
+    assert_eq!(person.properties[0].title.as_deref(), Some("Full name"));
+    assert_eq!(person.properties[0].description.as_deref(), Some("Given and family name"));
+    assert_eq!(person.properties[1].title, None);
+
+    let linkml_yaml = convert::build_linkml_doc(Path::new("person"), &shapes).unwrap();
+    let linkml: Yaml = serde_yaml::from_str(&linkml_yaml).unwrap();
+    assert_eq!(linkml["classes"]["Person"]["title"], Yaml::String("Person".to_string()));
+    assert_eq!(linkml["slots"]["name"]["description"], Yaml::String("Given and family name".to_string()));
+    let from_linkml = shex2linkml::shapes_from_linkml(&linkml_yaml).unwrap();
+    assert_eq!((&from_linkml[0].title, &from_linkml[0].properties[0].title), (&person.title, &person.properties[0].title));
+
+    let json_schema = convert::build_json_schema(Path::new("person"), &shapes);
+    let def = &json_schema["definitions"]["Person"];
+    assert_eq!(def["description"], "A human being, \"alive\" or not");
+    assert_eq!(def["properties"]["name"]["title"], "Full name");
+    let from_json = shex2linkml::shapes_from_json_schema(&json_schema).unwrap();
+    let name = from_json[0].properties.iter().find(|p| p.name == "name").unwrap();
+    assert_eq!((&from_json[0].title, &name.description), (&person.title, &person.properties[0].description));
+
+    // regenerated on the way back to ShEx, both from the shape model and from LinkML
+    let reparse = |shex: &str| convert::shapes_from_rudof_ast(&shex_compact::ShExParser::parse(shex, None, &base).expect("reparse shex")).unwrap();
+    assert_eq!(reparse(&convert::build_shex(&shapes)), shapes);
+    let back = shex2linkml::linkml_yaml_to_shex(&linkml_yaml).unwrap();
+    assert!(back.contains("} // <http://www.w3.org/2000/01/rdf-schema#label> \"Person\""), "{}", back);
+    let shexj: serde_json::Value = serde_json::from_str(&shex2linkml::linkml_yaml_to_shex_as(&linkml_yaml, shex2linkml::ShexSyntax::Json).unwrap()).unwrap();
+    assert_eq!(shexj["shapes"][0]["shapeExpr"]["annotations"][0]["object"]["value"], "Person");
+}
+
+#[test]
+fn test_start_shape() {
+    let shex = r#"
+        PREFIX ex: <http://example.org/>
+        PREFIX xsd: <http://www.w3.org/2001/XMLSchema#>
+        start = @ex:Person
+        ex:Address { ex:city xsd:string }
+        ex:Person { ex:name xsd:string ; ex:address @ex:Address }
+    "#;
+
+    let base = iri_s::iris::IriS::new_unchecked("http://example.org/");
+    let schema = shex_compact::ShExParser::parse(shex, None, &base).expect("parse shex");
+    let shapes = convert::shapes_from_rudof_ast(&schema).unwrap();
+    let starts: Vec<(&str, bool)> = shapes.iter().map(|s| (s.name.as_str(), s.start)).collect();
+    assert_eq!(starts, vec![("Address", false), ("Person", true)]);
+
+    let linkml_yaml = convert::build_linkml_doc(Path::new("person"), &shapes).unwrap();
+    let linkml: Yaml = serde_yaml::from_str(&linkml_yaml).unwrap();
+    assert_eq!(linkml["classes"]["Person"]["tree_root"], Yaml::Bool(true));
+    assert!(linkml["classes"]["Address"].get("tree_root").is_none());
+    assert!(shex2linkml::shapes_from_linkml(&linkml_yaml).unwrap()[1].start);
+    let back = shex2linkml::linkml_yaml_to_shex(&linkml_yaml).unwrap();
+    assert!(back.contains("\nstart = @<http://example.org/Person>\n"), "{}", back);
+
+    // without a shape map, START is the JSON Schema root
+    let json_schema = convert::build_json_schema(Path::new("person"), &shapes);
+    assert_eq!(json_schema["$ref"], "#/definitions/Person");
+    let from_json = shex2linkml::shapes_from_json_schema(&json_schema).unwrap();
+    assert!(from_json.iter().find(|s| s.name == "Person").unwrap().start);
+    let mapped = convert::build_json_schema_with_roots(Path::new("person"), &shapes, &["Address".to_string()], false);
+    assert_eq!(mapped["$ref"], "#/definitions/Address");
+
+    let reparsed = shex_compact::ShExParser::parse(&convert::build_shex(&shapes), None, &base).expect("reparse shex");
+    assert_eq!(convert::shapes_from_rudof_ast(&reparsed).unwrap(), shapes);
+
+    // an inline start shape is named `Start`
+    let inline = shex_compact::ShExParser::parse("PREFIX ex: <http://example.org/>\nstart = { ex:name . }", None, &base).expect("parse shex");
+    let inline = convert::shapes_from_rudof_ast(&inline).unwrap();
+    assert_eq!((inline[0].name.as_str(), inline[0].start, inline[0].properties.len()), ("Start", true, 1));
+}
+
+#[test]
+fn test_prefixes() {
+    let shex = r#"
+        BASE <http://example.org/people/>
+        PREFIX foaf: <http://xmlns.com/foaf/0.1/>
+        PREFIX schema: <http://schema.org/>
+        PREFIX xsd: <http://www.w3.org/2001/XMLSchema#>
+        <Person> {
+          foaf:name xsd:string ;
+          schema:gender [ schema:Female schema:Male ] ? ;
+          foaf:knows @<Person> *
+        }
+    "#;
+
+    let base = iri_s::iris::IriS::new_unchecked("http://example.org/");
+    let schema = shex_compact::ShExParser::parse(shex, None, &base).expect("parse shex");
+    let shapes = convert::shapes_from_rudof_ast(&schema).unwrap();
+    let prefixes = convert::prefixes_from_rudof_ast(&schema);
+    assert_eq!(prefixes.base.as_deref(), Some("http://example.org/people/"));
+    assert_eq!(prefixes.compact("http://xmlns.com/foaf/0.1/name").as_deref(), Some("foaf:name"));
+    assert_eq!(prefixes.compact("http://example.net/name"), None);
+
+    let linkml_yaml = convert::build_linkml_doc_with_prefixes(Path::new("people"), &shapes, &prefixes).unwrap();
+    let linkml: Yaml = serde_yaml::from_str(&linkml_yaml).unwrap();
+    assert_eq!(linkml["prefixes"]["foaf"].as_str(), Some("http://xmlns.com/foaf/0.1/"));
+    assert_eq!(linkml["prefixes"]["schema"].as_str(), Some("http://schema.org/"));
+    assert_eq!(linkml["prefixes"]["base"].as_str(), Some("http://example.org/people/"));
+    assert_eq!(linkml["default_prefix"].as_str(), Some("base"));
+    let from_linkml = shex2linkml::linkml_to_shacl::prefixes_from_linkml(&linkml_yaml).unwrap();
+    assert_eq!(from_linkml.base, prefixes.base);
+    assert!(from_linkml.prefixes.contains(&("foaf".to_string(), "http://xmlns.com/foaf/0.1/".to_string())));
+
+    let out = convert::build_shex_with_prefixes(&shapes, &prefixes);
+    assert!(out.starts_with("BASE <http://example.org/people/>\nPREFIX foaf: <http://xmlns.com/foaf/0.1/>\n"), "{}", out);
+    assert!(out.contains("  foaf:name xsd:string ;\n"), "{}", out);
+    assert!(out.contains("schema:gender [ schema:Female schema:Male ] ?"), "{}", out);
+    let reparsed = shex_compact::ShExParser::parse(&out, None, &base).expect("generated ShEx parses");
+    assert_eq!(convert::shapes_from_rudof_ast(&reparsed).unwrap(), shapes);
+
+    let shacl = convert::build_shacl_with_prefixes(&shapes, &prefixes);
+    assert!(shacl.contains("@prefix foaf: <http://xmlns.com/foaf/0.1/> .\n"), "{}", shacl);
+    assert!(shacl.contains("sh:path foaf:knows ;"), "{}", shacl);
+}
+
+#[test]
+fn test_slot_and_class_uris() {
+    let shex = r#"
+        PREFIX ex: <http://example.org/>
+        PREFIX foaf: <http://xmlns.com/foaf/0.1/>
+        PREFIX xsd: <http://www.w3.org/2001/XMLSchema#>
+        ex:Person {
+          foaf:name xsd:string ;
+          <http://example.net/vocab#nick> xsd:string ?
+        }
+    "#;
+
+    let base = iri_s::iris::IriS::new_unchecked("http://example.org/");
+    let schema = shex_compact::ShExParser::parse(shex, None, &base).expect("parse shex");
+    let shapes = convert::shapes_from_rudof_ast(&schema).unwrap();
+    let prefixes = convert::prefixes_from_rudof_ast(&schema);
+
+    let linkml_yaml = convert::build_linkml_doc_with_prefixes(Path::new("person"), &shapes, &prefixes).unwrap();
+    let linkml: Yaml = serde_yaml::from_str(&linkml_yaml).unwrap();
+    assert_eq!(linkml["classes"]["Person"]["class_uri"].as_str(), Some("ex:Person"));
+    assert_eq!(linkml["slots"]["name"]["slot_uri"].as_str(), Some("foaf:name"));
+    assert_eq!(linkml["slots"]["nick"]["slot_uri"].as_str(), Some("http://example.net/vocab#nick"));
+
+    // the back-conversion uses the real predicates
+    let back = shex2linkml::linkml_yaml_to_shex(&linkml_yaml).unwrap();
+    assert!(back.contains("foaf:name"), "{}", back);
+    assert!(back.contains("<http://example.net/vocab#nick>"), "{}", back);
+    let shexj = shex2linkml::linkml_to_shex::linkml_yaml_to_shex_as(&linkml_yaml, shex2linkml::linkml_to_shex::ShexSyntax::Json).unwrap();
+    assert!(shexj.contains("\"http://xmlns.com/foaf/0.1/name\""), "{}", shexj);
+}
+
+#[test]
+fn test_recursive_shapes() {
+    let shex = r#"
+        PREFIX ex: <http://example.org/>
+        PREFIX xsd: <http://www.w3.org/2001/XMLSchema#>
+        ex:Person { ex:name xsd:string ; ex:employer @ex:Company ? ; ex:knows @ex:Person * }
+        ex:Company { ex:ceo @ex:Person ; $ex:contact ex:email xsd:string ? }
+        ex:Branch { &ex:contact ; ex:parent @ex:Branch ? }
+        ex:Loop { $ex:loop ( ex:next @ex:Loop ? ; &ex:loop ) }
+    "#;
+
+    let base = iri_s::iris::IriS::new_unchecked("http://example.org/");
+    let schema = shex_compact::ShExParser::parse(shex, None, &base).expect("parse shex");
+    let shapes = convert::shapes_from_rudof_ast(&schema).unwrap();
+    let ranges = |name: &str| -> Vec<(String, String)> {
+        convert::find_shape(&shapes, name).unwrap().properties.iter().map(|p| (p.name.clone(), p.range.clone())).collect()
+    };
+    let pairs = |ps: &[(&str, &str)]| -> Vec<(String, String)> { ps.iter().map(|(n, r)| (n.to_string(), r.to_string())).collect() };
+    assert_eq!(ranges("Person"), pairs(&[("name", "string"), ("employer", "Company"), ("knows", "Person")]));
+    assert_eq!(ranges("Company"), pairs(&[("ceo", "Person"), ("email", "string")]));
+    // `&label` includes the labelled triple expression; a circular inclusion is expanded once
+    assert_eq!(ranges("Branch"), pairs(&[("email", "string"), ("parent", "Branch")]));
+    assert_eq!(ranges("Loop"), pairs(&[("next", "Loop")]));
+
+    let js = convert::build_json_schema(Path::new("org"), &shapes);
+    assert_eq!(js["definitions"]["Person"]["properties"]["employer"]["$ref"], "#/definitions/Company");
+    assert_eq!(js["definitions"]["Company"]["properties"]["ceo"]["$ref"], "#/definitions/Person");
+    assert_eq!(js["definitions"]["Person"]["properties"]["knows"]["items"]["$ref"], "#/definitions/Person");
+    let from_json = shex2linkml::shapes_from_json_schema(&js).unwrap();
+    assert_eq!(convert::find_shape(&from_json, "Company").unwrap().properties[0].range, "Person");
+
+    let linkml: Yaml = serde_yaml::from_str(&convert::build_linkml_doc(Path::new("org"), &shapes).unwrap()).unwrap();
+    assert_eq!(linkml["slots"]["ceo"]["range"].as_str(), Some("Person"));
+    assert_eq!(linkml["slots"]["employer"]["range"].as_str(), Some("Company"));
+
+    let out = convert::build_shex(&shapes);
+    let reparsed = shex_compact::ShExParser::parse(&out, None, &base).expect("generated ShEx parses");
+    assert_eq!(convert::shapes_from_rudof_ast(&reparsed).unwrap(), shapes);
+
+    // a shape map root keeps everything reachable through the cycle
+    let selected = convert::select_shapes(&shapes, &["Company".to_string()]).unwrap();
+    assert_eq!(selected.iter().map(|s| s.name.as_str()).collect::<Vec<_>>(), vec!["Person", "Company"]);
+}
+
+#[test]
+fn test_inline_shapes() {
+    let shex = r#"
+        PREFIX ex: <http://example.org/>
+        PREFIX xsd: <http://www.w3.org/2001/XMLSchema#>
+        ex:Person {
+          ex:name xsd:string ;
+          ex:address { ex:street xsd:string ; ex:geo { ex:lat xsd:decimal ; ex:long xsd:decimal } ? } ;
+          ( ex:contact { ex:email xsd:string } | ex:contact { ex:phone xsd:string } )
+        }
+        ex:PersonAddress { ex:postcode xsd:string }
+    "#;
+
+    let base = iri_s::iris::IriS::new_unchecked("http://example.org/");
+    let schema = shex_compact::ShExParser::parse(shex, None, &base).expect("parse shex");
+    let shapes = convert::shapes_from_rudof_ast(&schema).unwrap();
+    let names: Vec<&str> = shapes.iter().map(|s| s.name.as_str()).collect();
+    // inline shapes are named after their owner and property, avoiding declared and earlier names
+    assert_eq!(names, vec!["Person", "PersonAddress2", "PersonAddress2Geo", "PersonContact", "PersonContact2", "PersonAddress"]);
+    let person = &shapes[0];
+    let range = |name: &str| person.properties.iter().find(|p| p.name == name).unwrap().range.as_str();
+    assert_eq!(range("address"), "PersonAddress2");
+    assert_eq!(shapes[1].properties[1].range, "PersonAddress2Geo");
+    assert_eq!(shapes[2].properties[0].name, "lat");
+
+    let js = convert::build_json_schema(Path::new("person"), &shapes);
+    assert_eq!(js["definitions"]["Person"]["properties"]["address"]["$ref"], "#/definitions/PersonAddress2");
+    assert_eq!(js["definitions"]["PersonAddress2"]["properties"]["street"]["type"], "string");
+    assert_eq!(js["definitions"]["PersonAddress2Geo"]["required"], serde_json::json!(["lat", "long"]));
+
+    let linkml: Yaml = serde_yaml::from_str(&convert::build_linkml_doc(Path::new("person"), &shapes).unwrap()).unwrap();
+    assert_eq!(linkml["slots"]["address"]["range"].as_str(), Some("PersonAddress2"));
+    assert_eq!(linkml["classes"]["PersonAddress2Geo"]["slots"], serde_yaml::from_str::<Yaml>("[lat, long]").unwrap());
+}
+
+#[test]
+fn test_triple_expr_references() {
+    let shex = r#"
+        PREFIX ex: <http://example.org/>
+        PREFIX xsd: <http://www.w3.org/2001/XMLSchema#>
+        ex:Employee { ex:id xsd:string ; &ex:postal ; &ex:reach }
+        ex:Site {
+          $ex:postal ( ex:street xsd:string ; ex:city xsd:string ) ? ;
+          $ex:reach ( ex:email xsd:string | ex:phone xsd:string )
+        }
+    "#;
+
+    let base = iri_s::iris::IriS::new_unchecked("http://example.org/");
+    let schema = shex_compact::ShExParser::parse(shex, None, &base).expect("parse shex");
+    let shapes = convert::shapes_from_rudof_ast(&schema).unwrap();
+    let employee = convert::find_shape(&shapes, "Employee").unwrap();
+    let site = convert::find_shape(&shapes, "Site").unwrap();
+    // a forward reference shares the labelled expression, cardinality and OneOf group included
+    assert_eq!(employee.properties[1..], site.properties[..]);
+    let cards: Vec<(&str, Option<u64>)> = employee.properties.iter().map(|p| (p.name.as_str(), p.min)).collect();
+    assert_eq!(cards, vec![("id", Some(1)), ("street", Some(0)), ("city", Some(0)), ("email", Some(0)), ("phone", Some(0))]);
+    assert_eq!(employee.constraints, site.constraints);
+
+    // the shared properties survive into each output
+    let js = convert::build_json_schema(Path::new("org"), &shapes);
+    assert!(js["definitions"]["Employee"]["properties"]["city"].is_object());
+    let out = convert::build_shex(&shapes);
+    assert!(out.contains("ex:Employee {\n  <http://example.org/id> xsd:string ;\n  <http://example.org/street> xsd:string ?"), "{}", out);
+}
+
+#[test]
+fn test_repeated_predicates() {
+    let shex = r#"
+        PREFIX ex: <http://example.org/>
+        PREFIX xsd: <http://www.w3.org/2001/XMLSchema#>
+        ex:Person {
+          ex:phone [ ex:Home ] ;
+          ex:phone [ ex:Work ] ? ;
+          ex:member @ex:Team ;
+          ex:member @ex:Club *
+        }
+        ex:Team { ex:name xsd:string }
+        ex:Club { ex:name xsd:string }
+    "#;
+
+    let base = iri_s::iris::IriS::new_unchecked("http://example.org/");
+    let schema = shex_compact::ShExParser::parse(shex, None, &base).expect("parse shex");
+    let shapes = convert::shapes_from_rudof_ast(&schema).unwrap();
+    assert_eq!(shapes[0].properties.len(), 4);
+
+    // the counts add up and value sets are pooled
+    let merged = convert::merged_properties(&shapes[0].properties);
+    let summary: Vec<(&str, Option<u64>, Option<u64>)> = merged.iter().map(|p| (p.name.as_str(), p.min, p.max)).collect();
+    assert_eq!(summary, vec![("phone", Some(1), Some(2)), ("member", Some(1), None)]);
+    assert_eq!(merged[0].values, vec!["http://example.org/Home", "http://example.org/Work"]);
+
+    let linkml: Yaml = serde_yaml::from_str(&convert::build_linkml_doc(Path::new("person"), &shapes).unwrap()).unwrap();
+    assert_eq!(linkml["classes"]["Person"]["slots"], serde_yaml::from_str::<Yaml>("[phone, member]").unwrap());
+    assert_eq!(linkml["slots"]["phone"]["maximum_cardinality"].as_u64(), Some(2));
+    assert_eq!(linkml["slots"]["member"]["any_of"], serde_yaml::from_str::<Yaml>("[{range: Team}, {range: Club}]").unwrap());
+    assert!(linkml["slots"]["member"].get("range").is_none());
+
+    let js = convert::build_json_schema(Path::new("person"), &shapes);
+    let member = &js["definitions"]["Person"]["properties"]["member"];
+    assert_eq!(member["items"], serde_json::json!({ "anyOf": [{ "$ref": "#/definitions/Team" }, { "$ref": "#/definitions/Club" }] }));
+    assert_eq!(member["allOf"], serde_json::json!([{ "contains": { "$ref": "#/definitions/Team" } }]));
+    assert_eq!(js["definitions"]["Person"]["required"], serde_json::json!(["phone", "member"]));
+
+    // SHACL counts each triple constraint's values with a qualified value shape, so a
+    // node with both phones conforms
+    let shacl = convert::build_shacl(&shapes);
+    assert!(shacl.contains("sh:path <http://example.org/phone> ;\n    sh:or ( [ sh:in ( <http://example.org/Home> ) ] [ sh:in ( <http://example.org/Work> ) ] ) ;\n    sh:minCount 1 ;\n    sh:maxCount 2\n"), "{}", shacl);
+    assert!(shacl.contains("sh:qualifiedValueShape [ sh:in ( <http://example.org/Home> ) ] ;\n    sh:qualifiedMinCount 1 ;\n    sh:qualifiedMaxCount 1 ;\n    sh:qualifiedValueShapesDisjoint true\n"));
+    assert!(shacl.contains("sh:qualifiedValueShape [ sh:in ( <http://example.org/Work> ) ] ;\n    sh:qualifiedMaxCount 1 ;\n"));
+    assert!(shacl.contains("sh:qualifiedValueShape [ sh:node ex:Club ] ;\n    sh:qualifiedValueShapesDisjoint true\n"));
+    assert_eq!(shacl.matches("sh:in ( <http://example.org/Home> ) ;").count(), 0);
+
+    // generated code has one field per name
+    assert_eq!(convert::build_typescript(&shapes).matches("phone").count(), 1);
+}
+
+#[test]
+fn test_single_literal_values() {
+    let shex = r#"
+        PREFIX ex: <http://example.org/>
+        PREFIX xsd: <http://www.w3.org/2001/XMLSchema#>
+        ex:Person {
+          ex:kind [ "person" ] ;
+          ex:code /^[A-Z]{3}$/ ;
+          ex:status [ "active" "retired" ] ;
+          ex:type [ ex:Person ]
+        }
+    "#;
+
+    let base = iri_s::iris::IriS::new_unchecked("http://example.org/");
+    let schema = shex_compact::ShExParser::parse(shex, None, &base).expect("parse shex");
+    let shapes = convert::shapes_from_rudof_ast(&schema).unwrap();
+
+    let js = convert::build_json_schema(Path::new("person"), &shapes);
+    let props = &js["definitions"]["Person"]["properties"];
+    assert_eq!(props["kind"], serde_json::json!({ "type": "string", "const": "person" }));
+    assert_eq!(props["code"], serde_json::json!({ "type": "string", "pattern": "^[A-Z]{3}$" }));
+    assert_eq!(props["status"]["enum"], serde_json::json!(["active", "retired"]));
+    // a lone IRI is still an enumeration
+    assert_eq!(props["type"]["enum"], serde_json::json!(["http://example.org/Person"]));
+    let from_json = shex2linkml::shapes_from_json_schema(&js).unwrap();
+    assert_eq!(from_json[0].properties.iter().find(|p| p.name == "kind").unwrap().values, vec!["person"]);
+
+    let linkml_yaml = convert::build_linkml_doc(Path::new("person"), &shapes).unwrap();
+    let linkml: Yaml = serde_yaml::from_str(&linkml_yaml).unwrap();
+    assert_eq!(linkml["slots"]["kind"]["equals_string"].as_str(), Some("person"));
+    assert_eq!(linkml["slots"]["kind"]["range"].as_str(), Some("string"));
+    assert_eq!(linkml["slots"]["code"]["pattern"].as_str(), Some("^[A-Z]{3}$"));
+    assert!(linkml["enums"].get("KindEnum").is_none());
+    assert!(linkml["enums"].get("StatusEnum").is_some());
+    let from_linkml = shex2linkml::shapes_from_linkml(&linkml_yaml).unwrap();
+    assert_eq!(from_linkml[0].properties[0].values, vec!["person"]);
+    let back = shex2linkml::linkml_yaml_to_shex(&linkml_yaml).unwrap();
+    assert!(back.contains("[ \"person\" ]"), "{}", back);
+}
+
+#[test]
+fn test_abstract_shapes() {
+    let shex = r#"
+        PREFIX ex: <http://example.org/>
+        PREFIX xsd: <http://www.w3.org/2001/XMLSchema#>
+        ABSTRACT ex:Agent { ex:name xsd:string }
+        ex:Person EXTENDS @ex:Agent { ex:age xsd:integer ? }
+    "#;
+
+    let base = iri_s::iris::IriS::new_unchecked("http://example.org/");
+    let schema = shex_compact::ShExParser::parse(shex, None, &base).expect("parse shex");
+    let shapes = convert::shapes_from_rudof_ast(&schema).unwrap();
+    assert_eq!(shapes.iter().map(|s| s.is_abstract).collect::<Vec<_>>(), vec![true, false]);
+
+    let linkml_yaml = convert::build_linkml_doc(Path::new("agents"), &shapes).unwrap();
+    let linkml: Yaml = serde_yaml::from_str(&linkml_yaml).unwrap();
+    assert_eq!(linkml["classes"]["Agent"]["abstract"], Yaml::Bool(true));
+    assert!(linkml["classes"]["Person"].get("abstract").is_none());
+    assert!(shex2linkml::shapes_from_linkml(&linkml_yaml).unwrap()[0].is_abstract);
+    let back = shex2linkml::linkml_yaml_to_shex(&linkml_yaml).unwrap();
+    assert!(back.contains("ABSTRACT <http://example.org/Agent> {\n"), "{}", back);
+
+    let out = convert::build_shex(&shapes);
+    assert!(out.contains("\nABSTRACT ex:Agent {\n"), "{}", out);
+    let reparsed = shex_compact::ShExParser::parse(&out, None, &base).expect("generated ShEx parses");
+    assert!(convert::shapes_from_rudof_ast(&reparsed).unwrap()[0].is_abstract);
+
+    // an abstract shape is no root, but its children still reach it
+    let js = convert::build_json_schema_with_roots(Path::new("agents"), &shapes, &["Agent".to_string(), "Person".to_string()], false);
+    assert_eq!(js["$ref"], "#/definitions/Person");
+    assert_eq!(js["definitions"]["Person"]["allOf"], serde_json::json!([{ "$ref": "#/definitions/Agent" }]));
+}
+
+#[test]
+fn test_external_shapes() {
+    let shex = r#"
+        PREFIX ex: <http://example.org/>
+        PREFIX xsd: <http://www.w3.org/2001/XMLSchema#>
+        ex:Address EXTERNAL
+        ex:Person { ex:name xsd:string ; ex:address @ex:Address }
+    "#;
+
+    let base = iri_s::iris::IriS::new_unchecked("http://example.org/");
+    let schema = shex_compact::ShExParser::parse(shex, None, &base).expect("parse shex");
+    let shapes = convert::shapes_from_rudof_ast(&schema).unwrap();
+    let address = convert::find_shape(&shapes, "Address").unwrap();
+    assert!(address.external && address.properties.is_empty());
+    assert_eq!(shapes[1].properties[1].range, "Address");
+
+    // JSON Schema refers to the external shape by its IRI
+    let js = convert::build_json_schema(Path::new("person"), &shapes);
+    assert_eq!(js["definitions"]["Address"], serde_json::json!({ "$ref": "http://example.org/Address" }));
+    assert_eq!(js["definitions"]["Person"]["properties"]["address"]["$ref"], "#/definitions/Address");
+    let from_json = shex2linkml::shapes_from_json_schema(&js).unwrap();
+    let address = convert::find_shape(&from_json, "Address").unwrap();
+    assert_eq!((address.id.as_str(), address.external), ("http://example.org/Address", true));
+
+    let linkml_yaml = convert::build_linkml_doc(Path::new("person"), &shapes).unwrap();
+    let linkml: Yaml = serde_yaml::from_str(&linkml_yaml).unwrap();
+    assert_eq!(linkml["classes"]["Address"]["annotations"]["external"], Yaml::Bool(true));
+    assert!(convert::find_shape(&shex2linkml::shapes_from_linkml(&linkml_yaml).unwrap(), "Address").unwrap().external);
+    let back = shex2linkml::linkml_yaml_to_shex(&linkml_yaml).unwrap();
+    assert!(back.contains("<http://example.org/Address> EXTERNAL\n"), "{}", back);
+
+    let out = convert::build_shex(&shapes);
+    assert!(out.contains("\nex:Address EXTERNAL\n"), "{}", out);
+    let reparsed = shex_compact::ShExParser::parse(&out, None, &base).expect("generated ShEx parses");
+    assert!(convert::shapes_from_rudof_ast(&reparsed).unwrap()[0].external);
+
+    let shacl = convert::build_shacl(&shapes);
+    assert!(!shacl.contains("ex:Address a sh:NodeShape"), "{}", shacl);
+    assert!(shacl.contains("sh:node ex:Address"), "{}", shacl);
+}
+
+#[test]
+fn test_semantic_actions() {
+    let shex = r#"
+        PREFIX ex: <http://example.org/>
+        PREFIX xsd: <http://www.w3.org/2001/XMLSchema#>
+        ex:Person {
+          ex:name xsd:string %ex:js{ report(o) %} ;
+          ex:age xsd:integer ?
+        } %ex:js{ done() %}
+    "#;
+
+    let base = iri_s::iris::IriS::new_unchecked("http://example.org/");
+    let schema = shex_compact::ShExParser::parse(shex, None, &base).expect("parse shex");
+    let shapes = convert::shapes_from_rudof_ast(&schema).unwrap();
+    let js_act = |code: &str| convert::SemAct { name: "http://example.org/js".to_string(), code: Some(code.to_string()) };
+    assert_eq!(shapes[0].sem_acts, vec![js_act(" done() ")]);
+    assert_eq!(shapes[0].properties[0].sem_acts, vec![js_act(" report(o) ")]);
+    assert!(shapes[0].properties[1].sem_acts.is_empty());
+
+    // other formats can't run them, but carry them as annotations
+    let linkml_yaml = convert::build_linkml_doc(Path::new("person"), &shapes).unwrap();
+    let linkml: Yaml = serde_yaml::from_str(&linkml_yaml).unwrap();
+    assert_eq!(linkml["classes"]["Person"]["annotations"]["semantic_actions"][0]["name"].as_str(), Some("http://example.org/js"));
+    assert_eq!(linkml["slots"]["name"]["annotations"]["semantic_actions"][0]["code"].as_str(), Some(" report(o) "));
+    let from_linkml = shex2linkml::shapes_from_linkml(&linkml_yaml).unwrap();
+    assert_eq!((&from_linkml[0].sem_acts, &from_linkml[0].properties[0].sem_acts), (&shapes[0].sem_acts, &shapes[0].properties[0].sem_acts));
+
+    let json_schema = convert::build_json_schema(Path::new("person"), &shapes);
+    let def = &json_schema["definitions"]["Person"];
+    assert_eq!(def["x-shex-semacts"], serde_json::json!([{ "name": "http://example.org/js", "code": " done() " }]));
+    assert_eq!(def["properties"]["name"]["x-shex-semacts"][0]["code"], " report(o) ");
+    let from_json = shex2linkml::shapes_from_json_schema(&json_schema).unwrap();
+    assert_eq!((&from_json[0].sem_acts, &from_json[0].properties[0].sem_acts), (&shapes[0].sem_acts, &shapes[0].properties[0].sem_acts));
+
+    // and they're restored on the way back to ShEx
+    let out = convert::build_shex(&shapes);
+    assert!(out.contains("%<http://example.org/js>{ report(o) %}"), "{}", out);
+    let reparsed = shex_compact::ShExParser::parse(&out, None, &base).expect("generated ShEx parses");
+    assert_eq!(convert::shapes_from_rudof_ast(&reparsed).unwrap(), shapes);
+    let back = shex2linkml::linkml_yaml_to_shex(&linkml_yaml).unwrap();
+    assert!(back.contains("} %<http://example.org/js>{ done() %}\n"), "{}", back);
+    let shexj: serde_json::Value = serde_json::from_str(&shex2linkml::linkml_yaml_to_shex_as(&linkml_yaml, shex2linkml::ShexSyntax::Json).unwrap()).unwrap();
+    assert_eq!(shexj["shapes"][0]["shapeExpr"]["semActs"][0]["code"], " done() ");
+    assert_eq!(shexj["shapes"][0]["shapeExpr"]["expression"]["expressions"][0]["semActs"][0]["name"], "http://example.org/js");
+}
+
+#[test]
+fn test_conflicting_slot_usage() {
+    let shex = r#"
+        PREFIX ex: <http://example.org/>
+        PREFIX xsd: <http://www.w3.org/2001/XMLSchema#>
+        ex:Reading { ex:value xsd:integer ; ex:unit [ "C" "F" ] ; ex:note xsd:string ? }
+        ex:Label { ex:value xsd:string * ; ex:unit [ "en" "fr" ] ? ; ex:note xsd:string ? }
+    "#;
+
+    let base = iri_s::iris::IriS::new_unchecked("http://example.org/");
+    let schema = shex_compact::ShExParser::parse(shex, None, &base).expect("parse shex");
+    let shapes = convert::shapes_from_rudof_ast(&schema).unwrap();
+
+    // the global slot keeps what both classes agree on; each class refines the rest
+    let linkml_yaml = convert::build_linkml_doc(Path::new("readings"), &shapes).unwrap();
+    let linkml: Yaml = serde_yaml::from_str(&linkml_yaml).unwrap();
+    assert_eq!(linkml["slots"]["value"]["slot_uri"].as_str(), Some("ex:value"));
+    assert!(linkml["slots"]["value"].get("range").is_none());
+    assert_eq!(linkml["classes"]["Reading"]["slot_usage"]["value"]["range"].as_str(), Some("integer"));
+    assert_eq!(linkml["classes"]["Label"]["slot_usage"]["value"]["range"].as_str(), Some("string"));
+    assert_eq!(linkml["classes"]["Label"]["slot_usage"]["value"]["multivalued"], Yaml::Bool(true));
+    assert!(linkml["classes"]["Reading"]["slot_usage"]["value"].get("multivalued").is_none());
+    // a slot used alike everywhere needs no refinement
+    assert!(linkml["classes"]["Label"]["slot_usage"].get("note").is_none());
+    assert_eq!(linkml["slots"]["note"]["range"].as_str(), Some("string"));
+    // and differing value sets get an enum each
+    assert_eq!(linkml["classes"]["Reading"]["slot_usage"]["unit"]["range"].as_str(), Some("UnitEnum"));
+    assert_eq!(linkml["classes"]["Label"]["slot_usage"]["unit"]["range"].as_str(), Some("LabelUnitEnum"));
+
+    let from_linkml = shex2linkml::shapes_from_linkml(&linkml_yaml).unwrap();
+    let summary = |s: &convert::ShapeInfo| -> Vec<(String, String, Option<u64>, Option<u64>, Vec<String>)> {
+        s.properties.iter().map(|p| (p.name.clone(), p.range.clone(), p.min, p.max, p.values.clone())).collect()
+    };
+    assert_eq!(summary(&from_linkml[0]), summary(&shapes[0]));
+    assert_eq!(summary(&from_linkml[1]), summary(&shapes[1]));
+
+    // JSON Schema properties are scoped to their definitions already
+    let js = convert::build_json_schema(Path::new("readings"), &shapes);
+    assert_eq!(js["definitions"]["Reading"]["properties"]["value"]["type"], "integer");
+    assert_eq!(js["definitions"]["Label"]["properties"]["value"]["items"]["type"], "string");
+}
+
+#[test]
+fn test_name_collisions() {
+    let shex = r#"
+        PREFIX ex: <http://example.org/>
+        PREFIX foaf: <http://xmlns.com/foaf/0.1/>
+        PREFIX schema: <http://schema.org/>
+        PREFIX xsd: <http://www.w3.org/2001/XMLSchema#>
+        ex:Person { foaf:name xsd:string ; schema:name xsd:string ? ; ex:age xsd:integer }
+        ex:Org { schema:name xsd:string ; <http://example.net/age> xsd:integer ? }
+    "#;
+
+    let base = iri_s::iris::IriS::new_unchecked("http://example.org/");
+    let schema = shex_compact::ShExParser::parse(shex, None, &base).expect("parse shex");
+    let shapes = convert::shapes_from_rudof_ast(&schema).unwrap();
+    let prefixes = convert::prefixes_from_rudof_ast(&schema);
+    let names = |shapes: &[convert::ShapeInfo]| -> Vec<Vec<String>> {
+        shapes.iter().map(|s| s.properties.iter().map(|p| p.name.clone()).collect()).collect()
+    };
+
+    // prefix-qualified, numbered where no prefix covers the predicate
+    let mut qualified = shapes.clone();
+    let renames = naming::disambiguate_names(&mut qualified, &prefixes, naming::NameCollisions::Qualify);
+    assert_eq!(names(&qualified), vec![vec!["foaf_name", "schema_name", "ex_age"], vec!["schema_name", "age2"]]);
+    assert!(renames.contains(&("http://xmlns.com/foaf/0.1/name".to_string(), "foaf_name".to_string())));
+    assert_eq!(renames.len(), 4);
+    let linkml: Yaml = serde_yaml::from_str(&convert::build_linkml_doc_with_prefixes(Path::new("people"), &qualified, &prefixes).unwrap()).unwrap();
+    assert_eq!(linkml["slots"]["foaf_name"]["slot_uri"].as_str(), Some("foaf:name"));
+    assert_eq!(linkml["slots"]["schema_name"]["slot_uri"].as_str(), Some("schema:name"));
+
+    let mut numbered = shapes.clone();
+    naming::disambiguate_names(&mut numbered, &prefixes, naming::NameCollisions::Number);
+    assert_eq!(names(&numbered), vec![vec!["name", "name2", "age"], vec!["name2", "age2"]]);
+
+    let mut kept = shapes.clone();
+    assert!(naming::disambiguate_names(&mut kept, &prefixes, naming::NameCollisions::Keep).is_empty());
+    assert_eq!(kept, shapes);
+}
+
+#[test]
+fn test_naming_options() {
+    let shex = r#"
+        PREFIX ex: <http://example.org/>
+        PREFIX xsd: <http://www.w3.org/2001/XMLSchema#>
+        ex:postal_address { ex:street_name xsd:string ; ex:type xsd:string ? }
+        ex:Person { ex:givenName xsd:string ; ex:home @ex:postal_address }
+    "#;
+
+    let base = iri_s::iris::IriS::new_unchecked("http://example.org/");
+    let schema = shex_compact::ShExParser::parse(shex, None, &base).expect("parse shex");
+    let shapes = convert::shapes_from_rudof_ast(&schema).unwrap();
+    let prefixes = convert::prefixes_from_rudof_ast(&schema);
+    let names = |shapes: &[convert::ShapeInfo]| -> Vec<(String, Vec<String>)> {
+        shapes.iter().map(|s| (s.name.clone(), s.properties.iter().map(|p| p.name.clone()).collect())).collect()
+    };
+
+    let mut python = shapes.clone();
+    naming::apply_naming(&mut python, &prefixes, &naming::NamingOptions {
+        class_case: naming::NameCase::Pascal,
+        slot_case: naming::NameCase::Snake,
+        escape_keywords: vec![naming::Keywords::Rust],
+        ..Default::default()
+    });
+    assert_eq!(names(&python), vec![
+        ("PostalAddress".to_string(), vec!["street_name".to_string(), "type_".to_string()]),
+        ("Person".to_string(), vec!["given_name".to_string(), "home".to_string()]),
+    ]);
+    // references to a renamed shape follow it
+    assert_eq!(python[1].properties[1].range, "PostalAddress");
+
+    let mut qualified = shapes.clone();
+    naming::apply_naming(&mut qualified, &prefixes, &naming::NamingOptions {
+        slot_case: naming::NameCase::Camel,
+        qualify: true,
+        ..Default::default()
+    });
+    assert_eq!(names(&qualified), vec![
+        ("ex_postal_address".to_string(), vec!["exStreetName".to_string(), "exType".to_string()]),
+        ("ex_Person".to_string(), vec!["exGivenName".to_string(), "exHome".to_string()]),
+    ]);
+    assert_eq!(qualified[1].properties[1].range, "ex_postal_address");
+
+    let mut preserved = shapes.clone();
+    naming::apply_naming(&mut preserved, &prefixes, &naming::NamingOptions::default());
+    assert_eq!(preserved, shapes);
+}
+
+#[test]
+fn test_naming_collisions_from_casing() {
+    let shex = r#"
+        PREFIX ex: <http://example.org/>
+        PREFIX xsd: <http://www.w3.org/2001/XMLSchema#>
+        ex:fooShape { ex:fooBar xsd:string ; ex:foo_bar xsd:integer }
+        ex:FooShape { ex:name xsd:string }
+    "#;
+
+    let base = iri_s::iris::IriS::new_unchecked("http://example.org/");
+    let schema = shex_compact::ShExParser::parse(shex, None, &base).expect("parse shex");
+    let shapes = convert::shapes_from_rudof_ast(&schema).unwrap();
+    let prefixes = convert::prefixes_from_rudof_ast(&schema);
+
+    // `fooBar` and `foo_bar` only collide once snake_cased
+    let mut numbered = shapes.clone();
+    let renames = naming::apply_naming(&mut numbered, &prefixes, &naming::NamingOptions {
+        class_case: naming::NameCase::Pascal,
+        slot_case: naming::NameCase::Snake,
+        collisions: naming::NameCollisions::Number,
+        ..Default::default()
+    });
+    let slots: Vec<&str> = numbered[0].properties.iter().map(|p| p.name.as_str()).collect();
+    assert_eq!(slots, vec!["foo_bar", "foo_bar2"]);
+    assert_eq!(renames, vec![("http://example.org/foo_bar".to_string(), "foo_bar2".to_string())]);
+    assert_eq!(numbered.iter().map(|s| s.name.as_str()).collect::<Vec<_>>(), vec!["FooShape", "FooShape2"]);
+
+    // qualified names are cased before they're checked
+    let mut qualified = shapes.clone();
+    naming::apply_naming(&mut qualified, &prefixes, &naming::NamingOptions { slot_case: naming::NameCase::Snake, ..Default::default() });
+    let slots: Vec<&str> = qualified[0].properties.iter().map(|p| p.name.as_str()).collect();
+    assert_eq!(slots, vec!["ex_foo_bar", "foo_bar2"]);
+}
+
+#[test]
+fn test_name_map() {
+    let shex = r#"
+        PREFIX ex: <http://example.org/>
+        PREFIX foaf: <http://xmlns.com/foaf/0.1/>
+        PREFIX xsd: <http://www.w3.org/2001/XMLSchema#>
+        ex:P1 { foaf:name xsd:string ; ex:hasHome @ex:A1 ? }
+        ex:A1 { ex:street xsd:string }
+    "#;
+    let names = naming::parse_name_map(r#"
+        prefixes:
+          ex: http://example.org/
+        shapes:
+          ex:P1: Person
+          <http://example.org/A1>: Address
+        predicates:
+          http://xmlns.com/foaf/0.1/name: fullName
+          ex:hasHome: home
+    "#).unwrap();
+    assert_eq!(names.predicate("home"), Some("http://example.org/hasHome"));
+
+    let base = iri_s::iris::IriS::new_unchecked("http://example.org/");
+    let schema = shex_compact::ShExParser::parse(shex, None, &base).expect("parse shex");
+    let mut shapes = convert::shapes_from_rudof_ast(&schema).unwrap();
+    let prefixes = convert::prefixes_from_rudof_ast(&schema);
+    // curated names win over the case convention
+    naming::apply_naming(&mut shapes, &prefixes, &naming::NamingOptions { slot_case: naming::NameCase::Snake, names: names.clone(), ..Default::default() });
+    let linkml = convert::build_linkml_doc_with_prefixes(Path::new("people"), &shapes, &prefixes).unwrap();
+    let doc: Yaml = serde_yaml::from_str(&linkml).unwrap();
+    assert_eq!(doc["classes"]["Person"]["slots"], serde_yaml::from_str::<Yaml>("[fullName, home]").unwrap());
+    assert_eq!(doc["slots"]["home"]["range"].as_str(), Some("Address"));
+    assert_eq!(doc["slots"]["street"]["range"].as_str(), Some("string"));
+
+    // and map back to the original IRIs
+    let without_uris = linkml.lines().filter(|l| !l.contains("slot_uri")).collect::<Vec<_>>().join("\n");
+    let options = shex2linkml::linkml_to_shex::ShexOptions { names, ..Default::default() };
+    let back = shex2linkml::linkml_to_shex::linkml_yaml_to_shex_with(&without_uris, &options).unwrap();
+    assert!(back.contains("<http://example.org/P1>"));
+    assert!(back.contains("<http://xmlns.com/foaf/0.1/name>"));
+    assert!(back.contains("<http://example.org/hasHome>"));
+    assert!(naming::parse_name_map("predicates: { 'http://a/x': n, 'http://b/x': n }").is_err());
+}
+
+#[test]
+fn test_rewrite_namespaces() {
+    let shex = r#"
+        PREFIX ex: <http://example.org/old/>
+        PREFIX exv: <http://example.org/old/vocab#>
+        PREFIX xsd: <http://www.w3.org/2001/XMLSchema#>
+        ex:Person { exv:status [ exv:active exv:retired ] ; exv:homepage [ <http://example.org/old/pages/>~ ] ; ex:name xsd:string }
+    "#;
+
+    let base = iri_s::iris::IriS::new_unchecked("http://example.org/");
+    let schema = shex_compact::ShExParser::parse(shex, None, &base).expect("parse shex");
+    let mut shapes = convert::shapes_from_rudof_ast(&schema).unwrap();
+    let mut prefixes = convert::prefixes_from_rudof_ast(&schema);
+    let rewrites = vec![
+        convert::parse_rewrite("http://example.org/old/=https://example.com/new/").unwrap(),
+        convert::parse_rewrite("http://example.org/old/vocab#=https://example.com/terms/").unwrap(),
+    ];
+    assert!(convert::parse_rewrite("http://example.org/old/").is_err());
+    convert::rewrite_namespaces(&mut shapes, &mut prefixes, &rewrites);
+
+    assert_eq!(shapes[0].id, "https://example.com/new/Person");
+    let predicates: Vec<&str> = shapes[0].properties.iter().map(|p| p.predicate.as_str()).collect();
+    assert_eq!(predicates, vec!["https://example.com/terms/status", "https://example.com/terms/homepage", "https://example.com/new/name"]);
+    assert_eq!(shapes[0].properties[0].values, vec!["https://example.com/terms/active", "https://example.com/terms/retired"]);
+    assert_eq!(shapes[0].properties[1].iri_stems[0].stem, "https://example.com/new/pages/");
+    assert!(prefixes.prefixes.contains(&("exv".to_string(), "https://example.com/terms/".to_string())));
+
+    // the rewritten schema still writes CURIEs under the same prefixes
+    let shexc = convert::build_shex_with_prefixes(&shapes, &prefixes);
+    assert!(shexc.contains("PREFIX ex: <https://example.com/new/>"));
+    assert!(shexc.contains("exv:status [ exv:active exv:retired ]"));
+}
+
+#[test]
+fn test_iri_names() {
+    let shex = r#"
+        PREFIX ex: <http://example.org/>
+        PREFIX foaf: <http://xmlns.com/foaf/0.1/>
+        PREFIX xsd: <http://www.w3.org/2001/XMLSchema#>
+        ex:Person { foaf:name xsd:string ; ex:home @ex:Address ? ; <http://other.example/age> xsd:integer ? }
+        ex:Address { ex:street xsd:string }
+    "#;
+
+    let base = iri_s::iris::IriS::new_unchecked("http://example.org/");
+    let schema = shex_compact::ShExParser::parse(shex, None, &base).expect("parse shex");
+    let mut shapes = convert::shapes_from_rudof_ast(&schema).unwrap();
+    let prefixes = convert::prefixes_from_rudof_ast(&schema);
+    naming::apply_naming(&mut shapes, &prefixes, &naming::NamingOptions { iri_names: true, slot_case: naming::NameCase::Snake, ..Default::default() });
+
+    let linkml = convert::build_linkml_doc_with_prefixes(Path::new("people"), &shapes, &prefixes).unwrap();
+    let doc: Yaml = serde_yaml::from_str(&linkml).unwrap();
+    assert_eq!(doc["classes"]["ex:Person"]["slots"], serde_yaml::from_str::<Yaml>("['foaf:name', 'ex:home', 'http://other.example/age']").unwrap());
+    assert_eq!(doc["slots"]["ex:home"]["range"].as_str(), Some("ex:Address"));
+
+    // names alone are enough to get the IRIs back
+    let without_uris = linkml.lines().filter(|l| !l.contains("slot_uri")).collect::<Vec<_>>().join("\n");
+    let back = shex2linkml::linkml_to_shex::linkml_yaml_to_shex(&without_uris).unwrap();
+    assert!(back.contains("<http://example.org/Person>"));
+    assert!(back.contains("foaf:name "));
+    assert!(back.contains("<http://other.example/age> "));
+
+    let js = convert::build_json_schema(Path::new("people"), &naming::with_iri_property_names(&shapes));
+    let properties = js["definitions"]["ex:Person"]["properties"].as_object().unwrap();
+    for iri in ["http://xmlns.com/foaf/0.1/name", "http://example.org/home", "http://other.example/age"] {
+        assert!(properties.contains_key(iri));
+    }
+    let read_back = shex2linkml::json_schema_to_linkml::shapes_from_json_schema(&js).unwrap();
+    let person = read_back.iter().find(|s| s.name == "ex:Person").unwrap();
+    assert!(person.properties.iter().any(|p| p.predicate == "http://xmlns.com/foaf/0.1/name"));
+}
+
+#[test]
+fn test_output_order() {
+    let shex = r#"
+        PREFIX xsd: <http://www.w3.org/2001/XMLSchema#>
+        PREFIX ex: <http://example.org/>
+        ex:Person { ex:name xsd:string ; ex:age xsd:integer ? ; ex:address @ex:Address ? }
+        ex:Address { ex:street xsd:string ; ex:city xsd:string }
+    "#;
+    let base = iri_s::iris::IriS::new_unchecked("http://example.org/");
+    let schema = shex_compact::ShExParser::parse(shex, None, &base).expect("parse shex");
+    let mut shapes = convert::shapes_from_rudof_ast(&schema).unwrap();
+    let mut prefixes = convert::prefixes_from_rudof_ast(&schema);
+
+    // JSON Schema keeps the schema's order, as LinkML does
+    let keys = |v: &serde_json::Value| v.as_object().unwrap().keys().cloned().collect::<Vec<_>>();
+    let js = convert::build_json_schema(Path::new("people"), &shapes);
+    assert_eq!(keys(&js["definitions"]), vec!["Person", "Address"]);
+    assert_eq!(keys(&js["definitions"]["Person"]["properties"]), vec!["name", "age", "address"]);
+
+    // or everything goes in name order
+    convert::sort_schema(&mut shapes, &mut prefixes);
+    let js = convert::build_json_schema(Path::new("people"), &shapes);
+    assert_eq!(keys(&js["definitions"]), vec!["Address", "Person"]);
+    assert_eq!(keys(&js["definitions"]["Person"]["properties"]), vec!["address", "age", "name"]);
+    let linkml: Yaml = serde_yaml::from_str(&convert::build_linkml_doc_with_prefixes(Path::new("people"), &shapes, &prefixes).unwrap()).unwrap();
+    let classes: Vec<&str> = linkml["classes"].as_mapping().unwrap().keys().filter_map(|k| k.as_str()).collect();
+    assert_eq!(classes, vec!["Address", "Person"]);
+    let shexc = convert::build_shex_with_prefixes(&shapes, &prefixes);
+    assert!(shexc.find("PREFIX ex:").unwrap() < shexc.find("PREFIX xsd:").unwrap(), "{}", shexc);
+    assert!(shexc.find("ex:Address").unwrap() < shexc.find("ex:Person").unwrap(), "{}", shexc);
+}
+
 /*
 #[test]
 fn test_json_schema_generation() {
@@ -0,0 +1,586 @@
+use shex2linkml::convert::{self, PropertyInfo, ShapeConstraint, ShapeInfo};
+
+fn prop(name: &str, range: &str, min: u64, max: Option<u64>) -> PropertyInfo {
+    PropertyInfo {
+        name: name.to_string(),
+        predicate: format!("http://example.org/{}", name),
+        range: range.to_string(),
+        min: Some(min),
+        max,
+        ..Default::default()
+    }
+}
+
+fn person_shapes() -> Vec<ShapeInfo> {
+    vec![ShapeInfo {
+        id: "Person".to_string(),
+        name: "Person".to_string(),
+        properties: vec![
+            prop("name", "string", 1, Some(1)),
+            prop("age", "integer", 0, Some(1)),
+            prop("knows", "Person", 0, None),
+        ],
+        ..Default::default()
+    }]
+}
+
+#[test]
+fn test_build_shacl() {
+    let shacl = convert::build_shacl(&person_shapes());
+
+    assert!(shacl.contains("ex:Person a sh:NodeShape"));
+    assert!(shacl.contains("sh:path <http://example.org/name> ;\n    sh:datatype xsd:string ;\n    sh:minCount 1 ;\n    sh:maxCount 1"));
+    assert!(shacl.contains("sh:path <http://example.org/age> ;\n    sh:datatype xsd:integer ;\n    sh:maxCount 1"));
+    assert!(shacl.contains("sh:path <http://example.org/knows> ;\n    sh:node ex:Person\n"));
+}
+
+#[test]
+fn test_build_owl() {
+    let owl = convert::build_owl(&person_shapes());
+
+    assert!(owl.contains("ex:Person a owl:Class"));
+    assert!(owl.contains("owl:onProperty <http://example.org/name> ; owl:minCardinality \"1\"^^xsd:nonNegativeInteger"));
+    assert!(!owl.contains("owl:onProperty <http://example.org/knows> ; owl:maxCardinality"));
+    assert!(owl.contains("<http://example.org/age> a owl:DatatypeProperty ;\n  rdfs:domain ex:Person ;\n  rdfs:range xsd:integer ."));
+    assert!(owl.contains("<http://example.org/knows> a owl:ObjectProperty ;\n  rdfs:domain ex:Person ;\n  rdfs:range ex:Person ."));
+}
+
+#[test]
+fn test_build_graphql() {
+    let mut shapes = person_shapes();
+    let mut status = prop("status", "string", 0, Some(1));
+    status.values = vec!["active".to_string(), "retired".to_string()];
+    shapes[0].properties.push(status);
+
+    let graphql = convert::build_graphql(&shapes);
+
+    assert_eq!(graphql, "type Person {
+  name: String!
+  age: Int
+  knows: [Person!]
+  status: StatusEnum
+}
+
+enum StatusEnum {
+  active
+  retired
+}
+");
+}
+
+#[test]
+fn test_build_graphql_shared_enums() {
+    let mut shapes = person_shapes();
+    let mut status = prop("status", "string", 0, Some(1));
+    status.values = vec!["active".to_string(), "retired".to_string()];
+    shapes[0].properties = vec![status.clone()];
+    let mut project = shapes[0].clone();
+    project.name = "Project".to_string();
+    let mut company = shapes[0].clone();
+    company.name = "Company".to_string();
+    status.values = vec!["open".to_string(), "null".to_string()];
+    company.properties = vec![status];
+    shapes.extend([project, company]);
+
+    let graphql = convert::build_graphql(&shapes);
+
+    // the same value set shares its enum; another gets one named after its class
+    assert!(graphql.contains("type Project {\n  status: StatusEnum\n}"), "{}", graphql);
+    assert!(graphql.contains("type Company {\n  status: CompanyStatusEnum\n}"), "{}", graphql);
+    assert_eq!(graphql.matches("enum StatusEnum {").count(), 1, "{}", graphql);
+    assert!(graphql.ends_with("enum CompanyStatusEnum {\n  open\n  null_\n}\n"), "{}", graphql);
+}
+
+#[test]
+fn test_build_protobuf() {
+    let mut shapes = person_shapes();
+    let mut status = prop("status", "string", 1, Some(1));
+    status.values = vec!["active".to_string(), "retired".to_string()];
+    shapes[0].properties.push(status);
+
+    let proto = convert::build_protobuf(&shapes);
+
+    assert_eq!(proto, "syntax = \"proto3\";
+
+message Person {
+  string name = 1;
+  optional int64 age = 2;
+  repeated Person knows = 3;
+  StatusEnum status = 4;
+}
+
+enum StatusEnum {
+  STATUS_ENUM_UNSPECIFIED = 0;
+  STATUS_ENUM_ACTIVE = 1;
+  STATUS_ENUM_RETIRED = 2;
+}
+");
+}
+
+#[test]
+fn test_build_protobuf_shared_enums() {
+    let mut shapes = person_shapes();
+    let mut status = prop("status", "string", 1, Some(1));
+    status.values = vec!["active".to_string(), "retired".to_string()];
+    shapes[0].properties = vec![status.clone()];
+    let mut project = shapes[0].clone();
+    project.name = "Project".to_string();
+    let mut company = shapes[0].clone();
+    company.name = "Company".to_string();
+    status.values = vec!["active".to_string(), "closed".to_string()];
+    company.properties = vec![status];
+    shapes.extend([project, company]);
+
+    let proto = convert::build_protobuf(&shapes);
+
+    assert!(proto.contains("message Project {\n  StatusEnum status = 1;\n}"), "{}", proto);
+    assert!(proto.contains("message Company {\n  CompanyStatusEnum status = 1;\n}"), "{}", proto);
+    assert_eq!(proto.matches("enum StatusEnum {").count(), 1, "{}", proto);
+    // values are prefixed with their enum's name, so `active` is declared once per enum
+    assert!(proto.contains("  STATUS_ENUM_ACTIVE = 1;\n"), "{}", proto);
+    assert!(proto.contains("  COMPANY_STATUS_ENUM_ACTIVE = 1;\n"), "{}", proto);
+}
+
+#[test]
+fn test_build_typescript() {
+    let mut shapes = person_shapes();
+    let mut status = prop("status", "string", 0, Some(1));
+    status.values = vec!["active".to_string(), "retired".to_string()];
+    shapes[0].properties.push(status);
+    shapes[0].properties.push(prop("birth-date", "string", 0, Some(1)));
+
+    let ts = convert::build_typescript(&shapes);
+
+    assert_eq!(ts, "export interface Person {
+  name: string;
+  age?: number;
+  knows?: Person[];
+  status?: \"active\" | \"retired\";
+  \"birth-date\"?: string;
+}
+");
+}
+
+#[test]
+fn test_build_pydantic() {
+    let mut shapes = person_shapes();
+    shapes[0].properties.push(prop("email", "string", 1, Some(3)));
+    shapes[0].properties.push(prop("class", "string", 1, Some(1)));
+
+    let py = convert::build_pydantic(&shapes);
+
+    assert!(py.contains("class Person(BaseModel):
+    name: str
+    age: Optional[int] = None
+    knows: List[Person] = Field(default_factory=list)
+    email: List[str] = Field(min_length=1, max_length=3)
+    class_: str = Field(alias=\"class\")
+"));
+}
+
+#[test]
+fn test_build_pydantic_facets() {
+    let mut shapes = person_shapes();
+    shapes[0].properties[0].facets.pattern = Some("^\\w+$".to_string());
+    shapes[0].properties[0].facets.max_length = Some(40);
+    shapes[0].properties[1].facets.min_inclusive = Some(serde_json::Number::from(0));
+    shapes[0].properties[1].facets.max_exclusive = Some(serde_json::Number::from(150));
+    let mut tags = prop("tag", "string", 0, None);
+    tags.facets.min_length = Some(2);
+    shapes[0].properties.push(tags);
+
+    let py = convert::build_pydantic(&shapes);
+
+    assert!(py.contains("    name: str = Field(pattern=\"^\\\\w+$\", max_length=40)\n"), "{}", py);
+    assert!(py.contains("    age: Optional[int] = Field(default=None, ge=0, lt=150)\n"), "{}", py);
+    // a list's own Field bounds its length, so item facets go on the item type
+    assert!(py.contains("    tag: List[Annotated[str, Field(min_length=2)]] = Field(default_factory=list)\n"), "{}", py);
+}
+
+#[test]
+fn test_build_rust() {
+    let mut shapes = person_shapes();
+    shapes[0].properties.push(prop("homepage", "uriorcurie", 0, Some(1)));
+    shapes[0].properties.push(prop("type", "string", 1, Some(1)));
+    shapes[0].properties.push(prop("parent", "Person", 0, Some(1)));
+    shapes[0].properties.push(prop("birthDate", "date", 0, Some(1)));
+    let mut employer = prop("employer", "string", 0, Some(1));
+    employer.node_kind = Some(convert::NodeKind::Iri);
+    shapes[0].properties.push(employer);
+
+    let rs = convert::build_rust(&shapes);
+
+    assert!(rs.contains("pub struct Person {
+    pub name: String,
+    #[serde(default, skip_serializing_if = \"Option::is_none\")]
+    pub age: Option<i64>,
+    #[serde(default)]
+    pub knows: Vec<Person>,
+    #[serde(default, skip_serializing_if = \"Option::is_none\")]
+    pub homepage: Option<Iri>,
+    #[serde(rename = \"type\")]
+    pub type_: String,
+    #[serde(default, skip_serializing_if = \"Option::is_none\")]
+    pub parent: Option<Box<Person>>,
+    #[serde(rename = \"birthDate\", default, skip_serializing_if = \"Option::is_none\")]
+    pub birth_date: Option<String>,
+    #[serde(default, skip_serializing_if = \"Option::is_none\")]
+    pub employer: Option<Iri>,
+}"), "{}", rs);
+    assert!(rs.contains("pub struct Iri(pub String);"));
+}
+
+#[test]
+fn test_build_rust_shared_enums() {
+    let mut shapes = person_shapes();
+    let mut status = prop("status", "string", 1, Some(1));
+    status.values = vec!["active".to_string(), "retired".to_string()];
+    shapes[0].properties = vec![status.clone()];
+    let mut project = shapes[0].clone();
+    project.name = "Project".to_string();
+    let mut company = shapes[0].clone();
+    company.name = "Company".to_string();
+    status.values = vec!["open".to_string(), "closed".to_string()];
+    company.properties = vec![status];
+    shapes.extend([project, company]);
+
+    let rs = convert::build_rust(&shapes);
+
+    assert!(rs.contains("pub struct Project {\n    pub status: StatusEnum,\n}"), "{}", rs);
+    assert!(rs.contains("pub struct Company {\n    pub status: CompanyStatusEnum,\n}"), "{}", rs);
+    assert_eq!(rs.matches("pub enum StatusEnum {").count(), 1, "{}", rs);
+    assert!(rs.contains("pub enum CompanyStatusEnum {"), "{}", rs);
+}
+
+#[test]
+fn test_build_xsd() {
+    let mut shapes = person_shapes();
+    let mut status = prop("status", "string", 1, Some(1));
+    status.values = vec!["active".to_string(), "a&b".to_string()];
+    shapes[0].properties.push(status);
+
+    let xsd = convert::build_xsd(&shapes);
+
+    assert!(xsd.contains("  <xs:element name=\"Person\" type=\"Person\"/>"));
+    assert!(xsd.contains("      <xs:element name=\"name\" type=\"xs:string\"/>"));
+    assert!(xsd.contains("      <xs:element name=\"age\" type=\"xs:integer\" minOccurs=\"0\"/>"));
+    assert!(xsd.contains("      <xs:element name=\"knows\" type=\"Person\" minOccurs=\"0\" maxOccurs=\"unbounded\"/>"));
+    assert!(xsd.contains("            <xs:enumeration value=\"a&amp;b\"/>"));
+}
+
+#[test]
+fn test_build_bigquery() {
+    let mut shapes = person_shapes();
+    shapes.push(ShapeInfo {
+        id: "Team".to_string(),
+        name: "Team".to_string(),
+        properties: vec![prop("lead", "Person", 1, Some(1))],
+        ..Default::default()
+    });
+
+    let bq = convert::build_bigquery(&shapes);
+
+    assert_eq!(bq["Person"][0], serde_json::json!({ "name": "name", "mode": "REQUIRED", "type": "STRING" }));
+    assert_eq!(bq["Person"][1]["mode"], "NULLABLE");
+    assert_eq!(bq["Person"][1]["type"], "INTEGER");
+    // a self-reference can't nest, so it holds the IRI
+    assert_eq!(bq["Person"][2], serde_json::json!({ "name": "knows", "mode": "REPEATED", "type": "STRING" }));
+    assert_eq!(bq["Team"][0]["type"], "RECORD");
+    assert_eq!(bq["Team"][0]["fields"][2]["type"], "STRING");
+}
+
+#[test]
+fn test_build_mermaid() {
+    let mermaid = convert::build_mermaid(&person_shapes());
+
+    assert_eq!(mermaid, "classDiagram
+  class Person {
+    +string name [1]
+    +integer age [0..1]
+  }
+  Person --> \"0..*\" Person : knows
+");
+}
+
+#[test]
+fn test_build_plantuml_and_dot() {
+    let mut shapes = person_shapes();
+    shapes[0].id = "http://example.org/people/Person".to_string();
+    shapes.push(ShapeInfo {
+        id: "http://example.org/orgs/Team".to_string(),
+        name: "Team".to_string(),
+        properties: vec![prop("lead", "Person", 1, Some(1))],
+        ..Default::default()
+    });
+
+    let puml = convert::build_plantuml(&shapes, false);
+    assert!(puml.starts_with("@startuml\nclass Person {\n  +name : string [1]\n"));
+    assert!(puml.contains("Team --> \"1\" Person : lead\n"));
+    assert!(!puml.contains("package"));
+
+    let puml = convert::build_plantuml(&shapes, true);
+    assert!(puml.contains("package \"http://example.org/orgs/\" {\n  class Team {\n  }\n}\n"));
+
+    let dot = convert::build_dot(&shapes, true);
+    assert!(dot.contains("  subgraph cluster_0 {\n    label=\"http://example.org/people/\";\n    Person [label=\"{Person|name : string [1]\\lage : integer [0..1]\\l}\"];\n  }\n"));
+    assert!(dot.contains("  Person -> Person [label=\"knows 0..*\"];\n"));
+    assert!(dot.contains("  Team -> Person [label=\"lead 1\"];\n"));
+}
+
+#[test]
+fn test_build_rdfs() {
+    let rdfs = convert::build_rdfs(&person_shapes());
+
+    assert!(rdfs.contains("ex:Person a rdfs:Class ;\n  rdfs:label \"Person\" ."));
+    assert!(rdfs.contains("<http://example.org/age> a rdf:Property ;\n  rdfs:label \"age\" ;\n  rdfs:domain ex:Person ;\n  rdfs:range xsd:integer ."));
+    assert!(rdfs.contains("<http://example.org/knows> a rdf:Property ;\n  rdfs:label \"knows\" ;\n  rdfs:domain ex:Person ;\n  rdfs:range ex:Person ."));
+    assert!(!rdfs.contains("owl:"));
+}
+
+#[test]
+fn test_build_sparql() {
+    let queries = convert::build_sparql(&person_shapes(), false);
+
+    assert_eq!(queries.len(), 1);
+    assert_eq!(queries[0].0, "Person");
+    assert_eq!(queries[0].1, "PREFIX ex: <http://example.org/>
+
+SELECT ?this ?name ?age ?knows
+WHERE {
+  ?this <http://example.org/name> ?name .
+  OPTIONAL { ?this <http://example.org/age> ?age }
+  OPTIONAL { ?this <http://example.org/knows> ?knows }
+}
+");
+
+    let construct = &convert::build_sparql(&person_shapes(), true)[0].1;
+    assert!(construct.contains("CONSTRUCT {\n  ?this <http://example.org/name> ?name .\n  ?this <http://example.org/age> ?age .\n"));
+}
+
+#[test]
+fn test_build_zod() {
+    let mut shapes = person_shapes();
+    let mut status = prop("status", "string", 1, Some(1));
+    status.values = vec!["active".to_string(), "retired".to_string()];
+    shapes[0].properties.push(status);
+
+    let zod = convert::build_zod(&shapes);
+
+    assert!(zod.starts_with("import { z } from \"zod\";\n\nexport interface Person {\n"));
+    assert!(zod.ends_with("export const PersonSchema: z.ZodType<Person> = z.object({
+  name: z.string(),
+  age: z.number().int().optional(),
+  knows: z.array(z.lazy(() => PersonSchema)).optional(),
+  status: z.enum([\"active\", \"retired\"]),
+});
+"));
+}
+
+#[test]
+fn test_build_kotlin() {
+    let mut shapes = person_shapes();
+    let mut status = prop("status", "string", 1, Some(1));
+    status.values = vec!["active".to_string(), "on-leave".to_string()];
+    shapes[0].properties.push(status);
+    shapes[0].properties.push(prop("in", "string", 0, Some(1)));
+    shapes[0].properties.push(prop("birth-date", "string", 0, Some(1)));
+
+    let kt = convert::build_kotlin(&shapes);
+
+    assert!(kt.contains("@Serializable
+data class Person(
+    val name: String,
+    val age: Long? = null,
+    val knows: List<Person> = emptyList(),
+    val status: StatusEnum,
+    val `in`: String? = null,
+    @SerialName(\"birth-date\") val birth_date: String? = null,
+)"));
+    assert!(kt.contains("enum class StatusEnum {\n    @SerialName(\"active\") ACTIVE,\n    @SerialName(\"on-leave\") ON_LEAVE,\n}"));
+}
+
+#[test]
+fn test_build_kotlin_shared_enums() {
+    let mut shapes = person_shapes();
+    let mut status = prop("status", "string", 1, Some(1));
+    status.values = vec!["active".to_string(), "retired".to_string()];
+    shapes[0].properties = vec![status.clone()];
+    let mut project = shapes[0].clone();
+    project.name = "Project".to_string();
+    let mut company = shapes[0].clone();
+    company.name = "Company".to_string();
+    status.values = vec!["open".to_string(), "closed".to_string()];
+    company.properties = vec![status];
+    shapes.extend([project, company]);
+
+    let kt = convert::build_kotlin(&shapes);
+
+    assert!(kt.contains("data class Project(\n    val status: StatusEnum,\n)"), "{}", kt);
+    assert!(kt.contains("data class Company(\n    val status: CompanyStatusEnum,\n)"), "{}", kt);
+    assert_eq!(kt.matches("enum class StatusEnum {").count(), 1, "{}", kt);
+    assert!(kt.contains("enum class CompanyStatusEnum {"), "{}", kt);
+}
+
+#[test]
+fn test_codegen_numeric_and_temporal_ranges() {
+    let mut shapes = person_shapes();
+    let weight = convert::range_for_datatype("http://www.w3.org/2001/XMLSchema#double");
+    let born = convert::range_for_datatype("http://www.w3.org/2001/XMLSchema#date");
+    shapes[0].properties = vec![prop("weight", &weight, 1, Some(1)), prop("born", &born, 1, Some(1))];
+
+    assert!(convert::build_graphql(&shapes).contains("  weight: Float!\n"));
+    assert!(convert::build_protobuf(&shapes).contains("  double weight = 1;\n"));
+    assert!(convert::build_typescript(&shapes).contains("  weight: number;\n"));
+    assert!(convert::build_zod(&shapes).contains("  weight: z.number(),\n  born: z.string().date(),\n"));
+    let py = convert::build_pydantic(&shapes);
+    assert!(py.contains("from datetime import date\n"), "{}", py);
+    assert!(py.contains("    weight: float\n    born: date\n"), "{}", py);
+    assert!(convert::build_rust(&shapes).contains("    pub weight: f64,\n"));
+    assert!(convert::build_kotlin(&shapes).contains("    val weight: Double,\n"));
+    let bq = convert::build_bigquery(&shapes);
+    assert_eq!(bq["Person"][0]["type"], "FLOAT64");
+    assert_eq!(bq["Person"][1]["type"], "DATE");
+}
+
+#[test]
+fn test_build_skos() {
+    let mut shapes = person_shapes();
+    let mut status = prop("status", "string", 1, Some(1));
+    status.values = vec!["on leave".to_string(), "http://example.org/vocab/Retired".to_string()];
+    shapes[0].properties.push(status);
+
+    let skos = convert::build_skos(&shapes);
+
+    assert!(skos.contains("ex:StatusEnum a skos:ConceptScheme ;\n  skos:prefLabel \"StatusEnum\" ."));
+    assert!(skos.contains("<http://example.org/StatusEnum/on+leave> a skos:Concept ;\n  skos:inScheme ex:StatusEnum ;\n  skos:topConceptOf ex:StatusEnum ;\n  skos:prefLabel \"on leave\" ."));
+    assert!(skos.contains("<http://example.org/vocab/Retired> a skos:Concept ;\n  skos:inScheme ex:StatusEnum ;\n  skos:topConceptOf ex:StatusEnum ;\n  skos:prefLabel \"Retired\" ."));
+    assert!(!convert::build_skos(&person_shapes()).contains("skos:Concept"));
+}
+
+#[test]
+fn test_build_jsonld_frames() {
+    let mut shapes = person_shapes();
+    shapes.push(ShapeInfo {
+        id: "Team".to_string(),
+        name: "Team".to_string(),
+        properties: vec![prop("lead", "Person", 1, Some(1))],
+        ..Default::default()
+    });
+
+    let frames = convert::build_jsonld_frames(&shapes);
+
+    assert_eq!(frames[1].0, "Team");
+    let team = &frames[1].1;
+    assert_eq!(team["@type"], "ex:Team");
+    assert_eq!(team["lead"]["@type"], "ex:Person");
+    // Person -> knows -> Person is not expanded again
+    assert!(team["lead"].get("knows").is_none());
+    assert_eq!(team["@context"]["lead"], serde_json::json!({ "@id": "http://example.org/lead", "@type": "@id" }));
+    assert_eq!(team["@context"]["knows"], serde_json::json!({ "@id": "http://example.org/knows", "@type": "@id", "@container": "@set" }));
+    assert_eq!(team["@context"]["age"], serde_json::json!({ "@id": "http://example.org/age" }));
+}
+
+#[test]
+fn test_build_shacl_sparql_constraints() {
+    let mut shapes = person_shapes();
+    shapes[0].properties.push(prop("email", "string", 0, Some(1)));
+    shapes[0].properties.push(prop("phone", "string", 0, Some(1)));
+    shapes[0].constraints.push(ShapeConstraint::OneOf(vec![
+        vec![prop("email", "string", 1, Some(1))],
+        vec![prop("phone", "string", 1, Some(1))],
+    ]));
+    shapes[0].constraints.push(ShapeConstraint::Not("Robot".to_string()));
+    shapes.push(ShapeInfo {
+        id: "Robot".to_string(),
+        name: "Robot".to_string(),
+        properties: vec![prop("serial", "string", 1, Some(1))],
+        ..Default::default()
+    });
+
+    let shacl = convert::build_shacl(&shapes);
+
+    assert!(shacl.contains("  sh:sparql [
+    a sh:SPARQLConstraint ;
+    sh:message \"exactly one of (email | phone) is required\" ;
+    sh:prefixes ex: ;
+    sh:select \"\"\"
+SELECT $this
+WHERE {
+  BIND ((IF(EXISTS { $this <http://example.org/email> [] }, 1, 0) + IF(EXISTS { $this <http://example.org/phone> [] }, 1, 0)) AS ?matches)
+  FILTER (?matches != 1)
+}
+\"\"\"
+  ]"));
+    assert!(shacl.contains("sh:message \"must not conform to Robot\""));
+    assert!(shacl.contains("  FILTER (EXISTS { $this <http://example.org/serial> [] })\n"));
+    assert!(!shacl.contains("PREFIX "), "{}", shacl);
+
+    // an alternative with no required properties is matched by any of them
+    let place = ShapeInfo {
+        id: "Place".to_string(),
+        name: "Place".to_string(),
+        properties: vec![prop("street", "string", 0, Some(1)), prop("lat", "decimal", 0, Some(1))],
+        constraints: vec![ShapeConstraint::OneOf(vec![
+            vec![prop("street", "string", 0, Some(1))],
+            vec![prop("lat", "decimal", 1, Some(1))],
+        ])],
+        ..Default::default()
+    };
+    let place_shacl = convert::build_shacl(&[place]);
+    assert!(place_shacl.contains("  BIND ((IF(EXISTS { $this <http://example.org/street> [] }, 1, 0) + IF(EXISTS { $this <http://example.org/lat> [] }, 1, 0)) AS ?matches)\n"), "{}", place_shacl);
+    assert!(shacl.ends_with("\nex: sh:declare\n  [ sh:prefix \"ex\" ; sh:namespace \"http://example.org/\"^^xsd:anyURI ] ,\n  [ sh:prefix \"sh\" ; sh:namespace \"http://www.w3.org/ns/shacl#\"^^xsd:anyURI ] ,\n  [ sh:prefix \"xsd\" ; sh:namespace \"http://www.w3.org/2001/XMLSchema#\"^^xsd:anyURI ] .\n"), "{}", shacl);
+}
+
+#[test]
+fn test_prefix_file() {
+    let turtle = convert::parse_prefix_file("@base <http://example.org/base/> .\nPREFIX eg: <http://example.org/>\n@prefix foaf: <http://xmlns.com/foaf/0.1/> .\n").unwrap();
+    assert_eq!(turtle.base.as_deref(), Some("http://example.org/base/"));
+    assert_eq!(turtle.prefixes, vec![
+        ("eg".to_string(), "http://example.org/".to_string()),
+        ("foaf".to_string(), "http://xmlns.com/foaf/0.1/".to_string()),
+    ]);
+
+    // JSON-LD context terms for properties aren't namespaces
+    let context = convert::parse_prefix_file(r#"{ "@context": {
+        "@vocab": "http://schema.org/",
+        "eg": "http://example.org/",
+        "name": "http://xmlns.com/foaf/0.1/name",
+        "vocab": { "@id": "http://example.org/vocab", "@prefix": true }
+    } }"#).unwrap();
+    assert_eq!(context.prefixes, vec![
+        ("eg".to_string(), "http://example.org/".to_string()),
+        ("vocab".to_string(), "http://example.org/vocab".to_string()),
+    ]);
+
+    let mut prefixes = convert::parse_prefix_file("eg: http://example.org/\nfoaf: http://xmlns.com/foaf/0.1/\n").unwrap();
+    assert_eq!(prefixes, convert::Prefixes { base: None, ..turtle.clone() });
+    // a schema's own declarations win
+    prefixes.merge(convert::Prefixes { base: None, prefixes: vec![("eg".to_string(), "http://example.com/".to_string())] });
+    assert_eq!(prefixes.prefixes[0].1, "http://example.org/");
+
+    let owl = convert::build_owl_with_prefixes(&person_shapes(), &prefixes);
+    assert!(owl.starts_with("@prefix eg: <http://example.org/> .\n@prefix foaf: <http://xmlns.com/foaf/0.1/> .\n@prefix ex: <http://example.org/> .\n"));
+    assert!(owl.contains("owl:onProperty eg:name ; owl:minCardinality"));
+    assert!(owl.contains("eg:knows a owl:ObjectProperty"));
+    let rdfs = convert::build_rdfs_with_prefixes(&person_shapes(), &prefixes);
+    assert!(rdfs.contains("eg:age a rdf:Property"));
+    let shacl = convert::build_shacl_with_prefixes(&person_shapes(), &prefixes);
+    assert!(shacl.contains("sh:path eg:name ;"));
+
+    let sparql = convert::build_sparql_with_prefixes(&person_shapes(), false, &prefixes);
+    assert!(sparql[0].1.starts_with("PREFIX eg: <http://example.org/>\nPREFIX foaf: <http://xmlns.com/foaf/0.1/>\nPREFIX ex: <http://example.org/>\n\n"));
+    assert!(sparql[0].1.contains("?this eg:name ?name ."));
+    let frames = convert::build_jsonld_frames_with_prefixes(&person_shapes(), &prefixes);
+    assert_eq!(frames[0].1["@context"]["foaf"], "http://xmlns.com/foaf/0.1/");
+
+    // class terms compact too
+    let mut foaf_person = person_shapes();
+    foaf_person[0].name = "http://xmlns.com/foaf/0.1/Person".to_string();
+    foaf_person[0].properties[1].values = vec!["http://xmlns.com/foaf/0.1/Adult".to_string()];
+    assert!(convert::build_owl_with_prefixes(&foaf_person, &prefixes).contains("\nfoaf:Person a owl:Class"));
+    assert!(convert::build_rdfs_with_prefixes(&foaf_person, &prefixes).contains("\nfoaf:Person a rdfs:Class"));
+    assert_eq!(convert::build_jsonld_frames_with_prefixes(&foaf_person, &prefixes)[0].1["@type"], "foaf:Person");
+    let skos = convert::build_skos_with_prefixes(&foaf_person, &prefixes);
+    assert!(skos.starts_with("@prefix eg: <http://example.org/> .\n@prefix foaf: <http://xmlns.com/foaf/0.1/> .\n@prefix ex: <http://example.org/> .\n@prefix skos:"));
+    assert!(skos.contains("\nfoaf:Adult a skos:Concept"));
+}
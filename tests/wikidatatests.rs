@@ -0,0 +1,14 @@
+use shex2linkml::wikidata;
+
+#[test]
+fn test_entity_schema_id() {
+    assert_eq!(wikidata::entity_schema_id("E42").unwrap(), "E42");
+    assert_eq!(wikidata::entity_schema_id("https://www.wikidata.org/wiki/EntitySchema:E42").unwrap(), "E42");
+    assert_eq!(wikidata::entity_schema_id("http://www.wikidata.org/entity/E10/").unwrap(), "E10");
+    assert_eq!(
+        wikidata::entity_schema_text_url("https://www.wikidata.org/wiki/Special:EntitySchemaText/E236").unwrap(),
+        "https://www.wikidata.org/wiki/Special:EntitySchemaText/E236"
+    );
+    assert!(wikidata::entity_schema_id("Q42").is_err());
+    assert!(wikidata::entity_schema_id("schema.shex").is_err());
+}
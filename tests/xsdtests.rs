@@ -0,0 +1,52 @@
+use shex2linkml::{convert, xsd_to_linkml};
+
+const XSD: &str = r#"<?xml version="1.0"?>
+<xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema">
+  <xs:simpleType name="Status">
+    <xs:restriction base="xs:string">
+      <xs:enumeration value="active"/>
+      <xs:enumeration value="retired"/>
+    </xs:restriction>
+  </xs:simpleType>
+  <xs:complexType name="Agent">
+    <xs:sequence>
+      <xs:element name="name" type="xs:string"/>
+    </xs:sequence>
+  </xs:complexType>
+  <xs:complexType name="Person">
+    <xs:complexContent>
+      <xs:extension base="Agent">
+        <xs:sequence>
+          <xs:element name="age" type="xs:integer" minOccurs="0"/>
+          <xs:element name="knows" type="Person" minOccurs="0" maxOccurs="unbounded"/>
+          <xs:element name="status" type="Status"/>
+        </xs:sequence>
+        <xs:attribute name="id" type="xs:string" use="required"/>
+      </xs:extension>
+    </xs:complexContent>
+  </xs:complexType>
+</xs:schema>
+"#;
+
+#[test]
+fn test_shapes_from_xsd() {
+    let shapes = xsd_to_linkml::shapes_from_xsd(XSD).unwrap();
+    assert_eq!(shapes.iter().map(|s| s.name.as_str()).collect::<Vec<_>>(), vec!["Agent", "Person"]);
+
+    let person = &shapes[1];
+    let names: Vec<&str> = person.properties.iter().map(|p| p.name.as_str()).collect();
+    assert_eq!(names, vec!["name", "age", "knows", "status", "id"]);
+    let prop = |n: &str| person.properties.iter().find(|p| p.name == n).unwrap();
+    assert_eq!((prop("age").range.as_str(), prop("age").min, prop("age").max), ("integer", Some(0), Some(1)));
+    assert_eq!((prop("knows").range.as_str(), prop("knows").max), ("Person", None));
+    assert_eq!(prop("status").values, vec!["active", "retired"]);
+    assert_eq!(prop("id").min, Some(1));
+}
+
+#[test]
+fn test_xsd_roundtrip() {
+    let shapes = xsd_to_linkml::shapes_from_xsd(XSD).unwrap();
+    let again = xsd_to_linkml::shapes_from_xsd(&convert::build_xsd(&shapes)).unwrap();
+    assert_eq!(again.len(), 2);
+    assert_eq!(again[1].properties.len(), 5);
+}